@@ -5,6 +5,7 @@ use axfs::fops::{Directory, File};
 pub use axfs::fops::DirEntry as AxDirEntry;
 pub use axfs::fops::FileAttr as AxFileAttr;
 pub use axfs::fops::FilePerm as AxFilePerm;
+pub use axfs::fops::FileSystemInfo as AxFileSystemInfo;
 pub use axfs::fops::FileType as AxFileType;
 pub use axfs::fops::OpenOptions as AxOpenOptions;
 pub use axio::SeekFrom as AxSeekFrom;
@@ -78,6 +79,26 @@ pub fn ax_rename(old: &str, new: &str) -> AxResult {
     axfs::api::rename(old, new)
 }
 
+pub fn ax_symlink_attr(path: &str) -> AxResult<AxFileAttr> {
+    axfs::fops::symlink_attr(path)
+}
+
+pub fn ax_statfs(path: &str) -> AxResult<AxFileSystemInfo> {
+    axfs::api::statfs(path)
+}
+
+pub fn ax_symlink(original: &str, link: &str) -> AxResult {
+    axfs::api::symlink(original, link)
+}
+
+pub fn ax_hard_link(original: &str, link: &str) -> AxResult {
+    axfs::api::hard_link(original, link)
+}
+
+pub fn ax_read_link(path: &str) -> AxResult<String> {
+    axfs::api::read_link(path)
+}
+
 pub fn ax_current_dir() -> AxResult<String> {
     axfs::api::current_dir()
 }
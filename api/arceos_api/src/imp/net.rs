@@ -1,7 +1,10 @@
 use crate::io::AxPollState;
-use axerrno::AxResult;
+use axerrno::{AxError, AxResult};
 use axnet::{UdpSocket, TcpSocket};
 use core::net::{IpAddr, SocketAddr};
+use core::time::Duration;
+
+pub use axnet::DhcpLease as AxDhcpLease;
 
 /// A handle to a TCP socket.
 pub struct AxTcpSocketHandle(TcpSocket);
@@ -64,6 +67,82 @@ pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult {
     socket.0.shutdown()
 }
 
+pub fn ax_tcp_set_reuse_address(socket: &AxTcpSocketHandle, reuse: bool) -> AxResult {
+    socket.0.set_reuse_address(reuse);
+    Ok(())
+}
+
+pub fn ax_tcp_set_nodelay(socket: &AxTcpSocketHandle, nodelay: bool) -> AxResult {
+    socket.0.set_nodelay(nodelay);
+    Ok(())
+}
+
+pub fn ax_tcp_nodelay(socket: &AxTcpSocketHandle) -> AxResult<bool> {
+    Ok(socket.0.nodelay())
+}
+
+pub fn ax_tcp_set_keepalive(socket: &AxTcpSocketHandle, keepalive: bool) -> AxResult {
+    socket.0.set_keepalive(keepalive);
+    Ok(())
+}
+
+pub fn ax_tcp_keepalive(socket: &AxTcpSocketHandle) -> AxResult<bool> {
+    Ok(socket.0.keepalive())
+}
+
+pub fn ax_tcp_set_ttl(socket: &AxTcpSocketHandle, ttl: u8) -> AxResult {
+    socket.0.set_ttl(ttl)
+}
+
+pub fn ax_tcp_ttl(socket: &AxTcpSocketHandle) -> AxResult<u8> {
+    Ok(socket.0.ttl())
+}
+
+pub fn ax_tcp_set_recv_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult {
+    socket.0.set_recv_buffer_size(size)
+}
+
+pub fn ax_tcp_recv_buffer_size(socket: &AxTcpSocketHandle) -> AxResult<usize> {
+    Ok(socket.0.recv_buffer_size())
+}
+
+pub fn ax_tcp_set_send_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult {
+    socket.0.set_send_buffer_size(size)
+}
+
+pub fn ax_tcp_send_buffer_size(socket: &AxTcpSocketHandle) -> AxResult<usize> {
+    Ok(socket.0.send_buffer_size())
+}
+
+pub fn ax_tcp_set_read_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_read_timeout(timeout)
+}
+
+pub fn ax_tcp_read_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.read_timeout())
+}
+
+pub fn ax_tcp_set_write_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult {
+    socket.0.set_write_timeout(timeout)
+}
+
+pub fn ax_tcp_write_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.write_timeout())
+}
+
+pub fn ax_tcp_set_linger(socket: &AxTcpSocketHandle, linger: Option<Duration>) -> AxResult {
+    socket.0.set_linger(linger);
+    Ok(())
+}
+
+pub fn ax_tcp_linger(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>> {
+    Ok(socket.0.linger())
+}
+
+pub fn ax_tcp_take_error(socket: &AxTcpSocketHandle) -> AxResult<Option<AxError>> {
+    Ok(socket.0.take_error())
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // UDP socket
 ////////////////////////////////////////////////////////////////////////////////
@@ -125,6 +204,10 @@ pub fn ax_dns_query(domain_name: &str) -> AxResult<alloc::vec::Vec<IpAddr>> {
     axnet::dns_query(domain_name)
 }
 
+pub fn ax_dhcp_lease(iface: &str) -> Option<AxDhcpLease> {
+    axnet::dhcp_lease(iface)
+}
+
 pub fn ax_poll_interfaces() -> AxResult {
     axnet::poll_interfaces();
     Ok(())
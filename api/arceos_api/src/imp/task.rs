@@ -17,6 +17,8 @@ pub fn ax_yield_now() {
 }
 
 pub fn ax_exit(_exit_code: i32) -> ! {
+    #[cfg(feature = "fs")]
+    axfs::fops::sync().ok();
     #[cfg(feature = "multitask")]
     axtask::exit(_exit_code);
     #[cfg(not(feature = "multitask"))]
@@ -26,6 +28,9 @@ pub fn ax_exit(_exit_code: i32) -> ! {
 cfg_task! {
     use core::time::Duration;
 
+    pub use axtask::CpuMask as AxCpuMask;
+    pub use axtask::TaskStats as AxTaskStats;
+
     /// A handle to a task.
     pub struct AxTaskHandle {
         inner: axtask::AxTaskRef,
@@ -82,6 +87,25 @@ cfg_task! {
         }
     }
 
+    pub fn ax_set_affinity(cpumask: AxCpuMask) -> crate::AxResult {
+        if axtask::set_affinity(cpumask) {
+            Ok(())
+        } else {
+            axerrno::ax_err!(
+                InvalidInput,
+                "ax_set_affinity: no available CPU in the mask"
+            )
+        }
+    }
+
+    pub fn ax_get_affinity() -> AxCpuMask {
+        axtask::get_affinity()
+    }
+
+    pub fn ax_task_stats() -> alloc::vec::Vec<AxTaskStats> {
+        axtask::task_stats()
+    }
+
     pub fn ax_wait_queue_wait(
         wq: &AxWaitQueueHandle,
         until_condition: impl Fn() -> bool,
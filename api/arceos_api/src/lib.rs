@@ -85,6 +85,8 @@ pub mod task {
         @cfg "multitask";
         pub type AxTaskHandle;
         pub type AxWaitQueueHandle;
+        pub type AxCpuMask;
+        pub type AxTaskStats;
     }
 
     define_api! {
@@ -119,6 +121,16 @@ pub mod task {
         pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32>;
         /// Sets the priority of the current task.
         pub fn ax_set_current_priority(prio: isize) -> crate::AxResult;
+        /// Sets the CPU affinity of the current task.
+        ///
+        /// The current task is migrated to a CPU in `cpumask` immediately if
+        /// it is running on a CPU not in the set.
+        pub fn ax_set_affinity(cpumask: AxCpuMask) -> crate::AxResult;
+        /// Gets the CPU affinity of the current task.
+        pub fn ax_get_affinity() -> AxCpuMask;
+        /// Returns the statistics of all live tasks, in the order of the task
+        /// IDs.
+        pub fn ax_task_stats() -> alloc::vec::Vec<AxTaskStats>;
 
         /// Blocks the current task and put it into the wait queue, until the
         /// given condition becomes true, or the the given duration has elapsed
@@ -136,6 +148,16 @@ pub mod task {
     }
 }
 
+/// Synchronization primitives.
+pub mod sync {
+    #[cfg(feature = "multitask")]
+    #[doc(no_inline)]
+    pub use axsync::{
+        Barrier, BarrierWaitResult, Condvar, Mutex, MutexGuard, Once, OnceLock, RwLock,
+        RwLockReadGuard, RwLockWriteGuard, Semaphore, SemaphoreGuard, WaitTimeoutResult,
+    };
+}
+
 /// Filesystem manipulation operations.
 pub mod fs {
     use crate::AxResult;
@@ -150,6 +172,7 @@ pub mod fs {
         pub type AxFilePerm;
         pub type AxDirEntry;
         pub type AxSeekFrom;
+        pub type AxFileSystemInfo;
         #[cfg(feature = "myfs")]
         pub type AxDisk;
         #[cfg(feature = "myfs")]
@@ -213,6 +236,19 @@ pub mod fs {
         ///
         /// It will delete the original file if `old` already exists.
         pub fn ax_rename(old: &str, new: &str) -> AxResult;
+        /// Returns attributes of the node at the provided path, without
+        /// following the symbolic link.
+        pub fn ax_symlink_attr(path: &str) -> AxResult<AxFileAttr>;
+        /// Returns attributes of the filesystem that contains the node at the
+        /// provided path.
+        pub fn ax_statfs(path: &str) -> AxResult<AxFileSystemInfo>;
+        /// Creates a symbolic link `link` which points to `original`.
+        pub fn ax_symlink(original: &str, link: &str) -> AxResult;
+        /// Creates a hard link `link` which refers to the same file as
+        /// `original`.
+        pub fn ax_hard_link(original: &str, link: &str) -> AxResult;
+        /// Returns the target of the symbolic link.
+        pub fn ax_read_link(path: &str) -> AxResult<alloc::string::String>;
 
         /// Returns the current working directory.
         pub fn ax_current_dir() -> AxResult<alloc::string::String>;
@@ -223,13 +259,15 @@ pub mod fs {
 
 /// Networking primitives for TCP/UDP communication.
 pub mod net {
-    use crate::{io::AxPollState, AxResult};
+    use crate::{io::AxPollState, AxError, AxResult};
     use core::net::{IpAddr, SocketAddr};
+    use core::time::Duration;
 
     define_api_type! {
         @cfg "net";
         pub type AxTcpSocketHandle;
         pub type AxUdpSocketHandle;
+        pub type AxDhcpLease;
     }
 
     define_api! {
@@ -268,6 +306,46 @@ pub mod net {
         /// Closes the connection on the TCP socket.
         pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult;
 
+        /// Sets the `SO_REUSEADDR` option, which allows binding to a port
+        /// while connections on it still exist.
+        pub fn ax_tcp_set_reuse_address(socket: &AxTcpSocketHandle, reuse: bool) -> AxResult;
+        /// Enables or disables the Nagle's algorithm (`TCP_NODELAY`).
+        pub fn ax_tcp_set_nodelay(socket: &AxTcpSocketHandle, nodelay: bool) -> AxResult;
+        /// Returns whether the Nagle's algorithm is disabled (`TCP_NODELAY`).
+        pub fn ax_tcp_nodelay(socket: &AxTcpSocketHandle) -> AxResult<bool>;
+        /// Enables or disables the keep-alive probes (`SO_KEEPALIVE`).
+        pub fn ax_tcp_set_keepalive(socket: &AxTcpSocketHandle, keepalive: bool) -> AxResult;
+        /// Returns whether the keep-alive probes are enabled (`SO_KEEPALIVE`).
+        pub fn ax_tcp_keepalive(socket: &AxTcpSocketHandle) -> AxResult<bool>;
+        /// Sets the time-to-live of the outgoing packets (`IP_TTL`).
+        pub fn ax_tcp_set_ttl(socket: &AxTcpSocketHandle, ttl: u8) -> AxResult;
+        /// Returns the time-to-live of the outgoing packets (`IP_TTL`).
+        pub fn ax_tcp_ttl(socket: &AxTcpSocketHandle) -> AxResult<u8>;
+        /// Sets the size of the receive buffer (`SO_RCVBUF`), which takes
+        /// effect on the next connection. Fails if the socket is connected.
+        pub fn ax_tcp_set_recv_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult;
+        /// Returns the size of the receive buffer (`SO_RCVBUF`).
+        pub fn ax_tcp_recv_buffer_size(socket: &AxTcpSocketHandle) -> AxResult<usize>;
+        /// Sets the size of the send buffer (`SO_SNDBUF`), which takes
+        /// effect on the next connection. Fails if the socket is connected.
+        pub fn ax_tcp_set_send_buffer_size(socket: &AxTcpSocketHandle, size: usize) -> AxResult;
+        /// Returns the size of the send buffer (`SO_SNDBUF`).
+        pub fn ax_tcp_send_buffer_size(socket: &AxTcpSocketHandle) -> AxResult<usize>;
+        /// Sets the timeout of the blocking receive operations (`SO_RCVTIMEO`).
+        pub fn ax_tcp_set_read_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of the blocking receive operations (`SO_RCVTIMEO`).
+        pub fn ax_tcp_read_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>>;
+        /// Sets the timeout of the blocking send operations (`SO_SNDTIMEO`).
+        pub fn ax_tcp_set_write_timeout(socket: &AxTcpSocketHandle, timeout: Option<Duration>) -> AxResult;
+        /// Returns the timeout of the blocking send operations (`SO_SNDTIMEO`).
+        pub fn ax_tcp_write_timeout(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>>;
+        /// Sets the linger timeout of closing the connection (`SO_LINGER`).
+        pub fn ax_tcp_set_linger(socket: &AxTcpSocketHandle, linger: Option<Duration>) -> AxResult;
+        /// Returns the linger timeout of closing the connection (`SO_LINGER`).
+        pub fn ax_tcp_linger(socket: &AxTcpSocketHandle) -> AxResult<Option<Duration>>;
+        /// Returns and clears the pending error of the TCP socket (`SO_ERROR`).
+        pub fn ax_tcp_take_error(socket: &AxTcpSocketHandle) -> AxResult<Option<AxError>>;
+
         // UDP socket
 
         /// Creates a new UDP socket.
@@ -308,6 +386,9 @@ pub mod net {
 
         /// Resolves the host name to a list of IP addresses.
         pub fn ax_dns_query(domain_name: &str) -> AxResult<alloc::vec::Vec<IpAddr>>;
+        /// Returns the DHCP lease of the network interface (e.g. `"eth0"`), or
+        /// `None` if it is not configured by DHCP.
+        pub fn ax_dhcp_lease(iface: &str) -> Option<AxDhcpLease>;
         /// Poll the network stack.
         ///
         /// It may receive packets from the NIC and process them, and transmit queued
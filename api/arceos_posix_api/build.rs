@@ -2,16 +2,27 @@ fn main() {
     use std::io::Write;
 
     fn gen_pthread_mutex(out_file: &str) -> std::io::Result<()> {
-        // TODO: generate size and initial content automatically.
-        let (mutex_size, mutex_init) = if cfg!(feature = "multitask") {
+        // TODO: generate the initial content automatically. It is checked against
+        // `PthreadMutex::new` by the unit test in `imp/pthread/mutex.rs`.
+        let mutex_init: &[usize] = if cfg!(feature = "multitask") {
             if cfg!(feature = "smp") {
-                (6, "{0, 8, 0, 0, 0, 0}") // core::mem::transmute::<_, [usize; 6]>(axsync::Mutex::new(()))
+                &[0, 0, 0, 0, 8, 0, 0, 0, 0, 0] // core::mem::transmute::<_, [usize; 10]>(PthreadMutex::new(PTHREAD_MUTEX_NORMAL))
             } else {
-                (5, "{8, 0, 0, 0, 0}") // core::mem::transmute::<_, [usize; 5]>(axsync::Mutex::new(()))
+                &[0, 8, 0, 0, 0, 0, 0, 0] // core::mem::transmute::<_, [usize; 8]>(PthreadMutex::new(PTHREAD_MUTEX_NORMAL))
             }
         } else {
-            (1, "{0}")
+            &[0]
         };
+        let mutex_size = mutex_init.len();
+        let mutex_init = format!(
+            "{{{}}}",
+            mutex_init
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        println!("cargo:rustc-env=AX_PTHREAD_MUTEX_INIT={mutex_init}");
 
         let mut output = Vec::new();
         writeln!(
@@ -37,17 +48,30 @@ typedef struct {{
 
         let allow_types = [
             "stat",
+            "statfs",
             "size_t",
             "ssize_t",
             "off_t",
             "mode_t",
+            "uid_t",
+            "gid_t",
             "sock.*",
+            "linger",
             "fd_set",
             "timeval",
             "pthread_t",
             "pthread_attr_t",
             "pthread_mutex_t",
             "pthread_mutexattr_t",
+            "pthread_cond_t",
+            "pthread_condattr_t",
+            "pthread_rwlock_t",
+            "pthread_rwlockattr_t",
+            "pthread_barrier_t",
+            "pthread_barrierattr_t",
+            "pthread_key_t",
+            "pthread_once_t",
+            "cpu_set_t",
             "epoll_event",
             "iovec",
             "clockid_t",
@@ -59,6 +83,10 @@ typedef struct {{
             "AF_.*",
             "SOCK_.*",
             "IPPROTO_.*",
+            "IP_.*",
+            "SOL_.*",
+            "SO_.*",
+            "TCP_.*",
             "FD_.*",
             "F_.*",
             "_SC_.*",
@@ -67,6 +95,13 @@ typedef struct {{
             "RLIMIT_.*",
             "EAI_.*",
             "MAXADDRS",
+            "PTHREAD_.*",
+            "CLOCK_.*",
+            "MS_.*",
+            "MNT_.*",
+            "UMOUNT_.*",
+            "UTIME_.*",
+            "AT_.*",
         ];
 
         #[derive(Debug)]
@@ -1,8 +1,8 @@
 use alloc::sync::Arc;
-use core::ffi::{c_char, c_int};
+use core::ffi::{c_char, c_int, c_ulong, c_void};
 
 use axerrno::{LinuxError, LinuxResult};
-use axfs::fops::OpenOptions;
+use axfs::fops::{FileAttr, FilePerm, FileSetAttr, FileSystemInfo, MountFlags, OpenOptions};
 use axio::{PollState, SeekFrom};
 use axsync::Mutex;
 
@@ -42,21 +42,7 @@ impl FileLike for File {
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
-        let metadata = self.inner.lock().get_attr()?;
-        let ty = metadata.file_type() as u8;
-        let perm = metadata.perm().bits() as u32;
-        let st_mode = ((ty as u32) << 12) | perm;
-        Ok(ctypes::stat {
-            st_ino: 1,
-            st_nlink: 1,
-            st_mode,
-            st_uid: 1000,
-            st_gid: 1000,
-            st_size: metadata.size() as _,
-            st_blocks: metadata.blocks() as _,
-            st_blksize: 512,
-            ..Default::default()
-        })
+        Ok(attr_to_stat(&self.inner.lock().get_attr()?))
     }
 
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync> {
@@ -75,10 +61,32 @@ impl FileLike for File {
     }
 }
 
+/// Convert [`FileAttr`] to `struct stat`.
+fn attr_to_stat(attr: &FileAttr) -> ctypes::stat {
+    let ty = attr.file_type() as u8;
+    let perm = attr.perm().bits() as u32;
+    let st_mode = ((ty as u32) << 12) | perm;
+    ctypes::stat {
+        st_ino: 1,
+        st_nlink: 1,
+        st_mode,
+        st_uid: attr.uid(),
+        st_gid: attr.gid(),
+        st_size: attr.size() as _,
+        st_blocks: attr.blocks() as _,
+        st_blksize: 512,
+        st_atim: attr.atime().into(),
+        st_mtim: attr.mtime().into(),
+        st_ctim: attr.ctime().into(),
+        ..Default::default()
+    }
+}
+
 /// Convert open flags to [`OpenOptions`].
-fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
+fn flags_to_options(flags: c_int, mode: ctypes::mode_t) -> OpenOptions {
     let flags = flags as u32;
     let mut options = OpenOptions::new();
+    options.mode(mode);
     match flags & 0b11 {
         ctypes::O_RDONLY => options.read(true),
         ctypes::O_WRONLY => options.write(true),
@@ -99,6 +107,9 @@ fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     if flags & ctypes::O_EXEC != 0 {
         options.create_new(true);
     }
+    if flags & ctypes::O_NOFOLLOW != 0 {
+        options.nofollow(true);
+    }
     options
 }
 
@@ -143,11 +154,8 @@ pub unsafe fn sys_stat(path: *const c_char, buf: *mut ctypes::stat) -> c_int {
         if buf.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        let mut options = OpenOptions::new();
-        options.read(true);
-        let file = axfs::fops::File::open(path?, &options)?;
-        let st = File::new(file).stat()?;
-        unsafe { *buf = st };
+        let attr = axfs::fops::attr(path?)?;
+        unsafe { *buf = attr_to_stat(&attr) };
         Ok(0)
     })
 }
@@ -177,7 +185,198 @@ pub unsafe fn sys_lstat(path: *const c_char, buf: *mut ctypes::stat) -> ctypes::
         if buf.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        unsafe { *buf = Default::default() }; // TODO
+        let attr = axfs::fops::symlink_attr(path?)?;
+        unsafe { *buf = attr_to_stat(&attr) };
+        Ok(0)
+    })
+}
+
+/// Convert [`FileSystemInfo`] to `struct statfs`.
+fn info_to_statfs(info: &FileSystemInfo) -> ctypes::statfs {
+    ctypes::statfs {
+        f_type: info.fs_type as _,
+        f_bsize: info.block_size as _,
+        f_blocks: info.blocks,
+        f_bfree: info.blocks_free,
+        f_bavail: info.blocks_available,
+        f_files: info.files,
+        f_ffree: info.files_free,
+        f_namelen: info.name_max as _,
+        f_frsize: info.block_size as _,
+        ..Default::default()
+    }
+}
+
+/// Get the attributes of the filesystem containing `path` and write into
+/// `buf`.
+///
+/// Return 0 if success.
+pub unsafe fn sys_statfs(path: *const c_char, buf: *mut ctypes::statfs) -> c_int {
+    let path = char_ptr_to_str(path);
+    debug!("sys_statfs <= {:?} {:#x}", path, buf as usize);
+    syscall_body!(sys_statfs, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let info = axfs::fops::statfs(path?)?;
+        unsafe { *buf = info_to_statfs(&info) };
+        Ok(0)
+    })
+}
+
+/// Get the attributes of the filesystem containing the file `fd` and write
+/// into `buf`.
+///
+/// Return 0 if success.
+pub unsafe fn sys_fstatfs(fd: c_int, buf: *mut ctypes::statfs) -> c_int {
+    debug!("sys_fstatfs <= {} {:#x}", fd, buf as usize);
+    syscall_body!(sys_fstatfs, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let info = File::from_fd(fd)?.inner.lock().statfs()?;
+        unsafe { *buf = info_to_statfs(&info) };
+        Ok(0)
+    })
+}
+
+/// Change the permission mode of the file `path`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_chmod(path: *const c_char, mode: ctypes::mode_t) -> c_int {
+    syscall_body!(sys_chmod, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_chmod <= {:?} {:#o}", path, mode);
+        axfs::fops::set_attr(path, &mode_to_set_attr(mode))?;
+        Ok(0)
+    })
+}
+
+/// Change the permission mode of the file indicated by `fd`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_fchmod(fd: c_int, mode: ctypes::mode_t) -> c_int {
+    debug!("sys_fchmod <= {} {:#o}", fd, mode);
+    syscall_body!(sys_fchmod, {
+        File::from_fd(fd)?
+            .inner
+            .lock()
+            .set_attr(&mode_to_set_attr(mode))?;
+        Ok(0)
+    })
+}
+
+fn mode_to_set_attr(mode: ctypes::mode_t) -> FileSetAttr {
+    FileSetAttr {
+        mode: Some(FilePerm::from_bits_truncate(mode as u16)),
+        ..Default::default()
+    }
+}
+
+/// Convert the owner and group of `chown` to [`FileSetAttr`], where `-1`
+/// means unchanged.
+fn owner_to_set_attr(owner: ctypes::uid_t, group: ctypes::gid_t) -> FileSetAttr {
+    FileSetAttr {
+        uid: (owner != ctypes::uid_t::MAX).then_some(owner),
+        gid: (group != ctypes::gid_t::MAX).then_some(group),
+        ..Default::default()
+    }
+}
+
+/// Change the owner and group of the file `path`. An ID of `-1` is left
+/// unchanged.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_chown(path: *const c_char, owner: ctypes::uid_t, group: ctypes::gid_t) -> c_int {
+    syscall_body!(sys_chown, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_chown <= {:?} {} {}", path, owner, group);
+        axfs::fops::set_attr(path, &owner_to_set_attr(owner, group))?;
+        Ok(0)
+    })
+}
+
+/// Same as [`sys_chown`], but does not follow the symbolic link.
+pub fn sys_lchown(path: *const c_char, owner: ctypes::uid_t, group: ctypes::gid_t) -> c_int {
+    syscall_body!(sys_lchown, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_lchown <= {:?} {} {}", path, owner, group);
+        axfs::fops::set_symlink_attr(path, &owner_to_set_attr(owner, group))?;
+        Ok(0)
+    })
+}
+
+/// Change the owner and group of the file indicated by `fd`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_fchown(fd: c_int, owner: ctypes::uid_t, group: ctypes::gid_t) -> c_int {
+    debug!("sys_fchown <= {} {} {}", fd, owner, group);
+    syscall_body!(sys_fchown, {
+        File::from_fd(fd)?
+            .inner
+            .lock()
+            .set_attr(&owner_to_set_attr(owner, group))?;
+        Ok(0)
+    })
+}
+
+/// Change the last access and modification time of the file `path`.
+///
+/// `times` is the access and modification time, or the current time if it
+/// is null. A time whose `tv_nsec` is `UTIME_NOW` is set to the current time,
+/// and `UTIME_OMIT` leaves the time unchanged. If `path` is null, the file
+/// indicated by `dirfd` is changed. Otherwise `dirfd` must be `AT_FDCWD` for
+/// a relative `path`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub unsafe fn sys_utimensat(
+    dirfd: c_int,
+    path: *const c_char,
+    times: *const ctypes::timespec,
+    flags: c_int,
+) -> c_int {
+    debug!(
+        "sys_utimensat <= {} {:#x} {:#x} {:#x}",
+        dirfd, path as usize, times as usize, flags
+    );
+    syscall_body!(sys_utimensat, {
+        if flags & !(ctypes::AT_SYMLINK_NOFOLLOW as c_int) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let now = axhal::time::current_time();
+        let to_time = |ts: ctypes::timespec| match ts.tv_nsec as u32 {
+            ctypes::UTIME_NOW => Ok(Some(now)),
+            ctypes::UTIME_OMIT => Ok(None),
+            0..=999_999_999 if ts.tv_sec >= 0 => Ok(Some(ts.into())),
+            _ => Err(LinuxError::EINVAL),
+        };
+        let attr = if times.is_null() {
+            FileSetAttr {
+                atime: Some(now),
+                mtime: Some(now),
+                ..Default::default()
+            }
+        } else {
+            let times = unsafe { core::slice::from_raw_parts(times, 2) };
+            FileSetAttr {
+                atime: to_time(times[0])?,
+                mtime: to_time(times[1])?,
+                ..Default::default()
+            }
+        };
+        if path.is_null() {
+            File::from_fd(dirfd)?.inner.lock().set_attr(&attr)?;
+            return Ok(0);
+        }
+        let path = char_ptr_to_str(path)?;
+        if dirfd != ctypes::AT_FDCWD && !path.starts_with('/') {
+            return Err(LinuxError::EINVAL); // only `AT_FDCWD` is supported
+        }
+        if flags & ctypes::AT_SYMLINK_NOFOLLOW as c_int != 0 {
+            axfs::fops::set_symlink_attr(path, &attr)?;
+        } else {
+            axfs::fops::set_attr(path, &attr)?;
+        }
         Ok(0)
     })
 }
@@ -215,3 +414,138 @@ pub fn sys_rename(old: *const c_char, new: *const c_char) -> c_int {
         Ok(0)
     })
 }
+
+/// Create a symbolic link `linkpath` which contains the string `target`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_symlink(target: *const c_char, linkpath: *const c_char) -> c_int {
+    syscall_body!(sys_symlink, {
+        let target = char_ptr_to_str(target)?;
+        let linkpath = char_ptr_to_str(linkpath)?;
+        debug!(
+            "sys_symlink <= target: {:?}, linkpath: {:?}",
+            target, linkpath
+        );
+        axfs::api::symlink(target, linkpath)?;
+        Ok(0)
+    })
+}
+
+/// Create a new hard link `new` to the existing file `old`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_link(old: *const c_char, new: *const c_char) -> c_int {
+    syscall_body!(sys_link, {
+        let old_path = char_ptr_to_str(old)?;
+        let new_path = char_ptr_to_str(new)?;
+        debug!("sys_link <= old: {:?}, new: {:?}", old_path, new_path);
+        axfs::api::hard_link(old_path, new_path)?;
+        Ok(0)
+    })
+}
+
+/// Read the contents of the symbolic link `path` into `buf`.
+///
+/// The contents are truncated to `bufsiz` bytes and no terminating null byte
+/// is appended. Return the number of bytes placed in `buf`.
+pub fn sys_readlink(path: *const c_char, buf: *mut c_char, bufsiz: usize) -> ctypes::ssize_t {
+    let path = char_ptr_to_str(path);
+    debug!("sys_readlink <= {:?} {:#x} {}", path, buf as usize, bufsiz);
+    syscall_body!(sys_readlink, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let target = axfs::api::read_link(path?)?;
+        let len = target.len().min(bufsiz);
+        let dst = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, len) };
+        dst.copy_from_slice(&target.as_bytes()[..len]);
+        Ok(len as ctypes::ssize_t)
+    })
+}
+
+/// Remove the name `path` from the filesystem.
+///
+/// If it is a symbolic link, the link itself is removed. Return 0 if the
+/// operation succeeds, otherwise return -1.
+pub fn sys_unlink(path: *const c_char) -> c_int {
+    syscall_body!(sys_unlink, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_unlink <= {:?}", path);
+        axfs::api::remove_file(path)?;
+        Ok(0)
+    })
+}
+
+/// Synchronize the data of the file indicated by `fd` to the disk.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_fsync(fd: c_int) -> c_int {
+    debug!("sys_fsync <= {}", fd);
+    syscall_body!(sys_fsync, {
+        File::from_fd(fd)?.inner.lock().flush()?;
+        Ok(0)
+    })
+}
+
+/// Write all modified data in the caches to the disks.
+pub fn sys_sync() {
+    debug!("sys_sync");
+    if let Err(e) = axfs::fops::sync() {
+        warn!("sys_sync failed: {:?}", e);
+    }
+}
+
+/// Mount the filesystem of type `fstype` from `source` on the directory
+/// `target`.
+///
+/// Only `MS_RDONLY` in `flags` takes effect, and `data` is ignored.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_mount(
+    source: *const c_char,
+    target: *const c_char,
+    fstype: *const c_char,
+    flags: c_ulong,
+    _data: *const c_void,
+) -> c_int {
+    syscall_body!(sys_mount, {
+        let source = if source.is_null() {
+            ""
+        } else {
+            char_ptr_to_str(source)?
+        };
+        let target = char_ptr_to_str(target)?;
+        let fstype = char_ptr_to_str(fstype)?;
+        debug!(
+            "sys_mount <= source: {:?}, target: {:?}, fstype: {:?}, flags: {:#x}",
+            source, target, fstype, flags
+        );
+        let unsupported = (ctypes::MS_REMOUNT | ctypes::MS_BIND | ctypes::MS_MOVE) as c_ulong;
+        if flags & unsupported != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let mut mount_flags = MountFlags::empty();
+        if flags & ctypes::MS_RDONLY as c_ulong != 0 {
+            mount_flags |= MountFlags::RDONLY;
+        }
+        axfs::api::mount(source, target, fstype, mount_flags)?;
+        Ok(0)
+    })
+}
+
+/// Unmount the filesystem mounted on `target`.
+///
+/// `MNT_FORCE` and `MNT_DETACH` in `flags` unmount the filesystem as usual.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_umount2(target: *const c_char, flags: c_int) -> c_int {
+    syscall_body!(sys_umount2, {
+        let target = char_ptr_to_str(target)?;
+        debug!("sys_umount2 <= target: {:?}, flags: {:#x}", target, flags);
+        if flags & !(ctypes::MNT_FORCE | ctypes::MNT_DETACH) as c_int != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        axfs::api::umount(target)?;
+        Ok(0)
+    })
+}
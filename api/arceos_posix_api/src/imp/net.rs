@@ -1,7 +1,8 @@
 use alloc::{sync::Arc, vec, vec::Vec};
 use core::ffi::{c_char, c_int, c_void};
-use core::mem::size_of;
-use core::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::mem::{size_of, size_of_val};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::time::Duration;
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
@@ -12,12 +13,26 @@ use super::fd_ops::FileLike;
 use crate::ctypes;
 use crate::utils::char_ptr_to_str;
 
-pub enum Socket {
+/// The TTL restored by setting `IP_TTL` to -1.
+const DEFAULT_TTL: u8 = 64;
+
+pub struct Socket {
+    /// The address family of the socket, `AF_INET` or `AF_INET6`, in which the
+    /// addresses are returned. Both are dual-stack.
+    domain: u32,
+    inner: SocketInner,
+}
+
+enum SocketInner {
     Udp(Mutex<UdpSocket>),
     Tcp(Mutex<TcpSocket>),
 }
 
 impl Socket {
+    fn new(domain: u32, inner: SocketInner) -> Self {
+        Self { domain, inner }
+    }
+
     fn add_to_fd_table(self) -> LinuxResult<c_int> {
         super::fd_ops::add_file_like(Arc::new(self))
     }
@@ -30,97 +45,97 @@ impl Socket {
     }
 
     fn send(&self, buf: &[u8]) -> LinuxResult<usize> {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().send(buf)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().send(buf)?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().send(buf)?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().send(buf)?),
         }
     }
 
     fn recv(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().recv_from(buf).map(|e| e.0)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf)?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().recv_from(buf).map(|e| e.0)?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf)?),
         }
     }
 
     pub fn poll(&self) -> LinuxResult<PollState> {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().poll()?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().poll()?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().poll()?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().poll()?),
         }
     }
 
     fn local_addr(&self) -> LinuxResult<SocketAddr> {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().local_addr()?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().local_addr()?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().local_addr()?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().local_addr()?),
         }
     }
 
     fn peer_addr(&self) -> LinuxResult<SocketAddr> {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().peer_addr()?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().peer_addr()?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().peer_addr()?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().peer_addr()?),
         }
     }
 
     fn bind(&self, addr: SocketAddr) -> LinuxResult {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().bind(addr)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().bind(addr)?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().bind(addr)?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().bind(addr)?),
         }
     }
 
     fn connect(&self, addr: SocketAddr) -> LinuxResult {
-        match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().connect(addr)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().connect(addr)?),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().connect(addr)?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().connect(addr)?),
         }
     }
 
     fn sendto(&self, buf: &[u8], addr: SocketAddr) -> LinuxResult<usize> {
-        match self {
+        match &self.inner {
             // diff: must bind before sendto
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().send_to(buf, addr)?),
-            Socket::Tcp(_) => Err(LinuxError::EISCONN),
+            SocketInner::Udp(udpsocket) => Ok(udpsocket.lock().send_to(buf, addr)?),
+            SocketInner::Tcp(_) => Err(LinuxError::EISCONN),
         }
     }
 
     fn recvfrom(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<SocketAddr>)> {
-        match self {
+        match &self.inner {
             // diff: must bind before recvfrom
-            Socket::Udp(udpsocket) => Ok(udpsocket
+            SocketInner::Udp(udpsocket) => Ok(udpsocket
                 .lock()
                 .recv_from(buf)
                 .map(|res| (res.0, Some(res.1)))?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf).map(|res| (res, None))?),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf).map(|res| (res, None))?),
         }
     }
 
     fn listen(&self) -> LinuxResult {
-        match self {
-            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().listen()?),
+        match &self.inner {
+            SocketInner::Udp(_) => Err(LinuxError::EOPNOTSUPP),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().listen()?),
         }
     }
 
     fn accept(&self) -> LinuxResult<TcpSocket> {
-        match self {
-            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().accept()?),
+        match &self.inner {
+            SocketInner::Udp(_) => Err(LinuxError::EOPNOTSUPP),
+            SocketInner::Tcp(tcpsocket) => Ok(tcpsocket.lock().accept()?),
         }
     }
 
     fn shutdown(&self) -> LinuxResult {
-        match self {
-            Socket::Udp(udpsocket) => {
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => {
                 let udpsocket = udpsocket.lock();
                 udpsocket.peer_addr()?;
                 udpsocket.shutdown()?;
                 Ok(())
             }
 
-            Socket::Tcp(tcpsocket) => {
+            SocketInner::Tcp(tcpsocket) => {
                 let tcpsocket = tcpsocket.lock();
                 tcpsocket.peer_addr()?;
                 tcpsocket.shutdown()?;
@@ -128,6 +143,118 @@ impl Socket {
             }
         }
     }
+
+    fn set_int_option(&self, level: u32, name: u32, val: c_int) -> LinuxResult {
+        let flag = val != 0;
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => {
+                let udpsocket = udpsocket.lock();
+                match (level, name) {
+                    (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => udpsocket.set_reuse_address(flag),
+                    (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => {
+                        udpsocket.set_recv_buffer_size(val.max(0) as usize)
+                    }
+                    (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => {
+                        udpsocket.set_send_buffer_size(val.max(0) as usize)
+                    }
+                    (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => {} // no effect on datagrams
+                    (ctypes::IPPROTO_IP, ctypes::IP_TTL) => udpsocket.set_ttl(ttl_of(val)?)?,
+                    _ => return Err(LinuxError::ENOPROTOOPT),
+                }
+            }
+            SocketInner::Tcp(tcpsocket) => {
+                let tcpsocket = tcpsocket.lock();
+                match (level, name) {
+                    (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => tcpsocket.set_reuse_address(flag),
+                    (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => {
+                        tcpsocket.set_recv_buffer_size(val.max(0) as usize)?
+                    }
+                    (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => {
+                        tcpsocket.set_send_buffer_size(val.max(0) as usize)?
+                    }
+                    (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => tcpsocket.set_keepalive(flag),
+                    (ctypes::IPPROTO_TCP, ctypes::TCP_NODELAY) => tcpsocket.set_nodelay(flag),
+                    (ctypes::IPPROTO_IP, ctypes::IP_TTL) => tcpsocket.set_ttl(ttl_of(val)?)?,
+                    _ => return Err(LinuxError::ENOPROTOOPT),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn int_option(&self, level: u32, name: u32) -> LinuxResult<c_int> {
+        let val = match &self.inner {
+            SocketInner::Udp(udpsocket) => {
+                let udpsocket = udpsocket.lock();
+                match (level, name) {
+                    (ctypes::SOL_SOCKET, ctypes::SO_TYPE) => ctypes::SOCK_DGRAM as usize,
+                    (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => udpsocket.reuse_address() as _,
+                    (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => udpsocket.recv_buffer_size(),
+                    (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => udpsocket.send_buffer_size(),
+                    (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE | ctypes::SO_ERROR) => 0,
+                    (ctypes::IPPROTO_IP, ctypes::IP_TTL) => udpsocket.ttl() as _,
+                    _ => return Err(LinuxError::ENOPROTOOPT),
+                }
+            }
+            SocketInner::Tcp(tcpsocket) => {
+                let tcpsocket = tcpsocket.lock();
+                match (level, name) {
+                    (ctypes::SOL_SOCKET, ctypes::SO_TYPE) => ctypes::SOCK_STREAM as usize,
+                    (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => tcpsocket.reuse_address() as _,
+                    (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => tcpsocket.recv_buffer_size(),
+                    (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => tcpsocket.send_buffer_size(),
+                    (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => tcpsocket.keepalive() as _,
+                    (ctypes::SOL_SOCKET, ctypes::SO_ERROR) => tcpsocket
+                        .take_error()
+                        .map_or(0, |e| LinuxError::from(e).code() as _),
+                    (ctypes::IPPROTO_TCP, ctypes::TCP_NODELAY) => tcpsocket.nodelay() as _,
+                    (ctypes::IPPROTO_IP, ctypes::IP_TTL) => tcpsocket.ttl() as _,
+                    _ => return Err(LinuxError::ENOPROTOOPT),
+                }
+            }
+        };
+        Ok(val as c_int)
+    }
+
+    fn set_linger(&self, linger: Option<Duration>) -> LinuxResult {
+        match &self.inner {
+            SocketInner::Udp(_) => {} // no effect on datagrams
+            SocketInner::Tcp(tcpsocket) => tcpsocket.lock().set_linger(linger),
+        }
+        Ok(())
+    }
+
+    fn linger(&self) -> Option<Duration> {
+        match &self.inner {
+            SocketInner::Udp(_) => None,
+            SocketInner::Tcp(tcpsocket) => tcpsocket.lock().linger(),
+        }
+    }
+
+    /// Sets the timeout of `SO_RCVTIMEO` or `SO_SNDTIMEO`.
+    fn set_timeout(&self, name: u32, timeout: Option<Duration>) -> LinuxResult {
+        match (&self.inner, name) {
+            (SocketInner::Udp(udpsocket), ctypes::SO_RCVTIMEO) => {
+                udpsocket.lock().set_read_timeout(timeout)?
+            }
+            (SocketInner::Udp(udpsocket), _) => udpsocket.lock().set_write_timeout(timeout)?,
+            (SocketInner::Tcp(tcpsocket), ctypes::SO_RCVTIMEO) => {
+                tcpsocket.lock().set_read_timeout(timeout)?
+            }
+            (SocketInner::Tcp(tcpsocket), _) => tcpsocket.lock().set_write_timeout(timeout)?,
+        }
+        Ok(())
+    }
+
+    /// Returns the timeout of `SO_RCVTIMEO` or `SO_SNDTIMEO`.
+    fn timeout(&self, name: u32) -> Option<Duration> {
+        match (&self.inner, name) {
+            (SocketInner::Udp(udpsocket), ctypes::SO_RCVTIMEO) => udpsocket.lock().read_timeout(),
+            (SocketInner::Udp(udpsocket), _) => udpsocket.lock().write_timeout(),
+            (SocketInner::Tcp(tcpsocket), ctypes::SO_RCVTIMEO) => tcpsocket.lock().read_timeout(),
+            (SocketInner::Tcp(tcpsocket), _) => tcpsocket.lock().write_timeout(),
+        }
+    }
 }
 
 impl FileLike for Socket {
@@ -162,9 +289,9 @@ impl FileLike for Socket {
     }
 
     fn set_nonblocking(&self, nonblock: bool) -> LinuxResult {
-        match self {
-            Socket::Udp(udpsocket) => udpsocket.lock().set_nonblocking(nonblock),
-            Socket::Tcp(tcpsocket) => tcpsocket.lock().set_nonblocking(nonblock),
+        match &self.inner {
+            SocketInner::Udp(udpsocket) => udpsocket.lock().set_nonblocking(nonblock),
+            SocketInner::Tcp(tcpsocket) => tcpsocket.lock().set_nonblocking(nonblock),
         }
         Ok(())
     }
@@ -194,17 +321,105 @@ impl From<ctypes::sockaddr_in> for SocketAddrV4 {
     }
 }
 
-fn into_sockaddr(addr: SocketAddr) -> (ctypes::sockaddr, ctypes::socklen_t) {
+impl From<SocketAddrV6> for ctypes::sockaddr_in6 {
+    fn from(addr: SocketAddrV6) -> ctypes::sockaddr_in6 {
+        ctypes::sockaddr_in6 {
+            sin6_family: ctypes::AF_INET6 as u16,
+            sin6_port: addr.port().to_be(),
+            sin6_flowinfo: addr.flowinfo().to_be(),
+            sin6_addr: ctypes::in6_addr {
+                __in6_union: ctypes::in6_addr__bindgen_ty_1 {
+                    __s6_addr: addr.ip().octets(),
+                },
+            },
+            sin6_scope_id: addr.scope_id(),
+        }
+    }
+}
+
+impl From<ctypes::sockaddr_in6> for SocketAddrV6 {
+    fn from(addr: ctypes::sockaddr_in6) -> SocketAddrV6 {
+        SocketAddrV6::new(
+            // SAFETY: all the fields of the union are plain bytes.
+            Ipv6Addr::from(unsafe { addr.sin6_addr.__in6_union.__s6_addr }),
+            u16::from_be(addr.sin6_port),
+            u32::from_be(addr.sin6_flowinfo),
+            addr.sin6_scope_id,
+        )
+    }
+}
+
+/// Writes `addr` to the buffer `dst` of `*addrlen` bytes, and sets `*addrlen`
+/// to the actual size of the address. The address is truncated if the buffer
+/// is too small.
+///
+/// For `AF_INET6` sockets, IPv4 addresses are written as IPv4-mapped IPv6
+/// addresses (`::ffff:a.b.c.d`), and for `AF_INET` sockets, IPv4-mapped
+/// addresses are written as IPv4 ones.
+unsafe fn write_sockaddr(
+    addr: SocketAddr,
+    domain: u32,
+    dst: *mut ctypes::sockaddr,
+    addrlen: *mut ctypes::socklen_t,
+) {
+    let addr = match addr {
+        SocketAddr::V4(v4) if domain == ctypes::AF_INET6 => {
+            SocketAddrV6::new(v4.ip().to_ipv6_mapped(), v4.port(), 0, 0).into()
+        }
+        SocketAddr::V6(v6) if domain == ctypes::AF_INET => match v6.ip().to_ipv4_mapped() {
+            Some(ip) => SocketAddrV4::new(ip, v6.port()).into(),
+            None => addr,
+        },
+        _ => addr,
+    };
     debug!("    Sockaddr: {}", addr);
-    match addr {
-        SocketAddr::V4(addr) => (
-            unsafe { *(&ctypes::sockaddr_in::from(addr) as *const _ as *const ctypes::sockaddr) },
-            size_of::<ctypes::sockaddr>() as _,
-        ),
-        SocketAddr::V6(_) => panic!("IPv6 is not supported"),
+    let sin;
+    let sin6;
+    let src: &[u8] = match addr {
+        SocketAddr::V4(addr) => {
+            sin = ctypes::sockaddr_in::from(addr);
+            core::slice::from_raw_parts(&sin as *const _ as *const u8, size_of_val(&sin))
+        }
+        SocketAddr::V6(addr) => {
+            sin6 = ctypes::sockaddr_in6::from(addr);
+            core::slice::from_raw_parts(&sin6 as *const _ as *const u8, size_of_val(&sin6))
+        }
+    };
+    let len = src.len().min(*addrlen as usize);
+    core::ptr::copy_nonoverlapping(src.as_ptr(), dst as *mut u8, len);
+    *addrlen = src.len() as _;
+}
+
+/// Converts the value of `IP_TTL`, where -1 means the default TTL.
+fn ttl_of(val: c_int) -> LinuxResult<u8> {
+    match val {
+        -1 => Ok(DEFAULT_TTL),
+        1..=255 => Ok(val as u8),
+        _ => Err(LinuxError::EINVAL),
     }
 }
 
+/// Reads an option value of type `T` from the buffer `optval` of `optlen`
+/// bytes.
+unsafe fn read_optval<T>(optval: *const c_void, optlen: ctypes::socklen_t) -> LinuxResult<T> {
+    if optval.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    if (optlen as usize) < size_of::<T>() {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok((optval as *const T).read_unaligned())
+}
+
+/// Writes the option value `val` to the buffer `optval` of `*optlen` bytes,
+/// and sets `*optlen` to the length written. The value is truncated if the
+/// buffer is too small.
+unsafe fn write_optval<T>(val: &T, optval: *mut c_void, optlen: *mut ctypes::socklen_t) {
+    let len = size_of_val(val).min(*optlen as usize);
+    core::ptr::copy_nonoverlapping(val as *const T as *const u8, optval as *mut u8, len);
+    *optlen = len as _;
+}
+
 fn from_sockaddr(
     addr: *const ctypes::sockaddr,
     addrlen: ctypes::socklen_t,
@@ -212,16 +427,30 @@ fn from_sockaddr(
     if addr.is_null() {
         return Err(LinuxError::EFAULT);
     }
-    if addrlen != size_of::<ctypes::sockaddr>() as _ {
+    if (addrlen as usize) < size_of::<ctypes::sa_family_t>() {
         return Err(LinuxError::EINVAL);
     }
 
-    let mid = unsafe { *(addr as *const ctypes::sockaddr_in) };
-    if mid.sin_family != ctypes::AF_INET as u16 {
-        return Err(LinuxError::EINVAL);
-    }
-
-    let res = SocketAddr::V4(mid.into());
+    let res = match unsafe { (*addr).sa_family } as u32 {
+        ctypes::AF_INET => {
+            if (addrlen as usize) < size_of::<ctypes::sockaddr_in>() {
+                return Err(LinuxError::EINVAL);
+            }
+            SocketAddr::V4(unsafe { *(addr as *const ctypes::sockaddr_in) }.into())
+        }
+        ctypes::AF_INET6 => {
+            if (addrlen as usize) < size_of::<ctypes::sockaddr_in6>() {
+                return Err(LinuxError::EINVAL);
+            }
+            let addr = SocketAddrV6::from(unsafe { *(addr as *const ctypes::sockaddr_in6) });
+            // IPv4-mapped addresses of dual-stack sockets (see `write_sockaddr`)
+            match addr.ip().to_ipv4_mapped() {
+                Some(ip) => SocketAddr::V4(SocketAddrV4::new(ip, addr.port())),
+                None => SocketAddr::V6(addr),
+            }
+        }
+        _ => return Err(LinuxError::EAFNOSUPPORT),
+    };
     debug!("    load sockaddr:{:#x} => {:?}", addr as usize, res);
     Ok(res)
 }
@@ -233,14 +462,17 @@ pub fn sys_socket(domain: c_int, socktype: c_int, protocol: c_int) -> c_int {
     debug!("sys_socket <= {} {} {}", domain, socktype, protocol);
     let (domain, socktype, protocol) = (domain as u32, socktype as u32, protocol as u32);
     syscall_body!(sys_socket, {
+        // The sockets of both families are dual-stack.
         match (domain, socktype, protocol) {
-            (ctypes::AF_INET, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
-            | (ctypes::AF_INET, ctypes::SOCK_STREAM, 0) => {
-                Socket::Tcp(Mutex::new(TcpSocket::new())).add_to_fd_table()
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
+            | (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_STREAM, 0) => {
+                Socket::new(domain, SocketInner::Tcp(Mutex::new(TcpSocket::new())))
+                    .add_to_fd_table()
             }
-            (ctypes::AF_INET, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
-            | (ctypes::AF_INET, ctypes::SOCK_DGRAM, 0) => {
-                Socket::Udp(Mutex::new(UdpSocket::new())).add_to_fd_table()
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
+            | (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_DGRAM, 0) => {
+                Socket::new(domain, SocketInner::Udp(Mutex::new(UdpSocket::new())))
+                    .add_to_fd_table()
             }
             _ => Err(LinuxError::EINVAL),
         }
@@ -356,9 +588,7 @@ pub unsafe fn sys_recvfrom(
 
         let res = socket.recvfrom(buf)?;
         if let Some(addr) = res.1 {
-            unsafe {
-                (*socket_addr, *addrlen) = into_sockaddr(addr);
-            }
+            unsafe { write_sockaddr(addr, socket.domain, socket_addr, addrlen) };
         }
         Ok(res.0)
     })
@@ -419,10 +649,9 @@ pub unsafe fn sys_accept(
         let socket = Socket::from_fd(socket_fd)?;
         let new_socket = socket.accept()?;
         let addr = new_socket.peer_addr()?;
-        let new_fd = Socket::add_to_fd_table(Socket::Tcp(Mutex::new(new_socket)))?;
-        unsafe {
-            (*socket_addr, *socket_len) = into_sockaddr(addr);
-        }
+        let new_socket = Socket::new(socket.domain, SocketInner::Tcp(Mutex::new(new_socket)));
+        let new_fd = new_socket.add_to_fd_table()?;
+        unsafe { write_sockaddr(addr, socket.domain, socket_addr, socket_len) };
         Ok(new_fd)
     })
 }
@@ -441,9 +670,97 @@ pub fn sys_shutdown(
     })
 }
 
+/// Set options on a socket.
+///
+/// Unlike Linux, `SO_RCVBUF` and `SO_SNDBUF` can not resize the buffers of a
+/// connecting or connected TCP socket and fail with `EINVAL`. Set them before
+/// `connect`, or on the listening socket for the accepted connections.
+///
+/// Return 0 if success.
+pub unsafe fn sys_setsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *const c_void,
+    optlen: ctypes::socklen_t,
+) -> c_int {
+    debug!(
+        "sys_setsockopt <= {} {} {} {:#x} {}",
+        socket_fd, level, optname, optval as usize, optlen
+    );
+    syscall_body!(sys_setsockopt, {
+        let socket = Socket::from_fd(socket_fd)?;
+        let (level, optname) = (level as u32, optname as u32);
+        match (level, optname) {
+            (ctypes::SOL_SOCKET, ctypes::SO_LINGER) => {
+                let linger = unsafe { read_optval::<ctypes::linger>(optval, optlen)? };
+                let timeout = Duration::from_secs(linger.l_linger.max(0) as u64);
+                socket.set_linger((linger.l_onoff != 0).then_some(timeout))?;
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_RCVTIMEO | ctypes::SO_SNDTIMEO) => {
+                let tv = unsafe { read_optval::<ctypes::timeval>(optval, optlen)? };
+                if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+                    return Err(LinuxError::EDOM);
+                }
+                // a zero timeout means blocking forever
+                let timeout = Some(Duration::from(tv)).filter(|t| !t.is_zero());
+                socket.set_timeout(optname, timeout)?;
+            }
+            _ => {
+                let val = unsafe { read_optval::<c_int>(optval, optlen)? };
+                socket.set_int_option(level, optname, val)?;
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// Get options on a socket.
+///
+/// Return 0 if success.
+pub unsafe fn sys_getsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> c_int {
+    debug!(
+        "sys_getsockopt <= {} {} {} {:#x} {:#x}",
+        socket_fd, level, optname, optval as usize, optlen as usize
+    );
+    syscall_body!(sys_getsockopt, {
+        if optval.is_null() || optlen.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let socket = Socket::from_fd(socket_fd)?;
+        let (level, optname) = (level as u32, optname as u32);
+        match (level, optname) {
+            (ctypes::SOL_SOCKET, ctypes::SO_LINGER) => {
+                let linger = socket.linger();
+                let val = ctypes::linger {
+                    l_onoff: linger.is_some() as c_int,
+                    l_linger: linger.map_or(0, |t| t.as_secs() as c_int),
+                };
+                unsafe { write_optval(&val, optval, optlen) };
+            }
+            (ctypes::SOL_SOCKET, ctypes::SO_RCVTIMEO | ctypes::SO_SNDTIMEO) => {
+                let timeout = socket.timeout(optname).unwrap_or_default();
+                let val = ctypes::timeval::from(timeout);
+                unsafe { write_optval(&val, optval, optlen) };
+            }
+            _ => {
+                let val = socket.int_option(level, optname)?;
+                unsafe { write_optval(&val, optval, optlen) };
+            }
+        }
+        Ok(0)
+    })
+}
+
 /// Query addresses for a domain name.
 ///
-/// Only IPv4. Ports are always 0. Ignore servname and hint.
+/// Ignore servname and hint.
 /// Results' ai_flags and ai_canonname are 0 or NULL.
 ///
 /// Return address number if success.
@@ -502,7 +819,24 @@ pub unsafe fn sys_getaddrinfo(
                     lock: [0],
                     ref_: 0,
                 },
-                _ => panic!("IPv6 is not supported"),
+                IpAddr::V6(ip) => ctypes::aibuf {
+                    ai: ctypes::addrinfo {
+                        ai_family: ctypes::AF_INET6 as _,
+                        ai_socktype: ctypes::SOCK_STREAM as _,
+                        ai_protocol: ctypes::IPPROTO_TCP as _,
+                        ai_addrlen: size_of::<ctypes::sockaddr_in6>() as _,
+                        ai_addr: core::ptr::null_mut(),
+                        ai_canonname: core::ptr::null_mut(),
+                        ai_next: core::ptr::null_mut(),
+                        ai_flags: 0,
+                    },
+                    sa: ctypes::aibuf_sa {
+                        sin6: SocketAddrV6::new(ip, port, 0, 0).into(),
+                    },
+                    slot: i as i16,
+                    lock: [0],
+                    ref_: 0,
+                },
             };
             out.push(buf);
             out[i].ai.ai_addr =
@@ -546,12 +880,9 @@ pub unsafe fn sys_getsockname(
         if addr.is_null() || addrlen.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        if unsafe { *addrlen } < size_of::<ctypes::sockaddr>() as u32 {
-            return Err(LinuxError::EINVAL);
-        }
-        unsafe {
-            (*addr, *addrlen) = into_sockaddr(Socket::from_fd(sock_fd)?.local_addr()?);
-        }
+        let socket = Socket::from_fd(sock_fd)?;
+        let local_addr = socket.local_addr()?;
+        unsafe { write_sockaddr(local_addr, socket.domain, addr, addrlen) };
         Ok(0)
     })
 }
@@ -570,12 +901,9 @@ pub unsafe fn sys_getpeername(
         if addr.is_null() || addrlen.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        if unsafe { *addrlen } < size_of::<ctypes::sockaddr>() as u32 {
-            return Err(LinuxError::EINVAL);
-        }
-        unsafe {
-            (*addr, *addrlen) = into_sockaddr(Socket::from_fd(sock_fd)?.peer_addr()?);
-        }
+        let socket = Socket::from_fd(sock_fd)?;
+        let peer_addr = socket.peer_addr()?;
+        unsafe { write_sockaddr(peer_addr, socket.domain, addr, addrlen) };
         Ok(0)
     })
 }
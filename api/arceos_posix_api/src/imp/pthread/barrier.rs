@@ -0,0 +1,59 @@
+use crate::ctypes;
+use crate::utils::check_null_mut_ptr;
+
+use axerrno::LinuxError;
+use axsync::Barrier;
+
+use core::ffi::{c_int, c_uint};
+
+use super::{get_boxed, init_boxed, take_boxed};
+
+/// Initialize a barrier which blocks `count` threads.
+pub unsafe fn sys_pthread_barrier_init(
+    barrier: *mut ctypes::pthread_barrier_t,
+    _attr: *const ctypes::pthread_barrierattr_t,
+    count: c_uint,
+) -> c_int {
+    debug!(
+        "sys_pthread_barrier_init <= {:#x} {}",
+        barrier as usize, count
+    );
+    syscall_body!(sys_pthread_barrier_init, {
+        check_null_mut_ptr(barrier)?;
+        if count == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        unsafe { init_boxed(barrier, Barrier::new(count as usize)) };
+        Ok(0)
+    })
+}
+
+/// Destroy a barrier.
+pub unsafe fn sys_pthread_barrier_destroy(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    debug!("sys_pthread_barrier_destroy <= {:#x}", barrier as usize);
+    syscall_body!(sys_pthread_barrier_destroy, {
+        check_null_mut_ptr(barrier)?;
+        match unsafe { take_boxed::<_, Barrier>(barrier) } {
+            Some(_) => Ok(0),
+            None => Err(LinuxError::EINVAL),
+        }
+    })
+}
+
+/// Wait on a barrier until `count` threads have reached it.
+///
+/// Returns `PTHREAD_BARRIER_SERIAL_THREAD` to one (arbitrary) thread, and 0
+/// to the others.
+pub unsafe fn sys_pthread_barrier_wait(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    debug!("sys_pthread_barrier_wait <= {:#x}", barrier as usize);
+    syscall_body!(sys_pthread_barrier_wait, {
+        check_null_mut_ptr(barrier)?;
+        // A barrier has no static initializer, so it must be initialized.
+        let barrier = unsafe { get_boxed::<_, Barrier>(barrier) }.ok_or(LinuxError::EINVAL)?;
+        if barrier.wait().is_leader() {
+            Ok(ctypes::PTHREAD_BARRIER_SERIAL_THREAD)
+        } else {
+            Ok(0)
+        }
+    })
+}
@@ -0,0 +1,241 @@
+use crate::ctypes;
+use crate::utils::{check_null_mut_ptr, check_null_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::WaitQueue;
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+use super::mutex::PthreadMutex;
+use super::{get_or_init_boxed, init_boxed, take_boxed};
+
+/// The clock ID is stored in the low 31 bits of `pthread_condattr_t::__attr`.
+const CONDATTR_CLOCK_MASK: u32 = 0x7fff_ffff;
+
+struct PthreadCond {
+    wq: WaitQueue,
+    // Bumped on every signal, so that a waiter can find it has been signaled
+    // after it unlocked the mutex but before it is blocked.
+    seq: AtomicU32,
+}
+
+impl PthreadCond {
+    const fn new() -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    fn wait(&self, mutex: &PthreadMutex) -> LinuxResult {
+        let seq = self.seq.load(Ordering::Acquire);
+        let count = mutex.unlock_all()?;
+        self.wq
+            .wait_until(|| self.seq.load(Ordering::Acquire) != seq);
+        mutex.relock(count);
+        Ok(())
+    }
+
+    fn timed_wait(&self, mutex: &PthreadMutex, deadline: Duration) -> LinuxResult {
+        let seq = self.seq.load(Ordering::Acquire);
+        let count = mutex.unlock_all()?;
+        let timed_out = self.wait_until_deadline(seq, deadline);
+        mutex.relock(count);
+        if timed_out {
+            Err(LinuxError::ETIMEDOUT)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Waits for a signal newer than `seq`, returns `true` if `deadline` has
+    /// passed before that.
+    #[cfg(feature = "irq")]
+    fn wait_until_deadline(&self, seq: u32, deadline: Duration) -> bool {
+        let signaled = || self.seq.load(Ordering::Acquire) != seq;
+        match deadline.checked_sub(axhal::time::current_time()) {
+            Some(dur) => self.wq.wait_timeout_until(dur, signaled),
+            None => !signaled(),
+        }
+    }
+
+    /// Waits for a signal newer than `seq`, returns `true` if `deadline` has
+    /// passed before that.
+    ///
+    /// Without timer interrupts, it can only poll the deadline.
+    #[cfg(not(feature = "irq"))]
+    fn wait_until_deadline(&self, seq: u32, deadline: Duration) -> bool {
+        loop {
+            if self.seq.load(Ordering::Acquire) != seq {
+                return false;
+            }
+            if axhal::time::current_time() >= deadline {
+                return true;
+            }
+            axtask::yield_now();
+        }
+    }
+
+    fn signal(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        self.wq.notify_one(true);
+    }
+
+    fn broadcast(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        self.wq.notify_all(true);
+    }
+}
+
+fn cond_of(cond: *mut ctypes::pthread_cond_t) -> LinuxResult<&'static PthreadCond> {
+    check_null_mut_ptr(cond)?;
+    Ok(unsafe { get_or_init_boxed(cond, PthreadCond::new) })
+}
+
+fn mutex_of(mutex: *mut ctypes::pthread_mutex_t) -> LinuxResult<&'static PthreadMutex> {
+    check_null_mut_ptr(mutex)?;
+    Ok(unsafe { &*mutex.cast::<PthreadMutex>() })
+}
+
+/// Initialize a condition variable.
+pub unsafe fn sys_pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    _attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    debug!("sys_pthread_cond_init <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_init, {
+        check_null_mut_ptr(cond)?;
+        unsafe { init_boxed(cond, PthreadCond::new()) };
+        Ok(0)
+    })
+}
+
+/// Destroy a condition variable.
+pub unsafe fn sys_pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_destroy <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_destroy, {
+        check_null_mut_ptr(cond)?;
+        drop(unsafe { take_boxed::<_, PthreadCond>(cond) });
+        Ok(0)
+    })
+}
+
+/// Wait on a condition variable, the given mutex is released while waiting.
+pub unsafe fn sys_pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_wait <= {:#x} {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_wait, {
+        cond_of(cond)?.wait(mutex_of(mutex)?)?;
+        Ok(0)
+    })
+}
+
+/// Wait on a condition variable until the absolute time `abstime`.
+///
+/// Both `CLOCK_REALTIME` and `CLOCK_MONOTONIC` are counted from the same time
+/// source, so `abstime` is interpreted the same way for either clock.
+pub unsafe fn sys_pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_timedwait <= {:#x} {:#x} {:#x}",
+        cond as usize, mutex as usize, abstime as usize
+    );
+    syscall_body!(sys_pthread_cond_timedwait, {
+        check_null_ptr(abstime)?;
+        let abstime = unsafe { *abstime };
+        if !(0..1_000_000_000).contains(&abstime.tv_nsec) {
+            return Err(LinuxError::EINVAL);
+        }
+        let deadline = if abstime.tv_sec < 0 {
+            Duration::ZERO
+        } else {
+            Duration::from(abstime)
+        };
+        cond_of(cond)?.timed_wait(mutex_of(mutex)?, deadline)?;
+        Ok(0)
+    })
+}
+
+/// Wake up one task waiting on the condition variable.
+pub unsafe fn sys_pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_signal <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_signal, {
+        cond_of(cond)?.signal();
+        Ok(0)
+    })
+}
+
+/// Wake up all tasks waiting on the condition variable.
+pub unsafe fn sys_pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_broadcast <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_broadcast, {
+        cond_of(cond)?.broadcast();
+        Ok(0)
+    })
+}
+
+/// Initialize a condition variable attributes object with the default
+/// attributes.
+pub unsafe fn sys_pthread_condattr_init(attr: *mut ctypes::pthread_condattr_t) -> c_int {
+    debug!("sys_pthread_condattr_init <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_condattr_init, {
+        check_null_mut_ptr(attr)?;
+        unsafe { attr.write(ctypes::pthread_condattr_t::default()) };
+        Ok(0)
+    })
+}
+
+/// Destroy a condition variable attributes object.
+pub unsafe fn sys_pthread_condattr_destroy(attr: *mut ctypes::pthread_condattr_t) -> c_int {
+    debug!("sys_pthread_condattr_destroy <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_condattr_destroy, {
+        check_null_mut_ptr(attr)?;
+        Ok(0)
+    })
+}
+
+/// Set the clock used by `pthread_cond_timedwait`.
+pub unsafe fn sys_pthread_condattr_setclock(
+    attr: *mut ctypes::pthread_condattr_t,
+    clock_id: ctypes::clockid_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_condattr_setclock <= {:#x} {}",
+        attr as usize, clock_id
+    );
+    syscall_body!(sys_pthread_condattr_setclock, {
+        check_null_mut_ptr(attr)?;
+        match clock_id as u32 {
+            ctypes::CLOCK_REALTIME | ctypes::CLOCK_MONOTONIC => {
+                let attr = unsafe { &mut *attr };
+                attr.__attr = (attr.__attr & !CONDATTR_CLOCK_MASK) | clock_id as u32;
+                Ok(0)
+            }
+            _ => Err(LinuxError::EINVAL),
+        }
+    })
+}
+
+/// Get the clock used by `pthread_cond_timedwait`.
+pub unsafe fn sys_pthread_condattr_getclock(
+    attr: *const ctypes::pthread_condattr_t,
+    clock_id: *mut ctypes::clockid_t,
+) -> c_int {
+    debug!("sys_pthread_condattr_getclock <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_condattr_getclock, {
+        check_null_ptr(attr)?;
+        check_null_mut_ptr(clock_id)?;
+        unsafe { clock_id.write(((*attr).__attr & CONDATTR_CLOCK_MASK) as _) };
+        Ok(0)
+    })
+}
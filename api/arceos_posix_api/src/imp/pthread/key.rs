@@ -0,0 +1,169 @@
+//! Thread-specific data (TSD) keys.
+//!
+//! Each key slot has a sequence number, which is odd while the key is in use
+//! and bumped on both creation and deletion. A thread's value of a key is
+//! tagged with the sequence number when it was set, so the values set before
+//! a key is deleted are never seen through a new key with the same slot.
+
+use crate::ctypes;
+use crate::utils::check_null_mut_ptr;
+
+use axerrno::{LinuxError, LinuxResult};
+use spin::Mutex;
+
+use core::cell::UnsafeCell;
+use core::ffi::{c_int, c_void};
+
+use super::Pthread;
+
+const PTHREAD_KEYS_MAX: usize = ctypes::PTHREAD_KEYS_MAX as usize;
+const PTHREAD_DESTRUCTOR_ITERATIONS: usize = ctypes::PTHREAD_DESTRUCTOR_ITERATIONS as usize;
+
+type Destructor = Option<unsafe extern "C" fn(*mut c_void)>;
+
+#[derive(Clone, Copy)]
+struct KeySlot {
+    seq: usize,
+    destructor: Destructor,
+}
+
+impl KeySlot {
+    const EMPTY: Self = Self {
+        seq: 0,
+        destructor: None,
+    };
+
+    fn in_use(&self) -> bool {
+        self.seq & 1 != 0
+    }
+}
+
+static KEYS: Mutex<[KeySlot; PTHREAD_KEYS_MAX]> = Mutex::new([KeySlot::EMPTY; PTHREAD_KEYS_MAX]);
+
+/// The thread-specific values of a thread.
+pub(super) struct ThreadSpecificData {
+    /// `(sequence number of the key, value)` for each key slot.
+    values: UnsafeCell<[(usize, *mut c_void); PTHREAD_KEYS_MAX]>,
+}
+
+impl ThreadSpecificData {
+    pub const fn new() -> Self {
+        Self {
+            values: UnsafeCell::new([(0, core::ptr::null_mut()); PTHREAD_KEYS_MAX]),
+        }
+    }
+
+    /// Returns the values of the current thread.
+    ///
+    /// Only the owner thread can access its values, so no lock is needed.
+    #[allow(clippy::mut_from_ref)]
+    fn current() -> &'static mut [(usize, *mut c_void); PTHREAD_KEYS_MAX] {
+        let thread = Pthread::current().expect("fail to get current thread");
+        unsafe { &mut *thread.tsd.values.get() }
+    }
+
+    /// Calls the destructors of all non-null values of the current thread.
+    ///
+    /// Destructors may set new values, so it is repeated at most
+    /// `PTHREAD_DESTRUCTOR_ITERATIONS` times.
+    pub fn run_destructors() {
+        for _ in 0..PTHREAD_DESTRUCTOR_ITERATIONS {
+            let mut called = false;
+            for key in 0..PTHREAD_KEYS_MAX {
+                // Do not hold the borrow across the destructor call, which
+                // may access the values again.
+                let (seq, value) =
+                    core::mem::replace(&mut Self::current()[key], (0, core::ptr::null_mut()));
+                if value.is_null() {
+                    continue;
+                }
+                let slot = KEYS.lock()[key];
+                if slot.seq != seq {
+                    continue;
+                }
+                if let Some(destructor) = slot.destructor {
+                    unsafe { destructor(value) };
+                    called = true;
+                }
+            }
+            if !called {
+                break;
+            }
+        }
+    }
+}
+
+fn check_key(key: ctypes::pthread_key_t) -> LinuxResult<(usize, KeySlot)> {
+    let key = key as usize;
+    if key >= PTHREAD_KEYS_MAX {
+        return Err(LinuxError::EINVAL);
+    }
+    let slot = KEYS.lock()[key];
+    if slot.in_use() {
+        Ok((key, slot))
+    } else {
+        Err(LinuxError::EINVAL)
+    }
+}
+
+/// Create a thread-specific data key, with an optional destructor called
+/// on thread exit.
+pub unsafe fn sys_pthread_key_create(
+    key: *mut ctypes::pthread_key_t,
+    destructor: Destructor,
+) -> c_int {
+    debug!("sys_pthread_key_create <= {:#x}", key as usize);
+    syscall_body!(sys_pthread_key_create, {
+        check_null_mut_ptr(key)?;
+        let mut keys = KEYS.lock();
+        let (idx, slot) = keys
+            .iter_mut()
+            .enumerate()
+            .find(|(_, slot)| !slot.in_use())
+            .ok_or(LinuxError::EAGAIN)?;
+        slot.seq = slot.seq.wrapping_add(1);
+        slot.destructor = destructor;
+        unsafe { key.write(idx as _) };
+        Ok(0)
+    })
+}
+
+/// Delete a thread-specific data key.
+///
+/// The destructor is not called, and the values of the key in all threads
+/// become inaccessible.
+pub fn sys_pthread_key_delete(key: ctypes::pthread_key_t) -> c_int {
+    debug!("sys_pthread_key_delete <= {}", key);
+    syscall_body!(sys_pthread_key_delete, {
+        let mut keys = KEYS.lock();
+        match keys.get_mut(key as usize) {
+            Some(slot) if slot.in_use() => {
+                slot.seq = slot.seq.wrapping_add(1);
+                slot.destructor = None;
+                Ok(0)
+            }
+            _ => Err(LinuxError::EINVAL),
+        }
+    })
+}
+
+/// Get the value of the current thread bound to the given key.
+pub fn sys_pthread_getspecific(key: ctypes::pthread_key_t) -> *mut c_void {
+    match check_key(key) {
+        Ok((key, slot)) => match ThreadSpecificData::current()[key] {
+            (seq, value) if seq == slot.seq => value,
+            _ => core::ptr::null_mut(),
+        },
+        Err(_) => core::ptr::null_mut(),
+    }
+}
+
+/// Bind a value of the current thread to the given key.
+pub fn sys_pthread_setspecific(key: ctypes::pthread_key_t, value: *const c_void) -> c_int {
+    debug!("sys_pthread_setspecific <= {} {:#x}", key, value as usize);
+    syscall_body!(sys_pthread_setspecific, {
+        let (key, slot) = check_key(key)?;
+        ThreadSpecificData::current()[key] = (slot.seq, value as *mut c_void);
+        Ok(0)
+    })
+}
@@ -1,75 +1,103 @@
-use alloc::{boxed::Box, collections::BTreeMap, sync::Arc};
+use alloc::{boxed::Box, collections::BTreeMap};
 use core::cell::UnsafeCell;
 use core::ffi::{c_int, c_void};
+use core::sync::atomic::{AtomicPtr, AtomicU8, Ordering};
 
 use axerrno::{LinuxError, LinuxResult};
-use axtask::AxTaskRef;
-use spin::RwLock;
+use axtask::{AxTaskRef, WaitQueue};
+use spin::{Once, RwLock};
 
 use crate::ctypes;
+use crate::utils::{check_null_mut_ptr, check_null_ptr};
 
+pub mod barrier;
+pub mod condvar;
+pub mod key;
 pub mod mutex;
+pub mod once;
+pub mod rwlock;
+
+use self::key::ThreadSpecificData;
 
 lazy_static::lazy_static! {
     static ref TID_TO_PTHREAD: RwLock<BTreeMap<u64, ForceSendSync<ctypes::pthread_t>>> = {
         let mut map = BTreeMap::new();
         let main_task = axtask::current();
         let main_tid = main_task.id().as_u64();
-        let main_thread = Pthread {
-            inner: main_task.as_task_ref().clone(),
-            retval: Arc::new(Packet {
-                result: UnsafeCell::new(core::ptr::null_mut()),
-            }),
-        };
+        let main_thread = Pthread::new();
+        main_thread.task.call_once(|| main_task.as_task_ref().clone());
         let ptr = Box::into_raw(Box::new(main_thread)) as *mut c_void;
         map.insert(main_tid, ForceSendSync(ptr));
         RwLock::new(map)
     };
 }
 
-struct Packet<T> {
-    result: UnsafeCell<T>,
-}
+/// New threads wait on it until they are registered.
+static THREAD_REGISTERED: WaitQueue = WaitQueue::new();
 
-unsafe impl<T> Send for Packet<T> {}
-unsafe impl<T> Sync for Packet<T> {}
+/// The thread can be joined.
+const JOINABLE: u8 = 0;
+/// The thread is detached, its resources are released when it exits.
+const DETACHED: u8 = 1;
+/// The thread has exited and is waiting to be joined.
+const EXITED: u8 = 2;
 
 pub struct Pthread {
-    inner: AxTaskRef,
-    retval: Arc<Packet<*mut c_void>>,
+    /// Set by the creator once the thread is registered.
+    task: Once<AxTaskRef>,
+    retval: UnsafeCell<*mut c_void>,
+    state: AtomicU8,
+    tsd: ThreadSpecificData,
 }
 
 impl Pthread {
+    fn new() -> Self {
+        Self {
+            task: Once::new(),
+            retval: UnsafeCell::new(core::ptr::null_mut()),
+            state: AtomicU8::new(JOINABLE),
+            tsd: ThreadSpecificData::new(),
+        }
+    }
+
     fn create(
-        _attr: *const ctypes::pthread_attr_t,
+        attr: *const ctypes::pthread_attr_t,
         start_routine: extern "C" fn(arg: *mut c_void) -> *mut c_void,
         arg: *mut c_void,
     ) -> LinuxResult<ctypes::pthread_t> {
         let arg_wrapper = ForceSendSync(arg);
+        let stack_size = match unsafe { attr.as_ref() } {
+            Some(attr) if unsafe { attr.__u.__s[0] } != 0 => unsafe { attr.__u.__s[0] as usize },
+            _ => axconfig::TASK_STACK_SIZE,
+        };
 
-        let my_packet: Arc<Packet<*mut c_void>> = Arc::new(Packet {
-            result: UnsafeCell::new(core::ptr::null_mut()),
-        });
-        let their_packet = my_packet.clone();
-
+        let ptr = Box::into_raw(Box::new(Pthread::new())) as *mut c_void;
+        let ptr_wrapper = ForceSendSync(ptr);
         let main = move || {
             let arg = arg_wrapper;
+            let ptr = ptr_wrapper;
+            let thread = unsafe { &*(ptr.0 as *const Pthread) };
+            // The thread may look itself up (e.g. exits), wait until the
+            // creator has registered it.
+            THREAD_REGISTERED.wait_until(|| thread.task.is_completed());
             let ret = start_routine(arg.0);
-            unsafe { *their_packet.result.get() = ret };
-            drop(their_packet);
+            Self::exit_current(ret);
         };
 
-        let task_inner = axtask::spawn(main);
-        let tid = task_inner.id().as_u64();
-        let thread = Pthread {
-            inner: task_inner,
-            retval: my_packet,
-        };
-        let ptr = Box::into_raw(Box::new(thread)) as *mut c_void;
+        let task = axtask::spawn_raw(main, "".into(), stack_size);
+        let tid = task.id().as_u64();
         TID_TO_PTHREAD.write().insert(tid, ForceSendSync(ptr));
+        // Set the task last, as the new thread may exit and free itself
+        // right after that.
+        unsafe { &*(ptr as *const Pthread) }.task.call_once(|| task);
+        THREAD_REGISTERED.notify_all(false);
         Ok(ptr)
     }
 
+    fn task(&self) -> &AxTaskRef {
+        self.task.get().expect("thread is not registered")
+    }
+
     fn current_ptr() -> *mut Pthread {
         let tid = axtask::current().id().as_u64();
         match TID_TO_PTHREAD.read().get(&tid) {
@@ -84,7 +112,18 @@ impl Pthread {
 
     fn exit_current(retval: *mut c_void) -> ! {
         let thread = Self::current().expect("fail to get current thread");
-        unsafe { *thread.retval.result.get() = retval };
+        unsafe { *thread.retval.get() = retval };
+        ThreadSpecificData::run_destructors();
+        if thread
+            .state
+            .compare_exchange(JOINABLE, EXITED, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // Detached, no one will join it, so release the resources now.
+            let tid = thread.task().id().as_u64();
+            let ptr = TID_TO_PTHREAD.write().remove(&tid).unwrap().0;
+            drop(unsafe { Box::from_raw(ptr as *mut Pthread) });
+        }
         axtask::exit(0);
     }
 
@@ -92,15 +131,97 @@ impl Pthread {
         if core::ptr::eq(ptr, Self::current_ptr() as _) {
             return Err(LinuxError::EDEADLK);
         }
+        let state = unsafe { &*(ptr as *const Pthread) }
+            .state
+            .load(Ordering::Acquire);
+        if state == DETACHED {
+            return Err(LinuxError::EINVAL);
+        }
 
         let thread = unsafe { Box::from_raw(ptr as *mut Pthread) };
-        thread.inner.join();
-        let tid = thread.inner.id().as_u64();
-        let retval = unsafe { *thread.retval.result.get() };
+        thread.task().join();
+        let tid = thread.task().id().as_u64();
+        let retval = unsafe { *thread.retval.get() };
         TID_TO_PTHREAD.write().remove(&tid);
         drop(thread);
         Ok(retval)
     }
+
+    fn detach(ptr: ctypes::pthread_t) -> LinuxResult {
+        let thread = unsafe { &*(ptr as *const Pthread) };
+        match thread
+            .state
+            .compare_exchange(JOINABLE, DETACHED, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => Ok(()),
+            // Already exited, just reclaim it like a join.
+            Err(EXITED) => Self::join(ptr).map(|_| ()),
+            Err(_) => Err(LinuxError::EINVAL),
+        }
+    }
+}
+
+// Condition variables, read-write locks and barriers are too large to be
+// embedded in their C types, and their C static initializers are all zeros.
+// So they are allocated on the heap, and the pointer is stored in the first
+// word of the C object.
+
+/// Returns the object stored in `obj`, allocates one with `init` if `obj` is
+/// statically initialized.
+unsafe fn get_or_init_boxed<C, T>(obj: *mut C, init: impl FnOnce() -> T) -> &'static T {
+    let slot = unsafe { &*(obj as *const AtomicPtr<T>) };
+    let mut ptr = slot.load(Ordering::Acquire);
+    if ptr.is_null() {
+        let new = Box::into_raw(Box::new(init()));
+        match slot.compare_exchange(ptr, new, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => ptr = new,
+            Err(other) => {
+                // Initialized by others at the same time.
+                drop(unsafe { Box::from_raw(new) });
+                ptr = other;
+            }
+        }
+    }
+    unsafe { &*ptr }
+}
+
+/// Returns the object stored in `obj`, or `None` if it is not initialized.
+unsafe fn get_boxed<C, T>(obj: *mut C) -> Option<&'static T> {
+    unsafe {
+        (*(obj as *const AtomicPtr<T>))
+            .load(Ordering::Acquire)
+            .as_ref()
+    }
+}
+
+/// Stores a newly allocated `value` in `obj`, ignoring its old content.
+unsafe fn init_boxed<C, T>(obj: *mut C, value: T) {
+    let ptr = Box::into_raw(Box::new(value));
+    unsafe { obj.cast::<AtomicPtr<T>>().write(AtomicPtr::new(ptr)) };
+}
+
+/// Takes the object stored in `obj` out, leaving `obj` statically
+/// initialized.
+unsafe fn take_boxed<C, T>(obj: *mut C) -> Option<Box<T>> {
+    let ptr =
+        unsafe { (*(obj as *const AtomicPtr<T>)).swap(core::ptr::null_mut(), Ordering::AcqRel) };
+    if ptr.is_null() {
+        None
+    } else {
+        Some(unsafe { Box::from_raw(ptr) })
+    }
+}
+
+/// Finds the task of the thread with the given thread ID.
+pub(crate) fn find_task(tid: u64) -> Option<AxTaskRef> {
+    let curr = axtask::current();
+    if curr.id().as_u64() == tid {
+        return Some(curr.as_task_ref().clone());
+    }
+    TID_TO_PTHREAD
+        .read()
+        .get(&tid)
+        .map(|ptr| unsafe { &*(ptr.0 as *const Pthread) }.task().clone())
 }
 
 /// Returns the `pthread` struct of current thread.
@@ -147,6 +268,93 @@ pub unsafe fn sys_pthread_join(thread: ctypes::pthread_t, retval: *mut *mut c_vo
     })
 }
 
+/// Marks the given thread as detached, its resources are released
+/// automatically when it exits.
+pub unsafe fn sys_pthread_detach(thread: ctypes::pthread_t) -> c_int {
+    debug!("sys_pthread_detach <= {:#x}", thread as usize);
+    syscall_body!(sys_pthread_detach, {
+        check_null_mut_ptr(thread)?;
+        Pthread::detach(thread)?;
+        Ok(0)
+    })
+}
+
+/// Initialize a thread attributes object with the default attributes.
+pub unsafe fn sys_pthread_attr_init(attr: *mut ctypes::pthread_attr_t) -> c_int {
+    debug!("sys_pthread_attr_init <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_attr_init, {
+        check_null_mut_ptr(attr)?;
+        let mut new_attr = ctypes::pthread_attr_t::default();
+        unsafe {
+            new_attr.__u.__s[0] = axconfig::TASK_STACK_SIZE as _;
+            attr.write(new_attr);
+        }
+        Ok(0)
+    })
+}
+
+/// Destroy a thread attributes object.
+pub unsafe fn sys_pthread_attr_destroy(attr: *mut ctypes::pthread_attr_t) -> c_int {
+    debug!("sys_pthread_attr_destroy <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_attr_destroy, {
+        check_null_mut_ptr(attr)?;
+        Ok(0)
+    })
+}
+
+/// Get the stack size attribute.
+pub unsafe fn sys_pthread_attr_getstacksize(
+    attr: *const ctypes::pthread_attr_t,
+    stacksize: *mut usize,
+) -> c_int {
+    debug!("sys_pthread_attr_getstacksize <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_attr_getstacksize, {
+        check_null_ptr(attr)?;
+        check_null_mut_ptr(stacksize)?;
+        unsafe { stacksize.write((*attr).__u.__s[0] as usize) };
+        Ok(0)
+    })
+}
+
+/// Set the stack size attribute, which should be at least
+/// `PTHREAD_STACK_MIN`.
+pub unsafe fn sys_pthread_attr_setstacksize(
+    attr: *mut ctypes::pthread_attr_t,
+    stacksize: usize,
+) -> c_int {
+    debug!(
+        "sys_pthread_attr_setstacksize <= {:#x} {}",
+        attr as usize, stacksize
+    );
+    syscall_body!(sys_pthread_attr_setstacksize, {
+        check_null_mut_ptr(attr)?;
+        if stacksize < ctypes::PTHREAD_STACK_MIN as usize {
+            return Err(LinuxError::EINVAL);
+        }
+        unsafe { (*attr).__u.__s[0] = stacksize as _ };
+        Ok(0)
+    })
+}
+
+/// Sets the CPU affinity of the given thread.
+pub unsafe fn sys_pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_setaffinity_np <= {:#x} {} {:#x}",
+        thread as usize, cpusetsize, cpuset as usize
+    );
+    syscall_body!(sys_pthread_setaffinity_np, {
+        check_null_mut_ptr(thread)?;
+        let cpumask = unsafe { crate::imp::task::cpu_set_to_mask(cpusetsize, cpuset)? };
+        let thread = unsafe { &*(thread as *const Pthread) };
+        crate::imp::task::set_task_affinity(thread.task(), cpumask)?;
+        Ok(0)
+    })
+}
+
 #[derive(Clone, Copy)]
 struct ForceSendSync<T>(T);
 
@@ -1,52 +1,138 @@
-use crate::{ctypes, utils::check_null_mut_ptr};
+use crate::ctypes;
+use crate::utils::{check_null_mut_ptr, check_null_ptr};
 
-use axerrno::LinuxResult;
+use axerrno::{LinuxError, LinuxResult};
 use axsync::Mutex;
 
 use core::ffi::c_int;
 use core::mem::{size_of, ManuallyDrop};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 static_assertions::const_assert_eq!(
     size_of::<PthreadMutex>(),
     size_of::<ctypes::pthread_mutex_t>()
 );
 
+const PTHREAD_MUTEX_NORMAL: c_int = ctypes::PTHREAD_MUTEX_NORMAL as _;
+const PTHREAD_MUTEX_RECURSIVE: c_int = ctypes::PTHREAD_MUTEX_RECURSIVE as _;
+const PTHREAD_MUTEX_ERRORCHECK: c_int = ctypes::PTHREAD_MUTEX_ERRORCHECK as _;
+
+/// The mutex type is stored in the low bits of `pthread_mutexattr_t::__attr`.
+const MUTEXATTR_TYPE_MASK: u32 = 0x3;
+
 #[repr(C)]
-pub struct PthreadMutex(Mutex<()>);
+pub struct PthreadMutex {
+    inner: Mutex<()>,
+    kind: c_int,
+    /// The recursion depth of a recursive mutex, excluding the first lock.
+    count: AtomicUsize,
+}
 
 impl PthreadMutex {
-    const fn new() -> Self {
-        Self(Mutex::new(()))
+    const fn new(kind: c_int) -> Self {
+        Self {
+            inner: Mutex::new(()),
+            kind,
+            count: AtomicUsize::new(0),
+        }
     }
 
     fn lock(&self) -> LinuxResult {
-        let _guard = ManuallyDrop::new(self.0.lock());
+        if self.inner.is_owned_by_current() {
+            match self.kind {
+                PTHREAD_MUTEX_RECURSIVE => {
+                    self.count.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                PTHREAD_MUTEX_ERRORCHECK => return Err(LinuxError::EDEADLK),
+                _ => {}
+            }
+        }
+        let _guard = ManuallyDrop::new(self.inner.lock());
         Ok(())
     }
 
+    fn try_lock(&self) -> LinuxResult {
+        if self.kind == PTHREAD_MUTEX_RECURSIVE && self.inner.is_owned_by_current() {
+            self.count.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        match self.inner.try_lock() {
+            Some(guard) => {
+                let _guard = ManuallyDrop::new(guard);
+                Ok(())
+            }
+            None => Err(LinuxError::EBUSY),
+        }
+    }
+
     fn unlock(&self) -> LinuxResult {
-        unsafe { self.0.force_unlock() };
+        if self.kind != PTHREAD_MUTEX_NORMAL && !self.inner.is_owned_by_current() {
+            return Err(LinuxError::EPERM);
+        }
+        if self.kind == PTHREAD_MUTEX_RECURSIVE && self.count.load(Ordering::Relaxed) > 0 {
+            self.count.fetch_sub(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        unsafe { self.inner.force_unlock() };
         Ok(())
     }
+
+    /// Releases the mutex completely, returning the recursion depth so that
+    /// it can be restored by [`relock`](Self::relock) (used by condition
+    /// variables).
+    pub(super) fn unlock_all(&self) -> LinuxResult<usize> {
+        if self.kind != PTHREAD_MUTEX_NORMAL && !self.inner.is_owned_by_current() {
+            return Err(LinuxError::EPERM);
+        }
+        let count = self.count.swap(0, Ordering::Relaxed);
+        unsafe { self.inner.force_unlock() };
+        Ok(count)
+    }
+
+    /// Re-acquires the mutex released by [`unlock_all`](Self::unlock_all).
+    pub(super) fn relock(&self, count: usize) {
+        let _guard = ManuallyDrop::new(self.inner.lock());
+        self.count.store(count, Ordering::Relaxed);
+    }
 }
 
 /// Initialize a mutex.
-pub fn sys_pthread_mutex_init(
+pub unsafe fn sys_pthread_mutex_init(
     mutex: *mut ctypes::pthread_mutex_t,
-    _attr: *const ctypes::pthread_mutexattr_t,
+    attr: *const ctypes::pthread_mutexattr_t,
 ) -> c_int {
     debug!("sys_pthread_mutex_init <= {:#x}", mutex as usize);
     syscall_body!(sys_pthread_mutex_init, {
         check_null_mut_ptr(mutex)?;
+        let kind = if attr.is_null() {
+            PTHREAD_MUTEX_NORMAL
+        } else {
+            unsafe { ((*attr).__attr & MUTEXATTR_TYPE_MASK) as c_int }
+        };
         unsafe {
-            mutex.cast::<PthreadMutex>().write(PthreadMutex::new());
+            mutex.cast::<PthreadMutex>().write(PthreadMutex::new(kind));
         }
         Ok(0)
     })
 }
 
+/// Destroy a mutex.
+pub unsafe fn sys_pthread_mutex_destroy(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    debug!("sys_pthread_mutex_destroy <= {:#x}", mutex as usize);
+    syscall_body!(sys_pthread_mutex_destroy, {
+        check_null_mut_ptr(mutex)?;
+        let mutex = mutex.cast::<PthreadMutex>();
+        if unsafe { (*mutex).inner.is_locked() } {
+            return Err(LinuxError::EBUSY);
+        }
+        unsafe { core::ptr::drop_in_place(mutex) };
+        Ok(0)
+    })
+}
+
 /// Lock the given mutex.
-pub fn sys_pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+pub unsafe fn sys_pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     debug!("sys_pthread_mutex_lock <= {:#x}", mutex as usize);
     syscall_body!(sys_pthread_mutex_lock, {
         check_null_mut_ptr(mutex)?;
@@ -57,8 +143,20 @@ pub fn sys_pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     })
 }
 
+/// Try to lock the given mutex without blocking.
+pub unsafe fn sys_pthread_mutex_trylock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    debug!("sys_pthread_mutex_trylock <= {:#x}", mutex as usize);
+    syscall_body!(sys_pthread_mutex_trylock, {
+        check_null_mut_ptr(mutex)?;
+        unsafe {
+            (*mutex.cast::<PthreadMutex>()).try_lock()?;
+        }
+        Ok(0)
+    })
+}
+
 /// Unlock the given mutex.
-pub fn sys_pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+pub unsafe fn sys_pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     debug!("sys_pthread_mutex_unlock <= {:#x}", mutex as usize);
     syscall_body!(sys_pthread_mutex_unlock, {
         check_null_mut_ptr(mutex)?;
@@ -68,3 +166,86 @@ pub fn sys_pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
         Ok(0)
     })
 }
+
+/// Initialize a mutex attributes object with the default attributes.
+pub unsafe fn sys_pthread_mutexattr_init(attr: *mut ctypes::pthread_mutexattr_t) -> c_int {
+    debug!("sys_pthread_mutexattr_init <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_mutexattr_init, {
+        check_null_mut_ptr(attr)?;
+        unsafe { attr.write(ctypes::pthread_mutexattr_t::default()) };
+        Ok(0)
+    })
+}
+
+/// Destroy a mutex attributes object.
+pub unsafe fn sys_pthread_mutexattr_destroy(attr: *mut ctypes::pthread_mutexattr_t) -> c_int {
+    debug!("sys_pthread_mutexattr_destroy <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_mutexattr_destroy, {
+        check_null_mut_ptr(attr)?;
+        Ok(0)
+    })
+}
+
+/// Set the mutex type attribute.
+pub unsafe fn sys_pthread_mutexattr_settype(
+    attr: *mut ctypes::pthread_mutexattr_t,
+    kind: c_int,
+) -> c_int {
+    debug!(
+        "sys_pthread_mutexattr_settype <= {:#x} {}",
+        attr as usize, kind
+    );
+    syscall_body!(sys_pthread_mutexattr_settype, {
+        check_null_mut_ptr(attr)?;
+        match kind {
+            PTHREAD_MUTEX_NORMAL | PTHREAD_MUTEX_RECURSIVE | PTHREAD_MUTEX_ERRORCHECK => {
+                let attr = unsafe { &mut *attr };
+                attr.__attr = (attr.__attr & !MUTEXATTR_TYPE_MASK) | kind as u32;
+                Ok(0)
+            }
+            _ => Err(LinuxError::EINVAL),
+        }
+    })
+}
+
+/// Get the mutex type attribute.
+pub unsafe fn sys_pthread_mutexattr_gettype(
+    attr: *const ctypes::pthread_mutexattr_t,
+    kind: *mut c_int,
+) -> c_int {
+    debug!("sys_pthread_mutexattr_gettype <= {:#x}", attr as usize);
+    syscall_body!(sys_pthread_mutexattr_gettype, {
+        check_null_ptr(attr)?;
+        check_null_mut_ptr(kind)?;
+        unsafe { kind.write(((*attr).__attr & MUTEXATTR_TYPE_MASK) as c_int) };
+        Ok(0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initializer_matches_layout() {
+        const N: usize = size_of::<PthreadMutex>() / size_of::<usize>();
+        // Write into a zeroed buffer so that padding bytes compare as zero,
+        // as they do in the C initializer.
+        let mut words = [0usize; N];
+        unsafe {
+            words
+                .as_mut_ptr()
+                .cast::<PthreadMutex>()
+                .write(PthreadMutex::new(PTHREAD_MUTEX_NORMAL))
+        };
+        let init = env!("AX_PTHREAD_MUTEX_INIT");
+        let expected = init
+            .trim_matches(['{', '}'])
+            .split(',')
+            .map(|w| w.trim().parse::<usize>().unwrap());
+        assert!(
+            words.iter().copied().eq(expected),
+            "PTHREAD_MUTEX_INITIALIZER {init} does not match PthreadMutex::new(): {words:?}"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ctypes;
+use crate::utils::check_null_mut_ptr;
+
+use axtask::WaitQueue;
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+const INCOMPLETE: i32 = 0;
+const RUNNING: i32 = 1;
+const COMPLETE: i32 = 2;
+
+/// Threads waiting for others to complete `init_routine`.
+///
+/// `pthread_once_t` is only an `int`, so the wait queue is shared by all
+/// `once_control`s.
+static ONCE_WQ: WaitQueue = WaitQueue::new();
+
+/// Run `init_routine` once and only once for the given `once_control`.
+///
+/// Threads calling it while another thread is running `init_routine` will
+/// not return until it is completed.
+pub unsafe fn sys_pthread_once(
+    once_control: *mut ctypes::pthread_once_t,
+    init_routine: extern "C" fn(),
+) -> c_int {
+    debug!("sys_pthread_once <= {:#x}", once_control as usize);
+    syscall_body!(sys_pthread_once, {
+        check_null_mut_ptr(once_control)?;
+        let state = unsafe { AtomicI32::from_ptr(once_control) };
+        match state.compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire) {
+            Ok(_) => {
+                init_routine();
+                state.store(COMPLETE, Ordering::Release);
+                ONCE_WQ.notify_all(false);
+            }
+            Err(COMPLETE) => {}
+            Err(_) => ONCE_WQ.wait_until(|| state.load(Ordering::Acquire) == COMPLETE),
+        }
+        Ok(0)
+    })
+}
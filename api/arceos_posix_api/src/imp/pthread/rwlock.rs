@@ -0,0 +1,99 @@
+use crate::ctypes;
+use crate::utils::check_null_mut_ptr;
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::RwLock;
+
+use core::ffi::c_int;
+use core::mem::ManuallyDrop;
+
+use super::{get_or_init_boxed, init_boxed, take_boxed};
+
+type PthreadRwLock = RwLock<()>;
+
+fn rwlock_of(rwlock: *mut ctypes::pthread_rwlock_t) -> LinuxResult<&'static PthreadRwLock> {
+    check_null_mut_ptr(rwlock)?;
+    Ok(unsafe { get_or_init_boxed(rwlock, || RwLock::new(())) })
+}
+
+/// Initialize a read-write lock.
+pub unsafe fn sys_pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    _attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    debug!("sys_pthread_rwlock_init <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_init, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe { init_boxed(rwlock, RwLock::new(())) };
+        Ok(0)
+    })
+}
+
+/// Destroy a read-write lock.
+pub unsafe fn sys_pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_destroy <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_destroy, {
+        if rwlock_of(rwlock)?.is_locked() {
+            return Err(LinuxError::EBUSY);
+        }
+        drop(unsafe { take_boxed::<_, PthreadRwLock>(rwlock) });
+        Ok(0)
+    })
+}
+
+/// Lock a read-write lock for reading.
+pub unsafe fn sys_pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_rdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_rdlock, {
+        let _guard = ManuallyDrop::new(rwlock_of(rwlock)?.read());
+        Ok(0)
+    })
+}
+
+/// Try to lock a read-write lock for reading without blocking.
+pub unsafe fn sys_pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_tryrdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_tryrdlock, {
+        let guard = rwlock_of(rwlock)?.try_read().ok_or(LinuxError::EBUSY)?;
+        let _guard = ManuallyDrop::new(guard);
+        Ok(0)
+    })
+}
+
+/// Lock a read-write lock for writing.
+pub unsafe fn sys_pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_wrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_wrlock, {
+        let _guard = ManuallyDrop::new(rwlock_of(rwlock)?.write());
+        Ok(0)
+    })
+}
+
+/// Try to lock a read-write lock for writing without blocking.
+pub unsafe fn sys_pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_trywrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_trywrlock, {
+        let guard = rwlock_of(rwlock)?.try_write().ok_or(LinuxError::EBUSY)?;
+        let _guard = ManuallyDrop::new(guard);
+        Ok(0)
+    })
+}
+
+/// Unlock a read-write lock, which is held for either reading or writing.
+pub unsafe fn sys_pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_unlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_unlock, {
+        let rwlock = rwlock_of(rwlock)?;
+        if !rwlock.is_locked() {
+            return Err(LinuxError::EPERM);
+        }
+        // The lock is held by the caller, so the writer bit cannot change
+        // under us.
+        if rwlock.is_write_locked() {
+            unsafe { rwlock.force_write_unlock() };
+        } else {
+            unsafe { rwlock.force_read_unlock() };
+        }
+        Ok(0)
+    })
+}
@@ -1,5 +1,12 @@
 use core::ffi::c_int;
 
+#[cfg(feature = "multitask")]
+use {
+    crate::ctypes,
+    axerrno::{LinuxError, LinuxResult},
+    axtask::CpuMask,
+};
+
 /// Relinquish the CPU, and switches to another task.
 ///
 /// For single-threaded configuration (`multitask` feature is disabled), we just
@@ -33,8 +40,71 @@ pub fn sys_getpid() -> c_int {
 /// Exit current task
 pub fn sys_exit(exit_code: c_int) -> ! {
     debug!("sys_exit <= {}", exit_code);
+    #[cfg(feature = "fs")]
+    super::fs::sys_sync();
     #[cfg(feature = "multitask")]
     axtask::exit(exit_code);
     #[cfg(not(feature = "multitask"))]
     axhal::misc::terminate();
 }
+
+/// Converts the `cpu_set_t` from the user to a [`CpuMask`].
+///
+/// Only the first word of the set is used, as `CpuMask` cannot hold more CPUs.
+#[cfg(feature = "multitask")]
+pub(crate) unsafe fn cpu_set_to_mask(
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> LinuxResult<CpuMask> {
+    crate::utils::check_null_ptr(cpuset)?;
+    if cpusetsize < core::mem::size_of::<usize>() {
+        return Err(LinuxError::EINVAL);
+    }
+    let bits = unsafe { (*cpuset).__bits[0] } as usize;
+    let cpumask = CpuMask::from_raw_bits(bits);
+    if cpumask.is_empty() {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(cpumask)
+}
+
+/// Sets the CPU affinity of the given task, the current task will be migrated
+/// immediately if needed.
+#[cfg(feature = "multitask")]
+pub(crate) fn set_task_affinity(task: &axtask::AxTaskRef, cpumask: CpuMask) -> LinuxResult {
+    let ok = if task.id() == axtask::current().id() {
+        axtask::set_affinity(cpumask)
+    } else {
+        task.set_cpumask(cpumask)
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(LinuxError::EINVAL)
+    }
+}
+
+/// Set the CPU affinity of the thread with ID `pid` to `mask`.
+///
+/// If `pid` is zero, the current thread is used.
+#[cfg(feature = "multitask")]
+pub unsafe fn sys_sched_setaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    mask: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!(
+        "sys_sched_setaffinity <= {} {} {:#x}",
+        pid, cpusetsize, mask as usize
+    );
+    syscall_body!(sys_sched_setaffinity, {
+        let cpumask = unsafe { cpu_set_to_mask(cpusetsize, mask)? };
+        let task = if pid == 0 {
+            axtask::current().as_task_ref().clone()
+        } else {
+            super::pthread::find_task(pid as u64).ok_or(LinuxError::ESRCH)?
+        };
+        set_task_affinity(&task, cpumask)?;
+        Ok(0)
+    })
+}
@@ -40,7 +40,11 @@ pub use imp::time::{sys_clock_gettime, sys_nanosleep};
 #[cfg(feature = "fd")]
 pub use imp::fd_ops::{sys_close, sys_dup, sys_dup2, sys_fcntl};
 #[cfg(feature = "fs")]
-pub use imp::fs::{sys_fstat, sys_getcwd, sys_lseek, sys_lstat, sys_open, sys_rename, sys_stat};
+pub use imp::fs::{
+    sys_chmod, sys_chown, sys_fchmod, sys_fchown, sys_fstat, sys_fstatfs, sys_fsync, sys_getcwd,
+    sys_lchown, sys_link, sys_lseek, sys_lstat, sys_mount, sys_open, sys_readlink, sys_rename,
+    sys_stat, sys_statfs, sys_symlink, sys_sync, sys_umount2, sys_unlink, sys_utimensat,
+};
 #[cfg(feature = "select")]
 pub use imp::io_mpx::sys_select;
 #[cfg(feature = "epoll")]
@@ -48,14 +52,46 @@ pub use imp::io_mpx::{sys_epoll_create, sys_epoll_ctl, sys_epoll_wait};
 #[cfg(feature = "net")]
 pub use imp::net::{
     sys_accept, sys_bind, sys_connect, sys_freeaddrinfo, sys_getaddrinfo, sys_getpeername,
-    sys_getsockname, sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto, sys_shutdown,
-    sys_socket,
+    sys_getsockname, sys_getsockopt, sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto,
+    sys_setsockopt, sys_shutdown, sys_socket,
 };
 #[cfg(feature = "pipe")]
 pub use imp::pipe::sys_pipe;
 #[cfg(feature = "multitask")]
+pub use imp::pthread::barrier::{
+    sys_pthread_barrier_destroy, sys_pthread_barrier_init, sys_pthread_barrier_wait,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::condvar::{
+    sys_pthread_cond_broadcast, sys_pthread_cond_destroy, sys_pthread_cond_init,
+    sys_pthread_cond_signal, sys_pthread_cond_timedwait, sys_pthread_cond_wait,
+    sys_pthread_condattr_destroy, sys_pthread_condattr_getclock, sys_pthread_condattr_init,
+    sys_pthread_condattr_setclock,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::key::{
+    sys_pthread_getspecific, sys_pthread_key_create, sys_pthread_key_delete,
+    sys_pthread_setspecific,
+};
+#[cfg(feature = "multitask")]
 pub use imp::pthread::mutex::{
-    sys_pthread_mutex_init, sys_pthread_mutex_lock, sys_pthread_mutex_unlock,
+    sys_pthread_mutex_destroy, sys_pthread_mutex_init, sys_pthread_mutex_lock,
+    sys_pthread_mutex_trylock, sys_pthread_mutex_unlock, sys_pthread_mutexattr_destroy,
+    sys_pthread_mutexattr_gettype, sys_pthread_mutexattr_init, sys_pthread_mutexattr_settype,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::once::sys_pthread_once;
+#[cfg(feature = "multitask")]
+pub use imp::pthread::rwlock::{
+    sys_pthread_rwlock_destroy, sys_pthread_rwlock_init, sys_pthread_rwlock_rdlock,
+    sys_pthread_rwlock_tryrdlock, sys_pthread_rwlock_trywrlock, sys_pthread_rwlock_unlock,
+    sys_pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::{
+    sys_pthread_attr_destroy, sys_pthread_attr_getstacksize, sys_pthread_attr_init,
+    sys_pthread_attr_setstacksize, sys_pthread_create, sys_pthread_detach, sys_pthread_exit,
+    sys_pthread_join, sys_pthread_self, sys_pthread_setaffinity_np,
 };
 #[cfg(feature = "multitask")]
-pub use imp::pthread::{sys_pthread_create, sys_pthread_exit, sys_pthread_join, sys_pthread_self};
+pub use imp::task::sys_sched_setaffinity;
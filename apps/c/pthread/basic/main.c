@@ -1,7 +1,9 @@
 #include <assert.h>
 #include <pthread.h>
 #include <stdio.h>
+#include <errno.h>
 #include <stdlib.h>
+#include <time.h>
 #include <unistd.h>
 
 void *ThreadFunc1(void *arg)
@@ -117,6 +119,202 @@ void test_mutex()
     assert(data == NUM_THREADS);
 }
 
+void test_mutex_types()
+{
+    pthread_mutexattr_t attr;
+    pthread_mutex_t m;
+    int type;
+
+    pthread_mutexattr_init(&attr);
+    pthread_mutexattr_settype(&attr, PTHREAD_MUTEX_RECURSIVE);
+    pthread_mutexattr_gettype(&attr, &type);
+    assert(type == PTHREAD_MUTEX_RECURSIVE);
+    pthread_mutex_init(&m, &attr);
+    assert(pthread_mutex_lock(&m) == 0);
+    assert(pthread_mutex_lock(&m) == 0);
+    assert(pthread_mutex_trylock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_destroy(&m) == EBUSY);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == EPERM);
+    assert(pthread_mutex_destroy(&m) == 0);
+
+    pthread_mutexattr_settype(&attr, PTHREAD_MUTEX_ERRORCHECK);
+    pthread_mutex_init(&m, &attr);
+    assert(pthread_mutex_lock(&m) == 0);
+    assert(pthread_mutex_lock(&m) == EDEADLK);
+    assert(pthread_mutex_trylock(&m) == EBUSY);
+    assert(pthread_mutex_unlock(&m) == 0);
+    assert(pthread_mutex_unlock(&m) == EPERM);
+    assert(pthread_mutex_destroy(&m) == 0);
+    pthread_mutexattr_destroy(&attr);
+
+    puts("test_mutex_types: OK");
+}
+
+static pthread_cond_t cond = PTHREAD_COND_INITIALIZER;
+static int ready = 0;
+
+void *ThreadFunc4(void *arg)
+{
+    pthread_mutex_lock(&lock);
+    ready = 1;
+    pthread_cond_signal(&cond);
+    pthread_mutex_unlock(&lock);
+    return NULL;
+}
+
+void test_cond()
+{
+    pthread_t t;
+    struct timespec ts;
+
+    pthread_create(&t, NULL, ThreadFunc4, NULL);
+    pthread_mutex_lock(&lock);
+    while (!ready) pthread_cond_wait(&cond, &lock);
+    pthread_mutex_unlock(&lock);
+    pthread_join(t, NULL);
+
+    clock_gettime(CLOCK_REALTIME, &ts);
+    ts.tv_nsec += 10000000;
+    if (ts.tv_nsec >= 1000000000) {
+        ts.tv_sec += 1;
+        ts.tv_nsec -= 1000000000;
+    }
+    pthread_mutex_lock(&lock);
+    assert(pthread_cond_timedwait(&cond, &lock, &ts) == ETIMEDOUT);
+    pthread_mutex_unlock(&lock);
+
+    printf("test_cond: ready = %d\n", ready);
+}
+
+static pthread_rwlock_t rwlock = PTHREAD_RWLOCK_INITIALIZER;
+
+void *ThreadFunc5(void *arg)
+{
+    pthread_rwlock_wrlock(&rwlock);
+    int value = *(int *)arg;
+    for (int i = 0; i < 1000; i++) getpid();
+    *(int *)arg = value + 1;
+    pthread_rwlock_unlock(&rwlock);
+    return NULL;
+}
+
+void test_rwlock()
+{
+    const int NUM_THREADS = 10;
+    int data = 0;
+    pthread_t t[NUM_THREADS];
+
+    assert(pthread_rwlock_rdlock(&rwlock) == 0);
+    assert(pthread_rwlock_tryrdlock(&rwlock) == 0);
+    assert(pthread_rwlock_trywrlock(&rwlock) == EBUSY);
+    pthread_rwlock_unlock(&rwlock);
+    pthread_rwlock_unlock(&rwlock);
+
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], NULL, ThreadFunc5, &data);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_join(t[i], NULL);
+
+    printf("test_rwlock: data = %d\n", data);
+    assert(data == NUM_THREADS);
+}
+
+static pthread_barrier_t barrier;
+
+void *ThreadFunc6(void *arg)
+{
+    int ret = pthread_barrier_wait(&barrier);
+    assert(ret == 0 || ret == PTHREAD_BARRIER_SERIAL_THREAD);
+    return (void *)(long)(ret == PTHREAD_BARRIER_SERIAL_THREAD);
+}
+
+void test_barrier()
+{
+    const int NUM_THREADS = 4;
+    pthread_t t[NUM_THREADS];
+    long serial = 0;
+
+    pthread_barrier_init(&barrier, NULL, NUM_THREADS);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], NULL, ThreadFunc6, NULL);
+    for (int i = 0; i < NUM_THREADS; i++) {
+        void *ret;
+        pthread_join(t[i], &ret);
+        serial += (long)ret;
+    }
+    pthread_barrier_destroy(&barrier);
+
+    printf("test_barrier: serial threads = %ld\n", serial);
+    assert(serial == 1);
+}
+
+static pthread_key_t key;
+static int destructor_calls = 0;
+
+void key_destructor(void *value)
+{
+    __atomic_fetch_add(&destructor_calls, 1, __ATOMIC_SEQ_CST);
+}
+
+void *ThreadFunc7(void *arg)
+{
+    assert(pthread_getspecific(key) == NULL);
+    pthread_setspecific(key, arg);
+    assert(pthread_getspecific(key) == arg);
+    return NULL;
+}
+
+void test_key()
+{
+    const int NUM_THREADS = 4;
+    pthread_t t[NUM_THREADS];
+    int values[NUM_THREADS];
+
+    pthread_key_create(&key, key_destructor);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], NULL, ThreadFunc7, &values[i]);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_join(t[i], NULL);
+    pthread_key_delete(key);
+
+    printf("test_key: destructor calls = %d\n", destructor_calls);
+    assert(destructor_calls == NUM_THREADS);
+}
+
+static pthread_once_t once = PTHREAD_ONCE_INIT;
+static int once_calls = 0;
+
+void once_routine(void)
+{
+    once_calls++;
+}
+
+void *ThreadFunc8(void *arg)
+{
+    pthread_once(&once, once_routine);
+    return NULL;
+}
+
+void test_once_detach()
+{
+    const int NUM_THREADS = 4;
+    pthread_t t[NUM_THREADS];
+    pthread_attr_t attr;
+    size_t stacksize;
+
+    pthread_attr_init(&attr);
+    assert(pthread_attr_setstacksize(&attr, 1) == EINVAL);
+    pthread_attr_setstacksize(&attr, 0x10000);
+    pthread_attr_getstacksize(&attr, &stacksize);
+    assert(stacksize == 0x10000);
+
+    for (int i = 0; i < NUM_THREADS; i++) pthread_create(&t[i], &attr, ThreadFunc8, NULL);
+    for (int i = 0; i < NUM_THREADS; i++) pthread_detach(t[i]);
+    pthread_attr_destroy(&attr);
+    pthread_once(&once, once_routine);
+
+    printf("test_once_detach: once calls = %d\n", once_calls);
+    assert(once_calls == 1);
+}
+
 int main()
 {
     pthread_t main_thread = pthread_self();
@@ -125,6 +323,12 @@ int main()
     test_create_join();
     test_create_exit();
     test_mutex();
+    test_mutex_types();
+    test_cond();
+    test_rwlock();
+    test_barrier();
+    test_key();
+    test_once_detach();
     puts("(C)Pthread basic tests run OK!");
 
     return 0;
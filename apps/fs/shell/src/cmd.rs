@@ -19,6 +19,8 @@ type CmdHandler = fn(&str);
 const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("cat", do_cat),
     ("cd", do_cd),
+    #[cfg(feature = "axstd")]
+    ("df", do_df),
     ("echo", do_echo),
     ("exit", do_exit),
     ("help", do_help),
@@ -225,6 +227,65 @@ fn do_rm(args: &str) {
     }
 }
 
+#[cfg(feature = "axstd")]
+fn do_df(args: &str) {
+    // (filesystem, mount point) pairs
+    let mut targets: Vec<(String, String)> = args
+        .split_whitespace()
+        .map(|path| (String::from("-"), String::from(path)))
+        .collect();
+    let explicit_targets = !targets.is_empty();
+    if targets.is_empty() {
+        targets = match fs::read_to_string("/proc/mounts") {
+            Ok(mounts) => mounts
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.split_whitespace();
+                    Some((fields.next()?.into(), fields.next()?.into()))
+                })
+                .collect(),
+            Err(_) => vec![("rootfs".into(), "/".into())],
+        };
+    }
+
+    println!(
+        "{:<16} {:>10} {:>10} {:>10} {:>4} Mounted on",
+        "Filesystem", "1K-blocks", "Used", "Available", "Use%"
+    );
+    for (source, target) in targets {
+        let info = match fs::statfs(&target) {
+            Ok(info) => info,
+            Err(e) => {
+                if explicit_targets {
+                    print_err!("df", target, e);
+                }
+                continue;
+            }
+        };
+        // hide filesystems without a capacity, such as devfs
+        if info.blocks == 0 && !explicit_targets {
+            continue;
+        }
+        let kb = |blocks: u64| blocks * info.block_size / 1024;
+        let used = info.blocks - info.blocks_free;
+        let total = used + info.blocks_available;
+        let percent = if total == 0 {
+            String::from("-")
+        } else {
+            format!("{}%", (used * 100).div_ceil(total))
+        };
+        println!(
+            "{:<16} {:>10} {:>10} {:>10} {:>4} {}",
+            source,
+            kb(info.blocks),
+            kb(used),
+            kb(info.blocks_available),
+            percent,
+            target
+        );
+    }
+}
+
 fn do_cd(mut args: &str) {
     if args.is_empty() {
         args = "/";
@@ -43,6 +43,9 @@ pub enum AxError {
     ConnectionReset,
     /// A non-empty directory was specified where an empty directory was expected.
     DirectoryNotEmpty,
+    /// Too many levels of symbolic links were encountered while resolving a
+    /// path, or a symbolic link was not expected.
+    FilesystemLoop,
     /// Data not valid for the operation were encountered.
     ///
     /// Unlike [`InvalidInput`], this typically means that the operation
@@ -195,6 +198,7 @@ impl AxError {
             ConnectionRefused => "Connection refused",
             ConnectionReset => "Connection reset",
             DirectoryNotEmpty => "Directory not empty",
+            FilesystemLoop => "Filesystem loop or indirection limit",
             InvalidData => "Invalid data",
             InvalidInput => "Invalid input parameter",
             Io => "I/O error",
@@ -248,6 +252,7 @@ impl From<AxError> for LinuxError {
             ConnectionRefused => LinuxError::ECONNREFUSED,
             ConnectionReset => LinuxError::ECONNRESET,
             DirectoryNotEmpty => LinuxError::ENOTEMPTY,
+            FilesystemLoop => LinuxError::ELOOP,
             InvalidInput | InvalidData => LinuxError::EINVAL,
             Io => LinuxError::EIO,
             IsADirectory => LinuxError::EISDIR,
@@ -283,7 +288,7 @@ mod tests {
     #[test]
     fn test_try_from() {
         let max_code = core::mem::variant_count::<AxError>() as i32;
-        assert_eq!(max_code, 22);
+        assert_eq!(max_code, 23);
         assert_eq!(max_code, AxError::WriteZero.code());
 
         assert_eq!(AxError::AddrInUse.code(), 1);
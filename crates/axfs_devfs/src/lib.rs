@@ -7,20 +7,27 @@
 extern crate alloc;
 
 mod dir;
+mod full;
 mod null;
+mod random;
 mod zero;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
+pub use self::full::FullDev;
 pub use self::null::NullDev;
+pub use self::random::RandomDev;
 pub use self::zero::ZeroDev;
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
 
+/// Magic number of devfs, the same as `DEVFS_SUPER_MAGIC` in Linux.
+const DEVFS_MAGIC: u64 = 0x1373;
+
 /// A device filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct DeviceFileSystem {
     parent: Once<VfsNodeRef>,
@@ -37,14 +44,14 @@ impl DeviceFileSystem {
     }
 
     /// Create a subdirectory at the root directory.
-    pub fn mkdir(&self, name: &'static str) -> Arc<DirNode> {
+    pub fn mkdir(&self, name: &str) -> Arc<DirNode> {
         self.root.mkdir(name)
     }
 
     /// Add a node to the root directory.
     ///
     /// The node must implement [`axfs_vfs::VfsNodeOps`], and be wrapped in [`Arc`].
-    pub fn add(&self, name: &'static str, node: VfsNodeRef) {
+    pub fn add(&self, name: &str, node: VfsNodeRef) {
         self.root.add(name, node);
     }
 }
@@ -59,6 +66,15 @@ impl VfsOps for DeviceFileSystem {
         Ok(())
     }
 
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(FileSystemInfo {
+            fs_type: DEVFS_MAGIC,
+            block_size: 4096,
+            name_max: 255,
+            ..Default::default()
+        })
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
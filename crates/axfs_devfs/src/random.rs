@@ -0,0 +1,42 @@
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+
+/// A random device behaves like `/dev/random` and `/dev/urandom`.
+///
+/// Reads are filled by the random number generator given on creation, which
+/// should be cryptographically secure. All writes are discarded.
+pub struct RandomDev {
+    fill: fn(&mut [u8]),
+}
+
+impl RandomDev {
+    /// Create a random device, whose reads are filled by `fill`.
+    pub const fn new(fill: fn(&mut [u8])) -> Self {
+        Self { fill }
+    }
+}
+
+impl VfsNodeOps for RandomDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        (self.fill)(buf);
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
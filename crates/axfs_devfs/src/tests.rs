@@ -8,6 +8,9 @@ fn test_devfs_ops(devfs: &DeviceFileSystem) -> VfsResult {
     const N: usize = 32;
     let mut buf = [1; N];
 
+    assert_eq!(devfs.statfs()?.fs_type, 0x1373);
+    assert_eq!(devfs.statfs()?.blocks, 0);
+
     let root = devfs.root_dir();
     assert!(root.get_attr()?.is_dir());
     assert_eq!(root.get_attr()?.file_type(), VfsNodeType::Dir);
@@ -37,6 +40,19 @@ fn test_devfs_ops(devfs: &DeviceFileSystem) -> VfsResult {
     assert_eq!(buf, [0; N]);
     assert_eq!(node.write_at(0, &buf)?, N);
 
+    let node = devfs.root_dir().lookup("full")?;
+    assert_eq!(node.get_attr()?.file_type(), VfsNodeType::CharDevice);
+    assert_eq!(node.read_at(0, &mut buf)?, N);
+    assert_eq!(buf, [0; N]);
+    assert_eq!(node.write_at(0, &buf).err(), Some(VfsError::StorageFull));
+    assert_eq!(node.write_at(0, &[])?, 0);
+
+    let node = devfs.root_dir().lookup("random")?;
+    assert_eq!(node.get_attr()?.file_type(), VfsNodeType::CharDevice);
+    assert_eq!(node.read_at(0, &mut buf)?, N);
+    assert_eq!(buf, [0xa5; N]);
+    assert_eq!(node.write_at(0, &buf)?, N);
+
     let foo = devfs.root_dir().lookup(".///.//././/.////foo")?;
     assert!(foo.get_attr()?.is_dir());
     assert_eq!(
@@ -104,6 +120,8 @@ fn test_devfs() {
     let devfs = DeviceFileSystem::new();
     devfs.add("null", Arc::new(NullDev));
     devfs.add("zero", Arc::new(ZeroDev));
+    devfs.add("full", Arc::new(FullDev));
+    devfs.add("random", Arc::new(RandomDev::new(|buf| buf.fill(0xa5))));
 
     let dir_foo = devfs.mkdir("foo");
     dir_foo.add("f2", Arc::new(ZeroDev));
@@ -0,0 +1,192 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
+use axfs_vfs::{VfsError, VfsResult};
+use spin::RwLock;
+
+pub(super) type GenerateFn = Box<dyn Fn(&Arc<DirNode>) -> Vec<(String, VfsNodeRef)> + Send + Sync>;
+
+/// The directory node in the pseudo filesystem.
+///
+/// Besides the nodes added to it, a directory may have a generator, which
+/// generates more entries on each lookup and read (e.g., a directory for each
+/// task in `/proc`). It implements [`axfs_vfs::VfsNodeOps`].
+pub struct DirNode {
+    this: Weak<DirNode>,
+    parent: RwLock<Weak<dyn VfsNodeOps>>,
+    children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    generate: Option<GenerateFn>,
+}
+
+impl DirNode {
+    pub(super) fn new(parent: Option<&VfsNodeRef>) -> Arc<Self> {
+        Self::new_with(parent, None)
+    }
+
+    pub(super) fn new_with(parent: Option<&VfsNodeRef>, generate: Option<GenerateFn>) -> Arc<Self> {
+        let parent = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            parent: RwLock::new(parent),
+            children: RwLock::new(BTreeMap::new()),
+            generate,
+        })
+    }
+
+    pub(super) fn set_parent(&self, parent: Option<&VfsNodeRef>) {
+        *self.parent.write() = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
+    }
+
+    /// Create a subdirectory at this directory.
+    pub fn mkdir(self: &Arc<Self>, name: &str) -> Arc<Self> {
+        let parent = self.clone() as VfsNodeRef;
+        let node = Self::new(Some(&parent));
+        self.children.write().insert(name.into(), node.clone());
+        node
+    }
+
+    /// Create a subdirectory at this directory, whose entries are generated
+    /// by `generate` with the subdirectory itself as the argument.
+    pub fn mkdir_generated<F>(self: &Arc<Self>, name: &str, generate: F) -> Arc<Self>
+    where
+        F: Fn(&Arc<DirNode>) -> Vec<(String, VfsNodeRef)> + Send + Sync + 'static,
+    {
+        let parent = self.clone() as VfsNodeRef;
+        let node = Self::new_with(Some(&parent), Some(Box::new(generate)));
+        self.children.write().insert(name.into(), node.clone());
+        node
+    }
+
+    /// Create a directory that is not added to any directory, whose parent
+    /// is `parent`. It is used by generators to create subdirectories.
+    pub fn new_detached(parent: &Arc<DirNode>) -> Arc<Self> {
+        Self::new(Some(&(parent.clone() as VfsNodeRef)))
+    }
+
+    /// Create a directory that is not added to any directory, whose parent
+    /// is `parent`, and whose entries are generated by `generate`.
+    pub fn new_detached_generated<F>(parent: &Arc<DirNode>, generate: F) -> Arc<Self>
+    where
+        F: Fn(&Arc<DirNode>) -> Vec<(String, VfsNodeRef)> + Send + Sync + 'static,
+    {
+        Self::new_with(
+            Some(&(parent.clone() as VfsNodeRef)),
+            Some(Box::new(generate)),
+        )
+    }
+
+    /// Add a node to this directory.
+    pub fn add(&self, name: &str, node: VfsNodeRef) {
+        self.children.write().insert(name.into(), node);
+    }
+
+    /// Returns the generated entries, sorted by names.
+    fn generated(&self) -> Vec<(String, VfsNodeRef)> {
+        match (&self.generate, self.this.upgrade()) {
+            (Some(generate), Some(this)) => {
+                let mut entries = generate(&this);
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn child(&self, name: &str) -> Option<VfsNodeRef> {
+        if let Some(node) = self.children.read().get(name) {
+            return Some(node.clone());
+        }
+        self.generated()
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, node)| node)
+    }
+}
+
+impl VfsNodeOps for DirNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new_dir(4096, 0))
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.parent.read().upgrade()
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        let (name, rest) = split_path(path);
+        let node = match name {
+            "" | "." => Ok(self.clone() as VfsNodeRef),
+            ".." => self.parent().ok_or(VfsError::NotFound),
+            _ => self.child(name).ok_or(VfsError::NotFound),
+        }?;
+
+        if let Some(rest) = rest {
+            node.lookup(rest)
+        } else {
+            Ok(node)
+        }
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let children = self.children.read();
+        let generated = self.generated();
+        let mut children = children
+            .iter()
+            .chain(generated.iter().map(|(name, node)| (name, node)))
+            .skip(start_idx.max(2) - 2);
+        for (i, ent) in dirents.iter_mut().enumerate() {
+            match i + start_idx {
+                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
+                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+                _ => {
+                    if let Some((name, node)) = children.next() {
+                        *ent = VfsDirEntry::new(name, node.get_attr()?.file_type());
+                    } else {
+                        return Ok(i);
+                    }
+                }
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        log::debug!("create {:?} at pseudofs: {}", ty, path);
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest {
+            match name {
+                "" | "." => self.create(rest, ty),
+                ".." => self.parent().ok_or(VfsError::NotFound)?.create(rest, ty),
+                _ => self.child(name).ok_or(VfsError::NotFound)?.create(rest, ty),
+            }
+        } else if name.is_empty() || name == "." || name == ".." {
+            Ok(()) // already exists
+        } else {
+            Err(VfsError::PermissionDenied) // do not support to create nodes dynamically
+        }
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        log::debug!("remove at pseudofs: {}", path);
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest {
+            match name {
+                "" | "." => self.remove(rest),
+                ".." => self.parent().ok_or(VfsError::NotFound)?.remove(rest),
+                _ => self.child(name).ok_or(VfsError::NotFound)?.remove(rest),
+            }
+        } else {
+            Err(VfsError::PermissionDenied) // do not support to remove nodes dynamically
+        }
+    }
+
+    axfs_vfs::impl_vfs_dir_default! {}
+}
+
+fn split_path(path: &str) -> (&str, Option<&str>) {
+    let trimmed_path = path.trim_start_matches('/');
+    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
+        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+    })
+}
@@ -0,0 +1,78 @@
+use alloc::{boxed::Box, string::String};
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+
+type ReadFn = Box<dyn Fn() -> VfsResult<String> + Send + Sync>;
+type WriteFn = Box<dyn Fn(&[u8]) -> VfsResult + Send + Sync>;
+
+/// A file in the pseudo filesystem, whose content is generated on each read.
+///
+/// As the content is not known before it is read, the file size is always 0.
+/// Files are read-only unless created by [`FileNode::new_writable`].
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct FileNode {
+    read: ReadFn,
+    write: Option<WriteFn>,
+}
+
+impl FileNode {
+    /// Create a new file, whose content is generated by `read`.
+    pub fn new<F>(read: F) -> Self
+    where
+        F: Fn() -> VfsResult<String> + Send + Sync + 'static,
+    {
+        Self {
+            read: Box::new(read),
+            write: None,
+        }
+    }
+
+    /// Create a new writable file, whose content is generated by `read`.
+    ///
+    /// Each write passes the whole buffer to `write`, regardless of the
+    /// offset, and truncations are ignored.
+    pub fn new_writable<F, G>(read: F, write: G) -> Self
+    where
+        F: Fn() -> VfsResult<String> + Send + Sync + 'static,
+        G: Fn(&[u8]) -> VfsResult + Send + Sync + 'static,
+    {
+        Self {
+            read: Box::new(read),
+            write: Some(Box::new(write)),
+        }
+    }
+}
+
+impl VfsNodeOps for FileNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(if self.write.is_some() { 0o644 } else { 0o444 }),
+            VfsNodeType::File,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = (self.read)()?;
+        let start = content.len().min(offset as usize);
+        let end = content.len().min(start + buf.len());
+        let src = &content.as_bytes()[start..end];
+        buf[..src.len()].copy_from_slice(src);
+        Ok(src.len())
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let write = self.write.as_ref().ok_or(VfsError::PermissionDenied)?;
+        write(buf)?;
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        match self.write {
+            Some(_) => Ok(()),
+            None => Err(VfsError::PermissionDenied),
+        }
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -0,0 +1,77 @@
+//! Pseudo filesystems used by [ArceOS](https://github.com/rcore-os/arceos),
+//! such as procfs, whose file contents are generated on each read.
+//!
+//! The implementation is based on [`axfs_vfs`].
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod dir;
+mod file;
+mod symlink;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::dir::DirNode;
+pub use self::file::FileNode;
+pub use self::symlink::SymlinkNode;
+
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use spin::once::Once;
+
+/// A pseudo filesystem that implements [`axfs_vfs::VfsOps`].
+pub struct PseudoFileSystem {
+    parent: Once<VfsNodeRef>,
+    root: Arc<DirNode>,
+}
+
+impl PseudoFileSystem {
+    /// Create a new instance.
+    pub fn new() -> Self {
+        Self {
+            parent: Once::new(),
+            root: DirNode::new(None),
+        }
+    }
+
+    /// Create a new instance, whose root directory has entries generated by
+    /// `generate` besides the added ones. See [`DirNode::mkdir_generated`].
+    pub fn new_generated<F>(generate: F) -> Self
+    where
+        F: Fn(&Arc<DirNode>) -> Vec<(String, VfsNodeRef)> + Send + Sync + 'static,
+    {
+        Self {
+            parent: Once::new(),
+            root: DirNode::new_with(None, Some(Box::new(generate))),
+        }
+    }
+
+    /// Returns the root directory.
+    pub fn root(&self) -> &Arc<DirNode> {
+        &self.root
+    }
+}
+
+impl VfsOps for PseudoFileSystem {
+    fn mount(&self, _path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        if let Some(parent) = mount_point.parent() {
+            self.root.set_parent(Some(self.parent.call_once(|| parent)));
+        } else {
+            self.root.set_parent(None);
+        }
+        Ok(())
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
+
+impl Default for PseudoFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
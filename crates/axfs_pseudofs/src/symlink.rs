@@ -0,0 +1,44 @@
+use alloc::{boxed::Box, string::String};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+
+type TargetFn = Box<dyn Fn() -> String + Send + Sync>;
+
+/// A symbolic link in the pseudo filesystem, whose target is generated on each
+/// read (e.g., `/proc/self`).
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct SymlinkNode {
+    target: TargetFn,
+}
+
+impl SymlinkNode {
+    /// Create a new symbolic link, whose target is generated by `target`.
+    pub fn new<F>(target: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Self {
+            target: Box::new(target),
+        }
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o777),
+            VfsNodeType::SymLink,
+            (self.target)().len() as _,
+            0,
+        ))
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let target = (self.target)();
+        let len = target.len().min(buf.len());
+        buf[..len].copy_from_slice(&target.as_bytes()[..len]);
+        Ok(len)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use axfs_vfs::{VfsError, VfsNodeType, VfsOps, VfsResult};
+
+use crate::*;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static LIMIT: AtomicUsize = AtomicUsize::new(10);
+
+fn read_to_string(node: &axfs_vfs::VfsNodeRef) -> VfsResult<String> {
+    let mut buf = [0; 64];
+    let len = node.read_at(0, &mut buf)?;
+    Ok(String::from_utf8(buf[..len].to_vec()).unwrap())
+}
+
+fn test_pseudofs_ops(fs: &PseudoFileSystem) -> VfsResult {
+    let root = fs.root_dir();
+    assert!(root.get_attr()?.is_dir());
+
+    // the content is generated on each read
+    let node = root.clone().lookup("counter")?;
+    assert_eq!(node.get_attr()?.file_type(), VfsNodeType::File);
+    assert_eq!(node.get_attr()?.size(), 0);
+    assert_eq!(read_to_string(&node)?, "1\n");
+    assert_eq!(read_to_string(&node)?, "2\n");
+    let mut buf = [0; 8];
+    assert_eq!(node.read_at(1, &mut buf)?, 1);
+    assert_eq!(&buf[..1], b"\n");
+    assert_eq!(node.read_at(10, &mut buf)?, 0);
+    assert_eq!(
+        node.write_at(0, b"0").err(),
+        Some(VfsError::PermissionDenied)
+    );
+
+    // writable files pass the written data to the setter
+    let node = root.clone().lookup("limit")?;
+    assert_eq!(node.get_attr()?.perm().bits(), 0o644);
+    assert_eq!(node.truncate(0), Ok(()));
+    assert_eq!(node.write_at(0, b"20\n")?, 3);
+    assert_eq!(read_to_string(&node)?, "20\n");
+    assert_eq!(node.write_at(0, b"abc").err(), Some(VfsError::InvalidInput));
+    assert_eq!(read_to_string(&node)?, "20\n");
+
+    // generated directories
+    let node = root.clone().lookup("tasks//2/name")?;
+    assert_eq!(read_to_string(&node)?, "task 2");
+    assert_eq!(
+        root.clone().lookup("tasks/4").err(),
+        Some(VfsError::NotFound)
+    );
+    let task = root.clone().lookup("./tasks/1/../3")?;
+    assert!(task.get_attr()?.is_dir());
+    assert_eq!(read_to_string(&task.lookup("name")?)?, "task 3");
+
+    let mut entries: [_; 8] = core::array::from_fn(|_| axfs_vfs::VfsDirEntry::default());
+    let tasks = root.clone().lookup("tasks")?;
+    assert_eq!(tasks.read_dir(0, &mut entries)?, 5);
+    assert_eq!(entries[2].name_as_bytes(), b"1");
+    assert_eq!(entries[4].name_as_bytes(), b"3");
+
+    let link = root.clone().lookup("self")?;
+    assert_eq!(link.get_attr()?.file_type(), VfsNodeType::SymLink);
+    assert_eq!(link.readlink(&mut buf)?, 5);
+    assert_eq!(&buf[..5], b"tasks");
+
+    assert_eq!(
+        root.create("tasks/5", VfsNodeType::Dir).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(
+        fs.root_dir().remove("counter").err(),
+        Some(VfsError::PermissionDenied)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pseudofs() {
+    // .
+    // ├── counter
+    // ├── limit
+    // ├── self -> tasks
+    // └── tasks
+    //     ├── 1
+    //     │   └── name
+    //     ├── 2
+    //     │   └── name
+    //     └── 3
+    //         └── name
+
+    let fs = PseudoFileSystem::new();
+    fs.root().add(
+        "counter",
+        Arc::new(FileNode::new(|| {
+            Ok(format!("{}\n", COUNTER.fetch_add(1, Ordering::Relaxed) + 1))
+        })),
+    );
+    fs.root().add(
+        "limit",
+        Arc::new(FileNode::new_writable(
+            || Ok(format!("{}\n", LIMIT.load(Ordering::Relaxed))),
+            |buf| {
+                let value = core::str::from_utf8(buf).map_err(|_| VfsError::InvalidInput)?;
+                let value = value.trim().parse().map_err(|_| VfsError::InvalidInput)?;
+                LIMIT.store(value, Ordering::Relaxed);
+                Ok(())
+            },
+        )),
+    );
+    fs.root()
+        .add("self", Arc::new(SymlinkNode::new(|| "tasks".into())));
+    fs.root().mkdir_generated("tasks", |dir| {
+        (1..=3)
+            .rev()
+            .map(|i| {
+                let task = DirNode::new_detached(dir);
+                task.add(
+                    "name",
+                    Arc::new(FileNode::new(move || Ok(format!("task {}", i)))),
+                );
+                (format!("{}", i), task as _)
+            })
+            .collect()
+    });
+
+    test_pseudofs_ops(&fs).unwrap();
+}
@@ -3,24 +3,28 @@ use alloc::sync::{Arc, Weak};
 use alloc::{string::String, vec::Vec};
 
 use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
-use axfs_vfs::{VfsError, VfsResult};
+use axfs_vfs::{VfsError, VfsNodePerm, VfsResult, VfsSetAttr};
 use spin::RwLock;
 
 use crate::file::FileNode;
+use crate::meta::{Clock, NodeMeta};
+use crate::symlink::SymlinkNode;
 
 /// The directory node in the RAM filesystem.
 ///
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct DirNode {
     this: Weak<DirNode>,
+    meta: NodeMeta,
     parent: RwLock<Weak<dyn VfsNodeOps>>,
     children: RwLock<BTreeMap<String, VfsNodeRef>>,
 }
 
 impl DirNode {
-    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>) -> Arc<Self> {
+    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>, clock: Clock) -> Arc<Self> {
         Arc::new_cyclic(|this| Self {
             this: this.clone(),
+            meta: NodeMeta::new(VfsNodePerm::default_dir(), clock),
             parent: RwLock::new(parent.unwrap_or_else(|| Weak::<Self>::new())),
             children: RwLock::new(BTreeMap::new()),
         })
@@ -42,19 +46,66 @@ impl DirNode {
 
     /// Creates a new node with the given name and type in this directory.
     pub fn create_node(&self, name: &str, ty: VfsNodeType) -> VfsResult {
-        if self.exist(name) {
-            log::error!("AlreadyExists {}", name);
-            return Err(VfsError::AlreadyExists);
-        }
         let node: VfsNodeRef = match ty {
-            VfsNodeType::File => Arc::new(FileNode::new()),
-            VfsNodeType::Dir => Self::new(Some(self.this.clone())),
+            VfsNodeType::File => Arc::new(FileNode::new(self.meta.clock())),
+            VfsNodeType::Dir => Self::new(Some(self.this.clone()), self.meta.clock()),
             _ => return Err(VfsError::Unsupported),
         };
-        self.children.write().insert(name.into(), node);
+        self.insert_node(name, node)
+    }
+
+    /// Creates a new symbolic link with the given name in this directory.
+    pub fn create_symlink(&self, name: &str, target: &str) -> VfsResult {
+        let node = SymlinkNode::new(target, self.meta.clock());
+        self.insert_node(name, Arc::new(node))
+    }
+
+    /// Adds an existing non-directory node with the given name in this
+    /// directory, as a hard link.
+    ///
+    /// Only nodes of the RAM filesystem can be linked.
+    pub fn link_node(&self, name: &str, node: &VfsNodeRef) -> VfsResult {
+        let any = node.as_any();
+        if any.is::<DirNode>() {
+            Err(VfsError::PermissionDenied)
+        } else if any.is::<FileNode>() || any.is::<SymlinkNode>() {
+            self.insert_node(name, node.clone())
+        } else {
+            Err(VfsError::Unsupported)
+        }
+    }
+
+    fn insert_node(&self, name: &str, node: VfsNodeRef) -> VfsResult {
+        let mut children = self.children.write();
+        if children.contains_key(name) {
+            log::error!("AlreadyExists {}", name);
+            return Err(VfsError::AlreadyExists);
+        }
+        children.insert(name.into(), node);
+        self.meta.touch();
         Ok(())
     }
 
+    /// Returns the child directory `name` and the rest of the path, or
+    /// `None` if `path` has only one component.
+    fn next_dir<'a>(&self, path: &'a str) -> VfsResult<Option<(VfsNodeRef, &'a str)>> {
+        let (name, rest) = split_path(path);
+        let Some(rest) = rest else {
+            return Ok(None);
+        };
+        let dir = match name {
+            "" | "." => self.this.upgrade().ok_or(VfsError::NotFound)?,
+            ".." => self.parent().ok_or(VfsError::NotFound)?,
+            _ => self
+                .children
+                .read()
+                .get(name)
+                .ok_or(VfsError::NotFound)?
+                .clone(),
+        };
+        Ok(Some((dir, rest)))
+    }
+
     /// Removes a node by the given name in this directory.
     pub fn remove_node(&self, name: &str) -> VfsResult {
         let mut children = self.children.write();
@@ -65,13 +116,19 @@ impl DirNode {
             }
         }
         children.remove(name);
+        self.meta.touch();
         Ok(())
     }
 }
 
 impl VfsNodeOps for DirNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_dir(4096, 0))
+        Ok(self.meta.attr(VfsNodeType::Dir, 4096, 0))
+    }
+
+    fn set_attr(&self, attr: &VfsSetAttr) -> VfsResult {
+        self.meta.set(attr);
+        Ok(())
     }
 
     fn parent(&self) -> Option<VfsNodeRef> {
@@ -165,6 +222,28 @@ impl VfsNodeOps for DirNode {
         }
     }
 
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        log::debug!("symlink at ramfs: {} -> {}", path, target);
+        match self.next_dir(path)? {
+            Some((dir, rest)) => dir.symlink(rest, target),
+            None => match split_path(path).0 {
+                "" | "." | ".." => Err(VfsError::AlreadyExists),
+                name => self.create_symlink(name, target),
+            },
+        }
+    }
+
+    fn link(&self, path: &str, node: &VfsNodeRef) -> VfsResult {
+        log::debug!("link at ramfs: {}", path);
+        match self.next_dir(path)? {
+            Some((dir, rest)) => dir.link(rest, node),
+            None => match split_path(path).0 {
+                "" | "." | ".." => Err(VfsError::AlreadyExists),
+                name => self.link_node(name, node),
+            },
+        }
+    }
+
     axfs_vfs::impl_vfs_dir_default! {}
 }
 
@@ -1,17 +1,22 @@
 use alloc::vec::Vec;
 use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{VfsNodePerm, VfsNodeType, VfsSetAttr};
 use spin::RwLock;
 
+use crate::meta::{Clock, NodeMeta};
+
 /// The file node in the RAM filesystem.
 ///
 /// It implements [`axfs_vfs::VfsNodeOps`].
 pub struct FileNode {
+    meta: NodeMeta,
     content: RwLock<Vec<u8>>,
 }
 
 impl FileNode {
-    pub(super) const fn new() -> Self {
+    pub(super) fn new(clock: Clock) -> Self {
         Self {
+            meta: NodeMeta::new(VfsNodePerm::default_file(), clock),
             content: RwLock::new(Vec::new()),
         }
     }
@@ -19,7 +24,17 @@ impl FileNode {
 
 impl VfsNodeOps for FileNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_file(self.content.read().len() as _, 0))
+        let size = self.content.read().len() as u64;
+        Ok(self.meta.attr(VfsNodeType::File, size, 0))
+    }
+
+    fn set_attr(&self, attr: &VfsSetAttr) -> VfsResult {
+        self.meta.set(attr);
+        Ok(())
+    }
+
+    fn fsync(&self) -> VfsResult {
+        Ok(()) // nothing to write back
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
@@ -29,6 +44,7 @@ impl VfsNodeOps for FileNode {
         } else {
             content.resize(size as _, 0);
         }
+        self.meta.touch();
         Ok(())
     }
 
@@ -49,6 +65,7 @@ impl VfsNodeOps for FileNode {
         }
         let dst = &mut content[offset..offset + buf.len()];
         dst.copy_from_slice(&buf[..dst.len()]);
+        self.meta.touch();
         Ok(buf.len())
     }
 
@@ -8,17 +8,24 @@ extern crate alloc;
 
 mod dir;
 mod file;
+mod meta;
+mod symlink;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::dir::DirNode;
 pub use self::file::FileNode;
+pub use self::symlink::SymlinkNode;
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
+use core::time::Duration;
 use spin::once::Once;
 
+/// Magic number of ramfs, the same as `RAMFS_MAGIC` in Linux.
+const RAMFS_MAGIC: u64 = 0x8584_58f6;
+
 /// A RAM filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct RamFileSystem {
     parent: Once<VfsNodeRef>,
@@ -26,11 +33,17 @@ pub struct RamFileSystem {
 }
 
 impl RamFileSystem {
-    /// Create a new instance.
+    /// Create a new instance, whose timestamps are all zero.
     pub fn new() -> Self {
+        Self::with_clock(|| Duration::ZERO)
+    }
+
+    /// Create a new instance, which gets the current time from `clock` to
+    /// update the timestamps.
+    pub fn with_clock(clock: fn() -> Duration) -> Self {
         Self {
             parent: Once::new(),
-            root: DirNode::new(None),
+            root: DirNode::new(None, clock),
         }
     }
 
@@ -50,6 +63,15 @@ impl VfsOps for RamFileSystem {
         Ok(())
     }
 
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(FileSystemInfo {
+            fs_type: RAMFS_MAGIC,
+            block_size: 4096,
+            name_max: 255,
+            ..Default::default()
+        })
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
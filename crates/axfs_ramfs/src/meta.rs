@@ -0,0 +1,83 @@
+use core::time::Duration;
+
+use axfs_vfs::{VfsNodeAttr, VfsNodePerm, VfsNodeType, VfsSetAttr};
+use spin::RwLock;
+
+/// A function that returns the current time.
+pub(crate) type Clock = fn() -> Duration;
+
+struct MetaInner {
+    perm: VfsNodePerm,
+    uid: u32,
+    gid: u32,
+    atime: Duration,
+    mtime: Duration,
+    ctime: Duration,
+}
+
+/// The permission, owner and timestamps of a node.
+///
+/// The access time is not updated on reads, as if mounted with `noatime`.
+pub(crate) struct NodeMeta {
+    clock: Clock,
+    inner: RwLock<MetaInner>,
+}
+
+impl NodeMeta {
+    pub fn new(perm: VfsNodePerm, clock: Clock) -> Self {
+        let now = clock();
+        Self {
+            clock,
+            inner: RwLock::new(MetaInner {
+                perm,
+                uid: 0,
+                gid: 0,
+                atime: now,
+                mtime: now,
+                ctime: now,
+            }),
+        }
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Returns the attributes of the node with the given type and sizes.
+    pub fn attr(&self, ty: VfsNodeType, size: u64, blocks: u64) -> VfsNodeAttr {
+        let inner = self.inner.read();
+        let mut attr = VfsNodeAttr::new(inner.perm, ty, size, blocks);
+        attr.set_owner(inner.uid, inner.gid);
+        attr.set_times(inner.atime, inner.mtime, inner.ctime);
+        attr
+    }
+
+    /// Updates the modification and status change time, after the content
+    /// is modified.
+    pub fn touch(&self) {
+        let now = (self.clock)();
+        let mut inner = self.inner.write();
+        inner.mtime = now;
+        inner.ctime = now;
+    }
+
+    pub fn set(&self, attr: &VfsSetAttr) {
+        let mut inner = self.inner.write();
+        if let Some(perm) = attr.mode {
+            inner.perm = perm;
+        }
+        if let Some(uid) = attr.uid {
+            inner.uid = uid;
+        }
+        if let Some(gid) = attr.gid {
+            inner.gid = gid;
+        }
+        if let Some(atime) = attr.atime {
+            inner.atime = atime;
+        }
+        if let Some(mtime) = attr.mtime {
+            inner.mtime = mtime;
+        }
+        inner.ctime = (self.clock)();
+    }
+}
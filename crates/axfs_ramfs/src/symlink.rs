@@ -0,0 +1,46 @@
+use alloc::string::String;
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
+use axfs_vfs::{VfsError, VfsNodePerm, VfsNodeType, VfsSetAttr};
+
+use crate::meta::{Clock, NodeMeta};
+
+/// The symbolic link node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct SymlinkNode {
+    meta: NodeMeta,
+    target: String,
+}
+
+impl SymlinkNode {
+    pub(super) fn new(target: &str, clock: Clock) -> Self {
+        Self {
+            meta: NodeMeta::new(VfsNodePerm::from_bits_truncate(0o777), clock),
+            target: target.into(),
+        }
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(self
+            .meta
+            .attr(VfsNodeType::SymLink, self.target.len() as _, 0))
+    }
+
+    fn set_attr(&self, attr: &VfsSetAttr) -> VfsResult {
+        if attr.mode.is_some() {
+            return Err(VfsError::Unsupported); // always `rwxrwxrwx`
+        }
+        self.meta.set(attr);
+        Ok(())
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let len = self.target.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.target.as_bytes()[..len]);
+        Ok(len)
+    }
+
+    impl_vfs_non_dir_default! {}
+}
@@ -1,6 +1,8 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsError, VfsNodePerm, VfsNodeType, VfsResult, VfsSetAttr};
 
 use crate::*;
 
@@ -9,6 +11,9 @@ fn test_ramfs_ops(devfs: &RamFileSystem) -> VfsResult {
     const N_HALF: usize = N / 2;
     let mut buf = [1; N];
 
+    assert_eq!(devfs.statfs()?.fs_type, 0x8584_58f6);
+    assert_eq!(devfs.statfs()?.blocks, 0);
+
     let root = devfs.root_dir();
     assert!(root.get_attr()?.is_dir());
     assert_eq!(root.get_attr()?.file_type(), VfsNodeType::Dir);
@@ -134,3 +139,100 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+#[test]
+fn test_symlink() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+
+    assert_eq!(root.symlink("foo/link", "../f1"), Ok(()));
+    assert_eq!(
+        root.symlink(".//foo/./link", "f2").err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.symlink("bar/link", "f2").err(),
+        Some(VfsError::NotFound)
+    );
+    assert_eq!(
+        root.symlink("foo/..", "f2").err(),
+        Some(VfsError::AlreadyExists)
+    );
+
+    let link = root.clone().lookup("foo/link").unwrap();
+    let attr = link.get_attr().unwrap();
+    assert!(attr.is_symlink());
+    assert_eq!(attr.size(), 5);
+    let mut buf = [0; 8];
+    assert_eq!(link.readlink(&mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"../f1");
+    assert_eq!(link.readlink(&mut buf[..2]), Ok(2));
+    assert_eq!(&buf[..2], b"..");
+
+    let f1 = root.clone().lookup("foo/f1").unwrap();
+    assert_eq!(f1.readlink(&mut buf).err(), Some(VfsError::InvalidInput));
+    assert_eq!(root.link("f1", &f1), Ok(()));
+    assert_eq!(root.link("./f1", &f1).err(), Some(VfsError::AlreadyExists));
+    assert_eq!(
+        root.link("dir", &root.clone().lookup("foo").unwrap()).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(f1.write_at(0, b"hello"), Ok(5));
+    let f1_link = root.clone().lookup("f1").unwrap();
+    assert!(Arc::ptr_eq(&f1, &f1_link));
+    assert_eq!(f1_link.read_at(0, &mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"hello");
+
+    assert_eq!(root.remove("foo/f1"), Ok(()));
+    assert_eq!(root.remove("foo/link"), Ok(()));
+    assert_eq!(f1_link.get_attr().unwrap().size(), 5);
+    assert_eq!(root.remove("f1"), Ok(()));
+    assert_eq!(root.remove("foo"), Ok(()));
+    assert!(ramfs.root_dir_node().get_entries().is_empty());
+}
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+
+fn fake_clock() -> Duration {
+    Duration::from_secs(NOW.load(Ordering::Relaxed))
+}
+
+#[test]
+fn test_attrs() {
+    NOW.store(10, Ordering::Relaxed);
+    let ramfs = RamFileSystem::with_clock(fake_clock);
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    let f1 = root.clone().lookup("f1").unwrap();
+    let attr = f1.get_attr().unwrap();
+    assert_eq!(attr.mtime(), Duration::from_secs(10));
+    assert_eq!(attr.ctime(), Duration::from_secs(10));
+    assert_eq!(attr.perm().bits(), VfsNodePerm::default_file().bits());
+
+    NOW.store(20, Ordering::Relaxed);
+    assert_eq!(f1.write_at(0, b"hello"), Ok(5));
+    let attr = f1.get_attr().unwrap();
+    assert_eq!(attr.atime(), Duration::from_secs(10));
+    assert_eq!(attr.mtime(), Duration::from_secs(20));
+
+    NOW.store(30, Ordering::Relaxed);
+    root.create("f2", VfsNodeType::File).unwrap();
+    assert_eq!(root.get_attr().unwrap().mtime(), Duration::from_secs(30));
+
+    NOW.store(40, Ordering::Relaxed);
+    let set_attr = VfsSetAttr {
+        mode: Some(VfsNodePerm::from_bits_truncate(0o400)),
+        uid: Some(1000),
+        mtime: Some(Duration::from_secs(5)),
+        ..Default::default()
+    };
+    assert_eq!(f1.set_attr(&set_attr), Ok(()));
+    let attr = f1.get_attr().unwrap();
+    assert_eq!(attr.perm().bits(), 0o400);
+    assert_eq!((attr.uid(), attr.gid()), (1000, 0));
+    assert_eq!(attr.atime(), Duration::from_secs(10));
+    assert_eq!(attr.mtime(), Duration::from_secs(5));
+    assert_eq!(attr.ctime(), Duration::from_secs(40));
+}
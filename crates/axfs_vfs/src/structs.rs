@@ -1,8 +1,30 @@
-/// Filesystem attributes.
+use core::time::Duration;
+
+/// Filesystem attributes, returned by [`statfs`].
+///
+/// Filesystems without a fixed capacity (e.g. in-memory filesystems) report
+/// zero blocks and inodes.
 ///
-/// Currently not used.
-#[non_exhaustive]
-pub struct FileSystemInfo;
+/// [`statfs`]: crate::VfsOps::statfs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystemInfo {
+    /// Type of the filesystem, as the magic number used by Linux.
+    pub fs_type: u64,
+    /// Size of a block, in bytes.
+    pub block_size: u64,
+    /// Total number of blocks.
+    pub blocks: u64,
+    /// Number of free blocks.
+    pub blocks_free: u64,
+    /// Number of free blocks available to unprivileged users.
+    pub blocks_available: u64,
+    /// Total number of inodes.
+    pub files: u64,
+    /// Number of free inodes.
+    pub files_free: u64,
+    /// Maximum length of a file name, in bytes.
+    pub name_max: u64,
+}
 
 /// Node (file/directory) attributes.
 #[allow(dead_code)]
@@ -16,6 +38,35 @@ pub struct VfsNodeAttr {
     size: u64,
     /// Number of 512B blocks allocated.
     blocks: u64,
+    /// User ID of the owner.
+    uid: u32,
+    /// Group ID of the owner.
+    gid: u32,
+    /// Time of the last access.
+    atime: Duration,
+    /// Time of the last modification of the content.
+    mtime: Duration,
+    /// Time of the last change of the content or the attributes.
+    ctime: Duration,
+}
+
+/// Changes to node attributes, used by [`set_attr`].
+///
+/// The attributes which are `None` are left unchanged.
+///
+/// [`set_attr`]: crate::VfsNodeOps::set_attr
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VfsSetAttr {
+    /// New permission mode.
+    pub mode: Option<VfsNodePerm>,
+    /// New user ID of the owner.
+    pub uid: Option<u32>,
+    /// New group ID of the owner.
+    pub gid: Option<u32>,
+    /// New time of the last access.
+    pub atime: Option<Duration>,
+    /// New time of the last modification.
+    pub mtime: Option<Duration>,
 }
 
 bitflags::bitflags! {
@@ -205,6 +256,11 @@ impl VfsNodeAttr {
             ty,
             size,
             blocks,
+            uid: 0,
+            gid: 0,
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
         }
     }
 
@@ -215,6 +271,11 @@ impl VfsNodeAttr {
             ty: VfsNodeType::File,
             size,
             blocks,
+            uid: 0,
+            gid: 0,
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
         }
     }
 
@@ -226,6 +287,11 @@ impl VfsNodeAttr {
             ty: VfsNodeType::Dir,
             size,
             blocks,
+            uid: 0,
+            gid: 0,
+            atime: Duration::ZERO,
+            mtime: Duration::ZERO,
+            ctime: Duration::ZERO,
         }
     }
 
@@ -249,6 +315,44 @@ impl VfsNodeAttr {
         self.mode = perm
     }
 
+    /// Returns the user ID of the owner.
+    pub const fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the group ID of the owner.
+    pub const fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Sets the user ID and the group ID of the owner.
+    pub fn set_owner(&mut self, uid: u32, gid: u32) {
+        self.uid = uid;
+        self.gid = gid;
+    }
+
+    /// Returns the time of the last access.
+    pub const fn atime(&self) -> Duration {
+        self.atime
+    }
+
+    /// Returns the time of the last modification of the content.
+    pub const fn mtime(&self) -> Duration {
+        self.mtime
+    }
+
+    /// Returns the time of the last change of the content or the attributes.
+    pub const fn ctime(&self) -> Duration {
+        self.ctime
+    }
+
+    /// Sets the time of the last access, modification and status change.
+    pub fn set_times(&mut self, atime: Duration, mtime: Duration, ctime: Duration) {
+        self.atime = atime;
+        self.mtime = mtime;
+        self.ctime = ctime;
+    }
+
     /// Returns the type of the node.
     pub const fn file_type(&self) -> VfsNodeType {
         self.ty
@@ -263,6 +367,11 @@ impl VfsNodeAttr {
     pub const fn is_dir(&self) -> bool {
         self.ty.is_dir()
     }
+
+    /// Whether the node is a symbolic link.
+    pub const fn is_symlink(&self) -> bool {
+        self.ty.is_symlink()
+    }
 }
 
 impl VfsDirEntry {
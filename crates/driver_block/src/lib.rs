@@ -1,9 +1,13 @@
 //! Common traits and types for block storage device drivers (i.e. disk).
 
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![feature(doc_auto_cfg)]
 #![feature(const_trait_impl)]
 
+extern crate alloc;
+
+pub mod partition;
+
 #[cfg(feature = "ramdisk")]
 pub mod ramdisk;
 
@@ -22,6 +26,13 @@ pub trait BlockDriverOps: BaseDriverOps {
     /// The size of each block in bytes.
     fn block_size(&self) -> usize;
 
+    /// The first block of this device on the underlying disk, which is not
+    /// zero if the device is a [partition](partition::Partition). The number
+    /// of blocks in the partition is given by `num_blocks()`.
+    fn offset(&self) -> u64 {
+        0
+    }
+
     /// Reads blocked data from the given block.
     ///
     /// The size of the buffer may exceed the block size, in which case multiple
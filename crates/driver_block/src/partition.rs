@@ -0,0 +1,536 @@
+//! Partition tables (MBR and GPT) of block storage devices.
+//!
+//! [`read_partitions`] parses the partition table of a disk, and a
+//! [`Partition`] wraps a disk to access one of its partitions as a device.
+
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{BaseDriverOps, BlockDriverOps, DevError, DevResult, DeviceType};
+
+/// The MBR partition type of the protective partition in a GPT disk.
+const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xee;
+/// The MBR partition types of extended partitions.
+const MBR_TYPES_EXTENDED: [u8; 3] = [0x05, 0x0f, 0x85];
+/// The maximum number of logical partitions in an extended partition, to
+/// stop at loops in the chain.
+const MAX_LOGICAL_PARTITIONS: usize = 128;
+
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+/// The maximum number of entries in a GPT partition array.
+const GPT_MAX_ENTRIES: u32 = 1024;
+
+/// A globally unique identifier, which identifies the type of a GPT partition
+/// or the partition itself.
+///
+/// It is stored in the mixed-endian layout of GPT, and is formatted as
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` in lower case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Guid(pub [u8; 16]);
+
+impl Guid {
+    /// Returns whether all bits of the GUID are zero.
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 16]
+    }
+}
+
+impl fmt::Display for Guid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let b = &self.0;
+        write!(
+            f,
+            "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-",
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            u16::from_le_bytes([b[4], b[5]]),
+            u16::from_le_bytes([b[6], b[7]]),
+            b[8],
+            b[9],
+        )?;
+        b[10..].iter().try_for_each(|x| write!(f, "{:02x}", x))
+    }
+}
+
+impl FromStr for Guid {
+    type Err = DevError;
+
+    /// Parses a GUID in the format of `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`,
+    /// which is case-insensitive.
+    fn from_str(s: &str) -> DevResult<Self> {
+        let groups: Vec<&str> = s.split('-').collect();
+        if groups.iter().map(|g| g.len()).ne([8, 4, 4, 4, 12]) {
+            return Err(DevError::InvalidParam);
+        }
+        let digits = groups.concat();
+        if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(DevError::InvalidParam);
+        }
+        let mut hex = [0u8; 16];
+        for (i, byte) in hex.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        // the first three groups are little-endian
+        hex[0..4].reverse();
+        hex[4..6].reverse();
+        hex[6..8].reverse();
+        Ok(Self(hex))
+    }
+}
+
+/// A partition in the partition table of a disk.
+#[derive(Debug, Clone, Default)]
+pub struct PartitionInfo {
+    /// The partition number, starting from 1. The logical partitions in an
+    /// MBR extended partition are numbered from 5.
+    pub number: usize,
+    /// The first block of the partition on the disk.
+    pub start: u64,
+    /// The number of blocks in the partition.
+    pub num_blocks: u64,
+    /// The partition type in the MBR, or 0 for GPT partitions.
+    pub mbr_type: u8,
+    /// The partition type GUID, or zero for MBR partitions.
+    pub type_guid: Guid,
+    /// The unique partition GUID, or zero for MBR partitions.
+    pub part_guid: Guid,
+    /// The partition name, which is empty for MBR partitions.
+    pub label: String,
+}
+
+/// Reads the partition table of the disk `dev`.
+///
+/// Returns an empty list if the disk has no partition table, in which case
+/// the whole disk may contain a filesystem. GPT is used if the MBR contains a
+/// protective partition, and the backup GPT is used if the primary one is
+/// corrupted.
+pub fn read_partitions<D: BlockDriverOps + ?Sized>(dev: &mut D) -> DevResult<Vec<PartitionInfo>> {
+    let mut mbr = vec![0; dev.block_size()];
+    if mbr.len() < 512 || dev.num_blocks() == 0 {
+        return Ok(Vec::new());
+    }
+    dev.read_block(0, &mut mbr)?;
+    if mbr[510..512] != [0x55, 0xaa] {
+        return Ok(Vec::new());
+    }
+    let entries = mbr_entries(&mbr);
+    if entries.iter().any(|e| e.1 == MBR_TYPE_GPT_PROTECTIVE) {
+        return match read_gpt(dev, 1)? {
+            Some(parts) => Ok(parts),
+            None => Ok(read_gpt(dev, dev.num_blocks() - 1)?.unwrap_or_default()),
+        };
+    }
+    // A FAT boot sector also ends with 0x55aa, but its partition entries are
+    // part of the boot code, so check that the entries are sane.
+    let valid = entries.iter().all(|&(status, ty, start, len)| {
+        (status == 0 || status == 0x80)
+            && (ty == 0 || (start > 0 && start + len <= dev.num_blocks()))
+    });
+    if !valid {
+        return Ok(Vec::new());
+    }
+
+    let mut parts = Vec::new();
+    for (i, &(_, ty, start, len)) in entries.iter().enumerate() {
+        if ty == 0 || len == 0 {
+            continue;
+        }
+        if MBR_TYPES_EXTENDED.contains(&ty) {
+            read_logical_partitions(dev, start, len, &mut parts)?;
+        } else {
+            parts.push(PartitionInfo {
+                number: i + 1,
+                start,
+                num_blocks: len,
+                mbr_type: ty,
+                ..Default::default()
+            });
+        }
+    }
+    parts.sort_by_key(|p| p.number);
+    Ok(parts)
+}
+
+/// Returns the status, type, first block and number of blocks of the 4
+/// primary partitions in an MBR (or an EBR).
+fn mbr_entries(mbr: &[u8]) -> [(u8, u8, u64, u64); 4] {
+    core::array::from_fn(|i| {
+        let e = &mbr[446 + i * 16..446 + (i + 1) * 16];
+        (e[0], e[4], read_u32(e, 8) as u64, read_u32(e, 12) as u64)
+    })
+}
+
+/// Reads the chain of EBRs in the extended partition from block `ext_start`,
+/// and appends the logical partitions to `parts`.
+fn read_logical_partitions<D: BlockDriverOps + ?Sized>(
+    dev: &mut D,
+    ext_start: u64,
+    ext_len: u64,
+    parts: &mut Vec<PartitionInfo>,
+) -> DevResult {
+    let mut ebr = vec![0; dev.block_size()];
+    let mut ebr_start = ext_start;
+    for number in 5..5 + MAX_LOGICAL_PARTITIONS {
+        dev.read_block(ebr_start, &mut ebr)?;
+        if ebr[510..512] != [0x55, 0xaa] {
+            break;
+        }
+        let entries = mbr_entries(&ebr);
+        // The first entry is relative to this EBR, and the second one (the
+        // next EBR) is relative to the extended partition.
+        let (_, ty, start, len) = entries[0];
+        let start = ebr_start + start;
+        if ty != 0 && len != 0 && start + len <= ext_start + ext_len {
+            parts.push(PartitionInfo {
+                number,
+                start,
+                num_blocks: len,
+                mbr_type: ty,
+                ..Default::default()
+            });
+        }
+        let (_, next_ty, next_start, _) = entries[1];
+        if next_ty == 0 || next_start == 0 || next_start >= ext_len {
+            break;
+        }
+        ebr_start = ext_start + next_start;
+    }
+    Ok(())
+}
+
+/// Reads the GPT whose header is at block `header_lba`. Returns `None` if the
+/// header or the partition array is invalid.
+fn read_gpt<D: BlockDriverOps + ?Sized>(
+    dev: &mut D,
+    header_lba: u64,
+) -> DevResult<Option<Vec<PartitionInfo>>> {
+    let block_size = dev.block_size();
+    let mut header = vec![0; block_size];
+    dev.read_block(header_lba, &mut header)?;
+    let header_size = read_u32(&header, 12) as usize;
+    if &header[..8] != GPT_SIGNATURE || !(92..=block_size).contains(&header_size) {
+        return Ok(None);
+    }
+    let header_crc = read_u32(&header, 16);
+    header[16..20].fill(0);
+    if crc32(&header[..header_size]) != header_crc || read_u64(&header, 24) != header_lba {
+        return Ok(None);
+    }
+
+    let entries_lba = read_u64(&header, 72);
+    let num_entries = read_u32(&header, 80);
+    let entry_size = read_u32(&header, 84) as usize;
+    if num_entries > GPT_MAX_ENTRIES || entry_size < 128 || entry_size % 8 != 0 {
+        return Ok(None);
+    }
+    let array_size = num_entries as usize * entry_size;
+    let mut array = vec![0; array_size.div_ceil(block_size) * block_size];
+    if entries_lba + (array.len() / block_size) as u64 > dev.num_blocks() {
+        return Ok(None);
+    }
+    dev.read_block(entries_lba, &mut array)?;
+    if crc32(&array[..array_size]) != read_u32(&header, 88) {
+        return Ok(None);
+    }
+
+    let mut parts = Vec::new();
+    for (i, entry) in array[..array_size].chunks_exact(entry_size).enumerate() {
+        let type_guid = Guid(entry[0..16].try_into().unwrap());
+        let (first, last) = (read_u64(entry, 32), read_u64(entry, 40));
+        if type_guid.is_zero() || first > last || last >= dev.num_blocks() {
+            continue;
+        }
+        let name: Vec<u16> = entry[56..128]
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|&c| u16::from_le_bytes(c))
+            .take_while(|&c| c != 0)
+            .collect();
+        parts.push(PartitionInfo {
+            number: i + 1,
+            start: first,
+            num_blocks: last - first + 1,
+            mbr_type: 0,
+            type_guid,
+            part_guid: Guid(entry[16..32].try_into().unwrap()),
+            label: String::from_utf16_lossy(&name),
+        });
+    }
+    Ok(Some(parts))
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+/// The CRC-32 (IEEE 802.3) used by GPT.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (!(crc & 1)).wrapping_add(1));
+        }
+    }
+    !crc
+}
+
+/// A partition of a disk, which is accessed as a block device.
+///
+/// The blocks are numbered from the start of the partition, and accesses
+/// beyond the end of the partition fail.
+pub struct Partition<D> {
+    dev: D,
+    start: u64,
+    num_blocks: u64,
+}
+
+impl<D: BlockDriverOps> Partition<D> {
+    /// Creates a partition of the disk `dev` described by `info`.
+    pub fn new(dev: D, info: &PartitionInfo) -> Self {
+        Self {
+            dev,
+            start: info.start,
+            num_blocks: info.num_blocks,
+        }
+    }
+
+    /// Returns the underlying disk.
+    pub fn into_inner(self) -> D {
+        self.dev
+    }
+
+    fn check_range(&self, block_id: u64, len: usize) -> DevResult {
+        let count = len.div_ceil(self.dev.block_size()) as u64;
+        match block_id.checked_add(count) {
+            Some(end) if end <= self.num_blocks => Ok(()),
+            _ => Err(DevError::InvalidParam),
+        }
+    }
+}
+
+impl<D: BlockDriverOps> BaseDriverOps for Partition<D> {
+    fn device_name(&self) -> &str {
+        self.dev.device_name()
+    }
+
+    fn device_type(&self) -> DeviceType {
+        DeviceType::Block
+    }
+}
+
+impl<D: BlockDriverOps> BlockDriverOps for Partition<D> {
+    fn num_blocks(&self) -> u64 {
+        self.num_blocks
+    }
+
+    fn block_size(&self) -> usize {
+        self.dev.block_size()
+    }
+
+    fn offset(&self) -> u64 {
+        self.start
+    }
+
+    fn read_block(&mut self, block_id: u64, buf: &mut [u8]) -> DevResult {
+        self.check_range(block_id, buf.len())?;
+        self.dev.read_block(self.start + block_id, buf)
+    }
+
+    fn write_block(&mut self, block_id: u64, buf: &[u8]) -> DevResult {
+        self.check_range(block_id, buf.len())?;
+        self.dev.write_block(self.start + block_id, buf)
+    }
+
+    fn flush(&mut self) -> DevResult {
+        self.dev.flush()
+    }
+}
+
+#[cfg(all(test, feature = "ramdisk"))]
+mod tests {
+    use super::*;
+    use crate::ramdisk::RamDisk;
+
+    const BLOCK_SIZE: usize = 512;
+
+    fn write_block(disk: &mut RamDisk, block_id: u64, data: &[u8]) {
+        let mut buf = [0; BLOCK_SIZE];
+        buf[..data.len()].copy_from_slice(data);
+        disk.write_block(block_id, &buf).unwrap();
+    }
+
+    fn mbr(entries: &[(u8, u32, u32)]) -> [u8; BLOCK_SIZE] {
+        let mut mbr = [0; BLOCK_SIZE];
+        for (i, &(ty, start, len)) in entries.iter().enumerate() {
+            let e = &mut mbr[446 + i * 16..446 + (i + 1) * 16];
+            e[4] = ty;
+            e[8..12].copy_from_slice(&start.to_le_bytes());
+            e[12..16].copy_from_slice(&len.to_le_bytes());
+        }
+        mbr[510..].copy_from_slice(&[0x55, 0xaa]);
+        mbr
+    }
+
+    /// Writes a GPT with the header at `header_lba` and the entries at
+    /// `entries_lba`.
+    fn write_gpt(disk: &mut RamDisk, header_lba: u64, entries_lba: u64, parts: &[PartitionInfo]) {
+        let mut array = vec![0u8; 128 * 128];
+        for (i, part) in parts.iter().enumerate() {
+            let e = &mut array[i * 128..(i + 1) * 128];
+            e[0..16].copy_from_slice(&part.type_guid.0);
+            e[16..32].copy_from_slice(&part.part_guid.0);
+            e[32..40].copy_from_slice(&part.start.to_le_bytes());
+            e[40..48].copy_from_slice(&(part.start + part.num_blocks - 1).to_le_bytes());
+            for (j, c) in part.label.encode_utf16().enumerate() {
+                e[56 + j * 2..58 + j * 2].copy_from_slice(&c.to_le_bytes());
+            }
+        }
+        let mut header = [0u8; 92];
+        header[..8].copy_from_slice(GPT_SIGNATURE);
+        header[8..12].copy_from_slice(&0x10000u32.to_le_bytes());
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[24..32].copy_from_slice(&header_lba.to_le_bytes());
+        header[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+        header[80..84].copy_from_slice(&128u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        header[88..92].copy_from_slice(&crc32(&array).to_le_bytes());
+        let crc = crc32(&header);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+        write_block(disk, header_lba, &header);
+        disk.write_block(entries_lba, &array).unwrap();
+    }
+
+    #[test]
+    fn test_guid() {
+        let s = "4f68bce3-e8cd-4db1-96e7-fbcaf984b709";
+        let guid: Guid = s.parse().unwrap();
+        assert_eq!(guid.0[..4], [0xe3, 0xbc, 0x68, 0x4f]);
+        assert_eq!(guid.0[8..10], [0x96, 0xe7]);
+        assert_eq!(guid.to_string(), s);
+        assert_eq!(s.to_uppercase().parse::<Guid>().unwrap(), guid);
+        assert!("4f68bce3-e8cd-4db1-96e7".parse::<Guid>().is_err());
+        assert!("4f68bce3-e8cd-4db1-96e7-fbcaf984b7xx"
+            .parse::<Guid>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_no_partition_table() {
+        let mut disk = RamDisk::new(BLOCK_SIZE * 64);
+        assert!(read_partitions(&mut disk).unwrap().is_empty());
+
+        // a FAT boot sector with boot code in the partition entries
+        let mut boot = [0xccu8; BLOCK_SIZE];
+        boot[510..].copy_from_slice(&[0x55, 0xaa]);
+        write_block(&mut disk, 0, &boot);
+        assert!(read_partitions(&mut disk).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mbr() {
+        let mut disk = RamDisk::new(BLOCK_SIZE * 1024);
+        write_block(
+            &mut disk,
+            0,
+            &mbr(&[
+                (0x0c, 8, 100),
+                (0, 0, 0),
+                (0x05, 200, 300),
+                (0x83, 600, 400),
+            ]),
+        );
+        // two logical partitions in the extended partition
+        write_block(&mut disk, 200, &mbr(&[(0x83, 2, 48), (0x05, 50, 100)]));
+        write_block(&mut disk, 250, &mbr(&[(0x82, 10, 90)]));
+
+        let parts = read_partitions(&mut disk).unwrap();
+        let summary: Vec<_> = parts
+            .iter()
+            .map(|p| (p.number, p.mbr_type, p.start, p.num_blocks))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, 0x0c, 8, 100),
+                (4, 0x83, 600, 400),
+                (5, 0x83, 202, 48),
+                (6, 0x82, 260, 90)
+            ]
+        );
+
+        write_block(&mut disk, 600, b"partition 4");
+        let mut part = Partition::new(disk, &parts[1]);
+        assert_eq!(part.num_blocks(), 400);
+        assert_eq!(part.offset(), 600);
+        let mut buf = [0; BLOCK_SIZE];
+        part.read_block(0, &mut buf).unwrap();
+        assert_eq!(&buf[..11], b"partition 4");
+        assert!(part.read_block(399, &mut buf).is_ok());
+        assert!(part.read_block(400, &mut buf).is_err());
+        assert!(part.write_block(399, &[0; BLOCK_SIZE * 2]).is_err());
+    }
+
+    #[test]
+    fn test_gpt() {
+        const NUM_BLOCKS: u64 = 1024;
+        let mut disk = RamDisk::new(BLOCK_SIZE * NUM_BLOCKS as usize);
+        let parts = [
+            PartitionInfo {
+                number: 1,
+                start: 64,
+                num_blocks: 100,
+                type_guid: "c12a7328-f81f-11d2-ba4b-00a0c93ec93b".parse().unwrap(),
+                part_guid: "01234567-89ab-cdef-0123-456789abcdef".parse().unwrap(),
+                label: "EFI system".into(),
+                ..Default::default()
+            },
+            PartitionInfo {
+                number: 2,
+                start: 200,
+                num_blocks: 700,
+                type_guid: "4f68bce3-e8cd-4db1-96e7-fbcaf984b709".parse().unwrap(),
+                part_guid: "76543210-89ab-cdef-0123-456789abcdef".parse().unwrap(),
+                label: "root".into(),
+                ..Default::default()
+            },
+        ];
+        write_block(&mut disk, 0, &mbr(&[(0xee, 1, NUM_BLOCKS as u32 - 1)]));
+        write_gpt(&mut disk, 1, 2, &parts);
+        write_gpt(&mut disk, NUM_BLOCKS - 1, NUM_BLOCKS - 33, &parts);
+
+        let check = |disk: &mut RamDisk| {
+            let result = read_partitions(disk).unwrap();
+            assert_eq!(result.len(), 2);
+            for (a, b) in result.iter().zip(&parts) {
+                assert_eq!(a.number, b.number);
+                assert_eq!((a.start, a.num_blocks), (b.start, b.num_blocks));
+                assert_eq!((a.type_guid, a.part_guid), (b.type_guid, b.part_guid));
+                assert_eq!(a.label, b.label);
+            }
+        };
+        check(&mut disk);
+
+        // corrupt the primary GPT, then the backup one is used
+        let mut buf = [0; BLOCK_SIZE];
+        disk.read_block(2, &mut buf).unwrap();
+        buf[0] ^= 1;
+        disk.write_block(2, &buf).unwrap();
+        check(&mut disk);
+
+        // both are corrupted
+        write_block(&mut disk, NUM_BLOCKS - 1, &[0; 8]);
+        assert!(read_partitions(&mut disk).unwrap().is_empty());
+    }
+}
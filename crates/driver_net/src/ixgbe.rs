@@ -136,6 +136,12 @@ impl<H: IxgbeHal, const QS: usize, const QN: u16> NetDriverOps for IxgbeNic<H, Q
         let tx_buf = IxgbeNetBuf::alloc(&self.mem_pool, size).map_err(|_| DevError::NoMemory)?;
         Ok(NetBufPtr::from(tx_buf))
     }
+
+    fn dealloc_tx_buffer(&mut self, tx_buf: NetBufPtr) -> DevResult {
+        let tx_buf = ixgbe_ptr_to_buf(tx_buf, &self.mem_pool)?;
+        drop(tx_buf);
+        Ok(())
+    }
 }
 
 impl From<IxgbeNetBuf> for NetBufPtr {
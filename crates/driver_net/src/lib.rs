@@ -64,6 +64,10 @@ pub trait NetDriverOps: BaseDriverOps {
     /// Allocate a memory buffer of a specified size for network transmission,
     /// returns [`DevResult`]
     fn alloc_tx_buffer(&mut self, size: usize) -> DevResult<NetBufPtr>;
+
+    /// Gives back a buffer allocated by [`NetDriverOps::alloc_tx_buffer`]
+    /// which is not transmitted.
+    fn dealloc_tx_buffer(&mut self, tx_buf: NetBufPtr) -> DevResult;
 }
 
 /// A raw buffer struct for network device.
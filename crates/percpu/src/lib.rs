@@ -61,6 +61,9 @@ pub mod __priv {
 
 cfg_if::cfg_if! {
     if #[cfg(doc)] {
+        #[allow(unused_imports)]
+        use crate as percpu;
+
         /// Example per-CPU data for documentation only.
         #[doc(cfg(doc))]
         #[def_percpu]
@@ -99,4 +99,50 @@ fn test_percpu() {
         assert_eq!(s.foo, 0x2333);
         assert_eq!(s.bar, 100);
     });
+
+    test_remote_access();
+}
+
+#[cfg(all(target_os = "linux", not(feature = "sp-naive")))]
+fn test_remote_access() {
+    // test remote write
+    unsafe {
+        *U32.remote_ref_mut_raw(1) = 0xcafe_babe;
+        *USIZE.remote_ref_mut_raw(1) = 0xfeed_0000;
+        STRUCT.remote_ref_mut_raw(1).foo = 0x6666;
+        STRUCT.remote_ref_mut_raw(1).bar = 200;
+    }
+
+    // the values on the current CPU are not affected
+    assert_eq!(U32.read_current(), 0xdead_beef);
+    assert_eq!(USIZE.read_current(), 0xffff_0000);
+
+    // test remote read
+    unsafe {
+        assert_eq!(*U32.remote_ref_raw(0), 0xdead_beef);
+        assert_eq!(*U32.remote_ref_raw(1), 0xcafe_babe);
+        assert_eq!(*USIZE.remote_ref_raw(1), 0xfeed_0000);
+        assert_eq!(STRUCT.remote_ref_raw(1).foo, 0x6666);
+        assert_eq!(STRUCT.remote_ref_raw(1).bar, 200);
+    }
+
+    // test read on another CPU
+    set_local_thread_pointer(1);
+    assert_eq!(U32.read_current(), 0xcafe_babe);
+    assert_eq!(USIZE.read_current(), 0xfeed_0000);
+    STRUCT.with_current(|s| {
+        assert_eq!(s.foo, 0x6666);
+        assert_eq!(s.bar, 200);
+    });
+    set_local_thread_pointer(0);
+}
+
+#[cfg(all(target_os = "linux", feature = "sp-naive"))]
+fn test_remote_access() {
+    // all CPUs share the same data
+    unsafe {
+        *U32.remote_ref_mut_raw(1) = 0xcafe_babe;
+        assert_eq!(*U32.remote_ref_raw(0), 0xcafe_babe);
+    }
+    assert_eq!(U32.read_current(), 0xcafe_babe);
 }
@@ -0,0 +1,207 @@
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::BaseScheduler;
+
+/// A task wrapper for the [`EdfScheduler`].
+///
+/// It adds the reservation (period and budget) of the task, and its current
+/// deadline and remaining budget. All times are measured in timer ticks.
+pub struct EdfTask<T> {
+    inner: T,
+    period: AtomicU64,
+    budget: AtomicU64,
+    deadline: AtomicU64,
+    remaining: AtomicU64,
+    id: AtomicU64,
+}
+
+impl<T> EdfTask<T> {
+    /// Creates a new [`EdfTask`] from the inner task struct, without a
+    /// reservation.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            period: AtomicU64::new(0),
+            budget: AtomicU64::new(0),
+            deadline: AtomicU64::new(u64::MAX),
+            remaining: AtomicU64::new(0),
+            id: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the period and the budget of the task.
+    pub fn params(&self) -> (u64, u64) {
+        (
+            self.period.load(Ordering::Acquire),
+            self.budget.load(Ordering::Acquire),
+        )
+    }
+
+    /// Reserves `budget` ticks in every `period` ticks for the task, its
+    /// relative deadline is the end of the period. A zero `period` removes
+    /// the reservation, and the task only runs when no task with a
+    /// reservation is ready.
+    ///
+    /// Returns `false` if `budget` is zero or larger than `period`.
+    ///
+    /// It takes effect when the task is put into the scheduler next time, so
+    /// it must not be called while the task is in the ready queue.
+    pub fn set_params(&self, period: u64, budget: u64) -> bool {
+        if period != 0 && (budget == 0 || budget > period) {
+            return false;
+        }
+        self.period.store(period, Ordering::Release);
+        self.budget.store(budget, Ordering::Release);
+        // Start a new period when it is put into the scheduler.
+        let deadline = if period == 0 { u64::MAX } else { 0 };
+        self.deadline.store(deadline, Ordering::Release);
+        self.remaining.store(0, Ordering::Release);
+        true
+    }
+
+    /// Returns the absolute deadline of the current period, or [`u64::MAX`]
+    /// if the task has no reservation.
+    pub fn deadline(&self) -> u64 {
+        self.deadline.load(Ordering::Acquire)
+    }
+
+    fn is_periodic(&self) -> bool {
+        self.period.load(Ordering::Acquire) != 0
+    }
+
+    fn key(&self) -> (u64, u64) {
+        (self.deadline(), self.id.load(Ordering::Acquire))
+    }
+
+    /// Starts a new period at `now`.
+    fn replenish(&self, now: u64) {
+        let (period, budget) = self.params();
+        let mut deadline = self.deadline().saturating_add(period);
+        if deadline <= now {
+            deadline = now + period;
+        }
+        self.deadline.store(deadline, Ordering::Release);
+        self.remaining.store(budget, Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> Deref for EdfTask<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// An [Earliest Deadline First][1] (EDF) preemptive scheduler.
+///
+/// A task with a reservation (see [`EdfTask::set_params`]) gets `budget` ticks
+/// in every `period` ticks, and the ready task with the earliest deadline
+/// always runs first. When a task uses up its budget, its deadline is
+/// postponed by a period and the budget is replenished, like the soft
+/// [Constant Bandwidth Server][2], so that an overrunning task does not
+/// affect the others. Tasks without reservations are scheduled in FIFO order
+/// after all tasks with reservations.
+///
+/// The clock of the scheduler only advances at [`task_tick`], so the time
+/// that the CPU is idle is not counted.
+///
+/// [1]: https://en.wikipedia.org/wiki/Earliest_deadline_first_scheduling
+/// [2]: https://en.wikipedia.org/wiki/Constant_bandwidth_server
+/// [`task_tick`]: BaseScheduler::task_tick
+pub struct EdfScheduler<T> {
+    ready_queue: BTreeMap<(u64, u64), Arc<EdfTask<T>>>, // (deadline, taskid)
+    clock: u64,
+    id_pool: u64,
+}
+
+impl<T> EdfScheduler<T> {
+    /// Creates a new empty [`EdfScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            ready_queue: BTreeMap::new(),
+            clock: 0,
+            id_pool: 0,
+        }
+    }
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Earliest Deadline First"
+    }
+
+    /// Returns the current time of the scheduler in ticks.
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    fn insert(&mut self, task: Arc<EdfTask<T>>) {
+        task.id.store(self.id_pool, Ordering::Release);
+        self.id_pool += 1;
+        self.ready_queue.insert(task.key(), task);
+    }
+}
+
+impl<T> BaseScheduler for EdfScheduler<T> {
+    type SchedItem = Arc<EdfTask<T>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        // A task woken up after its deadline starts a new period, otherwise
+        // it continues with the remaining budget.
+        if task.is_periodic() && task.deadline() <= self.clock {
+            task.replenish(self.clock);
+        }
+        self.insert(task);
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        self.ready_queue.remove(&task.key())
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        self.ready_queue.pop_first().map(|(_, t)| t)
+    }
+
+    fn pick_next_task_if<F>(&mut self, mut filter: F) -> Option<Self::SchedItem>
+    where
+        F: FnMut(&Self::SchedItem) -> bool,
+    {
+        let key = *self.ready_queue.iter().find(|(_, t)| filter(t))?.0;
+        self.ready_queue.remove(&key)
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, _preempt: bool) {
+        if prev.is_periodic()
+            && (prev.remaining.load(Ordering::Acquire) == 0 || prev.deadline() <= self.clock)
+        {
+            prev.replenish(self.clock);
+        }
+        self.insert(prev);
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        self.clock += 1;
+        if current.is_periodic() {
+            let remaining = current.remaining.load(Ordering::Acquire).saturating_sub(1);
+            current.remaining.store(remaining, Ordering::Release);
+            if remaining == 0 {
+                return true;
+            }
+        }
+        self.ready_queue
+            .first_key_value()
+            .is_some_and(|((deadline, _), _)| *deadline < current.deadline())
+    }
+
+    fn set_priority(&mut self, _task: &Self::SchedItem, _prio: isize) -> bool {
+        false
+    }
+}
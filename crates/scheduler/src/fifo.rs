@@ -1,4 +1,5 @@
 use alloc::sync::Arc;
+use core::mem::ManuallyDrop;
 use core::ops::Deref;
 
 use linked_list::{Adapter, Links, List};
@@ -88,6 +89,23 @@ impl<T> BaseScheduler for FifoScheduler<T> {
         self.ready_queue.pop_front()
     }
 
+    fn pick_next_task_if<F>(&mut self, mut filter: F) -> Option<Self::SchedItem>
+    where
+        F: FnMut(&Self::SchedItem) -> bool,
+    {
+        let mut cursor = self.ready_queue.cursor_front();
+        while let Some(task) = cursor.current() {
+            // Safety: the list holds a strong reference of each task, borrow
+            // it without changing the reference count.
+            let task = ManuallyDrop::new(unsafe { Arc::from_raw(task) });
+            if filter(&task) {
+                return unsafe { self.ready_queue.remove(&task) };
+            }
+            cursor.move_next();
+        }
+        None
+    }
+
     fn put_prev_task(&mut self, prev: Self::SchedItem, _preempt: bool) {
         self.ready_queue.push_back(prev);
     }
@@ -5,12 +5,16 @@
 //! - [`FifoScheduler`]: FIFO (First-In-First-Out) scheduler (cooperative).
 //! - [`RRScheduler`]: Round-robin scheduler (preemptive).
 //! - [`CFScheduler`]: Completely Fair Scheduler (preemptive).
+//! - [`PrioScheduler`]: Fixed-priority real-time scheduler (preemptive).
+//! - [`EdfScheduler`]: Earliest Deadline First real-time scheduler (preemptive).
 
 #![cfg_attr(not(test), no_std)]
 #![feature(const_mut_refs)]
 
 mod cfs;
+mod edf;
 mod fifo;
+mod prio;
 mod round_robin;
 
 #[cfg(test)]
@@ -19,7 +23,9 @@ mod tests;
 extern crate alloc;
 
 pub use cfs::{CFSTask, CFScheduler};
+pub use edf::{EdfScheduler, EdfTask};
 pub use fifo::{FifoScheduler, FifoTask};
+pub use prio::{PrioScheduler, PrioTask, SchedPolicy, PRIO_MAX, PRIO_MIN};
 pub use round_robin::{RRScheduler, RRTask};
 
 /// The base scheduler trait that all schedulers should implement.
@@ -49,6 +55,15 @@ pub trait BaseScheduler {
     /// Returns [`None`] if there is not runnable task.
     fn pick_next_task(&mut self) -> Option<Self::SchedItem>;
 
+    /// Picks the first task satisfying `filter`, in the order they would be
+    /// picked by [`pick_next_task`](Self::pick_next_task), and removes it
+    /// from the scheduler. The order of other tasks is not changed.
+    ///
+    /// Returns [`None`] if there is no such task.
+    fn pick_next_task_if<F>(&mut self, filter: F) -> Option<Self::SchedItem>
+    where
+        F: FnMut(&Self::SchedItem) -> bool;
+
     /// Puts the previous task back to the scheduler. The previous task is
     /// usually placed at the end of the ready queue, making it less likely
     /// to be re-scheduled.
@@ -0,0 +1,218 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicIsize, AtomicU8, Ordering};
+
+use crate::BaseScheduler;
+
+/// The highest priority of the [`PrioScheduler`].
+pub const PRIO_MIN: isize = -64;
+/// The lowest priority of the [`PrioScheduler`].
+pub const PRIO_MAX: isize = 63;
+
+const PRIO_LEVELS: usize = (PRIO_MAX - PRIO_MIN + 1) as usize;
+
+/// How the tasks with the same priority are scheduled by the
+/// [`PrioScheduler`].
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// Like `SCHED_FIFO`, the task runs until it blocks, yields, or is
+    /// preempted by a task with a higher priority.
+    Fifo = 0,
+    /// Like `SCHED_RR`, the task is also preempted when its time slice is
+    /// used up, and put at the end of its priority level.
+    RoundRobin = 1,
+}
+
+/// A task wrapper for the [`PrioScheduler`].
+///
+/// It adds the priority, the scheduling policy and a time slice counter.
+pub struct PrioTask<T, const MAX_TIME_SLICE: usize> {
+    inner: T,
+    prio: AtomicIsize,
+    policy: AtomicU8,
+    time_slice: AtomicIsize,
+}
+
+impl<T, const S: usize> PrioTask<T, S> {
+    /// Creates a new [`PrioTask`] from the inner task struct, with priority 0
+    /// and the [`SchedPolicy::RoundRobin`] policy.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            prio: AtomicIsize::new(0),
+            policy: AtomicU8::new(SchedPolicy::RoundRobin as u8),
+            time_slice: AtomicIsize::new(S as isize),
+        }
+    }
+
+    /// Returns the scheduling policy of the task.
+    pub fn policy(&self) -> SchedPolicy {
+        match self.policy.load(Ordering::Acquire) {
+            0 => SchedPolicy::Fifo,
+            _ => SchedPolicy::RoundRobin,
+        }
+    }
+
+    /// Sets the scheduling policy of the task, which takes effect from the
+    /// next timer tick.
+    pub fn set_policy(&self, policy: SchedPolicy) {
+        self.policy.store(policy as u8, Ordering::Release);
+    }
+
+    fn prio(&self) -> isize {
+        self.prio.load(Ordering::Acquire)
+    }
+
+    fn level(&self) -> usize {
+        (self.prio() - PRIO_MIN) as usize
+    }
+
+    fn time_slice(&self) -> isize {
+        self.time_slice.load(Ordering::Acquire)
+    }
+
+    fn reset_time_slice(&self) {
+        self.time_slice.store(S as isize, Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> Deref for PrioTask<T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A preemptive fixed-priority scheduler with multiple priority levels.
+///
+/// The priority ranges from [`PRIO_MIN`] (the highest) to [`PRIO_MAX`] (the
+/// lowest), and a task always runs before the tasks with lower priorities. The
+/// tasks with the same priority are scheduled by their [`SchedPolicy`]s, like
+/// the `SCHED_FIFO` and `SCHED_RR` real-time policies of Linux.
+///
+/// Every priority level has a [`VecDeque`] as the ready queue, and a bitmap of
+/// non-empty levels is used to find the highest one in O(1) time.
+pub struct PrioScheduler<T, const MAX_TIME_SLICE: usize> {
+    ready_queues: [VecDeque<Arc<PrioTask<T, MAX_TIME_SLICE>>>; PRIO_LEVELS],
+    /// Bit `i` is set if the ready queue of level `i` is not empty.
+    bitmap: u128,
+}
+
+impl<T, const S: usize> PrioScheduler<T, S> {
+    /// Creates a new empty [`PrioScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            ready_queues: [const { VecDeque::new() }; PRIO_LEVELS],
+            bitmap: 0,
+        }
+    }
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Fixed-priority"
+    }
+
+    /// Returns the highest non-empty level.
+    fn highest_level(&self) -> Option<usize> {
+        if self.bitmap == 0 {
+            None
+        } else {
+            Some(self.bitmap.trailing_zeros() as usize)
+        }
+    }
+
+    fn update_bitmap(&mut self, level: usize) {
+        if self.ready_queues[level].is_empty() {
+            self.bitmap &= !(1 << level);
+        } else {
+            self.bitmap |= 1 << level;
+        }
+    }
+
+    fn remove_at(&mut self, level: usize, idx: usize) -> Option<Arc<PrioTask<T, S>>> {
+        let task = self.ready_queues[level].remove(idx);
+        self.update_bitmap(level);
+        task
+    }
+}
+
+impl<T, const S: usize> BaseScheduler for PrioScheduler<T, S> {
+    type SchedItem = Arc<PrioTask<T, S>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        let level = task.level();
+        self.ready_queues[level].push_back(task);
+        self.bitmap |= 1 << level;
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        let level = task.level();
+        let idx = self.ready_queues[level]
+            .iter()
+            .position(|t| Arc::ptr_eq(t, task))?;
+        self.remove_at(level, idx)
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        let level = self.highest_level()?;
+        let task = self.ready_queues[level].pop_front();
+        self.update_bitmap(level);
+        task
+    }
+
+    fn pick_next_task_if<F>(&mut self, mut filter: F) -> Option<Self::SchedItem>
+    where
+        F: FnMut(&Self::SchedItem) -> bool,
+    {
+        let (level, idx) = (0..PRIO_LEVELS)
+            .filter(|&level| self.bitmap & (1 << level) != 0)
+            .find_map(|level| {
+                let idx = self.ready_queues[level].iter().position(&mut filter)?;
+                Some((level, idx))
+            })?;
+        self.remove_at(level, idx)
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        let level = prev.level();
+        if preempt && (prev.policy() == SchedPolicy::Fifo || prev.time_slice() > 0) {
+            // Preempted by a higher priority task, keep it at the front.
+            self.ready_queues[level].push_front(prev);
+        } else {
+            prev.reset_time_slice();
+            self.ready_queues[level].push_back(prev);
+        }
+        self.bitmap |= 1 << level;
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        let expired = current.policy() == SchedPolicy::RoundRobin
+            && current.time_slice.fetch_sub(1, Ordering::Release) <= 1;
+        expired || self.highest_level().is_some_and(|l| l < current.level())
+    }
+
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        if !(PRIO_MIN..=PRIO_MAX).contains(&prio) {
+            return false;
+        }
+        if prio != task.prio() {
+            // Move the task to the new level if it is in the ready queue.
+            match self.remove_task(task) {
+                Some(task) => {
+                    task.prio.store(prio, Ordering::Release);
+                    self.add_task(task);
+                }
+                None => task.prio.store(prio, Ordering::Release),
+            }
+        }
+        true
+    }
+}
@@ -28,6 +28,26 @@ macro_rules! def_test_sched {
                 assert_eq!(n, NUM_TASKS);
             }
 
+            #[test]
+            fn test_pick_next_task_if() {
+                const NUM_TASKS: usize = 11;
+
+                let mut scheduler = <$scheduler>::new();
+                for i in 0..NUM_TASKS {
+                    scheduler.add_task(Arc::new(<$task>::new(i)));
+                }
+
+                let next = scheduler.pick_next_task_if(|t| *t.inner() % 4 == 3);
+                assert_eq!(*next.unwrap().inner(), 3);
+                assert!(scheduler.pick_next_task_if(|t| *t.inner() == 3).is_none());
+
+                // Other tasks are still in the original order.
+                for i in (0..NUM_TASKS).filter(|&i| i != 3) {
+                    assert_eq!(*scheduler.pick_next_task().unwrap().inner(), i);
+                }
+                assert!(scheduler.pick_next_task_if(|_| true).is_none());
+            }
+
             #[test]
             fn bench_yield() {
                 const NUM_TASKS: usize = 1_000_000;
@@ -82,3 +102,151 @@ macro_rules! def_test_sched {
 def_test_sched!(fifo, FifoScheduler::<usize>, FifoTask::<usize>);
 def_test_sched!(rr, RRScheduler::<usize, 5>, RRTask::<usize, 5>);
 def_test_sched!(cfs, CFScheduler::<usize>, CFSTask::<usize>);
+def_test_sched!(prio, PrioScheduler::<usize, 5>, PrioTask::<usize, 5>);
+def_test_sched!(edf, EdfScheduler::<usize>, EdfTask::<usize>);
+
+mod prio_levels {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    type Task = Arc<PrioTask<usize, 2>>;
+
+    fn new_task(scheduler: &mut PrioScheduler<usize, 2>, id: usize, prio: isize) -> Task {
+        let task = Arc::new(PrioTask::new(id));
+        assert!(scheduler.set_priority(&task, prio));
+        scheduler.add_task(task.clone());
+        task
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let mut scheduler = PrioScheduler::new();
+        new_task(&mut scheduler, 0, 10);
+        new_task(&mut scheduler, 1, PRIO_MIN);
+        new_task(&mut scheduler, 2, 10);
+        new_task(&mut scheduler, 3, PRIO_MAX);
+        new_task(&mut scheduler, 4, -5);
+        assert!(!scheduler.set_priority(&Arc::new(PrioTask::new(5)), PRIO_MAX + 1));
+
+        let order: Vec<_> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| *t.inner())
+            .collect();
+        assert_eq!(order, [1, 4, 0, 2, 3]);
+    }
+
+    #[test]
+    fn test_preempt_by_higher_priority() {
+        let mut scheduler = PrioScheduler::new();
+        new_task(&mut scheduler, 0, 0);
+        let curr = scheduler.pick_next_task().unwrap();
+        curr.set_policy(SchedPolicy::Fifo);
+        assert!(!scheduler.task_tick(&curr));
+
+        // A lower priority task does not preempt the current task.
+        new_task(&mut scheduler, 1, 1);
+        assert!(!scheduler.task_tick(&curr));
+        let high = new_task(&mut scheduler, 2, -1);
+        assert!(scheduler.task_tick(&curr));
+
+        // The preempted task stays at the front of its level.
+        new_task(&mut scheduler, 3, 0);
+        scheduler.put_prev_task(curr, true);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &high));
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 0);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 3);
+    }
+
+    #[test]
+    fn test_policy() {
+        let mut scheduler = PrioScheduler::new();
+        let fifo = new_task(&mut scheduler, 0, 0);
+        fifo.set_policy(SchedPolicy::Fifo);
+        let rr = new_task(&mut scheduler, 1, 0);
+        assert_eq!(rr.policy(), SchedPolicy::RoundRobin);
+
+        // A FIFO task is never preempted by the tasks with the same priority.
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &fifo));
+        for _ in 0..10 {
+            assert!(!scheduler.task_tick(&curr));
+        }
+        scheduler.put_prev_task(curr, false);
+
+        // A round-robin task is preempted when its time slice is used up.
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &rr));
+        assert!(!scheduler.task_tick(&curr));
+        assert!(scheduler.task_tick(&curr));
+        scheduler.put_prev_task(curr, true);
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &fifo));
+    }
+
+    #[test]
+    fn test_set_priority_in_queue() {
+        let mut scheduler = PrioScheduler::new();
+        new_task(&mut scheduler, 0, 0);
+        let task = new_task(&mut scheduler, 1, 1);
+        assert!(scheduler.set_priority(&task, -1));
+        assert!(Arc::ptr_eq(&scheduler.pick_next_task().unwrap(), &task));
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 0);
+        assert!(scheduler.pick_next_task().is_none());
+    }
+}
+
+mod edf_deadlines {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    fn new_task(
+        scheduler: &mut EdfScheduler<usize>,
+        id: usize,
+        period: u64,
+        budget: u64,
+    ) -> Arc<EdfTask<usize>> {
+        let task = Arc::new(EdfTask::new(id));
+        assert!(task.set_params(period, budget));
+        scheduler.add_task(task.clone());
+        task
+    }
+
+    #[test]
+    fn test_deadline_order() {
+        let mut scheduler = EdfScheduler::new();
+        new_task(&mut scheduler, 0, 0, 0);
+        new_task(&mut scheduler, 1, 30, 10);
+        new_task(&mut scheduler, 2, 10, 5);
+        new_task(&mut scheduler, 3, 20, 5);
+        assert!(!EdfTask::new(4).set_params(10, 20));
+
+        let order: Vec<_> = core::iter::from_fn(|| scheduler.pick_next_task())
+            .map(|t| (*t.inner(), t.deadline()))
+            .collect();
+        assert_eq!(order, [(2, 10), (3, 20), (1, 30), (0, u64::MAX)]);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut scheduler = EdfScheduler::new();
+        let short = new_task(&mut scheduler, 0, 10, 3);
+        let long = new_task(&mut scheduler, 1, 20, 10);
+
+        // The task with the earliest deadline runs until its budget is used up.
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &short));
+        assert!(!scheduler.task_tick(&curr));
+        assert!(!scheduler.task_tick(&curr));
+        assert!(scheduler.task_tick(&curr));
+
+        // Then its deadline is postponed by a period.
+        scheduler.put_prev_task(curr, true);
+        assert_eq!(short.deadline(), 20);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &long));
+        assert_eq!(scheduler.clock(), 3);
+
+        // A task without a reservation is preempted by any task with one.
+        scheduler.put_prev_task(curr, false);
+        let best_effort = Arc::new(EdfTask::new(2));
+        assert!(scheduler.task_tick(&best_effort));
+    }
+}
@@ -1,16 +1,35 @@
+use alloc::{format, string::String, vec};
+use core::fmt::Write;
+
+use crate::model::{self, DeviceAttr};
 use crate::{prelude::*, AllDevices};
 use axhal::mem::phys_to_virt;
 use driver_pci::{
-    BarInfo, Cam, Command, DeviceFunction, HeaderType, MemoryBarType, PciRangeAllocator, PciRoot,
+    BarInfo, Cam, Command, DeviceFunction, DeviceFunctionInfo, HeaderType, MemoryBarType,
+    PciRangeAllocator, PciRoot,
 };
 
 const PCI_BAR_NUM: u8 = 6;
 
+// Flags of the BARs in the `resource` attribute, the same as Linux.
+const IORESOURCE_IO: u64 = 0x100;
+const IORESOURCE_MEM: u64 = 0x200;
+const IORESOURCE_PREFETCH: u64 = 0x2000;
+const IORESOURCE_MEM_64: u64 = 0x10_0000;
+
+/// Assigns addresses to the BARs and enables the device. Returns the BARs in
+/// the format of the `resource` attribute, one line per BAR.
 fn config_pci_device(
     root: &mut PciRoot,
     bdf: DeviceFunction,
     allocator: &mut Option<PciRangeAllocator>,
-) -> DevResult {
+) -> DevResult<String> {
+    let mut resources = String::new();
+    let mut resource = |start: u64, size: u64, flags: u64| {
+        let end = if size > 0 { start + size - 1 } else { 0 };
+        writeln!(resources, "{:#018x} {:#018x} {:#018x}", start, end, flags).unwrap();
+    };
+
     let mut bar = 0;
     while bar < PCI_BAR_NUM {
         let info = root.bar_info(bdf, bar).unwrap();
@@ -42,6 +61,9 @@ fn config_pci_device(
             BarInfo::IO { address, size } => {
                 if address > 0 && size > 0 {
                     debug!("  BAR {}: IO  [{:#x}, {:#x})", bar, address, address + size);
+                    resource(address as u64, size as u64, IORESOURCE_IO);
+                } else {
+                    resource(0, 0, 0);
                 }
             }
             BarInfo::Memory {
@@ -63,12 +85,23 @@ fn config_pci_device(
                         },
                         if prefetchable { " pref" } else { "" },
                     );
+                    let mut flags = IORESOURCE_MEM;
+                    if address_type == MemoryBarType::Width64 {
+                        flags |= IORESOURCE_MEM_64;
+                    }
+                    if prefetchable {
+                        flags |= IORESOURCE_PREFETCH;
+                    }
+                    resource(address, size as u64, flags);
+                } else {
+                    resource(0, 0, 0);
                 }
             }
         }
 
         bar += 1;
         if info.takes_two_entries() {
+            resource(0, 0, 0);
             bar += 1;
         }
     }
@@ -79,7 +112,33 @@ fn config_pci_device(
         bdf,
         cmd | Command::IO_SPACE | Command::MEMORY_SPACE | Command::BUS_MASTER,
     );
-    Ok(())
+    Ok(resources)
+}
+
+/// Registers the PCI device to the driver model at
+/// `bus/pci/devices/<domain>:<bus>:<device>.<function>`.
+fn register_pci_device(bdf: DeviceFunction, dev_info: &DeviceFunctionInfo, resources: String) {
+    let path = format!(
+        "bus/pci/devices/0000:{:02x}:{:02x}.{}",
+        bdf.bus, bdf.device, bdf.function
+    );
+    let vendor = format!("{:#06x}\n", dev_info.vendor_id);
+    let device = format!("{:#06x}\n", dev_info.device_id);
+    let class = format!(
+        "{:#08x}\n",
+        (dev_info.class as u32) << 16 | (dev_info.subclass as u32) << 8 | dev_info.prog_if as u32
+    );
+    let revision = format!("{:#04x}\n", dev_info.revision);
+    model::register_device(
+        &path,
+        vec![
+            DeviceAttr::new("vendor", move || vendor.clone()),
+            DeviceAttr::new("device", move || device.clone()),
+            DeviceAttr::new("class", move || class.clone()),
+            DeviceAttr::new("revision", move || revision.clone()),
+            DeviceAttr::new("resource", move || resources.clone()),
+        ],
+    );
 }
 
 impl AllDevices {
@@ -99,18 +158,21 @@ impl AllDevices {
                     continue;
                 }
                 match config_pci_device(&mut root, bdf, &mut allocator) {
-                    Ok(_) => for_each_drivers!(type Driver, {
-                        if let Some(dev) = Driver::probe_pci(&mut root, bdf, &dev_info) {
-                            info!(
-                                "registered a new {:?} device at {}: {:?}",
-                                dev.device_type(),
-                                bdf,
-                                dev.device_name(),
-                            );
-                            self.add_device(dev);
-                            continue; // skip to the next device
-                        }
-                    }),
+                    Ok(resources) => {
+                        register_pci_device(bdf, &dev_info, resources);
+                        for_each_drivers!(type Driver, {
+                            if let Some(dev) = Driver::probe_pci(&mut root, bdf, &dev_info) {
+                                info!(
+                                    "registered a new {:?} device at {}: {:?}",
+                                    dev.device_type(),
+                                    bdf,
+                                    dev.device_name(),
+                                );
+                                self.add_device(dev);
+                                continue; // skip to the next device
+                            }
+                        })
+                    }
                     Err(e) => warn!(
                         "failed to enable PCI device at {}({}): {:?}",
                         bdf, dev_info, e
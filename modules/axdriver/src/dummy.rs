@@ -30,6 +30,7 @@ cfg_if! {
             fn transmit(&mut self, _: NetBufPtr) -> DevResult { Err(DevError::Unsupported) }
             fn receive(&mut self) -> DevResult<NetBufPtr> { Err(DevError::Unsupported) }
             fn alloc_tx_buffer(&mut self, _: usize) -> DevResult<NetBufPtr> { Err(DevError::Unsupported) }
+            fn dealloc_tx_buffer(&mut self, _: NetBufPtr) -> DevResult { Err(DevError::Unsupported) }
         }
     }
 }
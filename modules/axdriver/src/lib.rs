@@ -26,6 +26,12 @@
 //! that may introduce a little overhead. But on the other hand, it is more
 //! flexible, multiple instances of each device category are supported.
 //!
+//! # Driver Model
+//!
+//! Besides the drivers, the probed devices and their attributes (e.g., the
+//! vendor and device IDs of PCI devices) are registered in the [`model`]
+//! module, which is exported as sysfs by the filesystem module.
+//!
 //! # Supported Devices
 //!
 //! | Device Category | Cargo Feature | Description |
@@ -61,7 +67,6 @@
 #[macro_use]
 extern crate log;
 
-#[cfg(feature = "dyn")]
 extern crate alloc;
 
 #[macro_use]
@@ -78,6 +83,7 @@ mod virtio;
 #[cfg(feature = "ixgbe")]
 mod ixgbe;
 
+pub mod model;
 pub mod prelude;
 
 #[allow(unused_imports)]
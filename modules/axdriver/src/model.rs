@@ -0,0 +1,107 @@
+//! The driver model: a tree of devices and their attributes.
+//!
+//! Drivers and upper layer subsystems register each device with a path, such
+//! as `bus/pci/devices/0000:00:01.0` or `class/net/eth0`, and a list of
+//! attributes, which can be read (and written if the driver provides a setter)
+//! as text. The filesystem module exports the tree as sysfs.
+
+use alloc::collections::BTreeMap;
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
+
+use driver_common::{DevError, DevResult};
+use spinlock::SpinNoIrq;
+
+type ShowFn = Box<dyn Fn() -> String + Send + Sync>;
+type StoreFn = Box<dyn Fn(&str) -> DevResult + Send + Sync>;
+
+/// An attribute of a device, e.g., the MAC address of a NIC.
+pub struct DeviceAttr {
+    name: String,
+    show: ShowFn,
+    store: Option<StoreFn>,
+}
+
+impl DeviceAttr {
+    /// Creates a read-only attribute, whose value is returned by `show`.
+    ///
+    /// The name may contain `/` to put the attribute in a subdirectory of the
+    /// device (e.g., `statistics/rx_bytes`).
+    pub fn new<F>(name: &str, show: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            show: Box::new(show),
+            store: None,
+        }
+    }
+
+    /// Creates a writable attribute, whose value is returned by `show` and
+    /// set by `store`.
+    pub fn new_writable<F, G>(name: &str, show: F, store: G) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+        G: Fn(&str) -> DevResult + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            show: Box::new(show),
+            store: Some(Box::new(store)),
+        }
+    }
+
+    /// The name of the attribute.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the attribute can be written.
+    pub fn is_writable(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Returns the current value, usually ending with a newline.
+    pub fn show(&self) -> String {
+        (self.show)()
+    }
+
+    /// Sets the value. Returns [`DevError::Unsupported`] if the attribute is
+    /// read-only.
+    pub fn store(&self, value: &str) -> DevResult {
+        match &self.store {
+            Some(store) => store(value),
+            None => Err(DevError::Unsupported),
+        }
+    }
+}
+
+/// The attributes of all registered devices, keyed by the device paths.
+static DEVICES: SpinNoIrq<BTreeMap<String, Arc<[DeviceAttr]>>> = SpinNoIrq::new(BTreeMap::new());
+
+/// Registers a device at `path` with its attributes, replacing the device
+/// previously registered at the same path.
+pub fn register_device(path: &str, attrs: Vec<DeviceAttr>) {
+    let path = path.trim_matches('/');
+    debug!("register device {:?} with {} attributes", path, attrs.len());
+    DEVICES.lock().insert(path.into(), attrs.into());
+}
+
+/// Unregisters the device at `path`. Returns [`DevError::InvalidParam`] if
+/// no device is registered at the path.
+pub fn unregister_device(path: &str) -> DevResult {
+    match DEVICES.lock().remove(path.trim_matches('/')) {
+        Some(_) => Ok(()),
+        None => Err(DevError::InvalidParam),
+    }
+}
+
+/// Returns all registered devices and their attributes, in the order of the
+/// device paths.
+pub fn devices() -> Vec<(String, Arc<[DeviceAttr]>)> {
+    DEVICES
+        .lock()
+        .iter()
+        .map(|(path, attrs)| (path.clone(), attrs.clone()))
+        .collect()
+}
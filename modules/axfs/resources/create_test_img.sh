@@ -26,5 +26,23 @@ create_test_img() {
 	sudo umount mnt
 }
 
+create_ext2_test_img() {
+	local name=$1
+	local blkcount=$2
+	local dir=$(mktemp -d)
+	for i in $(seq 1 1000); do
+	  echo "Rust is cool!" >>"$dir/long.txt"
+	done
+	echo "Rust is cool!" >>"$dir/short.txt"
+	mkdir -p "$dir/very/long/path"
+	echo "Rust is cool!" >>"$dir/very/long/path/test.txt"
+	mkdir -p "$dir/very-long-dir-name"
+	echo "Rust is cool!" >>"$dir/very-long-dir-name/very-long-file-name.txt"
+	rm -f "$name"
+	mke2fs -q -t ext2 -b 1024 -L "Test!" -U 12345678-1234-1234-1234-123456789abc -E root_owner=0:0 -d "$dir" "$name" $blkcount
+	rm -rf "$dir"
+}
+
 create_test_img "$CUR_DIR/fat16.img" 2500 16
 create_test_img "$CUR_DIR/fat32.img" 34000 32
+create_ext2_test_img "$CUR_DIR/ext2.img" 2048
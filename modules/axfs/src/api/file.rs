@@ -1,5 +1,5 @@
 use axio::{prelude::*, Result, SeekFrom};
-use core::fmt;
+use core::{fmt, time::Duration};
 
 use crate::fops;
 
@@ -16,7 +16,7 @@ pub struct File {
 }
 
 /// Metadata information about a file.
-pub struct Metadata(fops::FileAttr);
+pub struct Metadata(pub(super) fops::FileAttr);
 
 /// Options and flags which can be used to configure how a file is opened.
 #[derive(Clone, Debug)]
@@ -64,6 +64,13 @@ impl OpenOptions {
         self
     }
 
+    /// Sets the permission mode of the file if it is created. The default is
+    /// `0o666`.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.0.mode(mode);
+        self
+    }
+
     /// Opens a file at `path` with the options specified by `self`.
     pub fn open(&self, path: &str) -> Result<File> {
         fops::File::open(path, &self.0).map(|inner| File { inner })
@@ -90,6 +97,16 @@ impl Metadata {
         self.0.is_file()
     }
 
+    /// Returns `true` if this metadata is for a symbolic link.
+    ///
+    /// It is only possible for the metadata returned by [`symlink_metadata`],
+    /// as other functions follow symbolic links.
+    ///
+    /// [`symlink_metadata`]: super::symlink_metadata
+    pub const fn is_symlink(&self) -> bool {
+        self.0.is_symlink()
+    }
+
     /// Returns the size of the file, in bytes, this metadata is for.
     #[allow(clippy::len_without_is_empty)]
     pub const fn len(&self) -> u64 {
@@ -110,6 +127,34 @@ impl Metadata {
     pub const fn blocks(&self) -> u64 {
         self.0.blocks()
     }
+
+    /// Returns the user ID of the owner of the file.
+    pub const fn uid(&self) -> u32 {
+        self.0.uid()
+    }
+
+    /// Returns the group ID of the owner of the file.
+    pub const fn gid(&self) -> u32 {
+        self.0.gid()
+    }
+
+    /// Returns the last access time of the file, as a clock time of
+    /// [`axhal::time`].
+    pub const fn accessed(&self) -> Duration {
+        self.0.atime()
+    }
+
+    /// Returns the last modification time of the file, as a clock time of
+    /// [`axhal::time`].
+    pub const fn modified(&self) -> Duration {
+        self.0.mtime()
+    }
+
+    /// Returns the last status change time of the file, as a clock time of
+    /// [`axhal::time`].
+    pub const fn changed(&self) -> Duration {
+        self.0.ctime()
+    }
 }
 
 impl fmt::Debug for Metadata {
@@ -119,6 +164,7 @@ impl fmt::Debug for Metadata {
             .field("is_dir", &self.is_dir())
             .field("is_file", &self.is_file())
             .field("permissions", &self.permissions())
+            .field("modified", &self.modified())
             .finish_non_exhaustive()
     }
 }
@@ -162,6 +208,19 @@ impl File {
     pub fn metadata(&self) -> Result<Metadata> {
         self.inner.get_attr().map(Metadata)
     }
+
+    /// Changes the permissions of the underlying file.
+    pub fn set_permissions(&self, perm: Permissions) -> Result<()> {
+        self.inner.set_attr(&fops::FileSetAttr {
+            mode: Some(perm),
+            ..Default::default()
+        })
+    }
+
+    /// Changes the modification time of the underlying file.
+    pub fn set_modified(&self, time: Duration) -> Result<()> {
+        self.inner.set_modified(time)
+    }
 }
 
 impl Read for File {
@@ -5,6 +5,8 @@ mod file;
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use crate::fops::FileSystemInfo;
+pub use crate::mounts::MountFlags;
 
 use alloc::{string::String, vec::Vec};
 use axio::{self as io, prelude::*};
@@ -56,7 +58,43 @@ pub fn write<C: AsRef<[u8]>>(path: &str, contents: C) -> io::Result<()> {
 /// Given a path, query the file system to get information about a file,
 /// directory, etc.
 pub fn metadata(path: &str) -> io::Result<Metadata> {
-    File::open(path)?.metadata()
+    crate::fops::attr(path).map(Metadata)
+}
+
+/// Query the metadata about a file without following symlinks.
+pub fn symlink_metadata(path: &str) -> io::Result<Metadata> {
+    crate::fops::symlink_attr(path).map(Metadata)
+}
+
+/// Changes the permissions found on a file or a directory.
+pub fn set_permissions(path: &str, perm: Permissions) -> io::Result<()> {
+    crate::fops::set_attr(
+        path,
+        &crate::fops::FileSetAttr {
+            mode: Some(perm),
+            ..Default::default()
+        },
+    )
+}
+
+/// Reads a symbolic link, returning the file that the link points to.
+pub fn read_link(path: &str) -> io::Result<String> {
+    crate::root::read_link(None, path)
+}
+
+/// Creates a new symbolic link on the filesystem.
+///
+/// The `link` path will be a symbolic link pointing to the `original` path.
+pub fn symlink(original: &str, link: &str) -> io::Result<()> {
+    crate::root::symlink(None, original, link)
+}
+
+/// Creates a new hard link on the filesystem.
+///
+/// The `link` path will be a link pointing to the `original` path. Note that
+/// directories cannot be hard linked.
+pub fn hard_link(original: &str, link: &str) -> io::Result<()> {
+    crate::root::link(None, original, link)
 }
 
 /// Creates a new, empty directory at the provided path.
@@ -87,3 +125,27 @@ pub fn remove_file(path: &str) -> io::Result<()> {
 pub fn rename(old: &str, new: &str) -> io::Result<()> {
     crate::root::rename(old, new)
 }
+
+/// Returns the attributes of the filesystem that contains the node at `path`,
+/// such as the total and free space.
+pub fn statfs(path: &str) -> io::Result<FileSystemInfo> {
+    crate::fops::statfs(path)
+}
+
+/// Mounts the filesystem of type `fstype` from `source` on the directory
+/// `target`.
+///
+/// `source` is the block device (e.g., `/dev/blk1`, `/dev/blk0p2` or
+/// `PARTLABEL=data`, see [`Disk::open`](crate::fops::Disk::open)) for disk
+/// filesystems, and is ignored by the others. `target` may be in another
+/// mounted filesystem.
+pub fn mount(source: &str, target: &str, fstype: &str, flags: MountFlags) -> io::Result<()> {
+    crate::root::mount(source, target, fstype, flags)
+}
+
+/// Unmounts the filesystem mounted on `target`.
+///
+/// It fails if other filesystems are mounted inside it.
+pub fn umount(target: &str) -> io::Result<()> {
+    crate::root::umount(target)
+}
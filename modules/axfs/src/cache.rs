@@ -0,0 +1,430 @@
+//! Block cache and page cache.
+//!
+//! The [`BlockCache`] sits between the filesystems and a block device. It
+//! keeps recently used blocks in memory, and writes modified blocks back to
+//! the device only when they are evicted or when [`sync`] is called.
+//!
+//! The [`PageCache`] sits between [`crate::fops::File`] and the filesystem
+//! nodes. It caches file contents in pages keyed by (node, page index). It
+//! is write-through, so that the file sizes and attributes reported by the
+//! filesystems are always up to date.
+
+use alloc::{collections::BTreeMap, sync::Arc, vec, vec::Vec};
+use axdriver::prelude::*;
+use axfs_vfs::{VfsNodeRef, VfsResult};
+use axsync::Mutex;
+
+/// The size of a disk block in bytes.
+pub const BLOCK_SIZE: usize = 512;
+
+/// The size of a page in the page cache in bytes.
+pub const PAGE_SIZE: usize = 4096;
+
+/// The maximum number of blocks in each block cache.
+const BLOCK_CACHE_SIZE: usize = 1024;
+
+/// The maximum number of pages in the page cache.
+const PAGE_CACHE_SIZE: usize = 256;
+
+/// All block caches, which are flushed by [`sync`].
+static BLOCK_CACHES: Mutex<Vec<Arc<BlockCache>>> = Mutex::new(Vec::new());
+
+/// The page cache shared by all opened files.
+pub(crate) static PAGE_CACHE: PageCache = PageCache::new();
+
+/// Hit and miss statistics of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of lookups that found the data in the cache.
+    pub hits: u64,
+    /// The number of lookups that had to read the data from the underlying
+    /// device or filesystem.
+    pub misses: u64,
+    /// The number of blocks or pages currently in the cache.
+    pub cached: usize,
+    /// The number of modified blocks not yet written back.
+    pub dirty: usize,
+}
+
+struct CachedBlock {
+    id: u64,
+    data: [u8; BLOCK_SIZE],
+    dirty: bool,
+    last_used: u64,
+}
+
+struct BlockCacheInner {
+    dev: AxBlockDevice,
+    blocks: Vec<CachedBlock>,
+    index: BTreeMap<u64, usize>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// A LRU cache of the blocks of a block device, with write-back.
+pub struct BlockCache {
+    num_blocks: u64,
+    inner: Mutex<BlockCacheInner>,
+}
+
+impl BlockCacheInner {
+    fn write_back(&mut self, slot: usize) -> DevResult {
+        let block = &mut self.blocks[slot];
+        if block.dirty {
+            trace!("block cache: write back block {}", block.id);
+            self.dev.write_block(block.id, &block.data)?;
+            block.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Returns the cached block `id`. If it is not cached, the least recently
+    /// used block is replaced, and the block is read from the device only if
+    /// `load` is true.
+    fn get(&mut self, id: u64, load: bool) -> DevResult<&mut CachedBlock> {
+        self.tick += 1;
+        if let Some(&slot) = self.index.get(&id) {
+            self.hits += 1;
+            let block = &mut self.blocks[slot];
+            block.last_used = self.tick;
+            return Ok(block);
+        }
+
+        self.misses += 1;
+        let slot = if self.blocks.len() < BLOCK_CACHE_SIZE {
+            self.blocks.push(CachedBlock {
+                id,
+                data: [0; BLOCK_SIZE],
+                dirty: false,
+                last_used: 0,
+            });
+            self.blocks.len() - 1
+        } else {
+            let (slot, _) = self
+                .blocks
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, b)| b.last_used)
+                .unwrap();
+            self.write_back(slot)?;
+            let old_id = self.blocks[slot].id;
+            if self.index.get(&old_id) == Some(&slot) {
+                self.index.remove(&old_id);
+            }
+            slot
+        };
+
+        let block = &mut self.blocks[slot];
+        block.id = id;
+        block.last_used = 0; // reused first if loading fails
+        if load {
+            self.dev.read_block(id, &mut block.data)?;
+        }
+        block.last_used = self.tick;
+        self.index.insert(id, slot);
+        Ok(block)
+    }
+}
+
+impl BlockCache {
+    /// Creates a block cache for the block device, and registers it so that
+    /// it is flushed by [`sync`].
+    pub fn new(dev: AxBlockDevice) -> Arc<Self> {
+        assert_eq!(BLOCK_SIZE, dev.block_size());
+        let cache = Arc::new(Self {
+            num_blocks: dev.num_blocks(),
+            inner: Mutex::new(BlockCacheInner {
+                dev,
+                blocks: Vec::new(),
+                index: BTreeMap::new(),
+                tick: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        });
+        BLOCK_CACHES.lock().push(cache.clone());
+        cache
+    }
+
+    /// The number of blocks of the device.
+    pub fn num_blocks(&self) -> u64 {
+        self.num_blocks
+    }
+
+    /// Reads data from the block `id` at `offset` within the block.
+    pub fn read(&self, id: u64, offset: usize, buf: &mut [u8]) -> DevResult {
+        let mut inner = self.inner.lock();
+        let block = inner.get(id, true)?;
+        buf.copy_from_slice(&block.data[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    /// Writes data to the block `id` at `offset` within the block.
+    ///
+    /// The block is marked dirty, and is written to the device later.
+    pub fn write(&self, id: u64, offset: usize, buf: &[u8]) -> DevResult {
+        let mut inner = self.inner.lock();
+        let whole = offset == 0 && buf.len() == BLOCK_SIZE;
+        let block = inner.get(id, !whole)?;
+        block.data[offset..offset + buf.len()].copy_from_slice(buf);
+        block.dirty = true;
+        Ok(())
+    }
+
+    /// Writes all dirty blocks back to the device.
+    pub fn sync(&self) -> DevResult {
+        let mut inner = self.inner.lock();
+        let dirty = inner
+            .index
+            .values()
+            .copied()
+            .filter(|&slot| inner.blocks[slot].dirty)
+            .collect::<Vec<_>>();
+        if dirty.is_empty() {
+            return Ok(());
+        }
+        debug!("block cache: sync {} blocks", dirty.len());
+        for slot in dirty {
+            inner.write_back(slot)?;
+        }
+        inner.dev.flush()
+    }
+
+    /// Returns the statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock();
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            cached: inner.index.len(),
+            dirty: inner.blocks.iter().filter(|b| b.dirty).count(),
+        }
+    }
+}
+
+/// Writes all dirty blocks in all block caches back to the devices.
+pub fn sync() -> DevResult {
+    let caches = BLOCK_CACHES.lock().clone();
+    caches.iter().try_for_each(|cache| cache.sync())
+}
+
+/// Returns the sum of the statistics of all block caches.
+pub fn block_cache_stats() -> CacheStats {
+    BLOCK_CACHES
+        .lock()
+        .iter()
+        .map(|cache| cache.stats())
+        .fold(CacheStats::default(), |acc, s| CacheStats {
+            hits: acc.hits + s.hits,
+            misses: acc.misses + s.misses,
+            cached: acc.cached + s.cached,
+            dirty: acc.dirty + s.dirty,
+        })
+}
+
+struct CachedPage {
+    node: VfsNodeRef,
+    data: Vec<u8>,
+    last_used: u64,
+}
+
+struct PageCacheInner {
+    pages: BTreeMap<(usize, u64), CachedPage>,
+    tick: u64,
+    /// Increased on every invalidation, to discard pages read concurrently.
+    generation: u64,
+    hits: u64,
+    misses: u64,
+}
+
+/// A LRU cache of file pages, keyed by (node, page index).
+///
+/// Some filesystems create a new node on each lookup, so that the same file
+/// may be opened as different nodes. Hence writes and truncations through a
+/// node also invalidate the affected pages of other nodes.
+pub struct PageCache {
+    inner: Mutex<PageCacheInner>,
+}
+
+fn node_id(node: &VfsNodeRef) -> usize {
+    Arc::as_ptr(node) as *const () as usize
+}
+
+/// Reads from `node` at `offset` until `buf` is full or the end of file is
+/// reached.
+fn read_full(node: &VfsNodeRef, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+    let mut read_len = 0;
+    while read_len < buf.len() {
+        match node.read_at(offset + read_len as u64, &mut buf[read_len..])? {
+            0 => break,
+            n => read_len += n,
+        }
+    }
+    Ok(read_len)
+}
+
+impl PageCache {
+    const fn new() -> Self {
+        Self {
+            inner: Mutex::new(PageCacheInner {
+                pages: BTreeMap::new(),
+                tick: 0,
+                generation: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Reads the file at `offset` through the cache. Returns the number of
+    /// bytes read.
+    pub fn read_at(&self, node: &VfsNodeRef, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let id = node_id(node);
+        let mut read_len = 0;
+        while read_len < buf.len() {
+            let pos = offset + read_len as u64;
+            let idx = pos / PAGE_SIZE as u64;
+            let start = (pos % PAGE_SIZE as u64) as usize;
+            let count = (buf.len() - read_len).min(PAGE_SIZE - start);
+            let dst = &mut buf[read_len..read_len + count];
+
+            let generation = {
+                let mut inner = self.inner.lock();
+                inner.tick += 1;
+                let tick = inner.tick;
+                if let Some(page) = inner.pages.get_mut(&(id, idx)) {
+                    page.last_used = tick;
+                    dst.copy_from_slice(&page.data[start..start + count]);
+                    inner.hits += 1;
+                    read_len += count;
+                    continue;
+                }
+                inner.misses += 1;
+                inner.generation
+            };
+
+            let mut data = vec![0; PAGE_SIZE];
+            let len = read_full(node, idx * PAGE_SIZE as u64, &mut data)?;
+            let avail = len.saturating_sub(start).min(count);
+            dst[..avail].copy_from_slice(&data[start..start + avail]);
+            read_len += avail;
+            // Only full pages are cached, as the file may grow later.
+            if len == PAGE_SIZE {
+                self.insert(node, idx, data, generation);
+            }
+            if avail < count {
+                break; // end of file
+            }
+        }
+        Ok(read_len)
+    }
+
+    fn insert(&self, node: &VfsNodeRef, idx: u64, data: Vec<u8>, generation: u64) {
+        let mut inner = self.inner.lock();
+        if inner.generation != generation {
+            return; // the page may be modified after being read
+        }
+        if inner.pages.len() >= PAGE_CACHE_SIZE {
+            let lru = inner
+                .pages
+                .iter()
+                .min_by_key(|(_, page)| page.last_used)
+                .map(|(&key, _)| key)
+                .unwrap();
+            inner.pages.remove(&lru);
+        }
+        inner.tick += 1;
+        let page = CachedPage {
+            node: node.clone(),
+            data,
+            last_used: inner.tick,
+        };
+        inner.pages.insert((node_id(node), idx), page);
+    }
+
+    /// Writes the file at `offset`, and updates the cached pages. Returns the
+    /// number of bytes written.
+    pub fn write_at(&self, node: &VfsNodeRef, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let write_len = node.write_at(offset, buf)?;
+        if write_len == 0 {
+            return Ok(0);
+        }
+        let id = node_id(node);
+        let end = offset + write_len as u64;
+        let first = offset / PAGE_SIZE as u64;
+        let last = (end - 1) / PAGE_SIZE as u64;
+
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.pages.retain(|&(page_id, idx), page| {
+            if idx < first || idx > last {
+                return true;
+            }
+            if page_id != id {
+                return false;
+            }
+            let page_start = idx * PAGE_SIZE as u64;
+            let from = offset.max(page_start);
+            let to = end.min(page_start + PAGE_SIZE as u64);
+            page.data[(from - page_start) as usize..(to - page_start) as usize]
+                .copy_from_slice(&buf[(from - offset) as usize..(to - offset) as usize]);
+            true
+        });
+        Ok(write_len)
+    }
+
+    /// Truncates the file, and drops the cached pages beyond the new end of
+    /// the file.
+    pub fn truncate(&self, node: &VfsNodeRef, size: u64) -> VfsResult {
+        node.truncate(size)?;
+        let id = node_id(node);
+        let first = size / PAGE_SIZE as u64;
+
+        // Other nodes of the same file now have the new size.
+        let others = {
+            let mut inner = self.inner.lock();
+            inner.generation += 1;
+            inner
+                .pages
+                .retain(|&(page_id, idx), _| page_id != id || idx < first);
+            let mut others = BTreeMap::new();
+            for (&(page_id, _), page) in inner.pages.iter() {
+                others.entry(page_id).or_insert_with(|| page.node.clone());
+            }
+            others
+        };
+        for (other_id, other) in others {
+            let Ok(attr) = other.get_attr() else {
+                self.invalidate(&other);
+                continue;
+            };
+            let first = attr.size() / PAGE_SIZE as u64;
+            let mut inner = self.inner.lock();
+            inner.generation += 1;
+            inner
+                .pages
+                .retain(|&(page_id, idx), _| page_id != other_id || idx < first);
+        }
+        Ok(())
+    }
+
+    /// Drops all cached pages of the node.
+    pub fn invalidate(&self, node: &VfsNodeRef) {
+        let id = node_id(node);
+        let mut inner = self.inner.lock();
+        inner.generation += 1;
+        inner.pages.retain(|&(page_id, _), _| page_id != id);
+    }
+
+    /// Returns the statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock();
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            cached: inner.pages.len(),
+            dirty: 0,
+        }
+    }
+}
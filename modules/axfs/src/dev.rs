@@ -1,28 +1,264 @@
+use alloc::{collections::BTreeMap, format, string::String, sync::Arc, vec, vec::Vec};
+use axdriver::model::{self, DeviceAttr};
 use axdriver::prelude::*;
+use axerrno::{ax_err, ax_err_type, AxResult};
+use axsync::Mutex;
+use driver_block::partition::{read_partitions, Guid, PartitionInfo};
 
-const BLOCK_SIZE: usize = 512;
+use crate::cache::{BlockCache, BLOCK_SIZE};
+
+/// A range of blocks on a disk, which is the whole disk or a partition. It is
+/// accessed through the block cache of the disk.
+#[derive(Clone)]
+pub(crate) struct BlockRange {
+    cache: Arc<BlockCache>,
+    start: u64,
+    num_blocks: u64,
+}
+
+impl BlockRange {
+    /// The number of blocks in the range.
+    pub fn num_blocks(&self) -> u64 {
+        self.num_blocks
+    }
+
+    /// Reads `buf` from the block `id` (relative to the range) at `offset`.
+    pub fn read(&self, id: u64, offset: usize, buf: &mut [u8]) -> DevResult {
+        self.check(id)?;
+        self.cache.read(self.start + id, offset, buf)
+    }
+
+    /// Writes `buf` to the block `id` (relative to the range) at `offset`.
+    pub fn write(&self, id: u64, offset: usize, buf: &[u8]) -> DevResult {
+        self.check(id)?;
+        self.cache.write(self.start + id, offset, buf)
+    }
+
+    /// Writes all modified blocks of the disk back to the device.
+    pub fn sync(&self) -> DevResult {
+        self.cache.sync()
+    }
+
+    fn check(&self, id: u64) -> DevResult {
+        if id < self.num_blocks {
+            Ok(())
+        } else {
+            Err(DevError::InvalidParam)
+        }
+    }
+
+    fn overlaps(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cache, &other.cache)
+            && self.start < other.start + other.num_blocks
+            && other.start < self.start + self.num_blocks
+    }
+}
+
+struct BlockDevice {
+    range: BlockRange,
+    /// The entry in the partition table, if it is a partition.
+    part: Option<PartitionInfo>,
+    /// Whether the device is opened by a [`Disk`].
+    in_use: bool,
+}
+
+/// All block devices by name.
+static BLOCK_DEVICES: Mutex<BTreeMap<String, BlockDevice>> = Mutex::new(BTreeMap::new());
+
+/// Registers a block device with `name`, so that it can be opened by
+/// [`Disk::open`]. It is also registered to the driver model at
+/// `block/<name>`.
+///
+/// If the device has a partition table, each partition is registered as
+/// well, named `<name>p<number>` (e.g., `blk0p1`).
+pub(crate) fn register_block_device(name: String, mut dev: AxBlockDevice) {
+    let parts = read_partitions(&mut dev).unwrap_or_else(|e| {
+        warn!("failed to read the partition table of {}: {:?}", name, e);
+        Vec::new()
+    });
+    let cache = BlockCache::new(dev);
+    let range = BlockRange {
+        num_blocks: cache.num_blocks(),
+        start: 0,
+        cache,
+    };
+    model::register_device(
+        &format!("block/{}", name),
+        vec![size_attr(range.num_blocks)],
+    );
+
+    let mut devices = BLOCK_DEVICES.lock();
+    for part in parts {
+        let part_name = format!("{}p{}", name, part.number);
+        info!(
+            "  partition {}: {} blocks from {}, label {:?}",
+            part_name, part.num_blocks, part.start, part.label
+        );
+        let (start, number) = (part.start, part.number);
+        model::register_device(
+            &format!("block/{}/{}", name, part_name),
+            vec![
+                size_attr(part.num_blocks),
+                DeviceAttr::new("start", move || format!("{}\n", start * SECTORS_PER_BLOCK)),
+                DeviceAttr::new("partition", move || format!("{}\n", number)),
+            ],
+        );
+        let range = BlockRange {
+            start: part.start,
+            num_blocks: part.num_blocks,
+            cache: range.cache.clone(),
+        };
+        let dev = BlockDevice {
+            range,
+            part: Some(part),
+            in_use: false,
+        };
+        devices.insert(part_name, dev);
+    }
+    let dev = BlockDevice {
+        range,
+        part: None,
+        in_use: false,
+    };
+    devices.insert(name, dev);
+}
+
+/// The number of 512-byte sectors in a block.
+const SECTORS_PER_BLOCK: u64 = BLOCK_SIZE as u64 / 512;
+
+/// The `size` attribute of a block device, which is in 512-byte sectors, the
+/// same as Linux.
+fn size_attr(num_blocks: u64) -> DeviceAttr {
+    let size = format!("{}\n", num_blocks * SECTORS_PER_BLOCK);
+    DeviceAttr::new("size", move || size.clone())
+}
+
+/// Returns the names and the block ranges of all block devices (including
+/// the partitions), including those in use.
+pub(crate) fn block_devices() -> Vec<(String, BlockRange)> {
+    BLOCK_DEVICES
+        .lock()
+        .iter()
+        .map(|(name, dev)| (name.clone(), dev.range.clone()))
+        .collect()
+}
+
+/// Finds the name of the block device referred to by `source`, which is one
+/// of:
+///
+/// - the name of the device, e.g., `blk1p2` or `/dev/blk1p2`;
+/// - `PARTUUID=<GUID>`, the unique GUID of a GPT partition;
+/// - `PARTLABEL=<name>`, the name of a GPT partition.
+fn find_device(devices: &BTreeMap<String, BlockDevice>, source: &str) -> Option<String> {
+    let find_partition = |matches: &dyn Fn(&PartitionInfo) -> bool| {
+        devices
+            .iter()
+            .find(|(_, dev)| dev.part.as_ref().is_some_and(matches))
+            .map(|(name, _)| name.clone())
+    };
+    if let Some(uuid) = source.strip_prefix("PARTUUID=") {
+        let uuid: Guid = uuid.parse().ok()?;
+        find_partition(&|part| part.part_guid == uuid)
+    } else if let Some(label) = source.strip_prefix("PARTLABEL=") {
+        find_partition(&|part| part.label == label)
+    } else {
+        let name = source.strip_prefix("/dev/").unwrap_or(source);
+        devices.contains_key(name).then(|| name.into())
+    }
+}
+
+/// Returns the name of the block device of the root filesystem.
+///
+/// It is given by the config key `root-partition` (see [`Disk::open`] for the
+/// format). If the key is empty, the first partition of `blk0` with the GPT
+/// root partition type of the architecture (as in the Discoverable Partitions
+/// Specification) is used, or else the first partition of `blk0`, or `blk0`
+/// itself if it has no partition table.
+pub(crate) fn root_device() -> AxResult<String> {
+    let devices = BLOCK_DEVICES.lock();
+    if !axconfig::ROOT_PARTITION.is_empty() {
+        return find_device(&devices, axconfig::ROOT_PARTITION)
+            .ok_or_else(|| ax_err_type!(NotFound, "root partition not found"));
+    }
+    let parts = || {
+        devices
+            .iter()
+            .filter(|(name, _)| name.starts_with("blk0p"))
+            .filter_map(|(name, dev)| Some((name, dev.part.as_ref()?)))
+    };
+    let root_type = ROOT_PARTITION_TYPE.and_then(|ty| ty.parse::<Guid>().ok());
+    let root = parts()
+        .filter(|(_, part)| Some(part.type_guid) == root_type)
+        .min_by_key(|(_, part)| part.number)
+        .or_else(|| parts().min_by_key(|(_, part)| part.number));
+    match root {
+        Some((name, _)) => Ok(name.clone()),
+        None if devices.contains_key("blk0") => Ok("blk0".into()),
+        None => ax_err!(NotFound, "no block device found"),
+    }
+}
+
+/// The GPT partition type of the root partition of the architecture.
+const ROOT_PARTITION_TYPE: Option<&str> = if cfg!(target_arch = "x86_64") {
+    Some("4f68bce3-e8cd-4db1-96e7-fbcaf984b709")
+} else if cfg!(target_arch = "aarch64") {
+    Some("b921b045-1df0-41c3-af44-4c6f280d3fae")
+} else if cfg!(target_arch = "riscv64") {
+    Some("72ec70a6-cf74-40e6-bd49-4bda08e8f224")
+} else {
+    None
+};
 
 /// A disk device with a cursor.
+///
+/// All reads and writes go through the shared [`BlockCache`] of the device.
+/// If the device is a partition, the positions are relative to the start of
+/// the partition.
 pub struct Disk {
+    name: String,
     block_id: u64,
     offset: usize,
-    dev: AxBlockDevice,
+    range: BlockRange,
 }
 
 impl Disk {
-    /// Create a new disk.
-    pub fn new(dev: AxBlockDevice) -> Self {
-        assert_eq!(BLOCK_SIZE, dev.block_size());
-        Self {
+    /// Opens the block device `source`, which is one of:
+    ///
+    /// - the name of the device, e.g., `blk1`, `blk1p2` or `/dev/blk1p2`;
+    /// - `PARTUUID=<GUID>`, the unique GUID of a GPT partition;
+    /// - `PARTLABEL=<name>`, the name of a GPT partition.
+    ///
+    /// A device can only be opened by one disk at a time, and a disk cannot
+    /// be opened if any of its partitions is opened, or vice versa. It is
+    /// released when the disk is dropped.
+    pub fn open(source: &str) -> AxResult<Self> {
+        let mut devices = BLOCK_DEVICES.lock();
+        let name = find_device(&devices, source)
+            .ok_or_else(|| ax_err_type!(NotFound, "no such block device"))?;
+        let range = devices[&name].range.clone();
+        if devices
+            .values()
+            .any(|dev| dev.in_use && dev.range.overlaps(&range))
+        {
+            return ax_err!(ResourceBusy, "block device is in use");
+        }
+        devices.get_mut(&name).unwrap().in_use = true;
+        Ok(Self {
+            name,
             block_id: 0,
             offset: 0,
-            dev,
-        }
+            range,
+        })
+    }
+
+    /// The name of the block device, e.g., `blk0p1`.
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     /// Get the size of the disk.
     pub fn size(&self) -> u64 {
-        self.dev.num_blocks() * BLOCK_SIZE as u64
+        self.range.num_blocks() * BLOCK_SIZE as u64
     }
 
     /// Get the position of the cursor.
@@ -38,55 +274,43 @@ impl Disk {
 
     /// Read within one block, returns the number of bytes read.
     pub fn read_one(&mut self, buf: &mut [u8]) -> DevResult<usize> {
-        let read_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
-            // whole block
-            self.dev
-                .read_block(self.block_id, &mut buf[0..BLOCK_SIZE])?;
-            self.block_id += 1;
-            BLOCK_SIZE
-        } else {
-            // partial block
-            let mut data = [0u8; BLOCK_SIZE];
-            let start = self.offset;
-            let count = buf.len().min(BLOCK_SIZE - self.offset);
-
-            self.dev.read_block(self.block_id, &mut data)?;
-            buf[..count].copy_from_slice(&data[start..start + count]);
-
-            self.offset += count;
-            if self.offset >= BLOCK_SIZE {
-                self.block_id += 1;
-                self.offset -= BLOCK_SIZE;
-            }
-            count
-        };
-        Ok(read_size)
+        let count = buf.len().min(BLOCK_SIZE - self.offset);
+        self.range
+            .read(self.block_id, self.offset, &mut buf[..count])?;
+        self.advance(count);
+        Ok(count)
     }
 
     /// Write within one block, returns the number of bytes written.
     pub fn write_one(&mut self, buf: &[u8]) -> DevResult<usize> {
-        let write_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
-            // whole block
-            self.dev.write_block(self.block_id, &buf[0..BLOCK_SIZE])?;
+        let count = buf.len().min(BLOCK_SIZE - self.offset);
+        self.range
+            .write(self.block_id, self.offset, &buf[..count])?;
+        self.advance(count);
+        Ok(count)
+    }
+
+    /// Write all modified blocks in the cache back to the device.
+    pub fn sync(&self) -> DevResult {
+        self.range.sync()
+    }
+
+    fn advance(&mut self, count: usize) {
+        self.offset += count;
+        if self.offset >= BLOCK_SIZE {
             self.block_id += 1;
-            BLOCK_SIZE
-        } else {
-            // partial block
-            let mut data = [0u8; BLOCK_SIZE];
-            let start = self.offset;
-            let count = buf.len().min(BLOCK_SIZE - self.offset);
-
-            self.dev.read_block(self.block_id, &mut data)?;
-            data[start..start + count].copy_from_slice(&buf[..count]);
-            self.dev.write_block(self.block_id, &data)?;
-
-            self.offset += count;
-            if self.offset >= BLOCK_SIZE {
-                self.block_id += 1;
-                self.offset -= BLOCK_SIZE;
-            }
-            count
-        };
-        Ok(write_size)
+            self.offset -= BLOCK_SIZE;
+        }
+    }
+}
+
+impl Drop for Disk {
+    fn drop(&mut self) {
+        if let Err(e) = self.range.sync() {
+            warn!("failed to sync block device {}: {:?}", self.name, e);
+        }
+        if let Some(dev) = BLOCK_DEVICES.lock().get_mut(&self.name) {
+            dev.in_use = false;
+        }
     }
 }
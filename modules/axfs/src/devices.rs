@@ -0,0 +1,196 @@
+//! Device nodes in devfs that are backed by the kernel, such as the console
+//! and the block devices.
+
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+
+use crate::cache::BLOCK_SIZE;
+use crate::dev::BlockRange;
+
+/// The console device behaves like `/dev/console`, which reads from and
+/// writes to the UART.
+///
+/// Reads block until at least one byte is received.
+pub(crate) struct ConsoleDev;
+
+impl VfsNodeOps for ConsoleDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        loop {
+            let mut read_len = 0;
+            while read_len < buf.len() {
+                match axhal::console::getchar() {
+                    Some(c) => {
+                        buf[read_len] = if c == b'\r' { b'\n' } else { c };
+                        read_len += 1;
+                    }
+                    None => break,
+                }
+            }
+            if read_len > 0 || buf.is_empty() {
+                return Ok(read_len);
+            }
+            axtask::yield_now();
+        }
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// A block device node (e.g., `/dev/blk0` or `/dev/blk0p1`), which reads and
+/// writes the raw device or partition through the block cache of the disk.
+pub(crate) struct BlockDevNode {
+    range: BlockRange,
+}
+
+impl BlockDevNode {
+    pub fn new(range: BlockRange) -> Self {
+        Self { range }
+    }
+
+    fn size(&self) -> u64 {
+        self.range.num_blocks() * BLOCK_SIZE as u64
+    }
+}
+
+impl VfsNodeOps for BlockDevNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.size();
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::BlockDevice,
+            size,
+            size / BLOCK_SIZE as u64,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let end = self.size().min(offset.saturating_add(buf.len() as u64));
+        let mut pos = offset;
+        while pos < end {
+            let block_offset = pos as usize % BLOCK_SIZE;
+            let count = (BLOCK_SIZE - block_offset).min((end - pos) as usize);
+            let start = (pos - offset) as usize;
+            self.range
+                .read(
+                    pos / BLOCK_SIZE as u64,
+                    block_offset,
+                    &mut buf[start..start + count],
+                )
+                .map_err(|_| VfsError::Io)?;
+            pos += count as u64;
+        }
+        Ok(end.saturating_sub(offset) as usize)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let end = self.size().min(offset.saturating_add(buf.len() as u64));
+        if end <= offset && !buf.is_empty() {
+            return Err(VfsError::StorageFull);
+        }
+        let mut pos = offset;
+        while pos < end {
+            let block_offset = pos as usize % BLOCK_SIZE;
+            let count = (BLOCK_SIZE - block_offset).min((end - pos) as usize);
+            let start = (pos - offset) as usize;
+            self.range
+                .write(
+                    pos / BLOCK_SIZE as u64,
+                    block_offset,
+                    &buf[start..start + count],
+                )
+                .map_err(|_| VfsError::Io)?;
+            pos += count as u64;
+        }
+        Ok(end.saturating_sub(offset) as usize)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.range.sync().map_err(|_| VfsError::Io)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// The framebuffer device behaves like `/dev/fb0`, whose content is the
+/// framebuffer of the main display. Each write flushes the framebuffer.
+///
+/// It does not support `mmap`, so the pixels are read and written by
+/// `read` and `write` at offsets.
+#[cfg(feature = "display")]
+pub(crate) struct FramebufferDev;
+
+#[cfg(feature = "display")]
+impl FramebufferDev {
+    /// Returns the framebuffer as a slice, or `None` if there is no display.
+    fn framebuffer(&self) -> Option<&'static mut [u8]> {
+        if !axdisplay::is_initialized() {
+            return None;
+        }
+        let info = axdisplay::framebuffer_info();
+        // SAFETY: the framebuffer is mapped by the display driver and lives
+        // as long as the kernel.
+        Some(unsafe {
+            core::slice::from_raw_parts_mut(info.fb_base_vaddr as *mut u8, info.fb_size)
+        })
+    }
+}
+
+#[cfg(feature = "display")]
+impl VfsNodeOps for FramebufferDev {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.framebuffer().map_or(0, |fb| fb.len() as u64);
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::CharDevice,
+            size,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let fb = self.framebuffer().ok_or(VfsError::Unsupported)?;
+        let start = fb.len().min(offset as usize);
+        let end = fb.len().min(start + buf.len());
+        buf[..end - start].copy_from_slice(&fb[start..end]);
+        Ok(end - start)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let fb = self.framebuffer().ok_or(VfsError::Unsupported)?;
+        let start = fb.len().min(offset as usize);
+        let end = fb.len().min(start + buf.len());
+        if start == end && !buf.is_empty() {
+            return Err(VfsError::StorageFull);
+        }
+        fb[start..end].copy_from_slice(&buf[..end - start]);
+        axdisplay::framebuffer_flush();
+        Ok(end - start)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -1,15 +1,20 @@
 //! Low-level filesystem operations.
 
-use axerrno::{ax_err, ax_err_type, AxResult};
-use axfs_vfs::{VfsError, VfsNodeRef};
+use alloc::sync::Arc;
+use axerrno::{ax_err, ax_err_type, AxError, AxResult};
+use axfs_vfs::{VfsError, VfsNodeRef, VfsOps};
 use axio::SeekFrom;
 use capability::{Cap, WithCap};
 use core::fmt;
+use core::time::Duration;
 
-#[cfg(feature = "myfs")]
+use crate::cache::PAGE_CACHE;
+
+pub use crate::cache::CacheStats;
 pub use crate::dev::Disk;
 #[cfg(feature = "myfs")]
 pub use crate::fs::myfs::MyFileSystemIf;
+pub use crate::mounts::{register_filesystem, FsFactory, MountFlags};
 
 /// Alias of [`axfs_vfs::VfsNodeType`].
 pub type FileType = axfs_vfs::VfsNodeType;
@@ -19,11 +24,19 @@ pub type DirEntry = axfs_vfs::VfsDirEntry;
 pub type FileAttr = axfs_vfs::VfsNodeAttr;
 /// Alias of [`axfs_vfs::VfsNodePerm`].
 pub type FilePerm = axfs_vfs::VfsNodePerm;
+/// Alias of [`axfs_vfs::VfsSetAttr`].
+pub type FileSetAttr = axfs_vfs::VfsSetAttr;
+/// Alias of [`axfs_vfs::FileSystemInfo`].
+pub type FileSystemInfo = axfs_vfs::FileSystemInfo;
 
 /// An opened file object, with open permissions and a cursor.
+///
+/// Reads and writes of regular files go through the page cache.
 pub struct File {
     node: WithCap<VfsNodeRef>,
+    fs: Arc<dyn VfsOps>,
     is_append: bool,
+    is_cached: bool,
     offset: u64,
 }
 
@@ -31,9 +44,14 @@ pub struct File {
 /// [`read_dir`](Directory::read_dir).
 pub struct Directory {
     node: WithCap<VfsNodeRef>,
+    fs: Arc<dyn VfsOps>,
     entry_idx: usize,
 }
 
+/// A directory node with the filesystem containing it, from which relative
+/// paths are opened.
+type DirBase<'a> = (&'a VfsNodeRef, &'a Arc<dyn VfsOps>);
+
 /// Options and flags which can be used to configure how a file is opened.
 #[derive(Clone)]
 pub struct OpenOptions {
@@ -44,9 +62,10 @@ pub struct OpenOptions {
     truncate: bool,
     create: bool,
     create_new: bool,
+    nofollow: bool,
     // system-specific
     _custom_flags: i32,
-    _mode: u32,
+    mode: u32,
 }
 
 impl OpenOptions {
@@ -60,9 +79,10 @@ impl OpenOptions {
             truncate: false,
             create: false,
             create_new: false,
+            nofollow: false,
             // system-specific
             _custom_flags: 0,
-            _mode: 0o666,
+            mode: 0o666,
         }
     }
     /// Sets the option for read access.
@@ -89,6 +109,16 @@ impl OpenOptions {
     pub fn create_new(&mut self, create_new: bool) {
         self.create_new = create_new;
     }
+    /// Sets the option to fail if the last component of the path is a
+    /// symbolic link, instead of following it.
+    pub fn nofollow(&mut self, nofollow: bool) {
+        self.nofollow = nofollow;
+    }
+    /// Sets the permission mode of the file if it is created. The default
+    /// is `0o666`.
+    pub fn mode(&mut self, mode: u32) {
+        self.mode = mode;
+    }
 
     const fn is_valid(&self) -> bool {
         if !self.read && !self.write && !self.append {
@@ -112,51 +142,78 @@ impl OpenOptions {
 }
 
 impl File {
-    fn _open_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open_at(base: Option<DirBase>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
         debug!("open file: {} {:?}", path, opts);
         if !opts.is_valid() {
             return ax_err!(InvalidInput);
         }
+        let dir = base.map(|(node, _)| node);
 
-        let node_option = crate::root::lookup(dir, path);
-        let node = if opts.create || opts.create_new {
+        let node_option = if opts.nofollow {
+            crate::root::lookup_nofollow(dir, path)
+        } else {
+            crate::root::lookup(dir, path)
+        };
+        let (node, created) = if opts.create || opts.create_new {
             match node_option {
                 Ok(node) => {
                     // already exists
                     if opts.create_new {
                         return ax_err!(AlreadyExists);
                     }
-                    node
+                    (node, false)
                 }
                 // not exists, create new
-                Err(VfsError::NotFound) => crate::root::create_file(dir, path)?,
+                Err(VfsError::NotFound) => (crate::root::create_file(dir, path)?, true),
                 Err(e) => return Err(e),
             }
         } else {
             // just open the existing
-            node_option?
+            (node_option?, false)
         };
 
         let attr = node.get_attr()?;
+        if attr.is_symlink() {
+            return ax_err!(FilesystemLoop); // only with `nofollow`
+        }
         if attr.is_dir()
             && (opts.create || opts.create_new || opts.write || opts.append || opts.truncate)
         {
             return ax_err!(IsADirectory);
         }
         let access_cap = opts.into();
-        if !perm_to_cap(attr.perm()).contains(access_cap) {
+        if created {
+            // The new file can be accessed regardless of its mode.
+            let perm = FilePerm::from_bits_truncate(opts.mode as u16);
+            if perm.bits() != attr.perm().bits() {
+                let set_attr = FileSetAttr {
+                    mode: Some(perm),
+                    ..Default::default()
+                };
+                match node.set_attr(&set_attr) {
+                    Ok(()) | Err(AxError::Unsupported) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        } else if !perm_to_cap(attr.perm()).contains(access_cap) {
             return ax_err!(PermissionDenied);
         }
 
         node.open()?;
-        if opts.truncate {
-            node.truncate(0)?;
-        }
-        Ok(Self {
+        let file = Self {
             node: WithCap::new(node, access_cap),
+            fs: match base {
+                Some((_, fs)) => fs.clone(),
+                None => crate::root::filesystem_of(path)?,
+            },
             is_append: opts.append,
+            is_cached: attr.is_file(),
             offset: 0,
-        })
+        };
+        if opts.truncate {
+            file.truncate(0)?;
+        }
+        Ok(file)
     }
 
     /// Opens a file at the path relative to the current directory. Returns a
@@ -165,10 +222,32 @@ impl File {
         Self::_open_at(None, path, opts)
     }
 
+    fn read_node(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        let node = self.node.access(Cap::READ)?;
+        if self.is_cached {
+            PAGE_CACHE.read_at(node, offset, buf)
+        } else {
+            node.read_at(offset, buf)
+        }
+    }
+
+    fn write_node(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        let node = self.node.access(Cap::WRITE)?;
+        if self.is_cached {
+            PAGE_CACHE.write_at(node, offset, buf)
+        } else {
+            node.write_at(offset, buf)
+        }
+    }
+
     /// Truncates the file to the specified size.
     pub fn truncate(&self, size: u64) -> AxResult {
-        self.node.access(Cap::WRITE)?.truncate(size)?;
-        Ok(())
+        let node = self.node.access(Cap::WRITE)?;
+        if self.is_cached {
+            PAGE_CACHE.truncate(node, size)
+        } else {
+            node.truncate(size)
+        }
     }
 
     /// Reads the file at the current position. Returns the number of bytes
@@ -176,8 +255,7 @@ impl File {
     ///
     /// After the read, the cursor will be advanced by the number of bytes read.
     pub fn read(&mut self, buf: &mut [u8]) -> AxResult<usize> {
-        let node = self.node.access(Cap::READ)?;
-        let read_len = node.read_at(self.offset, buf)?;
+        let read_len = self.read_node(self.offset, buf)?;
         self.offset += read_len as u64;
         Ok(read_len)
     }
@@ -186,9 +264,7 @@ impl File {
     ///
     /// It does not update the file cursor.
     pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
-        let node = self.node.access(Cap::READ)?;
-        let read_len = node.read_at(offset, buf)?;
-        Ok(read_len)
+        self.read_node(offset, buf)
     }
 
     /// Writes the file at the current position. Returns the number of bytes
@@ -197,11 +273,11 @@ impl File {
     /// After the write, the cursor will be advanced by the number of bytes
     /// written.
     pub fn write(&mut self, buf: &[u8]) -> AxResult<usize> {
-        let node = self.node.access(Cap::WRITE)?;
+        self.node.access(Cap::WRITE)?;
         if self.is_append {
             self.offset = self.get_attr()?.size();
         };
-        let write_len = node.write_at(self.offset, buf)?;
+        let write_len = self.write_node(self.offset, buf)?;
         self.offset += write_len as u64;
         Ok(write_len)
     }
@@ -211,9 +287,7 @@ impl File {
     ///
     /// It does not update the file cursor.
     pub fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
-        let node = self.node.access(Cap::WRITE)?;
-        let write_len = node.write_at(offset, buf)?;
-        Ok(write_len)
+        self.write_node(offset, buf)
     }
 
     /// Flushes the file, writes all buffered data to the underlying device.
@@ -240,10 +314,30 @@ impl File {
     pub fn get_attr(&self) -> AxResult<FileAttr> {
         self.node.access(Cap::empty())?.get_attr()
     }
+
+    /// Sets the permission, owner or timestamps of the file.
+    ///
+    /// It does not require the read or write access.
+    pub fn set_attr(&self, attr: &FileSetAttr) -> AxResult {
+        self.node.access(Cap::empty())?.set_attr(attr)
+    }
+
+    /// Sets the time of the last modification of the file.
+    pub fn set_modified(&self, mtime: Duration) -> AxResult {
+        self.set_attr(&FileSetAttr {
+            mtime: Some(mtime),
+            ..Default::default()
+        })
+    }
+
+    /// Gets the attributes of the filesystem that contains the file.
+    pub fn statfs(&self) -> AxResult<FileSystemInfo> {
+        self.fs.statfs()
+    }
 }
 
 impl Directory {
-    fn _open_dir_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open_dir_at(base: Option<DirBase>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
         debug!("open dir: {}", path);
         if !opts.read {
             return ax_err!(InvalidInput);
@@ -251,6 +345,7 @@ impl Directory {
         if opts.create || opts.create_new || opts.write || opts.append || opts.truncate {
             return ax_err!(InvalidInput);
         }
+        let dir = base.map(|(node, _)| node);
 
         let node = crate::root::lookup(dir, path)?;
         let attr = node.get_attr()?;
@@ -265,15 +360,25 @@ impl Directory {
         node.open()?;
         Ok(Self {
             node: WithCap::new(node, access_cap),
+            fs: match base {
+                Some((_, fs)) => fs.clone(),
+                None => crate::root::filesystem_of(path)?,
+            },
             entry_idx: 0,
         })
     }
 
     fn access_at(&self, path: &str) -> AxResult<Option<&VfsNodeRef>> {
+        Ok(self.base_at(path)?.map(|(node, _)| node))
+    }
+
+    /// Returns this directory with its filesystem if `path` is relative to
+    /// it, or `None` if `path` is absolute.
+    fn base_at(&self, path: &str) -> AxResult<Option<DirBase<'_>>> {
         if path.starts_with('/') {
             Ok(None)
         } else {
-            Ok(Some(self.node.access(Cap::EXECUTE)?))
+            Ok(Some((self.node.access(Cap::EXECUTE)?, &self.fs)))
         }
     }
 
@@ -286,13 +391,13 @@ impl Directory {
     /// Opens a directory at the path relative to this directory. Returns a
     /// [`Directory`] object.
     pub fn open_dir_at(&self, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(self.access_at(path)?, path, opts)
+        Self::_open_dir_at(self.base_at(path)?, path, opts)
     }
 
     /// Opens a file at the path relative to this directory. Returns a [`File`]
     /// object.
     pub fn open_file_at(&self, path: &str, opts: &OpenOptions) -> AxResult<File> {
-        File::_open_at(self.access_at(path)?, path, opts)
+        File::_open_at(self.base_at(path)?, path, opts)
     }
 
     /// Creates an empty file at the path relative to this directory.
@@ -336,11 +441,68 @@ impl Directory {
     pub fn rename(&self, old: &str, new: &str) -> AxResult {
         crate::root::rename(old, new)
     }
+
+    /// Gets the attributes of the filesystem that contains the directory.
+    pub fn statfs(&self) -> AxResult<FileSystemInfo> {
+        self.fs.statfs()
+    }
+}
+
+/// Writes all modified data in the block caches back to the disks.
+pub fn sync() -> AxResult {
+    crate::cache::sync().map_err(|_| AxError::Io)
+}
+
+/// Returns the statistics of the block caches of all disks.
+pub fn block_cache_stats() -> CacheStats {
+    crate::cache::block_cache_stats()
+}
+
+/// Returns the statistics of the page cache.
+pub fn page_cache_stats() -> CacheStats {
+    PAGE_CACHE.stats()
+}
+
+/// Gets the attributes of the node at the path relative to the current
+/// directory.
+///
+/// Unlike [`File::get_attr`], the node is not opened, so no permission is
+/// required.
+pub fn attr(path: &str) -> AxResult<FileAttr> {
+    crate::root::lookup(None, path)?.get_attr()
+}
+
+/// Gets the attributes of the node at the path relative to the current
+/// directory, without following the symbolic link.
+pub fn symlink_attr(path: &str) -> AxResult<FileAttr> {
+    crate::root::lookup_nofollow(None, path)?.get_attr()
+}
+
+/// Sets the permission, owner or timestamps of the node at the path relative
+/// to the current directory.
+pub fn set_attr(path: &str, attr: &FileSetAttr) -> AxResult {
+    crate::root::lookup(None, path)?.set_attr(attr)
+}
+
+/// Same as [`set_attr`], but does not follow the symbolic link.
+pub fn set_symlink_attr(path: &str, attr: &FileSetAttr) -> AxResult {
+    crate::root::lookup_nofollow(None, path)?.set_attr(attr)
+}
+
+/// Gets the attributes of the filesystem that contains the node at the path
+/// relative to the current directory.
+pub fn statfs(path: &str) -> AxResult<FileSystemInfo> {
+    crate::root::lookup(None, path)?;
+    crate::root::filesystem_of(path)?.statfs()
 }
 
 impl Drop for File {
     fn drop(&mut self) {
-        unsafe { self.node.access_unchecked().release().ok() };
+        let node = unsafe { self.node.access_unchecked() };
+        if self.is_cached {
+            PAGE_CACHE.invalidate(node);
+        }
+        node.release().ok();
     }
 }
 
@@ -371,6 +533,7 @@ impl fmt::Debug for OpenOptions {
         fmt_opt!(truncate, "TRUNC");
         fmt_opt!(create, "CREATE");
         fmt_opt!(create_new, "CREATE_NEW");
+        fmt_opt!(nofollow, "NOFOLLOW");
         Ok(())
     }
 }
@@ -0,0 +1,518 @@
+//! On-disk structures of ext2/ext4.
+//!
+//! See <https://www.kernel.org/doc/html/latest/filesystems/ext4/index.html>.
+
+use alloc::vec::Vec;
+
+use axfs_vfs::VfsNodeType;
+
+/// Byte offset of the superblock.
+pub const SUPERBLOCK_OFFSET: u64 = 1024;
+/// Size of the superblock.
+pub const SUPERBLOCK_SIZE: usize = 1024;
+pub const EXT2_MAGIC: u16 = 0xef53;
+
+pub const ROOT_INO: u32 = 2;
+/// Number of direct block pointers in an inode.
+pub const NDIR_BLOCKS: usize = 12;
+/// Size of the `i_block` field, which holds the block pointers, the extent
+/// tree root or the target of a fast symlink.
+pub const IBLOCK_SIZE: usize = 60;
+
+pub const INCOMPAT_FILETYPE: u32 = 0x2;
+pub const INCOMPAT_RECOVER: u32 = 0x4;
+pub const INCOMPAT_EXTENTS: u32 = 0x40;
+pub const INCOMPAT_64BIT: u32 = 0x80;
+pub const INCOMPAT_MMP: u32 = 0x100;
+pub const INCOMPAT_FLEX_BG: u32 = 0x200;
+pub const INCOMPAT_EA_INODE: u32 = 0x400;
+pub const INCOMPAT_CSUM_SEED: u32 = 0x2000;
+pub const INCOMPAT_LARGEDIR: u32 = 0x4000;
+pub const INCOMPAT_INLINE_DATA: u32 = 0x8000;
+
+pub const RO_COMPAT_SPARSE_SUPER: u32 = 0x1;
+pub const RO_COMPAT_LARGE_FILE: u32 = 0x2;
+
+/// Incompatible features that can be read.
+pub const INCOMPAT_READ_SUPP: u32 = INCOMPAT_FILETYPE
+    | INCOMPAT_RECOVER
+    | INCOMPAT_EXTENTS
+    | INCOMPAT_64BIT
+    | INCOMPAT_MMP
+    | INCOMPAT_FLEX_BG
+    | INCOMPAT_EA_INODE
+    | INCOMPAT_CSUM_SEED
+    | INCOMPAT_LARGEDIR
+    | INCOMPAT_INLINE_DATA;
+/// Incompatible features that can be written, i.e., those of ext2.
+pub const INCOMPAT_WRITE_SUPP: u32 = INCOMPAT_FILETYPE;
+/// Read-only compatible features that can be written.
+pub const RO_COMPAT_WRITE_SUPP: u32 = RO_COMPAT_SPARSE_SUPER | RO_COMPAT_LARGE_FILE;
+
+/// The directory uses hashed indexes.
+pub const INDEX_FL: u32 = 0x1000;
+/// The inode uses extents.
+pub const EXTENTS_FL: u32 = 0x80000;
+/// The inode has inline data.
+pub const INLINE_DATA_FL: u32 = 0x1000_0000;
+
+pub const S_IFMT: u16 = 0o170000;
+pub const S_IFIFO: u16 = 0o010000;
+pub const S_IFCHR: u16 = 0o020000;
+pub const S_IFDIR: u16 = 0o040000;
+pub const S_IFBLK: u16 = 0o060000;
+pub const S_IFREG: u16 = 0o100000;
+pub const S_IFLNK: u16 = 0o120000;
+pub const S_IFSOCK: u16 = 0o140000;
+
+pub const EXTENT_MAGIC: u16 = 0xf30a;
+/// An extent longer than this is uninitialized (reads as zeros).
+pub const EXTENT_INIT_MAX_LEN: u16 = 32768;
+
+pub fn read_u16(buf: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([buf[off], buf[off + 1]])
+}
+
+pub fn read_u32(buf: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(buf[off..off + 4].try_into().unwrap())
+}
+
+pub fn write_u16(buf: &mut [u8], off: usize, val: u16) {
+    buf[off..off + 2].copy_from_slice(&val.to_le_bytes());
+}
+
+pub fn write_u32(buf: &mut [u8], off: usize, val: u32) {
+    buf[off..off + 4].copy_from_slice(&val.to_le_bytes());
+}
+
+/// The superblock, kept in the raw form to be written back.
+pub struct Superblock {
+    pub raw: Vec<u8>,
+}
+
+impl Superblock {
+    pub fn inodes_count(&self) -> u32 {
+        read_u32(&self.raw, 0)
+    }
+
+    pub fn blocks_count(&self) -> u64 {
+        let hi = if self.is_64bit() {
+            read_u32(&self.raw, 0x150)
+        } else {
+            0
+        };
+        read_u32(&self.raw, 4) as u64 | (hi as u64) << 32
+    }
+
+    pub fn r_blocks_count(&self) -> u64 {
+        let hi = if self.is_64bit() {
+            read_u32(&self.raw, 0x154)
+        } else {
+            0
+        };
+        read_u32(&self.raw, 8) as u64 | (hi as u64) << 32
+    }
+
+    pub fn free_blocks_count(&self) -> u32 {
+        read_u32(&self.raw, 12)
+    }
+
+    pub fn set_free_blocks_count(&mut self, val: u32) {
+        write_u32(&mut self.raw, 12, val)
+    }
+
+    pub fn free_inodes_count(&self) -> u32 {
+        read_u32(&self.raw, 16)
+    }
+
+    pub fn set_free_inodes_count(&mut self, val: u32) {
+        write_u32(&mut self.raw, 16, val)
+    }
+
+    pub fn first_data_block(&self) -> u32 {
+        read_u32(&self.raw, 20)
+    }
+
+    pub fn block_size(&self) -> usize {
+        1024 << read_u32(&self.raw, 24)
+    }
+
+    pub fn blocks_per_group(&self) -> u32 {
+        read_u32(&self.raw, 32)
+    }
+
+    pub fn inodes_per_group(&self) -> u32 {
+        read_u32(&self.raw, 40)
+    }
+
+    /// The last write time, in seconds since the Unix epoch.
+    pub fn write_time(&self) -> u32 {
+        read_u32(&self.raw, 48)
+    }
+
+    pub fn magic(&self) -> u16 {
+        read_u16(&self.raw, 56)
+    }
+
+    fn rev_level(&self) -> u32 {
+        read_u32(&self.raw, 76)
+    }
+
+    pub fn inode_size(&self) -> usize {
+        if self.rev_level() == 0 {
+            128
+        } else {
+            read_u16(&self.raw, 88) as usize
+        }
+    }
+
+    pub fn feature_incompat(&self) -> u32 {
+        read_u32(&self.raw, 96)
+    }
+
+    pub fn feature_ro_compat(&self) -> u32 {
+        read_u32(&self.raw, 100)
+    }
+
+    pub fn is_64bit(&self) -> bool {
+        self.feature_incompat() & INCOMPAT_64BIT != 0
+    }
+
+    pub fn desc_size(&self) -> usize {
+        if self.is_64bit() {
+            read_u16(&self.raw, 254) as usize
+        } else {
+            32
+        }
+    }
+
+    /// The size of the extra inode fields that new inodes should reserve.
+    pub fn want_extra_isize(&self) -> u16 {
+        if self.inode_size() > 128 {
+            read_u16(&self.raw, 0x15e)
+        } else {
+            0
+        }
+    }
+
+    pub fn has_filetype(&self) -> bool {
+        self.feature_incompat() & INCOMPAT_FILETYPE != 0
+    }
+}
+
+/// A block group descriptor.
+pub struct GroupDesc {
+    pub block_bitmap: u64,
+    pub inode_bitmap: u64,
+    pub inode_table: u64,
+    pub free_blocks: u32,
+    pub free_inodes: u32,
+    pub used_dirs: u32,
+}
+
+impl GroupDesc {
+    pub fn parse(raw: &[u8], is_64bit: bool) -> Self {
+        let lo32 = |off| read_u32(raw, off) as u64;
+        let lo16 = |off| read_u16(raw, off) as u32;
+        let hi32 = |off| {
+            if is_64bit {
+                (read_u32(raw, off) as u64) << 32
+            } else {
+                0
+            }
+        };
+        let hi16 = |off| {
+            if is_64bit {
+                (read_u16(raw, off) as u32) << 16
+            } else {
+                0
+            }
+        };
+        Self {
+            block_bitmap: lo32(0) | hi32(0x20),
+            inode_bitmap: lo32(4) | hi32(0x24),
+            inode_table: lo32(8) | hi32(0x28),
+            free_blocks: lo16(12) | hi16(0x2c),
+            free_inodes: lo16(14) | hi16(0x2e),
+            used_dirs: lo16(16) | hi16(0x30),
+        }
+    }
+
+    /// Writes the counters back. Only the 32-byte ext2 layout is written, as
+    /// other filesystems are mounted read-only.
+    pub fn write_counts(&self, raw: &mut [u8]) {
+        write_u16(raw, 12, self.free_blocks as u16);
+        write_u16(raw, 14, self.free_inodes as u16);
+        write_u16(raw, 16, self.used_dirs as u16);
+    }
+}
+
+/// An inode, kept in the raw form to be written back.
+#[derive(Clone)]
+pub struct Inode {
+    pub raw: Vec<u8>,
+}
+
+impl Inode {
+    pub fn mode(&self) -> u16 {
+        read_u16(&self.raw, 0)
+    }
+
+    pub fn set_mode(&mut self, mode: u16) {
+        write_u16(&mut self.raw, 0, mode)
+    }
+
+    pub fn node_type(&self) -> VfsNodeType {
+        mode_to_type(self.mode())
+    }
+
+    /// The owner's user ID, with the high 16 bits in `osd2`.
+    pub fn uid(&self) -> u32 {
+        read_u16(&self.raw, 2) as u32 | (read_u16(&self.raw, 120) as u32) << 16
+    }
+
+    pub fn set_uid(&mut self, uid: u32) {
+        write_u16(&mut self.raw, 2, uid as u16);
+        write_u16(&mut self.raw, 120, (uid >> 16) as u16);
+    }
+
+    /// The owner's group ID, with the high 16 bits in `osd2`.
+    pub fn gid(&self) -> u32 {
+        read_u16(&self.raw, 24) as u32 | (read_u16(&self.raw, 122) as u32) << 16
+    }
+
+    pub fn set_gid(&mut self, gid: u32) {
+        write_u16(&mut self.raw, 24, gid as u16);
+        write_u16(&mut self.raw, 122, (gid >> 16) as u16);
+    }
+
+    /// The last access time, in seconds.
+    pub fn atime(&self) -> u32 {
+        read_u32(&self.raw, 8)
+    }
+
+    pub fn set_atime(&mut self, time: u32) {
+        write_u32(&mut self.raw, 8, time)
+    }
+
+    /// The last status change time, in seconds.
+    pub fn ctime(&self) -> u32 {
+        read_u32(&self.raw, 12)
+    }
+
+    pub fn set_ctime(&mut self, time: u32) {
+        write_u32(&mut self.raw, 12, time)
+    }
+
+    /// The last modification time, in seconds.
+    pub fn mtime(&self) -> u32 {
+        read_u32(&self.raw, 16)
+    }
+
+    pub fn set_mtime(&mut self, time: u32) {
+        write_u32(&mut self.raw, 16, time)
+    }
+
+    pub fn size(&self) -> u64 {
+        read_u32(&self.raw, 4) as u64 | (read_u32(&self.raw, 108) as u64) << 32
+    }
+
+    pub fn set_size(&mut self, size: u64) {
+        write_u32(&mut self.raw, 4, size as u32);
+        write_u32(&mut self.raw, 108, (size >> 32) as u32);
+    }
+
+    pub fn links_count(&self) -> u16 {
+        read_u16(&self.raw, 26)
+    }
+
+    pub fn set_links_count(&mut self, count: u16) {
+        write_u16(&mut self.raw, 26, count)
+    }
+
+    /// Number of 512-byte sectors allocated, including the indirect blocks.
+    pub fn blocks(&self) -> u64 {
+        read_u32(&self.raw, 28) as u64
+    }
+
+    pub fn set_blocks(&mut self, blocks: u64) {
+        write_u32(&mut self.raw, 28, blocks as u32)
+    }
+
+    pub fn flags(&self) -> u32 {
+        read_u32(&self.raw, 32)
+    }
+
+    pub fn set_flags(&mut self, flags: u32) {
+        write_u32(&mut self.raw, 32, flags)
+    }
+
+    /// The `i_block` field.
+    pub fn iblock(&self) -> &[u8] {
+        &self.raw[40..40 + IBLOCK_SIZE]
+    }
+
+    pub fn iblock_mut(&mut self) -> &mut [u8] {
+        &mut self.raw[40..40 + IBLOCK_SIZE]
+    }
+
+    pub fn block(&self, idx: usize) -> u32 {
+        read_u32(&self.raw, 40 + idx * 4)
+    }
+
+    pub fn set_block(&mut self, idx: usize, block: u32) {
+        write_u32(&mut self.raw, 40 + idx * 4, block)
+    }
+
+    pub fn file_acl(&self) -> u32 {
+        read_u32(&self.raw, 104)
+    }
+
+    pub fn set_file_acl(&mut self, block: u32) {
+        write_u32(&mut self.raw, 104, block)
+    }
+
+    pub fn set_dtime(&mut self, time: u32) {
+        write_u32(&mut self.raw, 20, time)
+    }
+
+    pub fn set_extra_isize(&mut self, size: u16) {
+        write_u16(&mut self.raw, 128, size)
+    }
+
+    /// Whether the inode is a symlink whose target is stored in `i_block`,
+    /// i.e., no blocks are allocated except the extended attribute block.
+    pub fn is_fast_symlink(&self, block_size: usize) -> bool {
+        let xattr_sectors = if self.file_acl() != 0 {
+            block_size as u64 / 512
+        } else {
+            0
+        };
+        self.mode() & S_IFMT == S_IFLNK
+            && self.flags() & INLINE_DATA_FL == 0
+            && self.blocks() == xattr_sectors
+    }
+}
+
+pub fn mode_to_type(mode: u16) -> VfsNodeType {
+    match mode & S_IFMT {
+        S_IFIFO => VfsNodeType::Fifo,
+        S_IFCHR => VfsNodeType::CharDevice,
+        S_IFDIR => VfsNodeType::Dir,
+        S_IFBLK => VfsNodeType::BlockDevice,
+        S_IFLNK => VfsNodeType::SymLink,
+        S_IFSOCK => VfsNodeType::Socket,
+        _ => VfsNodeType::File,
+    }
+}
+
+pub fn type_to_mode(ty: VfsNodeType) -> u16 {
+    match ty {
+        VfsNodeType::Fifo => S_IFIFO,
+        VfsNodeType::CharDevice => S_IFCHR,
+        VfsNodeType::Dir => S_IFDIR,
+        VfsNodeType::BlockDevice => S_IFBLK,
+        VfsNodeType::File => S_IFREG,
+        VfsNodeType::SymLink => S_IFLNK,
+        VfsNodeType::Socket => S_IFSOCK,
+    }
+}
+
+/// Converts the `file_type` of a directory entry to the node type.
+pub fn dirent_type(file_type: u8) -> Option<VfsNodeType> {
+    Some(match file_type {
+        1 => VfsNodeType::File,
+        2 => VfsNodeType::Dir,
+        3 => VfsNodeType::CharDevice,
+        4 => VfsNodeType::BlockDevice,
+        5 => VfsNodeType::Fifo,
+        6 => VfsNodeType::Socket,
+        7 => VfsNodeType::SymLink,
+        _ => return None,
+    })
+}
+
+pub fn dirent_file_type(ty: VfsNodeType) -> u8 {
+    match ty {
+        VfsNodeType::File => 1,
+        VfsNodeType::Dir => 2,
+        VfsNodeType::CharDevice => 3,
+        VfsNodeType::BlockDevice => 4,
+        VfsNodeType::Fifo => 5,
+        VfsNodeType::Socket => 6,
+        VfsNodeType::SymLink => 7,
+    }
+}
+
+/// Header of a directory entry: `inode`, `rec_len`, `name_len` and
+/// `file_type`.
+pub const DIRENT_HEADER_SIZE: usize = 8;
+
+/// The length of a directory entry with a name of `name_len` bytes.
+pub const fn dirent_len(name_len: usize) -> usize {
+    (DIRENT_HEADER_SIZE + name_len + 3) & !3
+}
+
+/// A directory entry parsed from a directory block.
+pub struct RawDirEntry {
+    /// Offset in the block.
+    pub offset: usize,
+    pub ino: u32,
+    pub rec_len: usize,
+    pub name_len: usize,
+    pub file_type: u8,
+}
+
+impl RawDirEntry {
+    pub fn parse(block: &[u8], offset: usize, has_filetype: bool) -> Option<Self> {
+        if offset + DIRENT_HEADER_SIZE > block.len() {
+            return None;
+        }
+        let rec_len = read_u16(block, offset + 4) as usize;
+        let (name_len, file_type) = if has_filetype {
+            (block[offset + 6] as usize, block[offset + 7])
+        } else {
+            (read_u16(block, offset + 6) as usize, 0)
+        };
+        if rec_len < DIRENT_HEADER_SIZE
+            || offset + rec_len > block.len()
+            || DIRENT_HEADER_SIZE + name_len > rec_len
+        {
+            return None;
+        }
+        Some(Self {
+            offset,
+            ino: read_u32(block, offset),
+            rec_len,
+            name_len,
+            file_type,
+        })
+    }
+
+    pub fn name<'a>(&self, block: &'a [u8]) -> &'a [u8] {
+        let start = self.offset + DIRENT_HEADER_SIZE;
+        &block[start..start + self.name_len]
+    }
+
+    /// Writes a directory entry at `offset` of the block.
+    pub fn write(
+        block: &mut [u8],
+        offset: usize,
+        ino: u32,
+        rec_len: usize,
+        name: &[u8],
+        file_type: u8,
+        has_filetype: bool,
+    ) {
+        write_u32(block, offset, ino);
+        write_u16(block, offset + 4, rec_len as u16);
+        if has_filetype {
+            block[offset + 6] = name.len() as u8;
+            block[offset + 7] = file_type;
+        } else {
+            write_u16(block, offset + 6, name.len() as u16);
+        }
+        let start = offset + DIRENT_HEADER_SIZE;
+        block[start..start + name.len()].copy_from_slice(name);
+    }
+}
@@ -0,0 +1,270 @@
+//! The [ext2] filesystem, with read-only support of [ext4] (extents, 64-bit
+//! block numbers and flexible block groups).
+//!
+//! Filesystems with features beyond ext2 are mounted read-only, so they must
+//! contain the mount points of other filesystems (e.g., `/dev` and `/tmp`).
+//! Journals are neither replayed nor written.
+//!
+//! [ext2]: https://en.wikipedia.org/wiki/Ext2
+//! [ext4]: https://en.wikipedia.org/wiki/Ext4
+
+mod layout;
+mod volume;
+
+use alloc::sync::Arc;
+
+use axfs_vfs::{FileSystemInfo, VfsDirEntry, VfsError, VfsNodePerm, VfsResult, VfsSetAttr};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
+use axsync::Mutex;
+use core::time::Duration;
+
+use self::layout::ROOT_INO;
+use self::volume::Volume;
+use crate::dev::Disk;
+
+type VolumeRef = Arc<Mutex<Volume>>;
+
+pub struct Ext2FileSystem {
+    vol: VolumeRef,
+    root: VfsNodeRef,
+}
+
+/// A directory.
+pub struct Ext2Dir {
+    vol: VolumeRef,
+    ino: u32,
+}
+
+/// A regular file, a symlink or a special file.
+pub struct Ext2File {
+    vol: VolumeRef,
+    ino: u32,
+}
+
+impl Ext2FileSystem {
+    /// Opens the filesystem on `disk`, which is mounted read-only if
+    /// `read_only` is true or some features are not supported.
+    pub fn new(disk: Disk, read_only: bool) -> VfsResult<Self> {
+        let vol = Arc::new(Mutex::new(Volume::open(disk, read_only)?));
+        Ok(Self {
+            vol: vol.clone(),
+            root: Arc::new(Ext2Dir { vol, ino: ROOT_INO }),
+        })
+    }
+}
+
+impl VfsOps for Ext2FileSystem {
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(self.vol.lock().statfs())
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
+
+fn new_node(vol: &VolumeRef, ino: u32, ty: VfsNodeType) -> VfsNodeRef {
+    let vol = vol.clone();
+    if ty == VfsNodeType::Dir {
+        Arc::new(Ext2Dir { vol, ino })
+    } else {
+        Arc::new(Ext2File { vol, ino })
+    }
+}
+
+fn get_attr(vol: &VolumeRef, ino: u32) -> VfsResult<VfsNodeAttr> {
+    let inode = vol.lock().read_inode(ino)?;
+    let mut attr = VfsNodeAttr::new(
+        VfsNodePerm::from_bits_truncate(inode.mode()),
+        inode.node_type(),
+        inode.size(),
+        inode.blocks(),
+    );
+    attr.set_owner(inode.uid(), inode.gid());
+    let secs = |time| Duration::from_secs(time as u64);
+    attr.set_times(
+        secs(inode.atime()),
+        secs(inode.mtime()),
+        secs(inode.ctime()),
+    );
+    Ok(attr)
+}
+
+/// Splits the path into the parent path and the last component.
+fn split_parent(path: &str) -> (&str, &str) {
+    let path = path.trim_end_matches('/');
+    match path.rsplit_once('/') {
+        Some((parent, name)) => (parent, name),
+        None => ("", path),
+    }
+}
+
+impl Ext2Dir {
+    /// Finds the node with the relative `path`, returns its inode number and
+    /// type.
+    fn walk(&self, vol: &mut Volume, path: &str) -> VfsResult<(u32, VfsNodeType)> {
+        let mut cur = (self.ino, VfsNodeType::Dir);
+        for name in path.split('/').filter(|s| !s.is_empty() && *s != ".") {
+            if cur.1 != VfsNodeType::Dir {
+                return Err(VfsError::NotADirectory);
+            }
+            if name == ".." && cur.0 == ROOT_INO {
+                // Same as other filesystems, the root has no parent.
+                return Err(VfsError::NotFound);
+            }
+            let dir = vol.read_inode(cur.0)?;
+            cur = vol.lookup(&dir, name)?.ok_or(VfsError::NotFound)?;
+        }
+        Ok(cur)
+    }
+
+    /// Finds the parent directory of the relative `path`, returns its inode
+    /// number and the last component of the path.
+    fn walk_parent<'a>(&self, vol: &mut Volume, path: &'a str) -> VfsResult<(u32, &'a str)> {
+        let (parent, name) = split_parent(path);
+        match self.walk(vol, parent)? {
+            (ino, VfsNodeType::Dir) => Ok((ino, name)),
+            _ => Err(VfsError::NotADirectory),
+        }
+    }
+
+    /// Same as [`Ext2Dir::walk_parent`], but fails if the node with the
+    /// relative `path` already exists.
+    fn walk_new<'a>(&self, vol: &mut Volume, path: &'a str) -> VfsResult<(u32, &'a str)> {
+        let (dir_ino, name) = self.walk_parent(vol, path)?;
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::AlreadyExists);
+        }
+        let dir = vol.read_inode(dir_ino)?;
+        if vol.lookup(&dir, name)?.is_some() {
+            return Err(VfsError::AlreadyExists);
+        }
+        Ok((dir_ino, name))
+    }
+}
+
+impl VfsNodeOps for Ext2Dir {
+    axfs_vfs::impl_vfs_dir_default! {}
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        get_attr(&self.vol, self.ino)
+    }
+
+    fn set_attr(&self, attr: &VfsSetAttr) -> VfsResult {
+        self.vol.lock().set_attr(self.ino, attr)
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        if self.ino == ROOT_INO {
+            return None;
+        }
+        let (ino, ty) = self.walk(&mut self.vol.lock(), "..").ok()?;
+        Some(new_node(&self.vol, ino, ty))
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        debug!("lookup at ext2: {}", path);
+        let (ino, ty) = self.walk(&mut self.vol.lock(), path)?;
+        Ok(new_node(&self.vol, ino, ty))
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        debug!("create {:?} at ext2: {}", ty, path);
+        let mut vol = self.vol.lock();
+        let (dir_ino, name) = self.walk_parent(&mut vol, path)?;
+        if name.is_empty() || name == "." || name == ".." {
+            return Ok(());
+        }
+        let dir = vol.read_inode(dir_ino)?;
+        if vol.lookup(&dir, name)?.is_some() {
+            return Ok(());
+        }
+        vol.create(dir_ino, name, ty)?;
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        debug!("remove at ext2: {}", path);
+        let mut vol = self.vol.lock();
+        let (dir_ino, name) = self.walk_parent(&mut vol, path)?;
+        vol.unlink(dir_ino, name)
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let mut vol = self.vol.lock();
+        let dir = vol.read_inode(self.ino)?;
+        let entries = vol.dir_entries(&dir)?;
+        let mut count = 0;
+        for (entry, out) in entries.iter().skip(start_idx).zip(dirents.iter_mut()) {
+            *out = VfsDirEntry::new(&entry.name, entry.ty);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        debug!("rename at ext2: {} -> {}", src_path, dst_path);
+        let mut vol = self.vol.lock();
+        let (src_dir, src_name) = self.walk_parent(&mut vol, src_path)?;
+        let (dst_dir, dst_name) = self.walk_parent(&mut vol, dst_path)?;
+        vol.rename(src_dir, src_name, dst_dir, dst_name)
+    }
+
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        debug!("symlink at ext2: {} -> {}", path, target);
+        let mut vol = self.vol.lock();
+        let (dir_ino, name) = self.walk_new(&mut vol, path)?;
+        vol.symlink(dir_ino, name, target)?;
+        Ok(())
+    }
+
+    fn link(&self, path: &str, node: &VfsNodeRef) -> VfsResult {
+        debug!("link at ext2: {}", path);
+        let file = match node.as_any().downcast_ref::<Ext2File>() {
+            Some(file) if Arc::ptr_eq(&file.vol, &self.vol) => file,
+            _ => return Err(VfsError::Unsupported), // not in this filesystem
+        };
+        let mut vol = self.vol.lock();
+        let (dir_ino, name) = self.walk_new(&mut vol, path)?;
+        vol.link(dir_ino, name, file.ino)
+    }
+}
+
+impl VfsNodeOps for Ext2File {
+    axfs_vfs::impl_vfs_non_dir_default! {}
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        get_attr(&self.vol, self.ino)
+    }
+
+    fn set_attr(&self, attr: &VfsSetAttr) -> VfsResult {
+        self.vol.lock().set_attr(self.ino, attr)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut vol = self.vol.lock();
+        let inode = vol.read_inode(self.ino)?;
+        vol.read_at(&inode, offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.vol.lock().write_at(self.ino, offset, buf)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.vol.lock().sync()
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        self.vol.lock().truncate(self.ino, size)
+    }
+
+    fn readlink(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut vol = self.vol.lock();
+        let inode = vol.read_inode(self.ino)?;
+        if inode.node_type() != VfsNodeType::SymLink {
+            return Err(VfsError::InvalidInput);
+        }
+        vol.read_link(&inode, buf)
+    }
+}
@@ -0,0 +1,1009 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use axfs_vfs::{FileSystemInfo, VfsError, VfsNodePerm, VfsNodeType, VfsResult, VfsSetAttr};
+
+use super::layout::*;
+use crate::dev::Disk;
+
+/// A directory entry returned by [`Volume::dir_entries`].
+pub struct DirEntry {
+    pub name: String,
+    pub ty: VfsNodeType,
+}
+
+/// An ext2/ext4 volume on a disk, on which all the operations are done.
+pub struct Volume {
+    disk: Disk,
+    sb: Superblock,
+    /// The raw group descriptor table.
+    gdt: Vec<u8>,
+    groups: Vec<GroupDesc>,
+    block_size: usize,
+    writable: bool,
+}
+
+impl Volume {
+    pub fn open(mut disk: Disk, read_only: bool) -> VfsResult<Self> {
+        let mut raw = vec![0; SUPERBLOCK_SIZE];
+        read_exact_at(&mut disk, SUPERBLOCK_OFFSET, &mut raw)?;
+        let sb = Superblock { raw };
+        if sb.magic() != EXT2_MAGIC {
+            warn!("ext2: bad magic number {:#x}", sb.magic());
+            return Err(VfsError::InvalidData);
+        }
+        let incompat = sb.feature_incompat();
+        if incompat & !INCOMPAT_READ_SUPP != 0 {
+            warn!(
+                "ext2: unsupported incompatible features {:#x}",
+                incompat & !INCOMPAT_READ_SUPP
+            );
+            return Err(VfsError::Unsupported);
+        }
+        if incompat & INCOMPAT_RECOVER != 0 {
+            warn!("ext2: the journal needs recovery, recent changes may be missing");
+        }
+        let writable = incompat & !INCOMPAT_WRITE_SUPP == 0
+            && sb.feature_ro_compat() & !RO_COMPAT_WRITE_SUPP == 0;
+        if !writable && !read_only {
+            info!("ext2: filesystem features are not fully supported, mounting read-only");
+        }
+
+        let block_size = sb.block_size();
+        let group_count = (sb.blocks_count() - sb.first_data_block() as u64)
+            .div_ceil(sb.blocks_per_group() as u64) as usize;
+        let desc_size = sb.desc_size();
+        let gdt_pos = (sb.first_data_block() as u64 + 1) * block_size as u64;
+        let mut gdt = vec![0; group_count * desc_size];
+        read_exact_at(&mut disk, gdt_pos, &mut gdt)?;
+        let groups = gdt
+            .chunks(desc_size)
+            .map(|raw| GroupDesc::parse(raw, sb.is_64bit()))
+            .collect();
+
+        Ok(Self {
+            disk,
+            sb,
+            gdt,
+            groups,
+            block_size,
+            writable: writable && !read_only,
+        })
+    }
+
+    fn check_writable(&self) -> VfsResult {
+        if self.writable {
+            Ok(())
+        } else {
+            Err(VfsError::PermissionDenied)
+        }
+    }
+
+    /// Writes all modified blocks back to the disk.
+    pub fn sync(&mut self) -> VfsResult {
+        self.disk.sync().map_err(|_| VfsError::Io)
+    }
+
+    pub fn statfs(&self) -> FileSystemInfo {
+        let free = self.sb.free_blocks_count() as u64;
+        FileSystemInfo {
+            fs_type: EXT2_MAGIC as u64,
+            block_size: self.block_size as u64,
+            blocks: self.sb.blocks_count(),
+            blocks_free: free,
+            blocks_available: free.saturating_sub(self.sb.r_blocks_count()),
+            files: self.sb.inodes_count() as u64,
+            files_free: self.sb.free_inodes_count() as u64,
+            name_max: 255,
+        }
+    }
+
+    fn read_block(&mut self, block: u64, buf: &mut [u8]) -> VfsResult {
+        read_exact_at(&mut self.disk, block * self.block_size as u64, buf)
+    }
+
+    fn write_block(&mut self, block: u64, buf: &[u8]) -> VfsResult {
+        write_all_at(&mut self.disk, block * self.block_size as u64, buf)
+    }
+
+    fn read_block_u32(&mut self, block: u64, idx: usize) -> VfsResult<u32> {
+        let mut buf = [0; 4];
+        read_exact_at(
+            &mut self.disk,
+            block * self.block_size as u64 + idx as u64 * 4,
+            &mut buf,
+        )?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn write_block_u32(&mut self, block: u64, idx: usize, val: u32) -> VfsResult {
+        write_all_at(
+            &mut self.disk,
+            block * self.block_size as u64 + idx as u64 * 4,
+            &val.to_le_bytes(),
+        )
+    }
+
+    // superblock and group descriptors:
+
+    fn write_group(&mut self, group: usize) -> VfsResult {
+        let desc_size = self.sb.desc_size();
+        let raw = &mut self.gdt[group * desc_size..(group + 1) * desc_size];
+        self.groups[group].write_counts(raw);
+        let pos = (self.sb.first_data_block() as u64 + 1) * self.block_size as u64
+            + (group * desc_size) as u64;
+        let raw = &self.gdt[group * desc_size..(group + 1) * desc_size];
+        write_all_at(&mut self.disk, pos, raw)?;
+        write_all_at(&mut self.disk, SUPERBLOCK_OFFSET, &self.sb.raw)
+    }
+
+    /// Finds and sets a zero bit in the bitmap block, within the first
+    /// `count` bits.
+    fn alloc_bit(&mut self, bitmap: u64, count: usize) -> VfsResult<Option<usize>> {
+        let mut buf = vec![0; self.block_size];
+        self.read_block(bitmap, &mut buf)?;
+        for (i, byte) in buf.iter_mut().enumerate().take(count.div_ceil(8)) {
+            if *byte == 0xff {
+                continue;
+            }
+            let bit = byte.trailing_ones() as usize;
+            if i * 8 + bit >= count {
+                break;
+            }
+            *byte |= 1 << bit;
+            self.write_block(bitmap, &buf)?;
+            return Ok(Some(i * 8 + bit));
+        }
+        Ok(None)
+    }
+
+    fn free_bit(&mut self, bitmap: u64, bit: usize) -> VfsResult {
+        let pos = bitmap * self.block_size as u64 + (bit / 8) as u64;
+        let mut byte = [0];
+        read_exact_at(&mut self.disk, pos, &mut byte)?;
+        if byte[0] & (1 << (bit % 8)) == 0 {
+            warn!("ext2: freeing a free bit {} in bitmap {}", bit, bitmap);
+            return Err(VfsError::InvalidData);
+        }
+        byte[0] &= !(1 << (bit % 8));
+        write_all_at(&mut self.disk, pos, &byte)
+    }
+
+    /// Allocates a zero-filled block, preferably in the group of `goal_ino`.
+    fn alloc_block(&mut self, goal_ino: u32) -> VfsResult<u64> {
+        let blocks_per_group = self.sb.blocks_per_group() as u64;
+        let first_data_block = self.sb.first_data_block() as u64;
+        let group_count = self.groups.len();
+        let goal = self.ino_group(goal_ino);
+        for group in (goal..group_count).chain(0..goal) {
+            if self.groups[group].free_blocks == 0 {
+                continue;
+            }
+            let group_start = first_data_block + group as u64 * blocks_per_group;
+            let count = blocks_per_group.min(self.sb.blocks_count() - group_start);
+            let bitmap = self.groups[group].block_bitmap;
+            if let Some(bit) = self.alloc_bit(bitmap, count as usize)? {
+                self.groups[group].free_blocks -= 1;
+                let free = self.sb.free_blocks_count();
+                self.sb.set_free_blocks_count(free - 1);
+                self.write_group(group)?;
+                let block = group_start + bit as u64;
+                self.write_block(block, &vec![0; self.block_size])?;
+                return Ok(block);
+            }
+        }
+        Err(VfsError::StorageFull)
+    }
+
+    fn free_block(&mut self, block: u64) -> VfsResult {
+        let blocks_per_group = self.sb.blocks_per_group() as u64;
+        let rel = block - self.sb.first_data_block() as u64;
+        let group = (rel / blocks_per_group) as usize;
+        let bitmap = self.groups[group].block_bitmap;
+        self.free_bit(bitmap, (rel % blocks_per_group) as usize)?;
+        self.groups[group].free_blocks += 1;
+        let free = self.sb.free_blocks_count();
+        self.sb.set_free_blocks_count(free + 1);
+        self.write_group(group)
+    }
+
+    fn ino_group(&self, ino: u32) -> usize {
+        ((ino - 1) / self.sb.inodes_per_group()) as usize
+    }
+
+    fn alloc_ino(&mut self, goal_ino: u32, is_dir: bool) -> VfsResult<u32> {
+        let inodes_per_group = self.sb.inodes_per_group();
+        let group_count = self.groups.len();
+        let goal = self.ino_group(goal_ino);
+        for group in (goal..group_count).chain(0..goal) {
+            if self.groups[group].free_inodes == 0 {
+                continue;
+            }
+            let bitmap = self.groups[group].inode_bitmap;
+            if let Some(bit) = self.alloc_bit(bitmap, inodes_per_group as usize)? {
+                let desc = &mut self.groups[group];
+                desc.free_inodes -= 1;
+                if is_dir {
+                    desc.used_dirs += 1;
+                }
+                let free = self.sb.free_inodes_count();
+                self.sb.set_free_inodes_count(free - 1);
+                self.write_group(group)?;
+                return Ok(group as u32 * inodes_per_group + bit as u32 + 1);
+            }
+        }
+        Err(VfsError::StorageFull)
+    }
+
+    fn free_ino(&mut self, ino: u32, is_dir: bool) -> VfsResult {
+        let group = self.ino_group(ino);
+        let bitmap = self.groups[group].inode_bitmap;
+        self.free_bit(bitmap, ((ino - 1) % self.sb.inodes_per_group()) as usize)?;
+        let desc = &mut self.groups[group];
+        desc.free_inodes += 1;
+        if is_dir {
+            desc.used_dirs -= 1;
+        }
+        let free = self.sb.free_inodes_count();
+        self.sb.set_free_inodes_count(free + 1);
+        self.write_group(group)
+    }
+
+    // inodes:
+
+    fn inode_pos(&self, ino: u32) -> VfsResult<u64> {
+        if ino == 0 || ino > self.sb.inodes_count() {
+            return Err(VfsError::InvalidData);
+        }
+        let group = self.ino_group(ino);
+        let index = ((ino - 1) % self.sb.inodes_per_group()) as u64;
+        Ok(self.groups[group].inode_table * self.block_size as u64
+            + index * self.sb.inode_size() as u64)
+    }
+
+    pub fn read_inode(&mut self, ino: u32) -> VfsResult<Inode> {
+        let pos = self.inode_pos(ino)?;
+        let mut raw = vec![0; self.sb.inode_size()];
+        read_exact_at(&mut self.disk, pos, &mut raw)?;
+        Ok(Inode { raw })
+    }
+
+    pub fn write_inode(&mut self, ino: u32, inode: &Inode) -> VfsResult {
+        let pos = self.inode_pos(ino)?;
+        write_all_at(&mut self.disk, pos, &inode.raw)
+    }
+
+    /// Allocates and initializes a new inode, and a block with `.` and `..`
+    /// for a directory in `parent`.
+    fn new_inode(&mut self, parent: u32, ty: VfsNodeType) -> VfsResult<(u32, Inode)> {
+        let is_dir = ty == VfsNodeType::Dir;
+        let ino = self.alloc_ino(parent, is_dir)?;
+        let perm = match ty {
+            VfsNodeType::Dir => VfsNodePerm::default_dir(),
+            VfsNodeType::SymLink => VfsNodePerm::from_bits_truncate(0o777),
+            _ => VfsNodePerm::default_file(),
+        };
+        let mut inode = Inode {
+            raw: vec![0; self.sb.inode_size()],
+        };
+        inode.set_mode(type_to_mode(ty) | perm.bits());
+        let now = now();
+        inode.set_atime(now);
+        inode.set_ctime(now);
+        inode.set_mtime(now);
+        inode.set_links_count(if is_dir { 2 } else { 1 });
+        inode.set_extra_isize(self.sb.want_extra_isize());
+        if is_dir {
+            let block = self.alloc_block(ino)?;
+            let mut buf = vec![0; self.block_size];
+            let has_filetype = self.sb.has_filetype();
+            let dot_len = dirent_len(1);
+            RawDirEntry::write(&mut buf, 0, ino, dot_len, b".", 2, has_filetype);
+            let rest = self.block_size - dot_len;
+            RawDirEntry::write(&mut buf, dot_len, parent, rest, b"..", 2, has_filetype);
+            self.write_block(block, &buf)?;
+            inode.set_block(0, block as u32);
+            inode.set_blocks(self.block_sectors());
+            inode.set_size(self.block_size as u64);
+        }
+        self.write_inode(ino, &inode)?;
+        Ok((ino, inode))
+    }
+
+    /// Frees the blocks and the inode, whose links count has dropped to zero.
+    fn delete_inode(&mut self, ino: u32, mut inode: Inode) -> VfsResult {
+        if !inode.is_fast_symlink(self.block_size) {
+            self.truncate_blocks(&mut inode, 0)?;
+        }
+        let acl = inode.file_acl() as u64;
+        if acl != 0 {
+            // The extended attribute block may be shared, drop the reference.
+            let refcount = self.read_block_u32(acl, 1)?;
+            if refcount > 1 {
+                self.write_block_u32(acl, 1, refcount - 1)?;
+            } else {
+                self.free_block(acl)?;
+            }
+            inode.set_file_acl(0);
+        }
+        inode.set_links_count(0);
+        // The clock starts from zero at boot. Use the last write time instead,
+        // since `e2fsck` treats a zero or small deletion time as a link in
+        // the orphan list.
+        let dtime = self.sb.write_time().max(self.sb.inodes_count());
+        inode.set_dtime(dtime);
+        inode.set_size(0);
+        inode.set_blocks(0);
+        self.write_inode(ino, &inode)?;
+        self.free_ino(ino, inode.node_type() == VfsNodeType::Dir)
+    }
+
+    fn block_sectors(&self) -> u64 {
+        self.block_size as u64 / 512
+    }
+
+    fn ptrs_per_block(&self) -> u64 {
+        self.block_size as u64 / 4
+    }
+
+    // block mapping:
+
+    /// Maps the logical block of the inode to the physical block, or returns
+    /// `None` for a hole.
+    fn bmap(&mut self, inode: &Inode, lblock: u64) -> VfsResult<Option<u64>> {
+        if inode.flags() & INLINE_DATA_FL != 0 {
+            return Err(VfsError::Unsupported);
+        }
+        if inode.flags() & EXTENTS_FL != 0 {
+            return self.extent_bmap(inode, lblock);
+        }
+        let (slot, path) = self.indirect_path(lblock)?;
+        let mut block = inode.block(slot) as u64;
+        for idx in path {
+            if block == 0 {
+                break;
+            }
+            block = self.read_block_u32(block, idx)? as u64;
+        }
+        Ok((block != 0).then_some(block))
+    }
+
+    /// Returns the slot in `i_block` and the indexes in the indirect blocks
+    /// of a logical block.
+    fn indirect_path(&self, lblock: u64) -> VfsResult<(usize, Vec<usize>)> {
+        let per = self.ptrs_per_block();
+        if lblock < NDIR_BLOCKS as u64 {
+            return Ok((lblock as usize, Vec::new()));
+        }
+        let mut rel = lblock - NDIR_BLOCKS as u64;
+        let mut span = per;
+        for level in 1..=3 {
+            if rel < span {
+                let mut path = Vec::with_capacity(level);
+                for _ in 0..level {
+                    span /= per;
+                    path.push((rel / span) as usize);
+                    rel %= span;
+                }
+                return Ok((NDIR_BLOCKS + level - 1, path));
+            }
+            rel -= span;
+            span *= per;
+        }
+        Err(VfsError::InvalidInput)
+    }
+
+    /// Maps the logical block of the inode to the physical block, allocating
+    /// it and the indirect blocks if necessary.
+    fn bmap_alloc(&mut self, ino: u32, inode: &mut Inode, lblock: u64) -> VfsResult<u64> {
+        let (slot, path) = self.indirect_path(lblock)?;
+        let mut block = inode.block(slot) as u64;
+        if block == 0 {
+            block = self.alloc_block(ino)?;
+            inode.set_block(slot, block as u32);
+            inode.set_blocks(inode.blocks() + self.block_sectors());
+        }
+        for idx in path {
+            let parent = block;
+            block = self.read_block_u32(parent, idx)? as u64;
+            if block == 0 {
+                block = self.alloc_block(ino)?;
+                self.write_block_u32(parent, idx, block as u32)?;
+                inode.set_blocks(inode.blocks() + self.block_sectors());
+            }
+        }
+        Ok(block)
+    }
+
+    fn extent_bmap(&mut self, inode: &Inode, lblock: u64) -> VfsResult<Option<u64>> {
+        let mut node = inode.iblock().to_vec();
+        loop {
+            if read_u16(&node, 0) != EXTENT_MAGIC {
+                return Err(VfsError::InvalidData);
+            }
+            let entries = read_u16(&node, 2) as usize;
+            let depth = read_u16(&node, 6);
+            let entry = |i: usize| &node[12 + i * 12..24 + i * 12];
+            if depth == 0 {
+                for i in 0..entries {
+                    let e = entry(i);
+                    let first = read_u32(e, 0) as u64;
+                    let mut len = read_u16(e, 4);
+                    let uninit = len > EXTENT_INIT_MAX_LEN;
+                    if uninit {
+                        len -= EXTENT_INIT_MAX_LEN;
+                    }
+                    if (first..first + len as u64).contains(&lblock) {
+                        if uninit {
+                            return Ok(None);
+                        }
+                        let start = (read_u16(e, 6) as u64) << 32 | read_u32(e, 8) as u64;
+                        return Ok(Some(start + lblock - first));
+                    }
+                }
+                return Ok(None);
+            }
+            // The last index whose first block is not after `lblock`.
+            let Some(i) = (0..entries)
+                .take_while(|&i| read_u32(entry(i), 0) as u64 <= lblock)
+                .last()
+            else {
+                return Ok(None);
+            };
+            let e = entry(i);
+            let leaf = (read_u16(e, 8) as u64) << 32 | read_u32(e, 4) as u64;
+            node = vec![0; self.block_size];
+            self.read_block(leaf, &mut node)?;
+        }
+    }
+
+    /// Frees the blocks after the first `keep` logical blocks.
+    fn truncate_blocks(&mut self, inode: &mut Inode, keep: u64) -> VfsResult {
+        if inode.flags() & (EXTENTS_FL | INLINE_DATA_FL) != 0 {
+            return Err(VfsError::Unsupported);
+        }
+        for slot in (keep.min(NDIR_BLOCKS as u64) as usize)..NDIR_BLOCKS {
+            let block = inode.block(slot) as u64;
+            if block != 0 {
+                self.free_block(block)?;
+                inode.set_block(slot, 0);
+                inode.set_blocks(inode.blocks() - self.block_sectors());
+            }
+        }
+        let per = self.ptrs_per_block();
+        let mut base = NDIR_BLOCKS as u64;
+        let mut span = per;
+        for level in 1..=3 {
+            let slot = NDIR_BLOCKS + level - 1;
+            let block = inode.block(slot) as u64;
+            if block != 0 && base + span > keep {
+                let freed = self.truncate_indirect(block, level, base, keep)?;
+                inode.set_blocks(inode.blocks() - freed * self.block_sectors());
+                if base >= keep {
+                    self.free_block(block)?;
+                    inode.set_block(slot, 0);
+                    inode.set_blocks(inode.blocks() - self.block_sectors());
+                }
+            }
+            base += span;
+            span *= per;
+        }
+        Ok(())
+    }
+
+    /// Frees the blocks in the subtree of the indirect `block` at `level`,
+    /// whose first logical block is `base`, after the first `keep` logical
+    /// blocks of the file. Returns the number of blocks freed, excluding
+    /// `block` itself.
+    fn truncate_indirect(
+        &mut self,
+        block: u64,
+        level: usize,
+        base: u64,
+        keep: u64,
+    ) -> VfsResult<u64> {
+        let per = self.ptrs_per_block();
+        let span = per.pow(level as u32 - 1);
+        let mut buf = vec![0; self.block_size];
+        self.read_block(block, &mut buf)?;
+        let mut freed = 0;
+        for i in 0..per as usize {
+            let child = read_u32(&buf, i * 4) as u64;
+            let child_base = base + i as u64 * span;
+            if child == 0 || child_base + span <= keep {
+                continue;
+            }
+            if level > 1 {
+                freed += self.truncate_indirect(child, level - 1, child_base, keep)?;
+            }
+            if child_base >= keep {
+                self.free_block(child)?;
+                write_u32(&mut buf, i * 4, 0);
+                freed += 1;
+            }
+        }
+        self.write_block(block, &buf)?;
+        Ok(freed)
+    }
+
+    // file data:
+
+    pub fn read_at(&mut self, inode: &Inode, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let size = inode.size();
+        if offset >= size {
+            return Ok(0);
+        }
+        let len = buf.len().min((size - offset) as usize);
+        let bs = self.block_size as u64;
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done as u64;
+            let in_block = (pos % bs) as usize;
+            let n = (len - done).min(self.block_size - in_block);
+            let out = &mut buf[done..done + n];
+            match self.bmap(inode, pos / bs)? {
+                Some(block) => read_exact_at(&mut self.disk, block * bs + in_block as u64, out)?,
+                None => out.fill(0),
+            }
+            done += n;
+        }
+        Ok(len)
+    }
+
+    /// Reads the target of the symbolic link.
+    pub fn read_link(&mut self, inode: &Inode, buf: &mut [u8]) -> VfsResult<usize> {
+        if inode.is_fast_symlink(self.block_size) {
+            // The target is stored in `i_block`.
+            let len = (inode.size() as usize).min(IBLOCK_SIZE).min(buf.len());
+            buf[..len].copy_from_slice(&inode.iblock()[..len]);
+            Ok(len)
+        } else {
+            self.read_at(inode, 0, buf)
+        }
+    }
+
+    pub fn write_at(&mut self, ino: u32, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.check_writable()?;
+        let mut inode = self.read_inode(ino)?;
+        let bs = self.block_size as u64;
+        let mut done = 0;
+        let result = loop {
+            if done == buf.len() {
+                break Ok(());
+            }
+            let pos = offset + done as u64;
+            let in_block = (pos % bs) as usize;
+            let n = (buf.len() - done).min(self.block_size - in_block);
+            let block = match self.bmap_alloc(ino, &mut inode, pos / bs) {
+                Ok(block) => block,
+                Err(e) => break Err(e),
+            };
+            let data = &buf[done..done + n];
+            if let Err(e) = write_all_at(&mut self.disk, block * bs + in_block as u64, data) {
+                break Err(e);
+            }
+            done += n;
+        };
+        let end = offset + done as u64;
+        if end > inode.size() {
+            inode.set_size(end);
+        }
+        if done > 0 {
+            touch(&mut inode);
+        }
+        self.write_inode(ino, &inode)?;
+        match result {
+            Err(e) if done == 0 => Err(e),
+            _ => Ok(done),
+        }
+    }
+
+    pub fn truncate(&mut self, ino: u32, size: u64) -> VfsResult {
+        self.check_writable()?;
+        let mut inode = self.read_inode(ino)?;
+        let bs = self.block_size as u64;
+        let old_size = inode.size();
+        if size < old_size {
+            self.truncate_blocks(&mut inode, size.div_ceil(bs))?;
+            // Zero the tail of the last block, which may be exposed by
+            // extending the file later.
+            if !size.is_multiple_of(bs) {
+                if let Some(block) = self.bmap(&inode, size / bs)? {
+                    let tail = vec![0; (bs - size % bs) as usize];
+                    write_all_at(&mut self.disk, block * bs + size % bs, &tail)?;
+                }
+            }
+        }
+        inode.set_size(size);
+        touch(&mut inode);
+        self.write_inode(ino, &inode)
+    }
+
+    /// Changes the permission, owner or timestamps of the node `ino`.
+    pub fn set_attr(&mut self, ino: u32, attr: &VfsSetAttr) -> VfsResult {
+        self.check_writable()?;
+        let mut inode = self.read_inode(ino)?;
+        if let Some(perm) = attr.mode {
+            inode.set_mode(inode.mode() & S_IFMT | perm.bits());
+        }
+        if let Some(uid) = attr.uid {
+            inode.set_uid(uid);
+        }
+        if let Some(gid) = attr.gid {
+            inode.set_gid(gid);
+        }
+        if let Some(atime) = attr.atime {
+            inode.set_atime(atime.as_secs() as u32);
+        }
+        if let Some(mtime) = attr.mtime {
+            inode.set_mtime(mtime.as_secs() as u32);
+        }
+        inode.set_ctime(now());
+        self.write_inode(ino, &inode)
+    }
+
+    // directories:
+
+    fn dirent_type(&mut self, ino: u32, file_type: u8) -> VfsResult<VfsNodeType> {
+        match dirent_type(file_type) {
+            Some(ty) if self.sb.has_filetype() => Ok(ty),
+            _ => Ok(self.read_inode(ino)?.node_type()),
+        }
+    }
+
+    /// Calls `f` on every entry in the directory with the block buffer and
+    /// the logical block index, until it returns `Some`.
+    fn find_dirent<T, F>(&mut self, dir: &Inode, mut f: F) -> VfsResult<Option<T>>
+    where
+        F: FnMut(&mut Self, &[u8], u64, &RawDirEntry) -> VfsResult<Option<T>>,
+    {
+        let bs = self.block_size as u64;
+        let has_filetype = self.sb.has_filetype();
+        let mut buf = vec![0; self.block_size];
+        for lblock in 0..dir.size().div_ceil(bs) {
+            let Some(block) = self.bmap(dir, lblock)? else {
+                continue;
+            };
+            self.read_block(block, &mut buf)?;
+            let mut offset = 0;
+            while let Some(entry) = RawDirEntry::parse(&buf, offset, has_filetype) {
+                if let Some(res) = f(self, &buf, lblock, &entry)? {
+                    return Ok(Some(res));
+                }
+                offset += entry.rec_len;
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn dir_entries(&mut self, dir: &Inode) -> VfsResult<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        self.find_dirent(dir, |vol, buf, _, e| {
+            if e.ino != 0 {
+                entries.push(DirEntry {
+                    name: String::from_utf8_lossy(e.name(buf)).into_owned(),
+                    ty: vol.dirent_type(e.ino, e.file_type)?,
+                });
+            }
+            Ok(None::<()>)
+        })?;
+        Ok(entries)
+    }
+
+    /// Finds the entry with `name` in the directory, returns its inode number
+    /// and type.
+    pub fn lookup(&mut self, dir: &Inode, name: &str) -> VfsResult<Option<(u32, VfsNodeType)>> {
+        self.find_dirent(dir, |vol, buf, _, e| {
+            if e.ino != 0 && e.name(buf) == name.as_bytes() {
+                Ok(Some((e.ino, vol.dirent_type(e.ino, e.file_type)?)))
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Adds an entry to the directory, reusing free space in the existing
+    /// blocks or appending a new block.
+    fn add_dirent(&mut self, dir_ino: u32, name: &str, ino: u32, ty: VfsNodeType) -> VfsResult {
+        if name.len() > 255 {
+            return Err(VfsError::InvalidInput);
+        }
+        let mut dir = self.read_inode(dir_ino)?;
+        let has_filetype = self.sb.has_filetype();
+        let file_type = dirent_file_type(ty);
+        let needed = dirent_len(name.len());
+        let bs = self.block_size as u64;
+
+        let slot = self.find_dirent(&dir, |_, _, lblock, e| {
+            let used = if e.ino == 0 {
+                0
+            } else {
+                dirent_len(e.name_len)
+            };
+            Ok((e.rec_len.saturating_sub(used) >= needed)
+                .then_some((lblock, e.offset, used, e.rec_len)))
+        })?;
+        let (block, buf) = match slot {
+            Some((lblock, offset, used, rec_len)) => {
+                let block = self.bmap(&dir, lblock)?.unwrap();
+                let mut buf = vec![0; self.block_size];
+                self.read_block(block, &mut buf)?;
+                if used > 0 {
+                    // Split the entry.
+                    write_u16(&mut buf, offset + 4, used as u16);
+                }
+                let name = name.as_bytes();
+                let new_offset = offset + used;
+                let new_len = rec_len - used;
+                RawDirEntry::write(
+                    &mut buf,
+                    new_offset,
+                    ino,
+                    new_len,
+                    name,
+                    file_type,
+                    has_filetype,
+                );
+                (block, buf)
+            }
+            None => {
+                let lblock = dir.size().div_ceil(bs);
+                let block = self.bmap_alloc(dir_ino, &mut dir, lblock)?;
+                dir.set_size((lblock + 1) * bs);
+                let mut buf = vec![0; self.block_size];
+                let name = name.as_bytes();
+                RawDirEntry::write(
+                    &mut buf,
+                    0,
+                    ino,
+                    self.block_size,
+                    name,
+                    file_type,
+                    has_filetype,
+                );
+                (block, buf)
+            }
+        };
+        self.write_block(block, &buf)?;
+        // The hashed index is not updated, drop it so that it is rebuilt by
+        // `e2fsck` (the blocks are still valid as linear directory blocks).
+        dir.set_flags(dir.flags() & !INDEX_FL);
+        touch(&mut dir);
+        self.write_inode(dir_ino, &dir)
+    }
+
+    /// Removes the entry with `name` from the directory, returns its inode
+    /// number.
+    fn remove_dirent(&mut self, dir_ino: u32, name: &str) -> VfsResult<u32> {
+        let mut dir = self.read_inode(dir_ino)?;
+        let mut prev = None;
+        let found = self.find_dirent(&dir, |_, buf, lblock, e| {
+            if e.offset == 0 {
+                prev = None;
+            }
+            if e.ino != 0 && e.name(buf) == name.as_bytes() {
+                return Ok(Some((lblock, e.offset, e.ino, prev)));
+            }
+            prev = Some(e.offset);
+            Ok(None)
+        })?;
+        let (lblock, offset, ino, prev) = found.ok_or(VfsError::NotFound)?;
+        let block = self.bmap(&dir, lblock)?.unwrap();
+        let mut buf = vec![0; self.block_size];
+        self.read_block(block, &mut buf)?;
+        match prev {
+            Some(prev) => {
+                // Merge into the previous entry.
+                let len = read_u16(&buf, prev + 4) + read_u16(&buf, offset + 4);
+                write_u16(&mut buf, prev + 4, len);
+            }
+            None => write_u32(&mut buf, offset, 0),
+        }
+        self.write_block(block, &buf)?;
+        dir.set_flags(dir.flags() & !INDEX_FL);
+        touch(&mut dir);
+        self.write_inode(dir_ino, &dir)?;
+        Ok(ino)
+    }
+
+    fn set_dotdot(&mut self, dir_ino: u32, parent: u32) -> VfsResult {
+        let dir = self.read_inode(dir_ino)?;
+        let found = self.find_dirent(&dir, |_, buf, lblock, e| {
+            Ok((e.name(buf) == b"..").then_some((lblock, e.offset)))
+        })?;
+        let (lblock, offset) = found.ok_or(VfsError::InvalidData)?;
+        let block = self.bmap(&dir, lblock)?.unwrap();
+        write_all_at(
+            &mut self.disk,
+            block * self.block_size as u64 + offset as u64,
+            &parent.to_le_bytes(),
+        )
+    }
+
+    fn add_links(&mut self, ino: u32, delta: i16) -> VfsResult<Inode> {
+        let mut inode = self.read_inode(ino)?;
+        inode.set_links_count(inode.links_count().wrapping_add_signed(delta));
+        inode.set_ctime(now());
+        self.write_inode(ino, &inode)?;
+        Ok(inode)
+    }
+
+    fn is_empty_dir(&mut self, dir: &Inode) -> VfsResult<bool> {
+        let other = self.find_dirent(dir, |_, buf, _, e| {
+            let name = e.name(buf);
+            Ok((e.ino != 0 && name != b"." && name != b"..").then_some(()))
+        })?;
+        Ok(other.is_none())
+    }
+
+    /// Creates a node with `name` and type `ty` in the directory.
+    pub fn create(&mut self, dir_ino: u32, name: &str, ty: VfsNodeType) -> VfsResult<u32> {
+        self.check_writable()?;
+        if ty == VfsNodeType::SymLink {
+            return Err(VfsError::Unsupported); // use `symlink` instead
+        }
+        let (ino, _) = self.new_inode(dir_ino, ty)?;
+        self.add_new_dirent(dir_ino, name, ino, ty)?;
+        if ty == VfsNodeType::Dir {
+            self.add_links(dir_ino, 1)?;
+        }
+        Ok(ino)
+    }
+
+    /// Creates a symbolic link with `name` in the directory, which points to
+    /// `target`.
+    pub fn symlink(&mut self, dir_ino: u32, name: &str, target: &str) -> VfsResult<u32> {
+        self.check_writable()?;
+        let (ino, mut inode) = self.new_inode(dir_ino, VfsNodeType::SymLink)?;
+        let target = target.as_bytes();
+        let res = if target.len() < IBLOCK_SIZE {
+            // A fast symlink, whose target is stored in `i_block`.
+            inode.iblock_mut()[..target.len()].copy_from_slice(target);
+            inode.set_size(target.len() as u64);
+            self.write_inode(ino, &inode)
+        } else {
+            self.write_at(ino, 0, target).map(|_| ())
+        };
+        match res {
+            Ok(()) => self.add_new_dirent(dir_ino, name, ino, VfsNodeType::SymLink)?,
+            Err(e) => {
+                let inode = self.read_inode(ino)?;
+                self.delete_inode(ino, inode)?;
+                return Err(e);
+            }
+        }
+        Ok(ino)
+    }
+
+    /// Adds an entry with `name` in the directory for the existing
+    /// non-directory node `ino`.
+    pub fn link(&mut self, dir_ino: u32, name: &str, ino: u32) -> VfsResult {
+        self.check_writable()?;
+        let inode = self.read_inode(ino)?;
+        let ty = inode.node_type();
+        if ty == VfsNodeType::Dir {
+            return Err(VfsError::PermissionDenied);
+        }
+        self.add_dirent(dir_ino, name, ino, ty)?;
+        self.add_links(ino, 1)?;
+        Ok(())
+    }
+
+    /// Adds the entry of the newly created node `ino`, or deletes the node if
+    /// it fails.
+    fn add_new_dirent(&mut self, dir_ino: u32, name: &str, ino: u32, ty: VfsNodeType) -> VfsResult {
+        if let Err(e) = self.add_dirent(dir_ino, name, ino, ty) {
+            let inode = self.read_inode(ino)?;
+            self.delete_inode(ino, inode)?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Removes the entry with `name` from the directory, and deletes the node
+    /// if it has no other links. A directory must be empty.
+    pub fn unlink(&mut self, dir_ino: u32, name: &str) -> VfsResult {
+        self.check_writable()?;
+        if name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        let dir = self.read_inode(dir_ino)?;
+        let (ino, ty) = self.lookup(&dir, name)?.ok_or(VfsError::NotFound)?;
+        let mut inode = self.read_inode(ino)?;
+        if ty == VfsNodeType::Dir && !self.is_empty_dir(&inode)? {
+            return Err(VfsError::DirectoryNotEmpty);
+        }
+        self.remove_dirent(dir_ino, name)?;
+        if ty == VfsNodeType::Dir {
+            self.add_links(dir_ino, -1)?;
+            inode.set_links_count(0); // the entry and `.`
+        } else {
+            inode.set_links_count(inode.links_count().saturating_sub(1));
+        }
+        if inode.links_count() == 0 {
+            self.delete_inode(ino, inode)
+        } else {
+            self.write_inode(ino, &inode)
+        }
+    }
+
+    /// Whether `ino` is `dir_ino` or one of its ancestors.
+    fn is_ancestor(&mut self, ino: u32, mut dir_ino: u32) -> VfsResult<bool> {
+        loop {
+            if dir_ino == ino {
+                return Ok(true);
+            }
+            if dir_ino == ROOT_INO {
+                return Ok(false);
+            }
+            let dir = self.read_inode(dir_ino)?;
+            dir_ino = self.lookup(&dir, "..")?.ok_or(VfsError::InvalidData)?.0;
+        }
+    }
+
+    /// Moves the entry `src_name` in `src_dir` to `dst_name` in `dst_dir`,
+    /// replacing the existing one.
+    pub fn rename(
+        &mut self,
+        src_dir: u32,
+        src_name: &str,
+        dst_dir: u32,
+        dst_name: &str,
+    ) -> VfsResult {
+        self.check_writable()?;
+        let dir = self.read_inode(src_dir)?;
+        let (ino, ty) = self.lookup(&dir, src_name)?.ok_or(VfsError::NotFound)?;
+        if ty == VfsNodeType::Dir && self.is_ancestor(ino, dst_dir)? {
+            return Err(VfsError::InvalidInput);
+        }
+        let dir = self.read_inode(dst_dir)?;
+        if let Some((dst_ino, dst_ty)) = self.lookup(&dir, dst_name)? {
+            if dst_ino == ino {
+                return Ok(());
+            }
+            match (ty == VfsNodeType::Dir, dst_ty == VfsNodeType::Dir) {
+                (true, false) => return Err(VfsError::NotADirectory),
+                (false, true) => return Err(VfsError::IsADirectory),
+                _ => {}
+            }
+            self.unlink(dst_dir, dst_name)?;
+        }
+        self.add_dirent(dst_dir, dst_name, ino, ty)?;
+        self.remove_dirent(src_dir, src_name)?;
+        if ty == VfsNodeType::Dir && src_dir != dst_dir {
+            self.set_dotdot(ino, dst_dir)?;
+            self.add_links(src_dir, -1)?;
+            self.add_links(dst_dir, 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The current time in seconds, for the timestamps of inodes.
+fn now() -> u32 {
+    axhal::time::current_time().as_secs() as u32
+}
+
+/// Updates the modification and status change time of the inode.
+fn touch(inode: &mut Inode) {
+    let now = now();
+    inode.set_mtime(now);
+    inode.set_ctime(now);
+}
+
+fn read_exact_at(disk: &mut Disk, pos: u64, mut buf: &mut [u8]) -> VfsResult {
+    disk.set_position(pos);
+    while !buf.is_empty() {
+        let n = disk.read_one(buf).map_err(|_| VfsError::Io)?;
+        buf = &mut buf[n..];
+    }
+    Ok(())
+}
+
+fn write_all_at(disk: &mut Disk, pos: u64, mut buf: &[u8]) -> VfsResult {
+    disk.set_position(pos);
+    while !buf.is_empty() {
+        let n = disk.write_one(buf).map_err(|_| VfsError::Io)?;
+        buf = &buf[n..];
+    }
+    Ok(())
+}
@@ -1,22 +1,44 @@
 use alloc::sync::Arc;
-use core::cell::UnsafeCell;
+use core::{cell::UnsafeCell, time::Duration};
 
-use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsDirEntry, VfsError, VfsNodePerm, VfsResult, VfsSetAttr};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
 use axsync::Mutex;
-use fatfs::{Dir, File, LossyOemCpConverter, NullTimeProvider, Read, Seek, SeekFrom, Write};
+use fatfs::{Date, DateTime, Dir, File, LossyOemCpConverter, Time, TimeProvider};
+use fatfs::{Read, Seek, SeekFrom, Write};
 
 use crate::dev::Disk;
 
 const BLOCK_SIZE: usize = 512;
 
+/// Magic number of FAT, the same as `MSDOS_SUPER_MAGIC` in Linux.
+const MSDOS_MAGIC: u64 = 0x4d44;
+
+/// 1980-01-01, the earliest date of FAT, in days since the Unix epoch.
+const FAT_EPOCH_DAYS: i64 = 3652;
+
+type FatFile<'a> = File<'a, Disk, AxTimeProvider, LossyOemCpConverter>;
+type FatDir<'a> = Dir<'a, Disk, AxTimeProvider, LossyOemCpConverter>;
+
 pub struct FatFileSystem {
-    inner: fatfs::FileSystem<Disk, NullTimeProvider, LossyOemCpConverter>,
+    inner: fatfs::FileSystem<Disk, AxTimeProvider, LossyOemCpConverter>,
     root_dir: UnsafeCell<Option<VfsNodeRef>>,
 }
 
-pub struct FileWrapper<'a>(Mutex<File<'a, Disk, NullTimeProvider, LossyOemCpConverter>>);
-pub struct DirWrapper<'a>(Dir<'a, Disk, NullTimeProvider, LossyOemCpConverter>);
+pub struct FileWrapper<'a> {
+    file: Mutex<FatFile<'a>>,
+    /// The last access and modification time, read from the directory entry
+    /// and updated on writes.
+    times: Mutex<(Duration, Duration)>,
+}
+pub struct DirWrapper<'a>(FatDir<'a>);
+
+/// Provides the current time for the timestamps of FAT.
+///
+/// The clock of ArceOS starts from zero at boot, which is mapped to the FAT
+/// epoch (1980-01-01 00:00:00), as FAT cannot store earlier dates.
+#[derive(Debug, Clone, Copy)]
+pub struct AxTimeProvider;
 
 unsafe impl Sync for FatFileSystem {}
 unsafe impl Send for FatFileSystem {}
@@ -30,7 +52,7 @@ impl FatFileSystem {
     pub fn new(mut disk: Disk) -> Self {
         let opts = fatfs::FormatVolumeOptions::new();
         fatfs::format_volume(&mut disk, opts).expect("failed to format volume");
-        let inner = fatfs::FileSystem::new(disk, fatfs::FsOptions::new())
+        let inner = fatfs::FileSystem::new(disk, fs_options())
             .expect("failed to initialize FAT filesystem");
         Self {
             inner,
@@ -40,7 +62,7 @@ impl FatFileSystem {
 
     #[cfg(not(feature = "use-ramdisk"))]
     pub fn new(disk: Disk) -> Self {
-        let inner = fatfs::FileSystem::new(disk, fatfs::FsOptions::new())
+        let inner = fatfs::FileSystem::new(disk, fs_options())
             .expect("failed to initialize FAT filesystem");
         Self {
             inner,
@@ -48,47 +70,102 @@ impl FatFileSystem {
         }
     }
 
+    /// Opens an existing FAT filesystem on `disk`, without formatting it.
+    pub fn open(disk: Disk) -> VfsResult<Self> {
+        let inner = fatfs::FileSystem::new(disk, fs_options()).map_err(as_vfs_err)?;
+        Ok(Self {
+            inner,
+            root_dir: UnsafeCell::new(None),
+        })
+    }
+
     pub fn init(&'static self) {
         // must be called before later operations
         unsafe { *self.root_dir.get() = Some(Self::new_dir(self.inner.root_dir())) }
     }
 
-    fn new_file(file: File<'_, Disk, NullTimeProvider, LossyOemCpConverter>) -> Arc<FileWrapper> {
-        Arc::new(FileWrapper(Mutex::new(file)))
+    fn new_file(file: FatFile<'_>, atime: Duration, mtime: Duration) -> Arc<FileWrapper> {
+        Arc::new(FileWrapper {
+            file: Mutex::new(file),
+            times: Mutex::new((atime, mtime)),
+        })
     }
 
-    fn new_dir(dir: Dir<'_, Disk, NullTimeProvider, LossyOemCpConverter>) -> Arc<DirWrapper> {
+    fn new_dir(dir: FatDir<'_>) -> Arc<DirWrapper> {
         Arc::new(DirWrapper(dir))
     }
 }
 
+impl FileWrapper<'_> {
+    /// Updates the modification time after the content is modified. The
+    /// directory entry is updated by `fatfs`.
+    fn touch(&self) {
+        self.times.lock().1 = axhal::time::current_time();
+    }
+}
+
 impl VfsNodeOps for FileWrapper<'static> {
     axfs_vfs::impl_vfs_non_dir_default! {}
 
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        let size = self.0.lock().seek(SeekFrom::End(0)).map_err(as_vfs_err)?;
+        let size = self
+            .file
+            .lock()
+            .seek(SeekFrom::End(0))
+            .map_err(as_vfs_err)?;
         let blocks = (size + BLOCK_SIZE as u64 - 1) / BLOCK_SIZE as u64;
         // FAT fs doesn't support permissions, we just set everything to 755
         let perm = VfsNodePerm::from_bits_truncate(0o755);
-        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, size, blocks))
+        let mut attr = VfsNodeAttr::new(perm, VfsNodeType::File, size, blocks);
+        let (atime, mtime) = *self.times.lock();
+        // FAT has no status change time.
+        attr.set_times(atime, mtime, mtime);
+        Ok(attr)
+    }
+
+    fn set_attr(&self, attr: &VfsSetAttr) -> VfsResult {
+        if attr.mode.is_some() || attr.uid.is_some() || attr.gid.is_some() {
+            return Err(VfsError::Unsupported);
+        }
+        let mut file = self.file.lock();
+        let mut times = self.times.lock();
+        if let Some(atime) = attr.atime {
+            // Only the date of the last access is stored.
+            let date = to_fat_time(atime).date;
+            file.set_accessed(date);
+            times.0 = from_fat_time(date, Time::new(0, 0, 0, 0));
+        }
+        if let Some(mtime) = attr.mtime {
+            file.set_modified(to_fat_time(mtime));
+            times.1 = mtime;
+        }
+        Ok(())
     }
 
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
         file.read(buf).map_err(as_vfs_err)
     }
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
-        file.write(buf).map_err(as_vfs_err)
+        let len = file.write(buf).map_err(as_vfs_err)?;
+        self.touch();
+        Ok(len)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.file.lock().flush().map_err(as_vfs_err)
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(size)).map_err(as_vfs_err)?; // TODO: more efficient
-        file.truncate().map_err(as_vfs_err)
+        file.truncate().map_err(as_vfs_err)?;
+        self.touch();
+        Ok(())
     }
 }
 
@@ -122,8 +199,26 @@ impl VfsNodeOps for DirWrapper<'static> {
         }
 
         // TODO: use `fatfs::Dir::find_entry`, but it's not public.
-        if let Ok(file) = self.0.open_file(path) {
-            Ok(FatFileSystem::new_file(file))
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let parent = match parent {
+            "" => None,
+            parent => self.0.open_dir(parent).ok(),
+        };
+        let entry = parent
+            .as_ref()
+            .unwrap_or(&self.0)
+            .iter()
+            .filter_map(Result::ok)
+            .find(|e| {
+                e.is_file()
+                    && (e.file_name().eq_ignore_ascii_case(name)
+                        || e.short_file_name().eq_ignore_ascii_case(name))
+            });
+        if let Some(entry) = entry {
+            let modified = entry.modified();
+            let mtime = from_fat_time(modified.date, modified.time);
+            let atime = from_fat_time(entry.accessed(), Time::new(0, 0, 0, 0));
+            Ok(FatFileSystem::new_file(entry.to_file(), atime, mtime))
         } else if let Ok(dir) = self.0.open_dir(path) {
             Ok(FatFileSystem::new_dir(dir))
         } else {
@@ -199,12 +294,90 @@ impl VfsNodeOps for DirWrapper<'static> {
 }
 
 impl VfsOps for FatFileSystem {
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        let stats = self.inner.stats().map_err(as_vfs_err)?;
+        let free = stats.free_clusters() as u64;
+        Ok(FileSystemInfo {
+            fs_type: MSDOS_MAGIC,
+            block_size: stats.cluster_size() as u64,
+            blocks: stats.total_clusters() as u64,
+            blocks_free: free,
+            blocks_available: free,
+            name_max: 255,
+            ..Default::default()
+        })
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         let root_dir = unsafe { (*self.root_dir.get()).as_ref().unwrap() };
         root_dir.clone()
     }
 }
 
+impl TimeProvider for AxTimeProvider {
+    fn get_current_date(&self) -> Date {
+        self.get_current_date_time().date
+    }
+
+    fn get_current_date_time(&self) -> DateTime {
+        to_fat_time(axhal::time::current_time())
+    }
+}
+
+fn fs_options() -> fatfs::FsOptions<AxTimeProvider, LossyOemCpConverter> {
+    fatfs::FsOptions::new().time_provider(AxTimeProvider)
+}
+
+/// Converts the clock time to the date and time of FAT.
+fn to_fat_time(time: Duration) -> DateTime {
+    let secs = time.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64 + FAT_EPOCH_DAYS);
+    let secs = secs % 86400;
+    DateTime::new(
+        Date::new(year.min(2107) as u16, month as u16, day as u16),
+        Time::new(
+            (secs / 3600) as u16,
+            (secs / 60 % 60) as u16,
+            (secs % 60) as u16,
+            time.subsec_millis() as u16,
+        ),
+    )
+}
+
+/// Converts the date and time of FAT to the clock time.
+fn from_fat_time(date: Date, time: Time) -> Duration {
+    let days = days_from_civil(date.year as i64, date.month as i64, date.day as i64);
+    let secs = (days - FAT_EPOCH_DAYS).max(0) as u64 * 86400
+        + time.hour as u64 * 3600
+        + time.min as u64 * 60
+        + time.sec as u64;
+    Duration::from_secs(secs) + Duration::from_millis(time.millis as u64)
+}
+
+/// Converts days since the Unix epoch to the year, month and day.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Converts the year, month and day to days since the Unix epoch.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
 impl fatfs::IoBase for Disk {
     type Error = ();
 }
@@ -243,7 +416,7 @@ impl Write for Disk {
         Ok(write_len)
     }
     fn flush(&mut self) -> Result<(), Self::Error> {
-        Ok(())
+        self.sync().map_err(|_| ())
     }
 }
 
@@ -1,6 +1,8 @@
 cfg_if::cfg_if! {
     if #[cfg(feature = "myfs")] {
         pub mod myfs;
+    } else if #[cfg(feature = "ext2")] {
+        pub mod ext2;
     } else if #[cfg(feature = "fatfs")] {
         pub mod fatfs;
     }
@@ -11,3 +13,6 @@ pub use axfs_devfs as devfs;
 
 #[cfg(feature = "ramfs")]
 pub use axfs_ramfs as ramfs;
+
+#[cfg(any(feature = "procfs", feature = "sysfs"))]
+pub use axfs_pseudofs as pseudofs;
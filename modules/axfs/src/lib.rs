@@ -6,17 +6,44 @@
 //!
 //! - `fatfs`: Use [FAT] as the main filesystem and mount it on `/`. This feature
 //!    is **enabled** by default.
-//! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`. This feature is
-//!    **enabled** by default.
+//! - `ext2`: Use [ext2] as the main filesystem and mount it on `/`. It
+//!    overrides `fatfs` if both are enabled. Filesystems with [ext4] features
+//!    (e.g., extents) are mounted read-only.
+//! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`, which contains
+//!    `null`, `zero`, `full`, `random`, `urandom`, `console`, `ttyS0` and a
+//!    node for each block device. This feature is **enabled** by default.
 //! - `ramfs`: Mount [`axfs_ramfs::RamFileSystem`] on `/tmp`. This feature is
 //!    **enabled** by default.
+//! - `procfs`: Mount [`axfs_pseudofs::PseudoFileSystem`] on `/proc`, whose
+//!    files (e.g., `/proc/meminfo`) are generated from the kernel state on
+//!    each read. This feature is **enabled** by default.
+//! - `sysfs`: Mount [`axfs_pseudofs::PseudoFileSystem`] on `/sys`, which
+//!    exports the devices and their attributes registered in
+//!    [`axdriver::model`]. This feature is **enabled** by default.
+//! - `irq`: Provide `/proc/interrupts` with the interrupt counters.
+//! - `multitask`: Provide a directory in `/proc` for each task.
+//! - `display`: Provide `/dev/fb0` for the framebuffer of [`axdisplay`].
 //! - `myfs`: Allow users to define their custom filesystems to override the
 //!    default. In this case, [`MyFileSystemIf`] is required to be implemented
 //!    to create and initialize other filesystems. This feature is **disabled** by
 //!    by default, but it will override other filesystem selection features if
 //!    both are enabled.
 //!
+//! Other filesystems can be mounted at runtime with [`api::mount`]. Block
+//! devices are named `blk0`, `blk1`, etc. If a device has an MBR or GPT
+//! partition table, its partitions are named `blk0p1`, `blk0p2`, etc., and
+//! GPT partitions can also be referred to by `PARTUUID=<GUID>` or
+//! `PARTLABEL=<name>` (see [`fops::Disk::open`]).
+//!
+//! The root filesystem is on the block device given by the config key
+//! `root-partition`. If it is empty, a partition of `blk0` is selected by the
+//! GPT partition type for the root partition of the architecture, or the
+//! first partition of `blk0` is used, or `blk0` itself if it has no partition
+//! table.
+//!
 //! [FAT]: https://en.wikipedia.org/wiki/File_Allocation_Table
+//! [ext2]: https://en.wikipedia.org/wiki/Ext2
+//! [ext4]: https://en.wikipedia.org/wiki/Ext4
 //! [`MyFileSystemIf`]: fops::MyFileSystemIf
 
 #![cfg_attr(all(not(test), not(doc)), no_std)]
@@ -26,7 +53,10 @@
 extern crate log;
 extern crate alloc;
 
+mod cache;
 mod dev;
+#[cfg(feature = "devfs")]
+mod devices;
 mod fs;
 mod mounts;
 mod root;
@@ -40,7 +70,14 @@ use axdriver::{prelude::*, AxDeviceContainer};
 pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     info!("Initialize filesystems...");
 
-    let dev = blk_devs.take_one().expect("No block device found!");
-    info!("  use block device 0: {:?}", dev.device_name());
-    self::root::init_rootfs(self::dev::Disk::new(dev));
+    let mut count = 0;
+    while let Some(dev) = blk_devs.take_one() {
+        info!("  block device {}: {:?}", count, dev.device_name());
+        self::dev::register_block_device(alloc::format!("blk{}", count), dev);
+        count += 1;
+    }
+    let root = self::dev::root_device().expect("No block device found for the root filesystem!");
+    let disk = self::dev::Disk::open(&root).expect("failed to open the root block device");
+    info!("  use block device {} for the root filesystem", root);
+    self::root::init_rootfs(disk);
 }
@@ -1,8 +1,104 @@
-use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeType, VfsOps, VfsResult};
+use alloc::{collections::BTreeMap, sync::Arc};
+#[cfg(any(feature = "procfs", feature = "sysfs"))]
+use alloc::{format, string::String};
+use axerrno::{ax_err, AxResult};
+use axfs_vfs::VfsOps;
+use axsync::Mutex;
 
 use crate::fs;
 
+bitflags::bitflags! {
+    /// Flags of [`mount`](crate::api::mount).
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct MountFlags: u32 {
+        /// Mount the filesystem read-only.
+        const RDONLY = 1;
+    }
+}
+
+/// Creates a filesystem to be mounted from the source (e.g., the name of a
+/// block device) and the mount flags.
+pub type FsFactory = fn(source: &str, flags: MountFlags) -> AxResult<Arc<dyn VfsOps>>;
+
+/// Filesystem types registered by [`register_filesystem`].
+static FS_TYPES: Mutex<BTreeMap<&'static str, FsFactory>> = Mutex::new(BTreeMap::new());
+
+/// Registers a filesystem type with `name`, so that it can be mounted by
+/// [`mount`](crate::api::mount).
+pub fn register_filesystem(name: &'static str, factory: FsFactory) -> AxResult {
+    if builtin_filesystem(name).is_some() || FS_TYPES.lock().contains_key(name) {
+        return ax_err!(AlreadyExists, "filesystem type already registered");
+    }
+    FS_TYPES.lock().insert(name, factory);
+    Ok(())
+}
+
+/// Creates a filesystem of type `fstype` to be mounted.
+pub(crate) fn new_filesystem(
+    fstype: &str,
+    source: &str,
+    flags: MountFlags,
+) -> AxResult<Arc<dyn VfsOps>> {
+    let factory = builtin_filesystem(fstype).or_else(|| FS_TYPES.lock().get(fstype).copied());
+    match factory {
+        Some(factory) => factory(source, flags),
+        None => ax_err!(NotFound, "unknown filesystem type"),
+    }
+}
+
+/// Returns the factory of the filesystem type `name` provided by this crate.
+fn builtin_filesystem(name: &str) -> Option<FsFactory> {
+    fn no_flags(flags: MountFlags) -> AxResult {
+        if flags.contains(MountFlags::RDONLY) {
+            ax_err!(Unsupported, "read-only mount is not supported")
+        } else {
+            Ok(())
+        }
+    }
+
+    let factory: FsFactory = match name {
+        #[cfg(feature = "devfs")]
+        "devfs" => |_, flags| {
+            no_flags(flags)?;
+            Ok(devfs())
+        },
+        #[cfg(feature = "ramfs")]
+        "ramfs" | "tmpfs" => |_, flags| {
+            no_flags(flags)?;
+            Ok(ramfs())
+        },
+        #[cfg(feature = "procfs")]
+        "proc" => |_, flags| {
+            no_flags(flags)?;
+            Ok(procfs())
+        },
+        #[cfg(feature = "sysfs")]
+        "sysfs" => |_, flags| {
+            no_flags(flags)?;
+            Ok(sysfs())
+        },
+        #[cfg(all(feature = "ext2", not(feature = "myfs")))]
+        "ext2" | "ext4" => |source, flags| {
+            let disk = crate::dev::Disk::open(source)?;
+            let read_only = flags.contains(MountFlags::RDONLY);
+            Ok(Arc::new(fs::ext2::Ext2FileSystem::new(disk, read_only)?))
+        },
+        // The nodes of FAT filesystems borrow the filesystem, so it is leaked,
+        // and the block device stays in use after unmounting.
+        #[cfg(all(feature = "fatfs", not(any(feature = "ext2", feature = "myfs"))))]
+        "vfat" => |source, flags| {
+            no_flags(flags)?;
+            let disk = crate::dev::Disk::open(source)?;
+            let fs = Arc::new(fs::fatfs::FatFileSystem::open(disk)?);
+            let fs: &'static Arc<_> = alloc::boxed::Box::leak(alloc::boxed::Box::new(fs));
+            fs.init();
+            Ok(fs.clone())
+        },
+        _ => return None,
+    };
+    Some(factory)
+}
+
 #[cfg(feature = "devfs")]
 pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
     let null = fs::devfs::NullDev;
@@ -12,69 +108,324 @@ pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
     let foo_dir = devfs.mkdir("foo");
     devfs.add("null", Arc::new(null));
     devfs.add("zero", Arc::new(zero));
+    devfs.add("full", Arc::new(fs::devfs::FullDev));
+    devfs.add(
+        "random",
+        Arc::new(fs::devfs::RandomDev::new(axhal::random::fill_bytes)),
+    );
+    devfs.add(
+        "urandom",
+        Arc::new(fs::devfs::RandomDev::new(axhal::random::fill_bytes)),
+    );
+    devfs.add("console", Arc::new(crate::devices::ConsoleDev));
+    devfs.add("ttyS0", Arc::new(crate::devices::ConsoleDev));
+    #[cfg(feature = "display")]
+    devfs.add("fb0", Arc::new(crate::devices::FramebufferDev));
+    for (name, range) in crate::dev::block_devices() {
+        devfs.add(&name, Arc::new(crate::devices::BlockDevNode::new(range)));
+    }
     foo_dir.add("bar", Arc::new(bar));
     Arc::new(devfs)
 }
 
 #[cfg(feature = "ramfs")]
 pub(crate) fn ramfs() -> Arc<fs::ramfs::RamFileSystem> {
-    Arc::new(fs::ramfs::RamFileSystem::new())
+    Arc::new(fs::ramfs::RamFileSystem::with_clock(
+        axhal::time::current_time,
+    ))
+}
+
+#[cfg(feature = "procfs")]
+pub(crate) fn procfs() -> Arc<fs::pseudofs::PseudoFileSystem> {
+    use fs::pseudofs::{FileNode, PseudoFileSystem};
+
+    fn file<F>(read: F) -> Arc<FileNode>
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Arc::new(FileNode::new(move || Ok(read())))
+    }
+
+    #[cfg(feature = "multitask")]
+    let procfs = PseudoFileSystem::new_generated(proc_task_dirs);
+    #[cfg(not(feature = "multitask"))]
+    let procfs = PseudoFileSystem::new();
+    let proc_root = procfs.root();
+
+    proc_root.add("meminfo", file(proc_meminfo));
+    proc_root.add("cpuinfo", file(proc_cpuinfo));
+    proc_root.add("uptime", file(proc_uptime));
+    proc_root.add("mounts", file(proc_mounts));
+    #[cfg(feature = "irq")]
+    proc_root.add("interrupts", file(proc_interrupts));
+
+    // Create /proc/sys/net/core/somaxconn and /proc/sys/vm/overcommit_memory
+    let sys_dir = proc_root.mkdir("sys");
+    let core_dir = sys_dir.mkdir("net").mkdir("core");
+    core_dir.add("somaxconn", file(|| "4096\n".into()));
+    let vm_dir = sys_dir.mkdir("vm");
+    vm_dir.add("overcommit_memory", file(|| "0\n".into()));
+
+    #[cfg(feature = "multitask")]
+    proc_root.add(
+        "self",
+        Arc::new(fs::pseudofs::SymlinkNode::new(|| {
+            format!("{}", axtask::current().id().as_u64())
+        })),
+    );
+
+    Arc::new(procfs)
+}
+
+#[cfg(feature = "procfs")]
+fn proc_meminfo() -> String {
+    const PAGE_SIZE: usize = 0x1000;
+    let allocator = axalloc::global_allocator();
+    let total = (allocator.used_pages() + allocator.available_pages()) * PAGE_SIZE;
+    let free = allocator.available_pages() * PAGE_SIZE + allocator.available_bytes();
+    let buffers = crate::cache::block_cache_stats().cached * crate::cache::BLOCK_SIZE;
+    let cached = crate::cache::PAGE_CACHE.stats().cached * PAGE_SIZE;
+    format!(
+        "MemTotal:{:>12} kB\nMemFree:{:>13} kB\nMemAvailable:{:>8} kB\n\
+         Buffers:{:>13} kB\nCached:{:>14} kB\n",
+        total / 1024,
+        free / 1024,
+        free / 1024,
+        buffers / 1024,
+        cached / 1024,
+    )
+}
+
+#[cfg(feature = "procfs")]
+fn proc_cpuinfo() -> String {
+    let mut info = String::new();
+    for cpu_id in 0..axconfig::SMP {
+        info += &format!(
+            "processor\t: {}\narch\t\t: {}\nplatform\t: {}\n\n",
+            cpu_id,
+            axconfig::ARCH,
+            axconfig::PLATFORM,
+        );
+    }
+    info
+}
+
+#[cfg(feature = "procfs")]
+fn proc_uptime() -> String {
+    let uptime = axhal::time::current_time();
+    #[cfg(feature = "multitask")]
+    let idle = core::time::Duration::from_nanos(
+        axtask::task_stats()
+            .iter()
+            .filter(|t| t.name == "idle")
+            .map(|t| t.runtime_ns)
+            .sum(),
+    );
+    #[cfg(not(feature = "multitask"))]
+    let idle = core::time::Duration::ZERO;
+    format!(
+        "{}.{:02} {}.{:02}\n",
+        uptime.as_secs(),
+        uptime.subsec_millis() / 10,
+        idle.as_secs(),
+        idle.subsec_millis() / 10,
+    )
 }
 
 #[cfg(feature = "procfs")]
-pub(crate) fn procfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
-    let procfs = fs::ramfs::RamFileSystem::new();
-    let proc_root = procfs.root_dir();
+fn proc_mounts() -> String {
+    let mut mounts = String::new();
+    for info in crate::root::mount_list() {
+        let mode = if info.flags.contains(MountFlags::RDONLY) {
+            "ro"
+        } else {
+            "rw"
+        };
+        mounts += &format!(
+            "{} {} {} {} 0 0\n",
+            info.source, info.target, info.fstype, mode
+        );
+    }
+    mounts
+}
+
+#[cfg(all(feature = "procfs", feature = "irq"))]
+fn proc_interrupts() -> String {
+    let mut interrupts = String::new();
+    for (irq_num, count) in axhal::irq::irq_counts() {
+        interrupts += &format!("{:>4}: {:>10}\n", irq_num, count);
+    }
+    interrupts
+}
+
+/// Generates a directory for each task in `/proc`.
+#[cfg(all(feature = "procfs", feature = "multitask"))]
+fn proc_task_dirs(
+    proc_root: &Arc<fs::pseudofs::DirNode>,
+) -> alloc::vec::Vec<(String, axfs_vfs::VfsNodeRef)> {
+    use fs::pseudofs::{DirNode, FileNode};
+
+    fn task_file<F>(id: u64, read: F) -> Arc<FileNode>
+    where
+        F: Fn(&axtask::TaskStats) -> String + Send + Sync + 'static,
+    {
+        Arc::new(FileNode::new(move || {
+            // The task may have been dropped after the directory was looked up.
+            let stats = axtask::task_stats().into_iter().find(|t| t.id == id);
+            stats.map(|t| read(&t)).ok_or(axfs_vfs::VfsError::NotFound)
+        }))
+    }
+
+    axtask::task_stats()
+        .into_iter()
+        .map(|task| {
+            let dir = DirNode::new_detached(proc_root);
+            dir.add("status", task_file(task.id, proc_task_status));
+            dir.add("stat", task_file(task.id, proc_task_stat));
+            (format!("{}", task.id), dir as axfs_vfs::VfsNodeRef)
+        })
+        .collect()
+}
 
-    // Create /proc/sys/net/core/somaxconn
-    proc_root.create("sys", VfsNodeType::Dir)?;
-    proc_root.create("sys/net", VfsNodeType::Dir)?;
-    proc_root.create("sys/net/core", VfsNodeType::Dir)?;
-    proc_root.create("sys/net/core/somaxconn", VfsNodeType::File)?;
-    let file_somaxconn = proc_root.clone().lookup("./sys/net/core/somaxconn")?;
-    file_somaxconn.write_at(0, b"4096\n")?;
+#[cfg(all(feature = "procfs", feature = "multitask"))]
+fn proc_task_state(state: axtask::TaskState) -> (char, &'static str) {
+    use axtask::TaskState;
+    match state {
+        TaskState::Running => ('R', "running"),
+        TaskState::Ready => ('R', "ready"),
+        TaskState::Blocked => ('S', "sleeping"),
+        TaskState::Exited => ('Z', "zombie"),
+    }
+}
+
+#[cfg(all(feature = "procfs", feature = "multitask"))]
+fn proc_task_status(task: &axtask::TaskStats) -> String {
+    let (state, state_name) = proc_task_state(task.state);
+    format!(
+        "Name:\t{}\nState:\t{} ({})\nTid:\t{}\nCpu:\t{}\nPriority:\t{}\n\
+         RunTime:\t{} ns\nStackSize:\t{} kB\nStackUsed:\t{} kB\n\
+         voluntary_ctxt_switches:\t{}\nwakeups:\t{}\n",
+        task.name,
+        state,
+        state_name,
+        task.id,
+        task.cpu_id,
+        task.priority,
+        task.runtime_ns,
+        task.stack_size / 1024,
+        task.stack_used / 1024,
+        task.context_switches,
+        task.wakeups,
+    )
+}
+
+/// Generates the `stat` file in the format of Linux, with the fields not
+/// tracked by ArceOS set to 0.
+#[cfg(all(feature = "procfs", feature = "multitask"))]
+fn proc_task_stat(task: &axtask::TaskStats) -> String {
+    let (state, _) = proc_task_state(task.state);
+    // The run time in clock ticks, assuming 100 ticks per second as Linux.
+    let utime = task.runtime_ns / 10_000_000;
+    format!(
+        "{} ({}) {} 0 0 0 0 0 0 0 0 0 0 {} 0 0 0 {} 0 1 0 0 0 0\n",
+        task.id, task.name, state, utime, task.priority,
+    )
+}
+
+#[cfg(feature = "sysfs")]
+pub(crate) fn sysfs() -> Arc<fs::pseudofs::PseudoFileSystem> {
+    use fs::pseudofs::{FileNode, PseudoFileSystem};
+
+    let sysfs = PseudoFileSystem::new_generated(|root| sysfs_entries(root, ""));
+    let sys_root = sysfs.root();
+
+    // The following files are not backed by the driver model, but are read
+    // by some applications.
+    let thp_dir = sys_root
+        .mkdir("kernel")
+        .mkdir("mm")
+        .mkdir("transparent_hugepage");
+    thp_dir.add(
+        "enabled",
+        Arc::new(FileNode::new(|| Ok("always [madvise] never\n".into()))),
+    );
+    let clocksource_dir = sys_root
+        .mkdir("devices")
+        .mkdir("system")
+        .mkdir("clocksource")
+        .mkdir("clocksource0");
+    clocksource_dir.add(
+        "current_clocksource",
+        Arc::new(FileNode::new(|| Ok("tsc\n".into()))),
+    );
 
-    // Create /proc/sys/vm/overcommit_memory
-    proc_root.create("sys/vm", VfsNodeType::Dir)?;
-    proc_root.create("sys/vm/overcommit_memory", VfsNodeType::File)?;
-    let file_over = proc_root.clone().lookup("./sys/vm/overcommit_memory")?;
-    file_over.write_at(0, b"0\n")?;
+    Arc::new(sysfs)
+}
 
-    // Create /proc/self/stat
-    proc_root.create("self", VfsNodeType::Dir)?;
-    proc_root.create("self/stat", VfsNodeType::File)?;
+/// Generates the entries of the sysfs directory at `dir_path` (e.g.,
+/// `class/net`) from the devices registered in [`axdriver::model`]. The
+/// attributes are files, and the other entries are directories.
+#[cfg(feature = "sysfs")]
+fn sysfs_entries(
+    dir: &Arc<fs::pseudofs::DirNode>,
+    dir_path: &str,
+) -> alloc::vec::Vec<(String, axfs_vfs::VfsNodeRef)> {
+    use fs::pseudofs::DirNode;
 
-    Ok(Arc::new(procfs))
+    let mut entries = BTreeMap::new();
+    for (dev_path, attrs) in axdriver::model::devices() {
+        for (index, attr) in attrs.iter().enumerate() {
+            let attr_path = format!("{}/{}", dev_path, attr.name());
+            let rest = if dir_path.is_empty() {
+                Some(attr_path.as_str())
+            } else {
+                attr_path
+                    .strip_prefix(dir_path)
+                    .and_then(|rest| rest.strip_prefix('/'))
+            };
+            match rest.map(|rest| rest.split_once('/').ok_or(rest)) {
+                Some(Ok((name, _))) if !entries.contains_key(name) => {
+                    let path = if dir_path.is_empty() {
+                        name.into()
+                    } else {
+                        format!("{}/{}", dir_path, name)
+                    };
+                    let subdir = DirNode::new_detached_generated(dir, move |subdir| {
+                        sysfs_entries(subdir, &path)
+                    });
+                    entries.insert(String::from(name), subdir as _);
+                }
+                Some(Err(name)) => {
+                    entries.insert(name.into(), sysfs_attr(attrs.clone(), index));
+                }
+                _ => {}
+            }
+        }
+    }
+    entries.into_iter().collect()
 }
 
+/// Creates the file of the `index`-th attribute in `attrs`.
 #[cfg(feature = "sysfs")]
-pub(crate) fn sysfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
-    let sysfs = fs::ramfs::RamFileSystem::new();
-    let sys_root = sysfs.root_dir();
-
-    // Create /sys/kernel/mm/transparent_hugepage/enabled
-    sys_root.create("kernel", VfsNodeType::Dir)?;
-    sys_root.create("kernel/mm", VfsNodeType::Dir)?;
-    sys_root.create("kernel/mm/transparent_hugepage", VfsNodeType::Dir)?;
-    sys_root.create("kernel/mm/transparent_hugepage/enabled", VfsNodeType::File)?;
-    let file_hp = sys_root
-        .clone()
-        .lookup("./kernel/mm/transparent_hugepage/enabled")?;
-    file_hp.write_at(0, b"always [madvise] never\n")?;
-
-    // Create /sys/devices/system/clocksource/clocksource0/current_clocksource
-    sys_root.create("devices", VfsNodeType::Dir)?;
-    sys_root.create("devices/system", VfsNodeType::Dir)?;
-    sys_root.create("devices/system/clocksource", VfsNodeType::Dir)?;
-    sys_root.create("devices/system/clocksource/clocksource0", VfsNodeType::Dir)?;
-    sys_root.create(
-        "devices/system/clocksource/clocksource0/current_clocksource",
-        VfsNodeType::File,
-    )?;
-    let file_cc = sys_root
-        .clone()
-        .lookup("devices/system/clocksource/clocksource0/current_clocksource")?;
-    file_cc.write_at(0, b"tsc\n")?;
-
-    Ok(Arc::new(sysfs))
+fn sysfs_attr(attrs: Arc<[axdriver::model::DeviceAttr]>, index: usize) -> axfs_vfs::VfsNodeRef {
+    use axdriver::prelude::DevError;
+    use axfs_vfs::VfsError;
+    use fs::pseudofs::FileNode;
+
+    let read = {
+        let attrs = attrs.clone();
+        move || Ok(attrs[index].show())
+    };
+    if !attrs[index].is_writable() {
+        return Arc::new(FileNode::new(read));
+    }
+    Arc::new(FileNode::new_writable(read, move |buf| {
+        let value = core::str::from_utf8(buf).map_err(|_| VfsError::InvalidInput)?;
+        attrs[index].store(value).map_err(|e| match e {
+            DevError::InvalidParam => VfsError::InvalidInput,
+            DevError::ResourceBusy => VfsError::ResourceBusy,
+            DevError::Unsupported => VfsError::PermissionDenied,
+            _ => VfsError::Io,
+        })
+    }))
 }
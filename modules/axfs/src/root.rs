@@ -1,103 +1,225 @@
 //! Root directory of the filesystem
-//!
-//! TODO: it doesn't work very well if the mount points have containment relationships.
 
-use alloc::{string::String, sync::Arc, vec::Vec};
+use alloc::{borrow::Cow, collections::BTreeMap, format, string::String, sync::Arc, vec, vec::Vec};
 use axerrno::{ax_err, AxError, AxResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
 use axsync::Mutex;
 use lazy_init::LazyInit;
 
-use crate::{api::FileType, fs, mounts};
+use crate::{api::FileType, fs, mounts, mounts::MountFlags};
+
+/// The maximum number of symbolic links followed in a path resolution.
+const MAX_SYMLINKS: usize = 40;
 
 static CURRENT_DIR_PATH: Mutex<String> = Mutex::new(String::new());
-static CURRENT_DIR: LazyInit<Mutex<VfsNodeRef>> = LazyInit::new();
 
+/// A filesystem mounted on a directory.
+#[cfg_attr(not(feature = "procfs"), allow(dead_code))]
 struct MountPoint {
-    path: &'static str,
+    source: String,
+    fstype: String,
+    flags: MountFlags,
     fs: Arc<dyn VfsOps>,
 }
 
+/// A tree of mount points indexed by path components. A node without a mount
+/// point only leads to the mount points below it.
+#[derive(Default)]
+struct MountTree {
+    mount: Option<MountPoint>,
+    children: BTreeMap<String, MountTree>,
+}
+
 struct RootDirectory {
     main_fs: Arc<dyn VfsOps>,
-    mounts: Vec<MountPoint>,
+    mounts: Mutex<MountTree>,
 }
 
 static ROOT_DIR: LazyInit<Arc<RootDirectory>> = LazyInit::new();
 
+/// The filesystem type of the root filesystem.
+#[cfg_attr(not(feature = "procfs"), allow(dead_code))]
+const ROOT_FSTYPE: &str = if cfg!(feature = "myfs") {
+    "myfs"
+} else if cfg!(feature = "ext2") {
+    "ext2"
+} else {
+    "vfat"
+};
+
+/// Information of a mounted filesystem.
+#[cfg_attr(not(feature = "procfs"), allow(dead_code))]
+pub(crate) struct MountInfo {
+    pub source: String,
+    pub target: String,
+    pub fstype: String,
+    pub flags: MountFlags,
+}
+
 impl MountPoint {
-    pub fn new(path: &'static str, fs: Arc<dyn VfsOps>) -> Self {
-        Self { path, fs }
+    pub fn new(source: &str, fstype: &str, flags: MountFlags, fs: Arc<dyn VfsOps>) -> Self {
+        Self {
+            source: source.into(),
+            fstype: fstype.into(),
+            flags,
+            fs,
+        }
     }
 }
 
 impl Drop for MountPoint {
     fn drop(&mut self) {
+        debug!("unmount {} ({})", self.source, self.fstype);
         self.fs.umount().ok();
     }
 }
 
+impl MountTree {
+    /// Appends the mount points in this tree at `path` to `list`, parents
+    /// first.
+    #[cfg_attr(not(feature = "procfs"), allow(dead_code))]
+    fn collect(&self, path: &str, list: &mut Vec<MountInfo>) {
+        if let Some(mp) = &self.mount {
+            list.push(MountInfo {
+                source: mp.source.clone(),
+                target: path.into(),
+                fstype: mp.fstype.clone(),
+                flags: mp.flags,
+            });
+        }
+        for (name, child) in &self.children {
+            child.collect(&format!("{}/{}", path, name), list);
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<&Self> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |tree, name| tree.children.get(name))
+    }
+
+    /// Removes the mount point at `names`, and the nodes that no longer lead
+    /// to any mount point.
+    fn remove(&mut self, names: &[&str]) -> AxResult<MountPoint> {
+        match names.split_first() {
+            None if self.mount.is_none() => ax_err!(InvalidInput, "not a mount point"),
+            None if !self.children.is_empty() => ax_err!(ResourceBusy, "nested mount points"),
+            None => Ok(self.mount.take().unwrap()),
+            Some((name, rest)) => {
+                let child = match self.children.get_mut(*name) {
+                    Some(child) => child,
+                    None => return ax_err!(InvalidInput, "not a mount point"),
+                };
+                let mp = child.remove(rest)?;
+                if child.mount.is_none() && child.children.is_empty() {
+                    self.children.remove(*name);
+                }
+                Ok(mp)
+            }
+        }
+    }
+}
+
 impl RootDirectory {
-    pub const fn new(main_fs: Arc<dyn VfsOps>) -> Self {
+    pub fn new(main_fs: Arc<dyn VfsOps>) -> Self {
         Self {
             main_fs,
-            mounts: Vec::new(),
+            mounts: Mutex::new(MountTree::default()),
         }
     }
 
-    pub fn mount(&mut self, path: &'static str, fs: Arc<dyn VfsOps>) -> AxResult {
+    /// Mounts the filesystem of `mp` on the directory `node`, whose canonical
+    /// absolute path is `path`.
+    pub fn mount(&self, path: &str, node: VfsNodeRef, mp: MountPoint) -> AxResult {
         if path == "/" {
             return ax_err!(InvalidInput, "cannot mount root filesystem");
         }
         if !path.starts_with('/') {
             return ax_err!(InvalidInput, "mount path must start with '/'");
         }
-        if self.mounts.iter().any(|mp| mp.path == path) {
-            return ax_err!(InvalidInput, "mount point already exists");
+        let mut mounts = self.mounts.lock();
+        if mounts.get(path).is_some_and(|tree| tree.mount.is_some()) {
+            return ax_err!(ResourceBusy, "mount point already exists");
         }
-        // create the mount point in the main filesystem if it does not exist
-        self.main_fs.root_dir().create(path, FileType::Dir)?;
-        fs.mount(path, self.main_fs.root_dir().lookup(path)?)?;
-        self.mounts.push(MountPoint::new(path, fs));
+        mp.fs.mount(path, node)?;
+        let tree = path
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .fold(&mut *mounts, |tree, name| {
+                tree.children.entry(name.into()).or_default()
+            });
+        tree.mount = Some(mp);
         Ok(())
     }
 
-    pub fn _umount(&mut self, path: &str) {
-        self.mounts.retain(|mp| mp.path != path);
+    /// Unmounts the filesystem mounted on the canonical absolute `path`.
+    pub fn umount(&self, path: &str) -> AxResult {
+        let names: Vec<_> = path.split('/').filter(|name| !name.is_empty()).collect();
+        if names.is_empty() {
+            return ax_err!(ResourceBusy, "cannot unmount root filesystem");
+        }
+        let mp = self.mounts.lock().remove(&names)?;
+        drop(mp); // unmount outside the lock
+        Ok(())
     }
 
-    pub fn contains(&self, path: &str) -> bool {
-        self.mounts.iter().any(|mp| mp.path == path)
+    pub fn is_mount_point(&self, path: &str) -> bool {
+        self.mounts
+            .lock()
+            .get(path)
+            .is_some_and(|tree| tree.mount.is_some())
     }
 
+    /// Finds the filesystem with the longest mount path that contains `path`,
+    /// and calls `f` with the filesystem and the rest of the path.
+    ///
+    /// `..` is resolved lexically until the path leaves the mount tree, so
+    /// that it can go across mount points.
     fn lookup_mounted_fs<F, T>(&self, path: &str, f: F) -> AxResult<T>
     where
         F: FnOnce(Arc<dyn VfsOps>, &str) -> AxResult<T>,
     {
         debug!("lookup at root: {}", path);
-        let path = path.trim_matches('/');
-        if let Some(rest) = path.strip_prefix("./") {
-            return self.lookup_mounted_fs(rest, f);
-        }
-
-        let mut idx = 0;
-        let mut max_len = 0;
-
-        // Find the filesystem that has the longest mounted path match
-        // TODO: more efficient, e.g. trie
-        for (i, mp) in self.mounts.iter().enumerate() {
-            // skip the first '/'
-            if path.starts_with(&mp.path[1..]) && mp.path.len() - 1 > max_len {
-                max_len = mp.path.len() - 1;
-                idx = i;
+        let (fs, rest_path) = {
+            let mounts = self.mounts.lock();
+            let mut trees = vec![(&*mounts, "")];
+            let mut rest = path;
+            loop {
+                let trimmed = rest.trim_start_matches('/');
+                let (name, next) = trimmed.split_once('/').unwrap_or((trimmed, ""));
+                match name {
+                    "" => break,
+                    "." => {}
+                    ".." if trees.len() > 1 => {
+                        trees.pop();
+                    }
+                    _ => match trees.last().unwrap().0.children.get(name) {
+                        Some(child) => trees.push((child, name)),
+                        None => {
+                            rest = trimmed;
+                            break;
+                        }
+                    },
+                }
+                rest = next;
             }
-        }
 
-        if max_len == 0 {
-            f(self.main_fs.clone(), path) // not matched any mount point
-        } else {
-            f(self.mounts[idx].fs.clone(), &path[max_len..]) // matched at `idx`
-        }
+            let (fs, names) = match trees.iter().rposition(|(tree, _)| tree.mount.is_some()) {
+                Some(i) => (
+                    trees[i].0.mount.as_ref().unwrap().fs.clone(),
+                    &trees[i + 1..],
+                ),
+                None => (self.main_fs.clone(), &trees[1..]),
+            };
+            let mut rest_path = String::new();
+            for (_, name) in names {
+                rest_path += name;
+                rest_path.push('/');
+            }
+            rest_path += rest;
+            (fs, rest_path)
+        };
+        f(fs, rest_path.trim_matches('/'))
     }
 }
 
@@ -141,12 +263,52 @@ impl VfsNodeOps for RootDirectory {
             }
         })
     }
+    fn symlink(&self, path: &str, target: &str) -> VfsResult {
+        self.lookup_mounted_fs(path, |fs, rest_path| {
+            if rest_path.is_empty() {
+                ax_err!(AlreadyExists)
+            } else {
+                fs.root_dir().symlink(rest_path, target)
+            }
+        })
+    }
+
+    fn link(&self, path: &str, node: &VfsNodeRef) -> VfsResult {
+        self.lookup_mounted_fs(path, |fs, rest_path| {
+            if rest_path.is_empty() {
+                ax_err!(AlreadyExists)
+            } else {
+                fs.root_dir().link(rest_path, node)
+            }
+        })
+    }
+}
+
+fn mount_at_init(
+    root_dir: &RootDirectory,
+    path: &str,
+    fstype: &str,
+    fs: Arc<dyn VfsOps>,
+) -> AxResult {
+    // create the mount point in the main filesystem if it does not exist
+    root_dir.main_fs.root_dir().create(path, FileType::Dir)?;
+    let node = root_dir.main_fs.root_dir().lookup(path)?;
+    root_dir.mount(
+        path,
+        node,
+        MountPoint::new(fstype, fstype, MountFlags::empty(), fs),
+    )
 }
 
 pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
     cfg_if::cfg_if! {
         if #[cfg(feature = "myfs")] { // override the default filesystem
             let main_fs = fs::myfs::new_myfs(disk);
+        } else if #[cfg(feature = "ext2")] {
+            let main_fs = Arc::new(
+                fs::ext2::Ext2FileSystem::new(disk, false)
+                    .expect("failed to initialize ext2 filesystem"),
+            );
         } else if #[cfg(feature = "fatfs")] {
             static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
             FAT_FS.init_by(Arc::new(fs::fatfs::FatFileSystem::new(disk)));
@@ -155,32 +317,27 @@ pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
         }
     }
 
-    let mut root_dir = RootDirectory::new(main_fs);
+    let root_dir = RootDirectory::new(main_fs);
 
     #[cfg(feature = "devfs")]
-    root_dir
-        .mount("/dev", mounts::devfs())
+    mount_at_init(&root_dir, "/dev", "devfs", mounts::devfs())
         .expect("failed to mount devfs at /dev");
 
     #[cfg(feature = "ramfs")]
-    root_dir
-        .mount("/tmp", mounts::ramfs())
+    mount_at_init(&root_dir, "/tmp", "tmpfs", mounts::ramfs())
         .expect("failed to mount ramfs at /tmp");
 
-    // Mount another ramfs as procfs
+    // Mount the pseudo filesystem generated from the kernel state as procfs
     #[cfg(feature = "procfs")]
-    root_dir // should not fail
-        .mount("/proc", mounts::procfs().unwrap())
+    mount_at_init(&root_dir, "/proc", "proc", mounts::procfs())
         .expect("fail to mount procfs at /proc");
 
-    // Mount another ramfs as sysfs
+    // Mount the pseudo filesystem generated from the driver model as sysfs
     #[cfg(feature = "sysfs")]
-    root_dir // should not fail
-        .mount("/sys", mounts::sysfs().unwrap())
+    mount_at_init(&root_dir, "/sys", "sysfs", mounts::sysfs())
         .expect("fail to mount sysfs at /sys");
 
     ROOT_DIR.init_by(Arc::new(root_dir));
-    CURRENT_DIR.init_by(Mutex::new(ROOT_DIR.clone()));
     *CURRENT_DIR_PATH.lock() = "/".into();
 }
 
@@ -188,24 +345,153 @@ fn parent_node_of(dir: Option<&VfsNodeRef>, path: &str) -> VfsNodeRef {
     if path.starts_with('/') {
         ROOT_DIR.clone()
     } else {
-        dir.cloned().unwrap_or_else(|| CURRENT_DIR.lock().clone())
+        dir.cloned().unwrap_or_else(|| ROOT_DIR.clone())
+    }
+}
+
+/// Returns `path` as an absolute path if it is relative to the current
+/// directory, so that it is resolved from the root directory, which knows all
+/// the mount points.
+fn path_at<'a>(dir: Option<&VfsNodeRef>, path: &'a str) -> Cow<'a, str> {
+    if dir.is_none() && !path.starts_with('/') {
+        (CURRENT_DIR_PATH.lock().clone() + path).into()
+    } else {
+        path.into()
     }
 }
 
 pub(crate) fn absolute_path(path: &str) -> AxResult<String> {
-    if path.starts_with('/') {
-        Ok(axfs_vfs::path::canonicalize(path))
+    Ok(axfs_vfs::path::canonicalize(&path_at(None, path)))
+}
+
+/// Reads the target path of the symbolic link `node`.
+fn read_link_node(node: &VfsNodeRef) -> AxResult<String> {
+    let attr = node.get_attr()?;
+    if !attr.is_symlink() {
+        return ax_err!(InvalidInput);
+    }
+    let mut buf = vec![0; attr.size() as usize];
+    let len = node.readlink(&mut buf)?;
+    buf.truncate(len);
+    String::from_utf8(buf).map_err(|_| AxError::InvalidData)
+}
+
+/// Removes the last component of the resolved `path` for a following `..`,
+/// so that it does not go across mount points. Returns false if there is no
+/// such component.
+fn pop_component(path: &mut String) -> bool {
+    if path == "/" {
+        return true;
+    }
+    let (parent, last) = path.rsplit_once('/').unwrap_or(("", path));
+    if last.is_empty() || last == "." || last == ".." {
+        return false;
+    }
+    let len = if parent.is_empty() && path.starts_with('/') {
+        1
     } else {
-        let path = CURRENT_DIR_PATH.lock().clone() + path;
-        Ok(axfs_vfs::path::canonicalize(&path))
+        parent.len()
+    };
+    path.truncate(len);
+    true
+}
+
+/// Returns a path equivalent to `path`, which contains no symbolic links,
+/// except the last component if `follow` is false.
+///
+/// Symbolic links are resolved component by component, and the target of a
+/// link is substituted into the path, so that `..` after the link refers to
+/// the parent of the target. `..` is then applied to the resolved path. The path is returned as is if it contains no
+/// links, or it does not exist.
+fn resolve_links<'a>(
+    dir: Option<&VfsNodeRef>,
+    path: &'a str,
+    follow: bool,
+) -> AxResult<Cow<'a, str>> {
+    let path = path_at(dir, path);
+    // Fast path: filesystems do not follow links, and fail with
+    // `NotADirectory` if a link is in the middle of the path.
+    match parent_node_of(dir, &path).lookup(&path) {
+        Ok(node) if !follow || !node.get_attr()?.is_symlink() => return Ok(path),
+        Err(e) if e != AxError::NotADirectory => return Ok(path),
+        _ => {}
     }
+
+    let mut resolved = String::from(if path.starts_with('/') { "/" } else { "" });
+    let mut rest = path.into_owned();
+    let mut links = 0;
+    loop {
+        let trimmed = rest.trim_start_matches('/');
+        let (name, next) = match trimmed.split_once('/') {
+            Some((name, next)) => (name, Some(next)),
+            None => (trimmed, None),
+        };
+        if name.is_empty() {
+            break;
+        }
+        let is_last = next.is_none_or(|n| n.trim_matches('/').is_empty());
+        let mut candidate = resolved.clone();
+        if name != ".." || !pop_component(&mut candidate) {
+            if !candidate.is_empty() && !candidate.ends_with('/') {
+                candidate.push('/');
+            }
+            candidate.push_str(name);
+        }
+
+        // Always follow the last component if it ends with a slash.
+        let follow_this = !is_last || follow || next.is_some();
+        let node = if name == "." || name == ".." || !follow_this {
+            None
+        } else if is_last {
+            parent_node_of(dir, &candidate).lookup(&candidate).ok()
+        } else {
+            Some(parent_node_of(dir, &candidate).lookup(&candidate)?)
+        };
+        match node {
+            Some(node) if node.get_attr()?.is_symlink() => {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return ax_err!(FilesystemLoop);
+                }
+                let target = read_link_node(&node)?;
+                if target.starts_with('/') {
+                    resolved = "/".into();
+                }
+                rest = match next {
+                    Some(next) => format!("{}/{}", target, next),
+                    None => target,
+                };
+            }
+            _ => {
+                if is_last && next.is_some() {
+                    candidate.push('/');
+                }
+                rest = next.unwrap_or_default().into();
+                resolved = candidate;
+            }
+        }
+    }
+    if resolved.is_empty() {
+        resolved.push('.');
+    }
+    Ok(resolved.into())
 }
 
-pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+fn lookup_at(dir: Option<&VfsNodeRef>, path: &str, follow: bool) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
     }
-    let node = parent_node_of(dir, path).lookup(path)?;
+    let path = &path_at(dir, path);
+    // Always follow the last component if it ends with a slash.
+    let follow = follow || path.ends_with('/');
+    let node = match parent_node_of(dir, path).lookup(path) {
+        Ok(node) if !follow || !node.get_attr()?.is_symlink() => node,
+        Err(e) if e != AxError::NotADirectory => return Err(e),
+        _ => {
+            let path = resolve_links(dir, path, follow)?;
+            parent_node_of(dir, &path).lookup(&path)?
+        }
+    };
     if path.ends_with('/') && !node.get_attr()?.is_dir() {
         ax_err!(NotADirectory)
     } else {
@@ -213,27 +499,51 @@ pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRe
     }
 }
 
+/// Looks up the node with `path`, and follows the symbolic links.
+pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    lookup_at(dir, path, true)
+}
+
+/// Looks up the node with `path`, but does not follow the last component if
+/// it is a symbolic link.
+pub(crate) fn lookup_nofollow(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    lookup_at(dir, path, false)
+}
+
+/// Returns the mounted filesystem that contains `path`, which is relative to
+/// the current directory. Symbolic links in the path are followed.
+pub(crate) fn filesystem_of(path: &str) -> AxResult<Arc<dyn VfsOps>> {
+    let path = resolve_links(None, path, true)?;
+    ROOT_DIR.lookup_mounted_fs(&path, |fs, _| Ok(fs))
+}
+
 pub(crate) fn create_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
     } else if path.ends_with('/') {
         return ax_err!(NotADirectory);
     }
-    let parent = parent_node_of(dir, path);
-    parent.create(path, VfsNodeType::File)?;
-    parent.lookup(path)
+    // Create the target if the path is a dangling symbolic link.
+    let path = resolve_links(dir, path, true)?;
+    let parent = parent_node_of(dir, &path);
+    parent.create(&path, VfsNodeType::File)?;
+    parent.lookup(&path)
 }
 
 pub(crate) fn create_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    match lookup(dir, path) {
+    match lookup_nofollow(dir, path) {
         Ok(_) => ax_err!(AlreadyExists),
-        Err(AxError::NotFound) => parent_node_of(dir, path).create(path, VfsNodeType::Dir),
+        Err(AxError::NotFound) => {
+            let path = resolve_links(dir, path, false)?;
+            parent_node_of(dir, &path).create(&path, VfsNodeType::Dir)
+        }
         Err(e) => Err(e),
     }
 }
 
 pub(crate) fn remove_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    let node = lookup(dir, path)?;
+    let node = lookup_nofollow(dir, path)?;
+    let path = &resolve_links(dir, path, false)?;
     let attr = node.get_attr()?;
     if attr.is_dir() {
         ax_err!(IsADirectory)
@@ -258,11 +568,12 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     {
         return ax_err!(InvalidInput);
     }
-    if ROOT_DIR.contains(&absolute_path(path)?) {
+    if ROOT_DIR.is_mount_point(&absolute_path(path)?) {
         return ax_err!(PermissionDenied);
     }
 
-    let node = lookup(dir, path)?;
+    let node = lookup_nofollow(dir, path)?;
+    let path = &resolve_links(dir, path, false)?;
     let attr = node.get_attr()?;
     if !attr.is_dir() {
         ax_err!(NotADirectory)
@@ -283,7 +594,6 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
         abs_path += "/";
     }
     if abs_path == "/" {
-        *CURRENT_DIR.lock() = ROOT_DIR.clone();
         *CURRENT_DIR_PATH.lock() = "/".into();
         return Ok(());
     }
@@ -295,16 +605,84 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
     } else if !attr.perm().owner_executable() {
         ax_err!(PermissionDenied)
     } else {
-        *CURRENT_DIR.lock() = node;
         *CURRENT_DIR_PATH.lock() = abs_path;
         Ok(())
     }
 }
 
 pub(crate) fn rename(old: &str, new: &str) -> AxResult {
+    let old = &resolve_links(None, old, false)?;
+    let new = &resolve_links(None, new, false)?;
     if parent_node_of(None, new).lookup(new).is_ok() {
         warn!("dst file already exist, now remove it");
         remove_file(None, new)?;
     }
     parent_node_of(None, old).rename(old, new)
 }
+
+pub(crate) fn symlink(dir: Option<&VfsNodeRef>, target: &str, path: &str) -> AxResult {
+    if path.is_empty() || target.is_empty() {
+        return ax_err!(NotFound);
+    }
+    let path = resolve_links(dir, path, false)?;
+    parent_node_of(dir, &path).symlink(&path, target)
+}
+
+pub(crate) fn link(dir: Option<&VfsNodeRef>, old: &str, new: &str) -> AxResult {
+    let node = lookup_nofollow(dir, old)?;
+    if node.get_attr()?.is_dir() {
+        return ax_err!(PermissionDenied);
+    }
+    if new.is_empty() {
+        return ax_err!(NotFound);
+    }
+    let new = resolve_links(dir, new, false)?;
+    parent_node_of(dir, &new).link(&new, &node)
+}
+
+pub(crate) fn read_link(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<String> {
+    read_link_node(&lookup_nofollow(dir, path)?)
+}
+
+pub(crate) fn mount(source: &str, target: &str, fstype: &str, flags: MountFlags) -> AxResult {
+    if target.is_empty() {
+        return ax_err!(NotFound);
+    }
+    let target = absolute_path(&resolve_links(None, target, true)?)?;
+    let node = lookup(None, &target)?;
+    if !node.get_attr()?.is_dir() {
+        return ax_err!(NotADirectory);
+    }
+    if ROOT_DIR.is_mount_point(&target) {
+        return ax_err!(ResourceBusy, "mount point already exists");
+    }
+    let fs = mounts::new_filesystem(fstype, source, flags)?;
+    ROOT_DIR.mount(&target, node, MountPoint::new(source, fstype, flags, fs))
+}
+
+pub(crate) fn umount(target: &str) -> AxResult {
+    if target.is_empty() {
+        return ax_err!(NotFound);
+    }
+    let target = absolute_path(&resolve_links(None, target, true)?)?;
+    if CURRENT_DIR_PATH.lock().starts_with(&format!("{}/", target)) {
+        return ax_err!(ResourceBusy, "the current directory is in use");
+    }
+    ROOT_DIR.umount(&target)
+}
+
+/// Returns all the mounted filesystems, including the root filesystem, with
+/// the parents before the children.
+#[cfg_attr(not(feature = "procfs"), allow(dead_code))]
+pub(crate) fn mount_list() -> Vec<MountInfo> {
+    let mut list = vec![MountInfo {
+        source: "rootfs".into(),
+        target: "/".into(),
+        fstype: ROOT_FSTYPE.into(),
+        flags: MountFlags::empty(),
+    }];
+    for (name, child) in &ROOT_DIR.mounts.lock().children {
+        child.collect(&format!("/{}", name), &mut list);
+    }
+    list
+}
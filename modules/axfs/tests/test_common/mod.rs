@@ -1,8 +1,10 @@
 use axfs::api as fs;
+use axfs::fops;
 use axio as io;
+use core::time::Duration;
 
 use fs::{File, FileType, OpenOptions};
-use io::{prelude::*, Error, Result};
+use io::{prelude::*, Error, Result, SeekFrom};
 
 macro_rules! assert_err {
     ($expr: expr) => {
@@ -252,6 +254,396 @@ fn test_devfs_ramfs() -> Result<()> {
     Ok(())
 }
 
+fn test_symlink() -> Result<()> {
+    // symbolic links in /tmp, pointing to other filesystems
+    assert_eq!(fs::create_dir("/tmp/dir"), Ok(()));
+    assert_eq!(fs::write("/tmp/dir/test.txt", "test"), Ok(()));
+    assert_eq!(fs::symlink("dir/test.txt", "/tmp/file_link"), Ok(()));
+    assert_eq!(fs::symlink("/tmp/dir", "/tmp/dir_link"), Ok(()));
+    assert_eq!(fs::symlink("/dev/../tmp/loop2", "/tmp/loop1"), Ok(()));
+    assert_eq!(fs::symlink("loop1", "/tmp/loop2"), Ok(()));
+    assert_eq!(fs::symlink("../very/long/path", "tmp/very_link"), Ok(()));
+    assert_err!(fs::symlink("dir", "/tmp/dir_link"), AlreadyExists);
+
+    assert_eq!(fs::read_link("/tmp/file_link"), Ok("dir/test.txt".into()));
+    assert_eq!(
+        fs::read_link("/tmp/dir_link/../loop1"),
+        Ok("/dev/../tmp/loop2".into())
+    );
+    assert_err!(fs::read_link("/tmp/dir"), InvalidInput);
+
+    assert_eq!(fs::read_to_string("/tmp/file_link"), Ok("test".into()));
+    assert_eq!(
+        fs::read_to_string("tmp/dir_link//test.txt"),
+        Ok("test".into())
+    );
+    assert_eq!(
+        fs::read_to_string("/tmp/very_link/test.txt"),
+        fs::read_to_string("/very/long/path/test.txt"),
+    );
+    assert!(fs::metadata("/tmp/dir_link")?.is_dir());
+    assert!(fs::symlink_metadata("/tmp/dir_link")?.is_symlink());
+    assert!(fs::symlink_metadata("/tmp/dir_link/")?.is_dir());
+    assert_err!(fs::metadata("/tmp/loop1"), FilesystemLoop);
+    assert_err!(fs::read("/tmp/loop2/test.txt"), FilesystemLoop);
+
+    // operations through links
+    assert_eq!(fs::write("/tmp/dir_link/new.txt", "new"), Ok(()));
+    assert_eq!(fs::read_to_string("/tmp/dir/new.txt"), Ok("new".into()));
+    assert_eq!(fs::create_dir("/tmp/dir_link/sub"), Ok(()));
+    assert_eq!(fs::remove_dir("/tmp/dir/sub"), Ok(()));
+    assert_err!(fs::remove_dir("/tmp/dir_link"), NotADirectory);
+    assert_eq!(fs::remove_file("/tmp/dir_link/new.txt"), Ok(()));
+
+    // hard links
+    assert_eq!(fs::hard_link("/tmp/dir/test.txt", "/tmp/hard.txt"), Ok(()));
+    assert_err!(fs::hard_link("/tmp/dir", "/tmp/hard_dir"), PermissionDenied);
+    assert_err!(
+        fs::hard_link("/tmp/dir/test.txt", "/tmp/file_link"),
+        AlreadyExists
+    );
+    assert_eq!(fs::write("/tmp/hard.txt", "changed"), Ok(()));
+    assert_eq!(fs::read_to_string("/tmp/file_link"), Ok("changed".into()));
+    assert_eq!(fs::remove_file("/tmp/dir/test.txt"), Ok(()));
+    assert_eq!(fs::read_to_string("/tmp/hard.txt"), Ok("changed".into()));
+    assert_err!(fs::read("/tmp/file_link"), NotFound);
+
+    // removing the links does not remove the targets
+    for name in [
+        "file_link",
+        "dir_link",
+        "loop1",
+        "loop2",
+        "very_link",
+        "hard.txt",
+    ] {
+        assert_eq!(fs::remove_file(&format!("/tmp/{}", name)), Ok(()));
+    }
+    assert_eq!(fs::remove_dir("/tmp/dir"), Ok(()));
+    assert_eq!(fs::read_dir("tmp").unwrap().count(), 0);
+    assert!(fs::metadata("/very/long/path/test.txt")?.is_file());
+
+    println!("test_symlink() OK!");
+    Ok(())
+}
+
+fn test_page_cache() -> Result<()> {
+    const PAGE_SIZE: usize = 4096;
+    let fname = "/cache.bin";
+    let data = (0..PAGE_SIZE * 3 + 100)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    fs::write(fname, &data)?;
+
+    // read twice, the second read hits the cache
+    let mut file = File::open(fname)?;
+    let mut buf = Vec::new();
+    assert_eq!(file.read_to_end(&mut buf)?, data.len());
+    assert_eq!(buf, data);
+    let stats = fops::page_cache_stats();
+    file.seek(SeekFrom::Start(0))?;
+    buf.clear();
+    assert_eq!(file.read_to_end(&mut buf)?, data.len());
+    assert_eq!(buf, data);
+    assert!(fops::page_cache_stats().hits >= stats.hits + 3);
+
+    // writes and truncations through another opened file are visible
+    let mut file2 = OpenOptions::new().write(true).open(fname)?;
+    file2.seek(SeekFrom::Start(PAGE_SIZE as u64 + 10))?;
+    file2.write_all(b"hello")?;
+    let mut buf = [0; 5];
+    file.seek(SeekFrom::Start(PAGE_SIZE as u64 + 10))?;
+    file.read_exact(&mut buf)?;
+    assert_eq!(&buf, b"hello");
+    file2.set_len(PAGE_SIZE as u64 + 12)?;
+    file.seek(SeekFrom::Start(PAGE_SIZE as u64))?;
+    let mut buf = Vec::new();
+    assert_eq!(file.read_to_end(&mut buf)?, 12);
+    assert_eq!(&buf[10..], b"he");
+    file2.flush()?;
+    drop(file2);
+    drop(file);
+
+    assert_eq!(fops::sync(), Ok(()));
+    assert_eq!(fs::remove_file(fname), Ok(()));
+
+    println!("test_page_cache() OK!");
+    Ok(())
+}
+
+fn test_mount() -> Result<()> {
+    use fs::MountFlags;
+
+    // mount a ramfs inside /tmp, and another one inside it
+    assert_eq!(fs::create_dir("/tmp/mnt"), Ok(()));
+    assert_eq!(fs::write("/tmp/mnt/hidden.txt", "hidden"), Ok(()));
+    assert_eq!(
+        fs::mount("none", "/tmp/mnt", "tmpfs", MountFlags::empty()),
+        Ok(())
+    );
+    assert_err!(fs::metadata("/tmp/mnt/hidden.txt"), NotFound);
+    assert_eq!(fs::create_dir("/tmp/mnt/inner"), Ok(()));
+    assert_eq!(
+        fs::mount("none", "/tmp/mnt/inner", "ramfs", MountFlags::empty()),
+        Ok(())
+    );
+    assert_eq!(fs::write("/tmp/mnt/inner/test.txt", "inner"), Ok(()));
+
+    assert_err!(
+        fs::mount("none", "/tmp/mnt", "tmpfs", MountFlags::empty()),
+        ResourceBusy
+    );
+    assert_err!(
+        fs::mount("none", "/very", "unknownfs", MountFlags::empty()),
+        NotFound
+    );
+    assert_err!(
+        fs::mount("/dev/blk100", "/very", "ext2", MountFlags::empty()),
+        NotFound
+    );
+    assert_err!(
+        fs::mount("none", "/dev/null", "tmpfs", MountFlags::empty()),
+        NotADirectory
+    );
+
+    // nested mount points are resolved by the longest path, across `..`
+    assert_eq!(
+        fs::read_to_string("/tmp/mnt/inner/../inner/./test.txt"),
+        Ok("inner".into())
+    );
+    fs::set_current_dir("/tmp/mnt")?;
+    assert_eq!(fs::read_to_string("inner/test.txt"), Ok("inner".into()));
+    assert_eq!(
+        fs::read_to_string("../../very/long/path/test.txt"),
+        fs::read_to_string("/very/long/path/test.txt"),
+    );
+    assert_err!(fs::umount("/tmp/mnt"), ResourceBusy);
+    fs::set_current_dir("/")?;
+    assert_err!(fs::remove_dir("/tmp/mnt/inner"), PermissionDenied);
+
+    assert_err!(fs::umount("/tmp/mnt"), ResourceBusy);
+    assert_err!(fs::umount("/very"), InvalidInput);
+    assert_err!(fs::umount("/"), ResourceBusy);
+    assert_eq!(fs::umount("/tmp/mnt/inner"), Ok(()));
+    assert_err!(fs::metadata("/tmp/mnt/inner/test.txt"), NotFound);
+    assert_eq!(fs::umount("tmp//mnt/"), Ok(()));
+    assert_eq!(
+        fs::read_to_string("/tmp/mnt/hidden.txt"),
+        Ok("hidden".into())
+    );
+    assert_eq!(fs::remove_file("/tmp/mnt/hidden.txt"), Ok(()));
+    assert_eq!(fs::remove_dir("/tmp/mnt"), Ok(()));
+
+    println!("test_mount() OK!");
+    Ok(())
+}
+
+fn test_procfs() -> Result<()> {
+    // the contents are generated on each read, though the sizes are 0
+    assert_eq!(fs::metadata("/proc/meminfo")?.len(), 0);
+    assert!(fs::read_to_string("/proc/meminfo")?.starts_with("MemTotal:"));
+    assert!(fs::read_to_string("/proc/cpuinfo")?.starts_with("processor"));
+    assert_eq!(
+        fs::read_to_string("/proc/sys/net/core/somaxconn"),
+        Ok("4096\n".into())
+    );
+    assert_err!(fs::write("/proc/meminfo", "0"), PermissionDenied);
+
+    let mounts = fs::read_to_string("/proc/mounts")?;
+    assert!(mounts.contains(" /tmp tmpfs rw 0 0\n"));
+    assert!(!mounts.contains("/tmp/mnt"));
+    assert_eq!(fs::create_dir("/tmp/mnt2"), Ok(()));
+    fs::mount("none", "/tmp/mnt2", "tmpfs", fs::MountFlags::empty())?;
+    let mounts = fs::read_to_string("/proc/mounts")?;
+    assert!(mounts.contains("none /tmp/mnt2 tmpfs rw 0 0\n"));
+    fs::umount("/tmp/mnt2")?;
+    assert_eq!(fs::remove_dir("/tmp/mnt2"), Ok(()));
+
+    println!("test_procfs() OK!");
+    Ok(())
+}
+
+fn test_sysfs() -> Result<()> {
+    use axdriver::model::{self, DeviceAttr};
+    use axdriver::prelude::DevError;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static VALUE: AtomicUsize = AtomicUsize::new(1);
+
+    let size = fs::read_to_string("/sys/block/blk0/size")?;
+    assert!(size.trim().parse::<u64>().is_ok());
+
+    // devices registered to the driver model appear at once
+    assert_err!(fs::metadata("/sys/class/test"), NotFound);
+    model::register_device(
+        "class/test/dev0",
+        vec![
+            DeviceAttr::new_writable(
+                "value",
+                || format!("{}\n", VALUE.load(Ordering::Relaxed)),
+                |value| {
+                    let value = value.trim().parse().map_err(|_| DevError::InvalidParam)?;
+                    VALUE.store(value, Ordering::Relaxed);
+                    Ok(())
+                },
+            ),
+            DeviceAttr::new("statistics/count", || "42\n".into()),
+        ],
+    );
+    assert!(fs::metadata("/sys/class/test/dev0")?.is_dir());
+    assert_eq!(
+        fs::read_to_string("/sys/class/test/dev0/statistics/count"),
+        Ok("42\n".into())
+    );
+    let names = fs::read_dir("/sys/class/test/dev0")?
+        .map(|e| e.map(|e| e.file_name()))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(names, ["statistics", "value"]);
+
+    // writable attributes call the setters
+    assert_eq!(fs::write("/sys/class/test/dev0/value", "7\n"), Ok(()));
+    assert_eq!(VALUE.load(Ordering::Relaxed), 7);
+    assert_err!(fs::write("/sys/class/test/dev0/value", "x"), InvalidInput);
+    assert_eq!(
+        fs::read_to_string("/sys/class/test/dev0/value"),
+        Ok("7\n".into())
+    );
+    assert_err!(
+        fs::write("/sys/class/test/dev0/statistics/count", "0"),
+        PermissionDenied
+    );
+
+    assert!(model::unregister_device("class/test/dev0").is_ok());
+    assert_err!(fs::metadata("/sys/class/test"), NotFound);
+
+    println!("test_sysfs() OK!");
+    Ok(())
+}
+
+fn test_devices() -> Result<()> {
+    // /dev/full is always full
+    assert_err!(fs::write("/dev/full", "test"), StorageFull);
+    let mut buf = [1; 16];
+    File::open("/dev/full")?.read_exact(&mut buf)?;
+    assert_eq!(buf, [0; 16]);
+
+    // /dev/urandom never blocks
+    let mut file = File::open("/dev/urandom")?;
+    let (mut buf1, mut buf2) = ([0; 100], [0; 100]);
+    assert_eq!(file.read(&mut buf1)?, 100);
+    assert_eq!(file.read(&mut buf2)?, 100);
+    assert_ne!(buf1, buf2);
+    assert!(fs::metadata("/dev/random")?.file_type().is_char_device());
+    assert!(fs::metadata("/dev/console")?.file_type().is_char_device());
+
+    // the raw block device can be read while it is mounted
+    let metadata = fs::metadata("/dev/blk0")?;
+    assert!(metadata.file_type().is_block_device());
+    let sectors: u64 = fs::read_to_string("/sys/block/blk0/size")?
+        .trim()
+        .parse()
+        .unwrap();
+    assert_eq!(metadata.len(), sectors * 512);
+    let mut file = File::open("/dev/blk0")?;
+    let tail = metadata.len().min(10);
+    file.seek(SeekFrom::Start(metadata.len() - tail))?;
+    let mut buf = [0; 1000];
+    assert_eq!(file.read(&mut buf)?, tail as usize);
+    assert_eq!(file.read(&mut buf)?, 0);
+
+    println!("test_devices() OK!");
+    Ok(())
+}
+
+fn test_attrs() -> Result<()> {
+    let fname = "./attr.txt";
+    println!("test attributes {:?}:", fname);
+    fs::write(fname, "test")?;
+
+    // modification time
+    let time = Duration::from_secs(1_000_000);
+    File::options()
+        .write(true)
+        .open(fname)?
+        .set_modified(time)?;
+    assert_eq!(fs::metadata(fname)?.modified().as_secs(), time.as_secs());
+
+    // permission bits are checked on open
+    match fs::set_permissions(fname, fs::Permissions::from_bits_truncate(0o444)) {
+        Ok(()) => {
+            assert_eq!(fs::metadata(fname)?.permissions().bits(), 0o444);
+            assert_eq!(fs::read_to_string(fname)?, "test");
+            assert_err!(File::options().write(true).open(fname), PermissionDenied);
+            fs::set_permissions(fname, fs::Permissions::from_bits_truncate(0o644))?;
+            assert!(File::options().write(true).open(fname).is_ok());
+
+            // the mode of a new file
+            let fname2 = "./attr2.txt";
+            let mut file = File::options()
+                .write(true)
+                .create_new(true)
+                .mode(0o400)
+                .open(fname2)?;
+            file.write_all(b"test")?; // the creating open is writable
+            drop(file);
+            assert_eq!(fs::metadata(fname2)?.permissions().bits(), 0o400);
+            assert_err!(File::options().write(true).open(fname2), PermissionDenied);
+            assert_err!(fs::remove_file(fname2), PermissionDenied);
+            fs::set_permissions(fname2, fs::Permissions::from_bits_truncate(0o600))?;
+            fs::remove_file(fname2)?;
+        }
+        Err(Error::Unsupported) => println!("permission bits are not supported"),
+        Err(e) => return Err(e),
+    }
+
+    fs::remove_file(fname)?;
+    println!("test_attrs() OK!");
+    Ok(())
+}
+
+fn test_statfs() -> Result<()> {
+    println!("test statfs:");
+    const DEVFS_MAGIC: u64 = 0x1373;
+    const RAMFS_MAGIC: u64 = 0x8584_58f6;
+
+    assert_eq!(fs::statfs("/dev")?.fs_type, DEVFS_MAGIC);
+    assert_eq!(fs::statfs("/dev/null")?.fs_type, DEVFS_MAGIC);
+    assert_eq!(fs::statfs("/tmp/..//tmp")?.fs_type, RAMFS_MAGIC);
+    assert_err!(fs::statfs("/dev/none"), NotFound);
+
+    // from an opened file or directory
+    let opts = {
+        let mut opts = fops::OpenOptions::new();
+        opts.read(true);
+        opts
+    };
+    let dir = fops::Directory::open_dir("/dev", &opts)?;
+    assert_eq!(dir.statfs()?.fs_type, DEVFS_MAGIC);
+    let file = dir.open_file_at("/tmp/../dev/zero", &opts)?;
+    assert_eq!(file.statfs()?.fs_type, DEVFS_MAGIC);
+
+    // the free space of the root filesystem is updated by writes
+    let root = fs::statfs("/")?;
+    if root.blocks > 0 {
+        assert!(root.block_size > 0 && root.name_max > 0);
+        assert!(root.blocks_available <= root.blocks_free);
+        assert!(root.blocks_free <= root.blocks);
+        let fname = "/statfs.bin";
+        let mut file = File::create(fname)?;
+        file.write_all(&[0xa5; 64 * 1024])?;
+        file.flush()?;
+        let info = fops::File::open(fname, &opts)?.statfs()?;
+        assert_eq!(info.fs_type, root.fs_type);
+        assert!(info.blocks_free < root.blocks_free);
+        drop(file);
+        fs::remove_file(fname)?;
+        assert_eq!(fs::statfs("/")?.blocks_free, root.blocks_free);
+    }
+
+    println!("test_statfs() OK!");
+    Ok(())
+}
+
 pub fn test_all() {
     test_read_write_file().expect("test_read_write_file() failed");
     test_read_dir().expect("test_read_dir() failed");
@@ -259,4 +651,12 @@ pub fn test_all() {
     test_create_file_dir().expect("test_create_file_dir() failed");
     test_remove_file_dir().expect("test_remove_file_dir() failed");
     test_devfs_ramfs().expect("test_devfs_ramfs() failed");
+    test_symlink().expect("test_symlink() failed");
+    test_page_cache().expect("test_page_cache() failed");
+    test_mount().expect("test_mount() failed");
+    test_procfs().expect("test_procfs() failed");
+    test_sysfs().expect("test_sysfs() failed");
+    test_devices().expect("test_devices() failed");
+    test_attrs().expect("test_attrs() failed");
+    test_statfs().expect("test_statfs() failed");
 }
@@ -0,0 +1,150 @@
+#![cfg(all(
+    feature = "ext2",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    )
+))]
+
+use axdriver::AxDeviceContainer;
+use axfs::api::{self as fs, MountFlags};
+use axio::Error;
+use driver_block::{partition::Guid, ramdisk::RamDisk, BlockDriverOps};
+
+const IMG_PATH: &str = "resources/ext2.img";
+const BLOCK_SIZE: usize = 512;
+
+/// The GPT partition type of the root partition of the architecture.
+const ROOT_TYPE: &str = if cfg!(target_arch = "x86_64") {
+    "4f68bce3-e8cd-4db1-96e7-fbcaf984b709"
+} else if cfg!(target_arch = "aarch64") {
+    "b921b045-1df0-41c3-af44-4c6f280d3fae"
+} else {
+    "72ec70a6-cf74-40e6-bd49-4bda08e8f224"
+};
+const DATA_TYPE: &str = "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7";
+const DATA_UUID: &str = "01234567-89ab-cdef-0123-456789abcdef";
+const ROOT_UUID: &str = "76543210-89ab-cdef-0123-456789abcdef";
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Creates a GPT disk with two partitions, which both contain the ext2 image:
+/// `data` (the first one) and `root`.
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let img = std::fs::read(path)?;
+    let img_blocks = img.len().div_ceil(BLOCK_SIZE);
+    let parts = [
+        (DATA_TYPE, DATA_UUID, "data", 2048),
+        (ROOT_TYPE, ROOT_UUID, "root", 2048 + img_blocks),
+    ];
+    let num_blocks = 2048 + img_blocks * 2 + 34;
+    let mut disk = vec![0u8; num_blocks * BLOCK_SIZE];
+
+    // protective MBR
+    let mbr = &mut disk[..BLOCK_SIZE];
+    mbr[446 + 4] = 0xee;
+    mbr[446 + 8..446 + 12].copy_from_slice(&1u32.to_le_bytes());
+    mbr[446 + 12..446 + 16].copy_from_slice(&(num_blocks as u32 - 1).to_le_bytes());
+    mbr[510..].copy_from_slice(&[0x55, 0xaa]);
+
+    // partition entries from block 2
+    let mut array = vec![0u8; 128 * 128];
+    for (i, &(ty, uuid, label, start)) in parts.iter().enumerate() {
+        let e = &mut array[i * 128..(i + 1) * 128];
+        e[..16].copy_from_slice(&ty.parse::<Guid>().unwrap().0);
+        e[16..32].copy_from_slice(&uuid.parse::<Guid>().unwrap().0);
+        e[32..40].copy_from_slice(&(start as u64).to_le_bytes());
+        e[40..48].copy_from_slice(&((start + img_blocks - 1) as u64).to_le_bytes());
+        for (j, c) in label.encode_utf16().enumerate() {
+            e[56 + j * 2..58 + j * 2].copy_from_slice(&c.to_le_bytes());
+        }
+        let offset = start * BLOCK_SIZE;
+        disk[offset..offset + img.len()].copy_from_slice(&img);
+    }
+    disk[2 * BLOCK_SIZE..2 * BLOCK_SIZE + array.len()].copy_from_slice(&array);
+
+    // GPT header at block 1
+    let header = &mut disk[BLOCK_SIZE..BLOCK_SIZE + 92];
+    header[..8].copy_from_slice(b"EFI PART");
+    header[8..12].copy_from_slice(&0x10000u32.to_le_bytes());
+    header[12..16].copy_from_slice(&92u32.to_le_bytes());
+    header[24..32].copy_from_slice(&1u64.to_le_bytes());
+    header[72..80].copy_from_slice(&2u64.to_le_bytes());
+    header[80..84].copy_from_slice(&128u32.to_le_bytes());
+    header[84..88].copy_from_slice(&128u32.to_le_bytes());
+    header[88..92].copy_from_slice(&crc32(&array).to_le_bytes());
+    let crc = crc32(header);
+    header[16..20].copy_from_slice(&crc.to_le_bytes());
+
+    Ok(RamDisk::from(&disk))
+}
+
+#[test]
+fn test_partition() {
+    println!("Testing partitions with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    let part_blocks = (disk.num_blocks() as usize - 2048 - 34) / 2;
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    // the root partition is selected by its type
+    fs::write("/root.txt", "root").unwrap();
+    let start = fs::read_to_string("/sys/block/blk0/blk0p2/start").unwrap();
+    assert_eq!(start.trim().parse::<usize>().unwrap(), 2048 + part_blocks);
+    assert_eq!(
+        fs::read_to_string("/sys/block/blk0/blk0p1/partition").unwrap(),
+        "1\n"
+    );
+    let metadata = fs::metadata("/dev/blk0p1").unwrap();
+    assert!(metadata.file_type().is_block_device());
+    assert_eq!(metadata.len(), part_blocks as u64 * BLOCK_SIZE as u64);
+
+    // mount the other partition by label
+    fs::create_dir("/mnt").unwrap();
+    fs::mount("PARTLABEL=data", "/mnt", "ext2", MountFlags::empty()).unwrap();
+    assert_eq!(fs::metadata("/mnt/root.txt").err(), Some(Error::NotFound));
+    fs::write("/mnt/data.txt", "data").unwrap();
+
+    // a disk cannot be opened twice, nor with its partitions
+    fs::create_dir("/mnt2").unwrap();
+    for source in [
+        "/dev/blk0",
+        "/dev/blk0p1",
+        "PARTUUID=76543210-89AB-CDEF-0123-456789ABCDEF",
+    ] {
+        assert_eq!(
+            fs::mount(source, "/mnt2", "ext2", MountFlags::empty()).err(),
+            Some(Error::ResourceBusy)
+        );
+    }
+    assert_eq!(
+        fs::mount("PARTLABEL=none", "/mnt2", "ext2", MountFlags::empty()).err(),
+        Some(Error::NotFound)
+    );
+
+    // mount it again by the partition GUID
+    fs::umount("/mnt").unwrap();
+    let source = format!("PARTUUID={}", DATA_UUID);
+    fs::mount(&source, "/mnt2", "ext2", MountFlags::empty()).unwrap();
+    assert_eq!(fs::read_to_string("/mnt2/data.txt").unwrap(), "data");
+    fs::umount("/mnt2").unwrap();
+
+    println!("test_partition() OK!");
+}
@@ -5,7 +5,33 @@ use tock_registers::interfaces::Readable;
 
 use super::TrapFrame;
 
-global_asm!(include_str!("trap.S"));
+/// Whether to switch to the exception stack on the data aborts in the task
+/// stack region, which must be at the top of the address space.
+const CHECK_STACK_OVERFLOW: bool = axconfig::TASK_STACK_REGION_SIZE != 0;
+
+const _: () = assert!(
+    !CHECK_STACK_OVERFLOW
+        || (axconfig::TASK_STACK_REGION_SIZE.is_power_of_two()
+            && axconfig::TASK_STACK_REGION_BASE.wrapping_add(axconfig::TASK_STACK_REGION_SIZE)
+                == 0)
+);
+
+/// The top of the exception stack of the current CPU, used in `trap.S`.
+#[percpu::def_percpu]
+static EXCEPTION_STACK_TOP: usize = 0;
+
+global_asm!(
+    include_str!("trap.S"),
+    check_stack_overflow = const CHECK_STACK_OVERFLOW as usize,
+    stack_region_shift = const axconfig::TASK_STACK_REGION_SIZE.trailing_zeros(),
+    exception_stack_top = sym __PERCPU_EXCEPTION_STACK_TOP,
+);
+
+/// Sets the exception stack of the current CPU.
+pub(crate) fn init_exception_stack(cpu_id: usize) {
+    let stack_top = crate::trap::exception_stack_top(cpu_id).as_usize();
+    unsafe { EXCEPTION_STACK_TOP.write_current_raw(stack_top) };
+}
 
 #[repr(u8)]
 #[derive(Debug)]
@@ -50,6 +76,9 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
         Some(ESR_EL1::EC::Value::DataAbortLowerEL)
         | Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => {
             let iss = esr.read(ESR_EL1::ISS);
+            if crate::trap::handle_page_fault((FAR_EL1.get() as usize).into(), true) {
+                return;
+            }
             warn!(
                 "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}",
                 tf.elr,
@@ -60,6 +89,9 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
         Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
         | Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
             let iss = esr.read(ESR_EL1::ISS);
+            if crate::trap::handle_page_fault((FAR_EL1.get() as usize).into(), false) {
+                return;
+            }
             panic!(
                 "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
                 tf.elr,
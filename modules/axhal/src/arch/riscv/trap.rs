@@ -1,23 +1,65 @@
 use riscv::register::scause::{self, Exception as E, Trap};
+use riscv::register::stval;
 
 use super::TrapFrame;
 
 include_asm_marcos!();
 
+/// Whether to switch to the exception stack on the page faults in the task
+/// stack region, which must be at the top of the address space.
+const CHECK_STACK_OVERFLOW: bool =
+    cfg!(target_arch = "riscv64") && axconfig::TASK_STACK_REGION_SIZE != 0;
+
+const _: () = assert!(
+    !CHECK_STACK_OVERFLOW
+        || (axconfig::TASK_STACK_REGION_SIZE.is_power_of_two()
+            && axconfig::TASK_STACK_REGION_BASE.wrapping_add(axconfig::TASK_STACK_REGION_SIZE)
+                == 0)
+);
+
+/// The top of the exception stack of the current CPU, used in `trap.S`.
+#[percpu::def_percpu]
+static EXCEPTION_STACK_TOP: usize = 0;
+
 core::arch::global_asm!(
     include_str!("trap.S"),
     trapframe_size = const core::mem::size_of::<TrapFrame>(),
+    check_stack_overflow = const CHECK_STACK_OVERFLOW as usize,
+    stack_region_shift = const axconfig::TASK_STACK_REGION_SIZE.trailing_zeros(),
+    exception_stack_top = sym __PERCPU_EXCEPTION_STACK_TOP,
 );
 
+/// Sets the exception stack of the current CPU.
+pub(crate) fn init_exception_stack(cpu_id: usize) {
+    let stack_top = crate::trap::exception_stack_top(cpu_id).as_usize();
+    unsafe { EXCEPTION_STACK_TOP.write_current_raw(stack_top) };
+}
+
 fn handle_breakpoint(sepc: &mut usize) {
     debug!("Exception(Breakpoint) @ {:#x} ", sepc);
     *sepc += 2
 }
 
+fn handle_page_fault(tf: &TrapFrame, from_user: bool) {
+    let vaddr = stval::read();
+    if !crate::trap::handle_page_fault(vaddr.into(), from_user) {
+        panic!(
+            "Unhandled {} Page Fault @ {:#x}, fault_vaddr={:#x}:\n{:#x?}",
+            if from_user { "User" } else { "Supervisor" },
+            tf.sepc,
+            vaddr,
+            tf
+        );
+    }
+}
+
 #[no_mangle]
-fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
+fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
     let scause = scause::read();
     match scause.cause() {
+        Trap::Exception(E::LoadPageFault)
+        | Trap::Exception(E::StorePageFault)
+        | Trap::Exception(E::InstructionPageFault) => handle_page_fault(tf, from_user),
         Trap::Exception(E::Breakpoint) => handle_breakpoint(&mut tf.sepc),
         Trap::Interrupt(_) => crate::trap::handle_irq_extern(scause.bits()),
         _ => {
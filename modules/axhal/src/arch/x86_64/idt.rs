@@ -6,6 +6,10 @@ use x86_64::structures::DescriptorTablePointer;
 
 const NUM_INT: usize = 256;
 
+/// The index in the interrupt stack table of the stack that the double fault
+/// handler runs on, so that the overflows of task stacks can be reported.
+pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
 /// A wrapper of the Interrupt Descriptor Table (IDT).
 #[repr(transparent)]
 pub struct IdtStruct {
@@ -32,7 +36,10 @@ impl IdtStruct {
             )
         };
         for i in 0..NUM_INT {
-            entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            let opts = entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            if i == x86::irq::DOUBLE_FAULT_VECTOR as usize {
+                unsafe { opts.set_stack_index(DOUBLE_FAULT_IST_INDEX) };
+            }
         }
         idt
     }
@@ -11,6 +11,10 @@ const IRQ_VECTOR_END: u8 = 0xff;
 fn x86_trap_handler(tf: &TrapFrame) {
     match tf.vector as u8 {
         PAGE_FAULT_VECTOR => {
+            let vaddr = unsafe { cr2() }.into();
+            if crate::trap::handle_page_fault(vaddr, tf.is_user()) {
+                return;
+            }
             if tf.is_user() {
                 warn!(
                     "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}",
@@ -28,6 +32,17 @@ fn x86_trap_handler(tf: &TrapFrame) {
                 );
             }
         }
+        DOUBLE_FAULT_VECTOR => {
+            // It runs on a separate stack, so the page faults caused by stack
+            // overflows, which become double faults, can still be handled.
+            crate::trap::handle_page_fault(unsafe { cr2() }.into(), tf.is_user());
+            panic!(
+                "#DF @ {:#x}, fault_vaddr={:#x}:\n{:#x?}",
+                tf.rip,
+                unsafe { cr2() },
+                tf
+            );
+        }
         BREAKPOINT_VECTOR => debug!("#BP @ {:#x} ", tf.rip),
         GENERAL_PROTECTION_FAULT_VECTOR => {
             panic!(
@@ -81,6 +81,8 @@ pub(crate) fn init_primary(cpu_id: usize) {
         CPU_ID.write_current_raw(cpu_id);
         IS_BSP.write_current_raw(true);
     }
+    #[cfg(not(target_arch = "x86_64"))]
+    crate::arch::init_exception_stack(cpu_id);
 }
 
 #[allow(dead_code)]
@@ -90,4 +92,6 @@ pub(crate) fn init_secondary(cpu_id: usize) {
         CPU_ID.write_current_raw(cpu_id);
         IS_BSP.write_current_raw(false);
     }
+    #[cfg(not(target_arch = "x86_64"))]
+    crate::arch::init_exception_stack(cpu_id);
 }
@@ -1,8 +1,10 @@
 //! Interrupt management.
 
+use core::sync::atomic::{AtomicU64, Ordering};
+
 use handler_table::HandlerTable;
 
-use crate::platform::irq::MAX_IRQ_COUNT;
+use crate::platform::irq::{MAX_IRQ_COUNT, TIMER_IRQ_NUM};
 
 pub use crate::platform::irq::{dispatch_irq, register_handler, set_enable};
 
@@ -11,10 +13,46 @@ pub type IrqHandler = handler_table::Handler;
 
 static IRQ_HANDLER_TABLE: HandlerTable<MAX_IRQ_COUNT> = HandlerTable::new();
 
+/// The number of times each IRQ has been handled. The last one is for the
+/// timer IRQ if its number is out of range (e.g., on RISC-V).
+static IRQ_COUNTS: [AtomicU64; MAX_IRQ_COUNT + 1] =
+    [const { AtomicU64::new(0) }; MAX_IRQ_COUNT + 1];
+
+/// Whether the timer IRQ number is out of range and counted in the last slot.
+const TIMER_IRQ_OUT_OF_RANGE: bool = !matches!(TIMER_IRQ_NUM, 0..MAX_IRQ_COUNT);
+
+fn irq_count_slot(irq_num: usize) -> Option<&'static AtomicU64> {
+    if TIMER_IRQ_OUT_OF_RANGE && irq_num == TIMER_IRQ_NUM {
+        IRQ_COUNTS.last()
+    } else {
+        IRQ_COUNTS[..MAX_IRQ_COUNT].get(irq_num)
+    }
+}
+
+/// Counts an IRQ that is being handled.
+pub(crate) fn count_irq(irq_num: usize) {
+    if let Some(count) = irq_count_slot(irq_num) {
+        count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns the number of times each IRQ has been handled on all CPUs, as
+/// `(irq_num, count)` pairs of the IRQs that have been handled at least once.
+pub fn irq_counts() -> impl Iterator<Item = (usize, u64)> {
+    (0..MAX_IRQ_COUNT)
+        .chain(TIMER_IRQ_OUT_OF_RANGE.then_some(TIMER_IRQ_NUM))
+        .filter_map(|irq_num| {
+            let count = irq_count_slot(irq_num)?.load(Ordering::Relaxed);
+            (count > 0).then_some((irq_num, count))
+        })
+}
+
 /// Platform-independent IRQ dispatching.
 #[allow(dead_code)]
 pub(crate) fn dispatch_irq_common(irq_num: usize) {
     trace!("IRQ {}", irq_num);
+    count_irq(irq_num);
+    crate::random::add_entropy(crate::time::current_time_nanos() ^ irq_num as u64);
     if !IRQ_HANDLER_TABLE.handle(irq_num) {
         warn!("Unhandled IRQ {}", irq_num);
     }
@@ -1,7 +1,8 @@
 //! Description tables (per-CPU GDT, per-CPU ISS, IDT)
 
-use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment};
+use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment, DOUBLE_FAULT_IST_INDEX};
 use lazy_init::LazyInit;
+use x86_64::VirtAddr;
 
 static IDT: LazyInit<IdtStruct> = LazyInit::new();
 
@@ -16,7 +17,11 @@ fn init_percpu() {
         IDT.load();
         let tss = TSS.current_ref_mut_raw();
         let gdt = GDT.current_ref_mut_raw();
-        tss.init_by(TaskStateSegment::new());
+        let mut new_tss = TaskStateSegment::new();
+        let stack_top = crate::trap::exception_stack_top(crate::cpu::this_cpu_id());
+        new_tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] =
+            VirtAddr::new(stack_top.as_usize() as u64);
+        tss.init_by(new_tss);
         gdt.init_by(GdtStruct::new(tss));
         gdt.load();
         gdt.load_tss();
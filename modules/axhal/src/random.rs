@@ -0,0 +1,101 @@
+//! The kernel cryptographically secure pseudo-random number generator.
+//!
+//! It is a ChaCha20 keystream generator with fast key erasure: each request
+//! derives a new key for the generator and a one-time key for the output from
+//! the current key, so that the output of previous requests cannot be
+//! recovered from the state. The timestamps of interrupts and requests are
+//! mixed into the key as entropy.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use spinlock::SpinNoIrq;
+
+use crate::time::current_time_nanos;
+
+const CHACHA_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// The key of the generator.
+static KEY: SpinNoIrq<[u32; 8]> = SpinNoIrq::new([0; 8]);
+
+/// The entropy collected since the last request.
+static ENTROPY_POOL: AtomicU64 = AtomicU64::new(0);
+
+#[inline(always)]
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// Computes the ChaCha20 block of `key` at `counter`, with a zero nonce.
+fn chacha20_block(key: &[u32; 8], counter: u64) -> [u32; 16] {
+    let mut input = [0; 16];
+    input[..4].copy_from_slice(&CHACHA_CONSTANTS);
+    input[4..12].copy_from_slice(key);
+    input[12] = counter as u32;
+    input[13] = (counter >> 32) as u32;
+
+    let mut state = input;
+    for _ in 0..10 {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+    for (s, i) in state.iter_mut().zip(input) {
+        *s = s.wrapping_add(i);
+    }
+    state
+}
+
+/// Mixes `value` (e.g., the timestamp of an event) into the entropy pool.
+///
+/// It is lock-free, so it can be called in interrupt handlers.
+pub fn add_entropy(value: u64) {
+    let pool = ENTROPY_POOL.load(Ordering::Relaxed);
+    let mixed = pool.rotate_left(13) ^ value.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    ENTROPY_POOL.store(mixed, Ordering::Relaxed);
+}
+
+/// Fills `buf` with random bytes.
+pub fn fill_bytes(buf: &mut [u8]) {
+    let output_key = {
+        let mut key = KEY.lock();
+        let pool = ENTROPY_POOL.swap(0, Ordering::Relaxed);
+        let now = current_time_nanos();
+        key[0] ^= pool as u32;
+        key[1] ^= (pool >> 32) as u32;
+        key[2] ^= now as u32;
+        key[3] ^= (now >> 32) as u32;
+
+        let block = chacha20_block(&key, 0);
+        key.copy_from_slice(&block[..8]);
+        let mut output_key = [0; 8];
+        output_key.copy_from_slice(&block[8..]);
+        output_key
+    };
+
+    for (counter, chunk) in buf.chunks_mut(64).enumerate() {
+        let block = chacha20_block(&output_key, counter as u64);
+        let bytes = block.iter().flat_map(|word| word.to_le_bytes());
+        for (dst, src) in chunk.iter_mut().zip(bytes) {
+            *dst = src;
+        }
+    }
+}
+
+/// Returns a random `u64`.
+pub fn random_u64() -> u64 {
+    let mut buf = [0; 8];
+    fill_bytes(&mut buf);
+    u64::from_le_bytes(buf)
+}
@@ -1,6 +1,7 @@
 //! Trap handling.
 
-use crate_interface::{call_interface, def_interface};
+use crate_interface::def_interface;
+use memory_addr::VirtAddr;
 
 /// Trap handler interface.
 ///
@@ -13,11 +14,71 @@ use crate_interface::{call_interface, def_interface};
 pub trait TrapHandler {
     /// Handles interrupt requests for the given IRQ number.
     fn handle_irq(irq_num: usize);
-    // more e.g.: handle_page_fault();
+    /// Handles page faults at the given virtual address.
+    ///
+    /// Returns `true` if the page fault is handled, otherwise the fault is
+    /// reported as an unhandled exception.
+    fn handle_page_fault(vaddr: VirtAddr, is_user: bool) -> bool;
+}
+
+/// Size of the per-CPU stack to handle the page faults that are caused by
+/// stack overflows, on which the stack of the current task is unusable.
+#[cfg(any(
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", platform_family = "x86-pc")
+))]
+const EXCEPTION_STACK_SIZE: usize = 0x4000; // 16K
+
+#[cfg(any(
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", platform_family = "x86-pc")
+))]
+#[repr(align(16))]
+struct ExceptionStack([u8; EXCEPTION_STACK_SIZE]);
+
+#[cfg(any(
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", platform_family = "x86-pc")
+))]
+static mut EXCEPTION_STACKS: [ExceptionStack; axconfig::SMP] =
+    [const { ExceptionStack([0; EXCEPTION_STACK_SIZE]) }; axconfig::SMP];
+
+/// Returns the top address of the exception stack of the given CPU.
+#[cfg(any(
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", platform_family = "x86-pc")
+))]
+pub(crate) fn exception_stack_top(cpu_id: usize) -> VirtAddr {
+    let stack = unsafe { core::ptr::addr_of!(EXCEPTION_STACKS[cpu_id]) };
+    VirtAddr::from(stack as usize + EXCEPTION_STACK_SIZE)
 }
 
 /// Call the external IRQ handler.
-#[allow(dead_code)]
+#[cfg(any(
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", target_os = "none")
+))]
 pub(crate) fn handle_irq_extern(irq_num: usize) {
-    call_interface!(TrapHandler::handle_irq, irq_num);
+    crate_interface::call_interface!(TrapHandler::handle_irq, irq_num);
+}
+
+/// Call the external page fault handler.
+#[cfg(any(
+    target_arch = "riscv32",
+    target_arch = "riscv64",
+    target_arch = "aarch64",
+    all(target_arch = "x86_64", target_os = "none")
+))]
+pub(crate) fn handle_page_fault(vaddr: VirtAddr, is_user: bool) -> bool {
+    crate_interface::call_interface!(TrapHandler::handle_page_fault, vaddr, is_user)
 }
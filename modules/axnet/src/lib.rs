@@ -4,16 +4,26 @@
 //! using various underlying network stacks. Currently, only [smoltcp] is
 //! supported.
 //!
+//! Both IPv4 and IPv6 are supported. Each interface has an IPv6 link-local
+//! address, and its global IPv6 addresses and default router are configured
+//! by SLAAC from the router advertisements.
+//!
 //! # Organization
 //!
 //! - [`TcpSocket`]: A TCP socket that provides POSIX-like APIs.
 //! - [`UdpSocket`]: A UDP socket that provides POSIX-like APIs.
 //! - [`dns_query`]: Function for DNS query.
+//! - [`add_ip_addr`], [`add_route`], [`remove_route`]: Functions to configure
+//!   the addresses of the interfaces (`eth0`, `eth1`, ...) and the routing
+//!   table.
+//! - [`dhcp_lease`]: Function to query the lease of an interface configured by
+//!   DHCP.
 //!
 //! # Cargo Features
 //!
 //! - `smoltcp`: Use [smoltcp] as the underlying network stack. This is enabled
 //!   by default.
+//! - `multitask`: Renew the DHCP leases in a background task.
 //!
 //! [smoltcp]: https://github.com/smoltcp-rs/smoltcp
 
@@ -34,16 +44,25 @@ cfg_if::cfg_if! {
 
 pub use self::net_impl::TcpSocket;
 pub use self::net_impl::UdpSocket;
+pub use self::net_impl::{add_ip_addr, add_route, remove_ip_addr, remove_route};
 pub use self::net_impl::{bench_receive, bench_transmit};
+pub use self::net_impl::{dhcp_lease, DhcpLease};
 pub use self::net_impl::{dns_query, poll_interfaces};
 
+use alloc::vec::Vec;
 use axdriver::{prelude::*, AxDeviceContainer};
 
 /// Initializes the network subsystem by NIC devices.
 pub fn init_network(mut net_devs: AxDeviceContainer<AxNetDevice>) {
     info!("Initialize network subsystem...");
 
-    let dev = net_devs.take_one().expect("No NIC device found!");
-    info!("  use NIC 0: {:?}", dev.device_name());
-    net_impl::init(dev);
+    let mut devs = Vec::new();
+    while let Some(dev) = net_devs.take_one() {
+        info!("  use NIC {}: {:?}", devs.len(), dev.device_name());
+        devs.push(dev);
+    }
+    if devs.is_empty() {
+        warn!("No NIC device found, only the loopback interface is available");
+    }
+    net_impl::init(devs);
 }
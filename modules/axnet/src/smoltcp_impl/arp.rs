@@ -0,0 +1,417 @@
+use alloc::{collections::BTreeMap, collections::VecDeque, vec::Vec};
+use core::sync::atomic::Ordering;
+
+use axerrno::{ax_err, AxResult};
+use driver_net::{DevError, NetBufPtr};
+use smoltcp::time::{Duration, Instant};
+use smoltcp::wire::{ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame};
+use smoltcp::wire::{EthernetProtocol, EthernetRepr, IpAddress, IpCidr, Ipv4Address};
+
+use super::ndisc::multicast_ether_addr;
+use super::InterfaceWrapper;
+
+/// How long a resolved address is valid.
+const ENTRY_LIFETIME: Duration = Duration::from_secs(60);
+/// The minimum interval between two requests for the same address.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_ENTRIES: usize = 256;
+/// The maximum number of packets waiting for address resolution.
+const MAX_PENDING: usize = 16;
+
+/// The neighbor cache of an interface, which is filled by ARP for IPv4 and by
+/// NDP for IPv6, with the packets waiting for the addresses to be resolved.
+#[derive(Default)]
+pub struct NeighborCache {
+    entries: BTreeMap<IpAddress, (EthernetAddress, Instant)>,
+    /// The addresses being resolved, with the time of the last request.
+    requests: BTreeMap<IpAddress, Instant>,
+    pending: VecDeque<(IpAddress, Vec<u8>)>,
+}
+
+impl NeighborCache {
+    fn lookup(&self, addr: IpAddress, now: Instant) -> Option<EthernetAddress> {
+        match self.entries.get(&addr) {
+            Some(&(hw_addr, expires_at)) if now < expires_at => Some(hw_addr),
+            _ => None,
+        }
+    }
+
+    /// Records the hardware address of `addr`, and returns the packets which
+    /// are waiting for it.
+    fn fill(&mut self, addr: IpAddress, hw_addr: EthernetAddress, now: Instant) -> Vec<Vec<u8>> {
+        if self.entries.len() >= MAX_ENTRIES && !self.entries.contains_key(&addr) {
+            self.entries.retain(|_, (_, expires_at)| now < *expires_at);
+            if self.entries.len() >= MAX_ENTRIES {
+                self.entries.pop_first();
+            }
+        }
+        self.entries.insert(addr, (hw_addr, now + ENTRY_LIFETIME));
+        self.requests.remove(&addr);
+
+        let mut packets = Vec::new();
+        self.pending.retain(|(next_hop, packet)| {
+            if *next_hop == addr {
+                packets.push(packet.clone());
+                false
+            } else {
+                true
+            }
+        });
+        packets
+    }
+
+    /// Queues `packet` until `addr` is resolved. Returns whether a request
+    /// should be sent.
+    fn enqueue(&mut self, addr: IpAddress, packet: Vec<u8>, now: Instant) -> bool {
+        if self.pending.len() >= MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((addr, packet));
+        match self.requests.get(&addr) {
+            Some(&last) if now < last + REQUEST_INTERVAL => false,
+            _ => {
+                self.requests.insert(addr, now);
+                true
+            }
+        }
+    }
+}
+
+impl InterfaceWrapper {
+    pub fn has_ip_addr(&self, addr: IpAddress) -> bool {
+        self.ip_addrs
+            .lock()
+            .iter()
+            .any(|cidr| cidr.address() == addr)
+    }
+
+    /// Returns the address used to send packets to `dst` through `next_hop`,
+    /// which is of the same family, and in the same network as `next_hop` if
+    /// possible. Link-local addresses are only used for link-local
+    /// destinations.
+    pub fn source_addr(&self, dst: IpAddress, next_hop: IpAddress) -> Option<IpAddress> {
+        let is_link_local =
+            |addr: IpAddress| matches!(addr, IpAddress::Ipv6(a) if a.is_link_local());
+        let ip_addrs = self.ip_addrs.lock();
+        let candidates: Vec<&IpCidr> = ip_addrs
+            .iter()
+            .filter(|cidr| {
+                cidr.address().version() == dst.version()
+                    && (is_link_local(dst) || !is_link_local(cidr.address()))
+            })
+            .collect();
+        candidates
+            .iter()
+            .find(|cidr| cidr.contains_addr(&next_hop))
+            .or(candidates.first())
+            .map(|cidr| cidr.address())
+    }
+
+    pub fn add_ip_addr(&self, cidr: IpCidr) -> AxResult {
+        let mut ip_addrs = self.ip_addrs.lock();
+        if ip_addrs.iter().any(|c| c.address() == cidr.address()) {
+            return ax_err!(AlreadyExists, "address already assigned");
+        }
+        ip_addrs.push(cidr);
+        Ok(())
+    }
+
+    pub fn remove_ip_addr(&self, addr: IpAddress) -> AxResult<IpCidr> {
+        let mut ip_addrs = self.ip_addrs.lock();
+        match ip_addrs.iter().position(|c| c.address() == addr) {
+            Some(idx) => Ok(ip_addrs.remove(idx)),
+            None => ax_err!(NotFound, "address not assigned"),
+        }
+    }
+
+    /// Receives frames until an IP packet for this host arrives, and returns
+    /// the frame with the offset of the packet. ARP and NDP packets are handled
+    /// here.
+    pub fn recv_ip_packet(&self) -> Option<(NetBufPtr, usize)> {
+        loop {
+            let buf = {
+                let dev = self.dev.lock();
+                let mut nic = dev.inner.borrow_mut();
+                if let Err(e) = nic.recycle_tx_buffers() {
+                    warn!("recycle_tx_buffers failed: {:?}", e);
+                    return None;
+                }
+                match nic.receive() {
+                    Ok(buf) => {
+                        dev.stats.rx_packets.fetch_add(1, Ordering::Relaxed);
+                        dev.stats
+                            .rx_bytes
+                            .fetch_add(buf.packet_len() as u64, Ordering::Relaxed);
+                        buf
+                    }
+                    Err(DevError::Again) => return None,
+                    Err(e) => {
+                        warn!("receive failed: {:?}", e);
+                        return None;
+                    }
+                }
+            };
+            trace!("RECV {} bytes: {:02X?}", buf.packet_len(), buf.packet());
+
+            let (dst_addr, ethertype) = match EthernetFrame::new_checked(buf.packet()) {
+                Ok(frame) => (frame.dst_addr(), frame.ethertype()),
+                Err(_) => {
+                    self.recycle_rx_buffer(buf);
+                    continue;
+                }
+            };
+            let header_len = EthernetFrame::<&[u8]>::header_len();
+            if dst_addr != self.ether_addr && dst_addr.is_unicast() {
+                self.recycle_rx_buffer(buf);
+                continue;
+            }
+            match ethertype {
+                EthernetProtocol::Ipv4 => {
+                    if !self.process_dhcp(&buf.packet()[header_len..]) {
+                        return Some((buf, header_len));
+                    }
+                    self.recycle_rx_buffer(buf);
+                }
+                EthernetProtocol::Ipv6 => {
+                    if !self.process_ndisc(&buf.packet()[header_len..]) {
+                        return Some((buf, header_len));
+                    }
+                    self.recycle_rx_buffer(buf);
+                }
+                EthernetProtocol::Arp => {
+                    let repr = ArpPacket::new_checked(&buf.packet()[header_len..])
+                        .and_then(|packet| ArpRepr::parse(&packet));
+                    self.recycle_rx_buffer(buf);
+                    if let Ok(repr) = repr {
+                        self.process_arp(repr);
+                    }
+                }
+                _ => self.recycle_rx_buffer(buf),
+            }
+        }
+    }
+
+    pub fn recycle_rx_buffer(&self, buf: NetBufPtr) {
+        let dev = self.dev.lock();
+        if let Err(e) = dev.inner.borrow_mut().recycle_rx_buffer(buf) {
+            warn!("recycle_rx_buffer failed: {:?}", e);
+        }
+    }
+
+    /// Sends an IP packet to `next_hop`, which is queued if its hardware
+    /// address is not resolved yet.
+    pub fn send_ip_packet(&self, next_hop: IpAddress, packet: Vec<u8>) {
+        match self.next_hop_ether_addr(next_hop) {
+            Some(dst_addr) => self.send_frame(dst_addr, ethertype_of(next_hop), &packet),
+            None => self.queue_for_neighbor(next_hop, packet),
+        }
+    }
+
+    /// Sends an IP packet built in place in `buf`, a frame allocated by
+    /// [`Self::alloc_frame`], to `next_hop`. The packet is copied out and
+    /// queued if the hardware address of `next_hop` is not resolved yet.
+    pub fn send_ip_frame(&self, next_hop: IpAddress, buf: NetBufPtr) {
+        match self.next_hop_ether_addr(next_hop) {
+            Some(dst_addr) => self.transmit_frame(dst_addr, ethertype_of(next_hop), buf),
+            None => {
+                let header_len = EthernetFrame::<&[u8]>::header_len();
+                let packet = buf.packet()[header_len..].to_vec();
+                self.free_frame(buf);
+                self.queue_for_neighbor(next_hop, packet);
+            }
+        }
+    }
+
+    /// Returns the hardware address of `next_hop`, or `None` if it is a
+    /// neighbor not resolved yet.
+    fn next_hop_ether_addr(&self, next_hop: IpAddress) -> Option<EthernetAddress> {
+        let is_broadcast = next_hop.is_broadcast()
+            || self.ip_addrs.lock().iter().any(|cidr| match cidr {
+                IpCidr::Ipv4(cidr) => cidr.broadcast().map(IpAddress::Ipv4) == Some(next_hop),
+                IpCidr::Ipv6(_) => false,
+            });
+        match next_hop {
+            _ if is_broadcast => Some(EthernetAddress::BROADCAST),
+            IpAddress::Ipv4(addr) if addr.is_multicast() => {
+                let b = addr.0;
+                Some(EthernetAddress([0x01, 0x00, 0x5e, b[1] & 0x7f, b[2], b[3]]))
+            }
+            IpAddress::Ipv6(addr) if addr.is_multicast() => Some(multicast_ether_addr(addr)),
+            _ => self.neighbors.lock().lookup(next_hop, Self::current_time()),
+        }
+    }
+
+    /// Queues `packet` until the neighbor `next_hop` is resolved, and sends a
+    /// request for it if needed. The packet is sent at once if `next_hop` has
+    /// been resolved in the meantime.
+    fn queue_for_neighbor(&self, next_hop: IpAddress, packet: Vec<u8>) {
+        let now = Self::current_time();
+        let mut neighbors = self.neighbors.lock();
+        if let Some(hw_addr) = neighbors.lookup(next_hop, now) {
+            drop(neighbors);
+            self.send_frame(hw_addr, ethertype_of(next_hop), &packet);
+            return;
+        }
+        if neighbors.enqueue(next_hop, packet, now) {
+            drop(neighbors);
+            match next_hop {
+                IpAddress::Ipv4(addr) => {
+                    self.send_arp(ArpOperation::Request, EthernetAddress::BROADCAST, addr)
+                }
+                IpAddress::Ipv6(addr) => self.send_neighbor_solicit(addr),
+            }
+        }
+    }
+
+    /// Records the hardware address of the neighbor `addr`, and sends the
+    /// packets waiting for it. A new entry is created only if `create` is
+    /// true, otherwise only a known neighbor is updated.
+    pub fn update_neighbor(&self, addr: IpAddress, hw_addr: EthernetAddress, create: bool) {
+        let packets = {
+            let mut neighbors = self.neighbors.lock();
+            let is_known =
+                neighbors.entries.contains_key(&addr) || neighbors.requests.contains_key(&addr);
+            if create || is_known {
+                neighbors.fill(addr, hw_addr, Self::current_time())
+            } else {
+                Vec::new()
+            }
+        };
+        for packet in packets {
+            self.send_frame(hw_addr, ethertype_of(addr), &packet);
+        }
+    }
+
+    fn process_arp(&self, repr: ArpRepr) {
+        let ArpRepr::EthernetIpv4 {
+            operation,
+            source_hardware_addr,
+            source_protocol_addr,
+            target_protocol_addr,
+            ..
+        } = repr
+        else {
+            return;
+        };
+        if !source_hardware_addr.is_unicast() || source_protocol_addr.is_unspecified() {
+            return;
+        }
+
+        // Learn the sender if we are the target or already know it (RFC 826).
+        let is_target = self.has_ip_addr(target_protocol_addr.into());
+        self.update_neighbor(source_protocol_addr.into(), source_hardware_addr, is_target);
+
+        if is_target && operation == ArpOperation::Request {
+            self.send_arp(
+                ArpOperation::Reply,
+                source_hardware_addr,
+                source_protocol_addr,
+            );
+        }
+    }
+
+    fn send_arp(&self, operation: ArpOperation, dst: EthernetAddress, target: Ipv4Address) {
+        let source = match self.source_addr(target.into(), target.into()) {
+            Some(IpAddress::Ipv4(addr)) => addr,
+            _ => Ipv4Address::UNSPECIFIED,
+        };
+        let repr = ArpRepr::EthernetIpv4 {
+            operation,
+            source_hardware_addr: self.ether_addr,
+            source_protocol_addr: source,
+            target_hardware_addr: if operation == ArpOperation::Reply {
+                dst
+            } else {
+                EthernetAddress([0; 6])
+            },
+            target_protocol_addr: target,
+        };
+        let mut packet = alloc::vec![0; repr.buffer_len()];
+        repr.emit(&mut ArpPacket::new_unchecked(&mut packet[..]));
+        self.send_frame(dst, EthernetProtocol::Arp, &packet);
+    }
+
+    /// Sends `payload` in an Ethernet frame. The frame is dropped if the NIC is
+    /// busy, which is recovered by the upper protocols.
+    pub fn send_frame(
+        &self,
+        dst_addr: EthernetAddress,
+        ethertype: EthernetProtocol,
+        payload: &[u8],
+    ) {
+        if let Some(mut buf) = self.alloc_frame(payload.len()) {
+            let header_len = EthernetFrame::<&[u8]>::header_len();
+            buf.packet_mut()[header_len..].copy_from_slice(payload);
+            self.transmit_frame(dst_addr, ethertype, buf);
+        }
+    }
+
+    /// Allocates a TX buffer for a frame with a payload of `payload_len` bytes,
+    /// which is filled in place before [`Self::transmit_frame`] is called.
+    /// Returns `None` if the NIC is busy, or the payload is larger than the MTU.
+    pub fn alloc_frame(&self, payload_len: usize) -> Option<NetBufPtr> {
+        let dev = self.dev.lock();
+        if payload_len > dev.mtu {
+            debug!("{}: packet larger than MTU dropped", self.name);
+            return None;
+        }
+        let mut nic = dev.inner.borrow_mut();
+        if let Err(e) = nic.recycle_tx_buffers() {
+            warn!("recycle_tx_buffers failed: {:?}", e);
+            return None;
+        }
+        if !nic.can_transmit() {
+            debug!("{}: TX queue full, packet dropped", self.name);
+            return None;
+        }
+        match nic.alloc_tx_buffer(EthernetFrame::<&[u8]>::buffer_len(payload_len)) {
+            Ok(buf) => Some(buf),
+            Err(e) => {
+                warn!("alloc_tx_buffer failed: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Gives back a frame allocated by [`Self::alloc_frame`] without sending
+    /// it.
+    pub fn free_frame(&self, buf: NetBufPtr) {
+        let dev = self.dev.lock();
+        if let Err(e) = dev.inner.borrow_mut().dealloc_tx_buffer(buf) {
+            warn!("dealloc_tx_buffer failed: {:?}", e);
+        }
+    }
+
+    /// Fills the Ethernet header of a frame allocated by [`Self::alloc_frame`],
+    /// and sends it.
+    fn transmit_frame(
+        &self,
+        dst_addr: EthernetAddress,
+        ethertype: EthernetProtocol,
+        mut buf: NetBufPtr,
+    ) {
+        let mut frame = EthernetFrame::new_unchecked(buf.packet_mut());
+        EthernetRepr {
+            src_addr: self.ether_addr,
+            dst_addr,
+            ethertype,
+        }
+        .emit(&mut frame);
+        let len = buf.packet_len();
+        trace!("SEND {} bytes: {:02X?}", len, buf.packet());
+        let dev = self.dev.lock();
+        if let Err(e) = dev.inner.borrow_mut().transmit(buf) {
+            warn!("transmit failed: {:?}", e);
+            return;
+        }
+        dev.stats.tx_packets.fetch_add(1, Ordering::Relaxed);
+        dev.stats.tx_bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+}
+
+fn ethertype_of(addr: IpAddress) -> EthernetProtocol {
+    match addr {
+        IpAddress::Ipv4(_) => EthernetProtocol::Ipv4,
+        IpAddress::Ipv6(_) => EthernetProtocol::Ipv6,
+    }
+}
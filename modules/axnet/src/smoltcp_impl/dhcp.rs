@@ -0,0 +1,372 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::net::IpAddr;
+
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::time::{Duration, Instant};
+use smoltcp::wire::{DhcpMessageType, DhcpPacket, DhcpRepr, DHCP_CLIENT_PORT, DHCP_SERVER_PORT};
+use smoltcp::wire::{EthernetAddress, IpAddress, IpProtocol, Ipv4Address, Ipv4Cidr};
+use smoltcp::wire::{Ipv4Packet, Ipv4Repr, UdpPacket, UdpRepr};
+
+use super::addr::into_core_ipaddr;
+use super::InterfaceWrapper;
+
+const DISCOVER_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+const REQUEST_RETRIES: u32 = 5;
+const MIN_RENEW_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_LEASE_DURATION: u32 = 120;
+
+/// Subnet mask, router and DNS servers.
+const PARAMETER_REQUEST_LIST: &[u8] = &[1, 3, 6];
+
+/// The configuration of an interface obtained from a DHCP server.
+#[derive(Debug, Clone)]
+pub struct DhcpLease {
+    /// The name of the interface, e.g. `"eth0"`.
+    pub iface: String,
+    pub address: IpAddr,
+    pub prefix_len: u8,
+    /// The default gateway.
+    pub router: Option<IpAddr>,
+    pub dns_servers: Vec<IpAddr>,
+    /// The DHCP server which grants the lease.
+    pub server: IpAddr,
+    pub lease_duration: core::time::Duration,
+    /// The remaining time of the lease, which is renewed before it expires.
+    pub expires_in: core::time::Duration,
+}
+
+/// The part of a lease which is applied to the network stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpConfig {
+    pub address: Ipv4Cidr,
+    pub router: Option<Ipv4Address>,
+    pub dns_servers: Vec<Ipv4Address>,
+}
+
+enum State {
+    Discovering {
+        retry_at: Instant,
+    },
+    Requesting {
+        retry_at: Instant,
+        retry: u32,
+        server: Ipv4Address,
+        requested_ip: Ipv4Address,
+    },
+    Bound {
+        config: DhcpConfig,
+        server: Ipv4Address,
+        lease_duration: u32,
+        renew_at: Instant,
+        expires_at: Instant,
+        retry_at: Instant,
+    },
+}
+
+/// A DHCPv4 client of an interface.
+pub struct DhcpClient {
+    state: State,
+    transaction_id: u32,
+    /// The configuration which has been applied to the network stack.
+    applied: Option<DhcpConfig>,
+}
+
+impl DhcpClient {
+    pub fn new() -> Self {
+        Self {
+            state: State::Discovering {
+                retry_at: Instant::ZERO,
+            },
+            transaction_id: 0,
+            applied: None,
+        }
+    }
+
+    fn reset(&mut self, now: Instant) {
+        self.state = State::Discovering { retry_at: now };
+    }
+
+    fn process(&mut self, hw_addr: EthernetAddress, repr: &DhcpRepr, now: Instant) {
+        if repr.transaction_id != self.transaction_id || repr.client_hardware_address != hw_addr {
+            return;
+        }
+        match (&self.state, repr.message_type) {
+            (State::Discovering { .. }, DhcpMessageType::Offer) => {
+                let Some(server) = repr.server_identifier else {
+                    return;
+                };
+                if !repr.your_ip.is_unicast() {
+                    return;
+                }
+                debug!("DHCP offer {} from {}", repr.your_ip, server);
+                self.state = State::Requesting {
+                    retry_at: now,
+                    retry: 0,
+                    server,
+                    requested_ip: repr.your_ip,
+                };
+            }
+            (State::Requesting { server, .. }, DhcpMessageType::Ack)
+            | (State::Bound { server, .. }, DhcpMessageType::Ack) => {
+                let server = repr.server_identifier.unwrap_or(*server);
+                match Self::bind(repr, server, now) {
+                    Some(state) => self.state = state,
+                    None => self.reset(now),
+                }
+            }
+            (State::Requesting { .. }, DhcpMessageType::Nak)
+            | (State::Bound { .. }, DhcpMessageType::Nak) => {
+                debug!("DHCP request rejected");
+                self.reset(now);
+            }
+            _ => {}
+        }
+    }
+
+    fn bind(repr: &DhcpRepr, server: Ipv4Address, now: Instant) -> Option<State> {
+        if !repr.your_ip.is_unicast() {
+            return None;
+        }
+        let prefix_len = match repr.subnet_mask {
+            Some(mask) => {
+                let mask = u32::from_be_bytes(mask.0);
+                if mask.leading_ones() + mask.trailing_zeros() != 32 {
+                    return None;
+                }
+                mask.leading_ones() as u8
+            }
+            None => super::IP_PREFIX,
+        };
+        let dns_servers = repr
+            .dns_servers
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|addr| addr.is_unicast())
+            .collect();
+        let lease_duration = repr.lease_duration.unwrap_or(DEFAULT_LEASE_DURATION);
+        let renew_duration = repr.renew_duration.unwrap_or(lease_duration / 2);
+        Some(State::Bound {
+            config: DhcpConfig {
+                address: Ipv4Cidr::new(repr.your_ip, prefix_len),
+                router: repr.router.filter(|addr| addr.is_unicast()),
+                dns_servers,
+            },
+            server,
+            lease_duration,
+            renew_at: now + Duration::from_secs(renew_duration as u64),
+            expires_at: now + Duration::from_secs(lease_duration as u64),
+            retry_at: now,
+        })
+    }
+
+    /// Returns the message to send if a timer expires.
+    fn dispatch(&mut self, hw_addr: EthernetAddress, now: Instant) -> Option<DhcpRepr<'static>> {
+        let mut repr = DhcpRepr {
+            message_type: DhcpMessageType::Discover,
+            transaction_id: self.transaction_id,
+            secs: 0,
+            client_hardware_address: hw_addr,
+            client_ip: Ipv4Address::UNSPECIFIED,
+            your_ip: Ipv4Address::UNSPECIFIED,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            router: None,
+            subnet_mask: None,
+            relay_agent_ip: Ipv4Address::UNSPECIFIED,
+            broadcast: false,
+            requested_ip: None,
+            client_identifier: Some(hw_addr),
+            server_identifier: None,
+            parameter_request_list: Some(PARAMETER_REQUEST_LIST),
+            max_size: None,
+            lease_duration: None,
+            renew_duration: None,
+            rebind_duration: None,
+            dns_servers: None,
+            additional_options: &[],
+        };
+        match &mut self.state {
+            State::Discovering { retry_at } => {
+                if now < *retry_at {
+                    return None;
+                }
+                *retry_at = now + DISCOVER_TIMEOUT;
+                self.transaction_id = axhal::random::random_u64() as u32;
+                repr.transaction_id = self.transaction_id;
+            }
+            State::Requesting {
+                retry_at,
+                retry,
+                server,
+                requested_ip,
+            } => {
+                if now < *retry_at {
+                    return None;
+                }
+                if *retry >= REQUEST_RETRIES {
+                    self.reset(now);
+                    return None;
+                }
+                *retry += 1;
+                *retry_at = now + REQUEST_TIMEOUT;
+                repr.message_type = DhcpMessageType::Request;
+                repr.requested_ip = Some(*requested_ip);
+                repr.server_identifier = Some(*server);
+            }
+            State::Bound {
+                config,
+                renew_at,
+                expires_at,
+                retry_at,
+                ..
+            } => {
+                if now >= *expires_at {
+                    debug!("DHCP lease expired");
+                    self.reset(now);
+                    return None;
+                }
+                if now < *renew_at || now < *retry_at {
+                    return None;
+                }
+                // retry at the half of the remaining time
+                let timeout = Duration::from_micros((*expires_at - now).total_micros() / 2);
+                *retry_at = now + timeout.max(MIN_RENEW_TIMEOUT);
+                self.transaction_id = axhal::random::random_u64() as u32;
+                repr.transaction_id = self.transaction_id;
+                repr.message_type = DhcpMessageType::Request;
+                repr.client_ip = config.address.address();
+            }
+        }
+        Some(repr)
+    }
+
+    /// Returns the configurations to remove and to apply, if the lease has
+    /// changed since the last call.
+    fn take_change(&mut self) -> Option<(Option<DhcpConfig>, Option<DhcpConfig>)> {
+        let current = match &self.state {
+            State::Bound { config, .. } => Some(config),
+            _ => None,
+        };
+        if current == self.applied.as_ref() {
+            return None;
+        }
+        let current = current.cloned();
+        let old = core::mem::replace(&mut self.applied, current.clone());
+        Some((old, current))
+    }
+
+    fn lease(&self, iface: &str, now: Instant) -> Option<DhcpLease> {
+        let State::Bound {
+            config,
+            server,
+            lease_duration,
+            expires_at,
+            ..
+        } = &self.state
+        else {
+            return None;
+        };
+        let to_core = |addr: Ipv4Address| into_core_ipaddr(IpAddress::Ipv4(addr));
+        Some(DhcpLease {
+            iface: iface.into(),
+            address: to_core(config.address.address()),
+            prefix_len: config.address.prefix_len(),
+            router: config.router.map(to_core),
+            dns_servers: config.dns_servers.iter().copied().map(to_core).collect(),
+            server: to_core(*server),
+            lease_duration: core::time::Duration::from_secs(*lease_duration as u64),
+            expires_in: core::time::Duration::from_micros((*expires_at - now).total_micros()),
+        })
+    }
+}
+
+/// Builds a broadcast IP packet of the DHCP message.
+fn emit_packet(repr: &DhcpRepr, src_addr: Ipv4Address) -> Vec<u8> {
+    let udp_repr = UdpRepr {
+        src_port: DHCP_CLIENT_PORT,
+        dst_port: DHCP_SERVER_PORT,
+    };
+    let dhcp_len = repr.buffer_len();
+    let ip_repr = Ipv4Repr {
+        src_addr,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + dhcp_len,
+        hop_limit: 64,
+    };
+    let caps = ChecksumCapabilities::default();
+    let mut packet = vec![0; ip_repr.buffer_len() + ip_repr.payload_len];
+    let mut ip_packet = Ipv4Packet::new_unchecked(&mut packet[..]);
+    ip_repr.emit(&mut ip_packet, &caps);
+    udp_repr.emit(
+        &mut UdpPacket::new_unchecked(ip_packet.payload_mut()),
+        &IpAddress::Ipv4(src_addr),
+        &IpAddress::Ipv4(Ipv4Address::BROADCAST),
+        dhcp_len,
+        |buf| repr.emit(&mut DhcpPacket::new_unchecked(buf)).unwrap(),
+        &caps,
+    );
+    packet
+}
+
+impl InterfaceWrapper {
+    /// Configures the interface by DHCP.
+    pub fn enable_dhcp(&self) {
+        *self.dhcp.lock() = Some(DhcpClient::new());
+    }
+
+    /// Handles the packet if it is a DHCP reply, and returns whether it is
+    /// handled.
+    pub fn process_dhcp(&self, packet: &[u8]) -> bool {
+        let mut dhcp = self.dhcp.lock();
+        let Some(client) = dhcp.as_mut() else {
+            return false;
+        };
+        let Ok(ip_packet) = Ipv4Packet::new_checked(packet) else {
+            return false;
+        };
+        if ip_packet.next_header() != IpProtocol::Udp {
+            return false;
+        }
+        let Ok(udp_packet) = UdpPacket::new_checked(ip_packet.payload()) else {
+            return false;
+        };
+        if udp_packet.src_port() != DHCP_SERVER_PORT || udp_packet.dst_port() != DHCP_CLIENT_PORT {
+            return false;
+        }
+        if let Ok(dhcp_packet) = DhcpPacket::new_checked(udp_packet.payload()) {
+            if let Ok(repr) = DhcpRepr::parse(&dhcp_packet) {
+                client.process(self.ether_addr, &repr, Self::current_time());
+            }
+        }
+        true
+    }
+
+    /// Sends the DHCP messages due, and returns the configurations to remove
+    /// and to apply if the lease has changed.
+    pub fn poll_dhcp(&self) -> Option<(Option<DhcpConfig>, Option<DhcpConfig>)> {
+        let mut dhcp = self.dhcp.lock();
+        let client = dhcp.as_mut()?;
+        let packet = client
+            .dispatch(self.ether_addr, Self::current_time())
+            .map(|repr| emit_packet(&repr, repr.client_ip));
+        let change = client.take_change();
+        drop(dhcp);
+
+        if let Some(packet) = packet {
+            self.send_ip_packet(Ipv4Address::BROADCAST.into(), packet);
+        }
+        change
+    }
+
+    /// Whether the interface is waiting for a DHCP lease.
+    pub fn dhcp_pending(&self) -> bool {
+        matches!(self.dhcp.lock().as_ref(), Some(client) if client.applied.is_none())
+    }
+
+    pub fn dhcp_lease(&self) -> Option<DhcpLease> {
+        let dhcp = self.dhcp.lock();
+        dhcp.as_ref()?.lease(&self.name, Self::current_time())
+    }
+}
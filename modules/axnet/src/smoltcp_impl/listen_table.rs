@@ -7,19 +7,23 @@ use smoltcp::iface::{SocketHandle, SocketSet};
 use smoltcp::socket::tcp::{self, State};
 use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
-use super::{SocketSetWrapper, LISTEN_QUEUE_SIZE, SOCKET_SET};
+use super::tcp::TcpOptions;
+use super::{LISTEN_QUEUE_SIZE, SOCKET_SET};
 
 const PORT_NUM: usize = 65536;
 
 struct ListenTableEntry {
     listen_endpoint: IpListenEndpoint,
+    /// The options of the sockets created for the incoming connections.
+    options: TcpOptions,
     syn_queue: VecDeque<SocketHandle>,
 }
 
 impl ListenTableEntry {
-    pub fn new(listen_endpoint: IpListenEndpoint) -> Self {
+    pub fn new(listen_endpoint: IpListenEndpoint, options: TcpOptions) -> Self {
         Self {
             listen_endpoint,
+            options,
             syn_queue: VecDeque::with_capacity(LISTEN_QUEUE_SIZE),
         }
     }
@@ -61,12 +65,12 @@ impl ListenTable {
         self.tcp[port as usize].lock().is_none()
     }
 
-    pub fn listen(&self, listen_endpoint: IpListenEndpoint) -> AxResult {
+    pub fn listen(&self, listen_endpoint: IpListenEndpoint, options: TcpOptions) -> AxResult {
         let port = listen_endpoint.port;
         assert_ne!(port, 0);
         let mut entry = self.tcp[port as usize].lock();
         if entry.is_none() {
-            *entry = Some(Box::new(ListenTableEntry::new(listen_endpoint)));
+            *entry = Some(Box::new(ListenTableEntry::new(listen_endpoint, options)));
             Ok(())
         } else {
             ax_err!(AddrInUse, "socket listen() failed")
@@ -78,6 +82,12 @@ impl ListenTable {
         *self.tcp[port as usize].lock() = None;
     }
 
+    pub fn set_options(&self, port: u16, options: TcpOptions) {
+        if let Some(entry) = self.tcp[port as usize].lock().deref_mut() {
+            entry.options = options;
+        }
+    }
+
     pub fn can_accept(&self, port: u16) -> AxResult<bool> {
         if let Some(entry) = self.tcp[port as usize].lock().deref() {
             Ok(entry.syn_queue.iter().any(|&handle| is_connected(handle)))
@@ -126,7 +136,7 @@ impl ListenTable {
                 warn!("SYN queue overflow!");
                 return;
             }
-            let mut socket = SocketSetWrapper::new_tcp_socket();
+            let mut socket = entry.options.new_socket();
             if socket.listen(entry.listen_endpoint).is_ok() {
                 let handle = sockets.add(socket);
                 debug!(
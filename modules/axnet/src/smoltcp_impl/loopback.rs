@@ -0,0 +1,45 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use core::sync::atomic::Ordering;
+
+use smoltcp::wire::{Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr};
+
+use super::DeviceStats;
+
+/// The address of the loopback interface `lo`.
+pub const LOOPBACK_ADDR: Ipv4Cidr = Ipv4Cidr::new(Ipv4Address::new(127, 0, 0, 1), 8);
+/// The IPv6 address of `lo`.
+pub const LOOPBACK_ADDR_V6: Ipv6Cidr = Ipv6Cidr::new(Ipv6Address::LOOPBACK, 128);
+
+/// The maximum number of packets waiting to be received on `lo`.
+const MAX_QUEUE_LEN: usize = 64;
+
+/// The loopback interface `lo`, which sends the packets back to the stack
+/// without any NIC.
+#[derive(Default)]
+pub struct Loopback {
+    queue: VecDeque<Vec<u8>>,
+    pub stats: DeviceStats,
+}
+
+impl Loopback {
+    pub fn send(&mut self, packet: Vec<u8>) {
+        if self.queue.len() >= MAX_QUEUE_LEN {
+            debug!("lo: queue full, packet dropped");
+            return;
+        }
+        self.stats.tx_packets.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .tx_bytes
+            .fetch_add(packet.len() as u64, Ordering::Relaxed);
+        self.queue.push_back(packet);
+    }
+
+    pub fn recv(&mut self) -> Option<Vec<u8>> {
+        let packet = self.queue.pop_front()?;
+        self.stats.rx_packets.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .rx_bytes
+            .fetch_add(packet.len() as u64, Ordering::Relaxed);
+        Some(packet)
+    }
+}
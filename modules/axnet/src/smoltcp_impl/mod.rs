@@ -1,27 +1,44 @@
 mod addr;
+mod arp;
 mod bench;
+mod dhcp;
 mod dns;
 mod listen_table;
+mod loopback;
+mod ndisc;
+mod route;
 mod tcp;
 mod udp;
 
-use alloc::vec;
+use alloc::{format, string::String, vec, vec::Vec};
 use core::cell::RefCell;
+use core::net::IpAddr;
 use core::ops::DerefMut;
+use core::sync::atomic::{AtomicU64, Ordering};
 
+use axdriver::model::{self, DeviceAttr};
 use axdriver::prelude::*;
+use axerrno::{ax_err, ax_err_type, AxResult};
 use axhal::time::{current_time_nanos, NANOS_PER_MICROS};
 use axsync::Mutex;
 use driver_net::{DevError, NetBufPtr};
 use lazy_init::LazyInit;
 use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
 use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
-use smoltcp::socket::{self, AnySocket};
+use smoltcp::socket::{self, AnySocket, Socket};
 use smoltcp::time::Instant;
-use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr};
+use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, IpProtocol};
+use smoltcp::wire::{Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr};
 
+use self::addr::{from_core_ipaddr, into_core_ipaddr};
+use self::arp::NeighborCache;
+use self::dhcp::{DhcpClient, DhcpConfig};
 use self::listen_table::ListenTable;
+use self::loopback::{LOOPBACK_ADDR, LOOPBACK_ADDR_V6};
+use self::ndisc::{Slaac, SlaacEvent};
+use self::route::{network_of, Route, RouteTable, Router};
 
+pub use self::dhcp::DhcpLease;
 pub use self::dns::dns_query;
 pub use self::tcp::TcpSocket;
 pub use self::udp::UdpSocket;
@@ -38,9 +55,12 @@ macro_rules! env_or_default {
 const IP: &str = env_or_default!("AX_IP");
 const GATEWAY: &str = env_or_default!("AX_GW");
 const DNS_SEVER: &str = "8.8.8.8";
+const MAX_DNS_SERVERS: usize = 4;
 const IP_PREFIX: u8 = 24;
 
 const STANDARD_MTU: usize = 1500;
+const MIN_MTU: usize = 68;
+const ETHERNET_HEADER_LEN: usize = 14;
 
 const RANDOM_SEED: u64 = 0xA2CE_05A2_CE05_A2CE;
 
@@ -48,22 +68,56 @@ const TCP_RX_BUF_LEN: usize = 64 * 1024;
 const TCP_TX_BUF_LEN: usize = 64 * 1024;
 const UDP_RX_BUF_LEN: usize = 64 * 1024;
 const UDP_TX_BUF_LEN: usize = 64 * 1024;
+/// The bounds of the buffer sizes set by `SO_RCVBUF` and `SO_SNDBUF`.
+const MIN_SOCKET_BUF_LEN: usize = 2048;
+const MAX_SOCKET_BUF_LEN: usize = 4 * 1024 * 1024;
 const LISTEN_QUEUE_SIZE: usize = 512;
+const DEFAULT_HOP_LIMIT: u8 = 64;
 
 static LISTEN_TABLE: LazyInit<ListenTable> = LazyInit::new();
 static SOCKET_SET: LazyInit<SocketSetWrapper> = LazyInit::new();
-static ETH0: LazyInit<InterfaceWrapper> = LazyInit::new();
+static IFACES: LazyInit<Vec<InterfaceWrapper>> = LazyInit::new();
+static STACK: LazyInit<StackWrapper> = LazyInit::new();
+static ROUTES: Mutex<RouteTable> = Mutex::new(RouteTable::new());
+/// The DNS servers obtained by DHCP.
+static DNS_SERVERS: Mutex<Vec<IpAddress>> = Mutex::new(Vec::new());
+
+/// How long to wait for the DHCP leases at initialization.
+const DHCP_TIMEOUT: core::time::Duration = core::time::Duration::from_secs(10);
 
 struct SocketSetWrapper<'a>(Mutex<SocketSet<'a>>);
 
 struct DeviceWrapper {
     inner: RefCell<AxNetDevice>, // use `RefCell` is enough since it's wrapped in `Mutex` in `InterfaceWrapper`.
+    mtu: usize,
+    stats: DeviceStats,
+}
+
+/// The numbers of packets and bytes received and transmitted by a NIC.
+#[derive(Default)]
+struct DeviceStats {
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+    tx_packets: AtomicU64,
+    tx_bytes: AtomicU64,
 }
 
+/// A NIC with its IPv4 and IPv6 addresses, which sends and receives IP
+/// packets in Ethernet frames.
 struct InterfaceWrapper {
-    name: &'static str,
+    name: String,
     ether_addr: EthernetAddress,
     dev: Mutex<DeviceWrapper>,
+    ip_addrs: Mutex<Vec<IpCidr>>,
+    neighbors: Mutex<NeighborCache>,
+    dhcp: Mutex<Option<DhcpClient>>,
+    slaac: Mutex<Slaac>,
+}
+
+/// The smoltcp interface with the addresses of all the NICs, whose packets
+/// are dispatched by the [`Router`].
+struct StackWrapper {
+    router: Mutex<Router>,
     iface: Mutex<Interface>,
 }
 
@@ -72,27 +126,32 @@ impl<'a> SocketSetWrapper<'a> {
         Self(Mutex::new(SocketSet::new(vec![])))
     }
 
-    pub fn new_tcp_socket() -> socket::tcp::Socket<'a> {
-        let tcp_rx_buffer = socket::tcp::SocketBuffer::new(vec![0; TCP_RX_BUF_LEN]);
-        let tcp_tx_buffer = socket::tcp::SocketBuffer::new(vec![0; TCP_TX_BUF_LEN]);
+    pub fn new_tcp_socket(rx_buf_len: usize, tx_buf_len: usize) -> socket::tcp::Socket<'a> {
+        let tcp_rx_buffer = socket::tcp::SocketBuffer::new(vec![0; rx_buf_len]);
+        let tcp_tx_buffer = socket::tcp::SocketBuffer::new(vec![0; tx_buf_len]);
         socket::tcp::Socket::new(tcp_rx_buffer, tcp_tx_buffer)
     }
 
-    pub fn new_udp_socket() -> socket::udp::Socket<'a> {
+    pub fn new_udp_socket(rx_buf_len: usize, tx_buf_len: usize) -> socket::udp::Socket<'a> {
         let udp_rx_buffer = socket::udp::PacketBuffer::new(
             vec![socket::udp::PacketMetadata::EMPTY; 8],
-            vec![0; UDP_RX_BUF_LEN],
+            vec![0; rx_buf_len],
         );
         let udp_tx_buffer = socket::udp::PacketBuffer::new(
             vec![socket::udp::PacketMetadata::EMPTY; 8],
-            vec![0; UDP_TX_BUF_LEN],
+            vec![0; tx_buf_len],
         );
         socket::udp::Socket::new(udp_rx_buffer, udp_tx_buffer)
     }
 
     pub fn new_dns_socket() -> socket::dns::Socket<'a> {
-        let server_addr = DNS_SEVER.parse().expect("invalid DNS server address");
-        socket::dns::Socket::new(&[server_addr], vec![])
+        let servers = DNS_SERVERS.lock();
+        if servers.is_empty() {
+            let server_addr = DNS_SEVER.parse().expect("invalid DNS server address");
+            socket::dns::Socket::new(&[server_addr], vec![])
+        } else {
+            socket::dns::Socket::new(&servers[..servers.len().min(MAX_DNS_SERVERS)], vec![])
+        }
     }
 
     pub fn add<T: AnySocket<'a>>(&self, socket: T) -> SocketHandle {
@@ -119,8 +178,28 @@ impl<'a> SocketSetWrapper<'a> {
         f(socket)
     }
 
+    /// Returns whether a TCP connection or a bound UDP socket uses the local
+    /// `port` of the given protocol.
+    pub fn is_port_in_use(&self, port: u16, protocol: IpProtocol) -> bool {
+        self.0.lock().iter().any(|(_, socket)| match socket {
+            Socket::Tcp(socket) if protocol == IpProtocol::Tcp => {
+                socket.local_endpoint().is_some_and(|e| e.port == port)
+            }
+            Socket::Udp(socket) if protocol == IpProtocol::Udp => socket.endpoint().port == port,
+            _ => false,
+        })
+    }
+
     pub fn poll_interfaces(&self) {
-        ETH0.poll(&self.0);
+        STACK.poll(&self.0);
+        for (idx, iface) in IFACES.iter().enumerate() {
+            if let Some((old, new)) = iface.poll_dhcp() {
+                apply_dhcp_config(idx, old, new);
+            }
+            for event in iface.poll_slaac() {
+                apply_slaac_event(idx, event);
+            }
+        }
     }
 
     pub fn remove(&self, handle: SocketHandle) {
@@ -130,17 +209,16 @@ impl<'a> SocketSetWrapper<'a> {
 }
 
 impl InterfaceWrapper {
-    fn new(name: &'static str, dev: AxNetDevice, ether_addr: EthernetAddress) -> Self {
-        let mut config = Config::new(HardwareAddress::Ethernet(ether_addr));
-        config.random_seed = RANDOM_SEED;
-
-        let mut dev = DeviceWrapper::new(dev);
-        let iface = Mutex::new(Interface::new(config, &mut dev, Self::current_time()));
+    fn new(name: String, dev: AxNetDevice) -> Self {
+        let ether_addr = EthernetAddress(dev.mac_address().0);
         Self {
             name,
             ether_addr,
-            dev: Mutex::new(dev),
-            iface,
+            dev: Mutex::new(DeviceWrapper::new(dev)),
+            ip_addrs: Mutex::new(Vec::new()),
+            neighbors: Mutex::new(NeighborCache::default()),
+            dhcp: Mutex::new(None),
+            slaac: Mutex::new(Slaac::default()),
         }
     }
 
@@ -149,33 +227,110 @@ impl InterfaceWrapper {
     }
 
     pub fn name(&self) -> &str {
-        self.name
+        &self.name
     }
 
     pub fn ethernet_address(&self) -> EthernetAddress {
         self.ether_addr
     }
 
-    pub fn setup_ip_addr(&self, ip: IpAddress, prefix_len: u8) {
+    pub fn mtu(&self) -> usize {
+        self.dev.lock().mtu
+    }
+
+    /// Sets the MTU, which must be in `MIN_MTU..=STANDARD_MTU` since the
+    /// buffers of the NIC are allocated for the standard MTU.
+    pub fn set_mtu(&self, mtu: usize) -> DevResult {
+        if !(MIN_MTU..=STANDARD_MTU).contains(&mtu) {
+            return Err(DevError::InvalidParam);
+        }
+        self.dev.lock().mtu = mtu;
+        STACK.update_mtu();
+        Ok(())
+    }
+}
+
+impl StackWrapper {
+    fn new(ifaces: &'static [InterfaceWrapper]) -> Self {
+        let mut router = Router::new(ifaces, Self::min_mtu());
+        let iface = Self::new_iface(&mut router);
+        Self {
+            router: Mutex::new(router),
+            iface: Mutex::new(iface),
+        }
+    }
+
+    fn new_iface(router: &mut Router) -> Interface {
+        let mut config = Config::new(HardwareAddress::Ip);
+        config.random_seed = RANDOM_SEED;
+        let mut iface = Interface::new(config, router, InterfaceWrapper::current_time());
+        // Let all the packets go to the router, which selects the next hops
+        // by the routing table.
+        let routes = iface.routes_mut();
+        routes
+            .add_default_ipv4_route(Ipv4Address::UNSPECIFIED)
+            .unwrap();
+        routes
+            .add_default_ipv6_route(Ipv6Address::UNSPECIFIED)
+            .unwrap();
+        iface
+    }
+
+    /// The largest packet size that all the NICs can send.
+    fn min_mtu() -> usize {
+        IFACES
+            .iter()
+            .map(|iface| iface.mtu())
+            .min()
+            .unwrap_or(STANDARD_MTU)
+    }
+
+    fn update_mtu(&self) {
+        let mut router = self.router.lock();
         let mut iface = self.iface.lock();
-        iface.update_ip_addrs(|ip_addrs| {
-            ip_addrs.push(IpCidr::new(ip, prefix_len)).unwrap();
+        router.set_mtu(Self::min_mtu());
+
+        // smoltcp only reads the device capabilities when creating the
+        // interface, so recreate it with the same addresses.
+        let mut new_iface = Self::new_iface(&mut router);
+        let ip_addrs: Vec<IpCidr> = iface.ip_addrs().to_vec();
+        new_iface.update_ip_addrs(|addrs| {
+            for ip_addr in ip_addrs {
+                addrs.push(ip_addr).unwrap();
+            }
         });
+        *iface = new_iface;
     }
 
-    pub fn setup_gateway(&self, gateway: IpAddress) {
-        let mut iface = self.iface.lock();
-        match gateway {
-            IpAddress::Ipv4(v4) => iface.routes_mut().add_default_ipv4_route(v4).unwrap(),
-        };
+    fn add_ip_addr(&self, cidr: IpCidr) -> AxResult {
+        let mut result = Ok(());
+        self.iface.lock().update_ip_addrs(|ip_addrs| {
+            if ip_addrs.push(cidr).is_err() {
+                result = ax_err!(NoMemory, "too many IP addresses");
+            }
+            // smoltcp uses the first address as the source of the packets from
+            // unbound sockets, which must not be the loopback address, and
+            // should not be a link-local one.
+            ip_addrs.sort_by_key(|addr| match addr {
+                IpCidr::Ipv4(addr) => (*addr == LOOPBACK_ADDR, false),
+                IpCidr::Ipv6(addr) => (*addr == LOOPBACK_ADDR_V6, addr.address().is_link_local()),
+            });
+        });
+        result
+    }
+
+    fn remove_ip_addr(&self, cidr: IpCidr) {
+        self.iface.lock().update_ip_addrs(|ip_addrs| {
+            ip_addrs.retain(|addr| *addr != cidr);
+        });
     }
 
     pub fn poll(&self, sockets: &Mutex<SocketSet>) {
-        let mut dev = self.dev.lock();
+        let mut router = self.router.lock();
         let mut iface = self.iface.lock();
         let mut sockets = sockets.lock();
-        let timestamp = Self::current_time();
-        iface.poll(timestamp, dev.deref_mut(), &mut sockets);
+        let timestamp = InterfaceWrapper::current_time();
+        iface.poll(timestamp, router.deref_mut(), &mut sockets);
     }
 }
 
@@ -183,6 +338,8 @@ impl DeviceWrapper {
     fn new(inner: AxNetDevice) -> Self {
         Self {
             inner: RefCell::new(inner),
+            mtu: STANDARD_MTU,
+            stats: DeviceStats::default(),
         }
     }
 }
@@ -210,7 +367,14 @@ impl Device for DeviceWrapper {
                 return None;
             }
         };
-        Some((AxNetRxToken(&self.inner, rx_buf), AxNetTxToken(&self.inner)))
+        self.stats.rx_packets.fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .rx_bytes
+            .fetch_add(rx_buf.packet_len() as u64, Ordering::Relaxed);
+        Some((
+            AxNetRxToken(&self.inner, rx_buf),
+            AxNetTxToken(&self.inner, &self.stats),
+        ))
     }
 
     fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
@@ -220,7 +384,7 @@ impl Device for DeviceWrapper {
             return None;
         }
         if dev.can_transmit() {
-            Some(AxNetTxToken(&self.inner))
+            Some(AxNetTxToken(&self.inner, &self.stats))
         } else {
             None
         }
@@ -228,7 +392,7 @@ impl Device for DeviceWrapper {
 
     fn capabilities(&self) -> DeviceCapabilities {
         let mut caps = DeviceCapabilities::default();
-        caps.max_transmission_unit = 1514;
+        caps.max_transmission_unit = self.mtu + ETHERNET_HEADER_LEN;
         caps.max_burst_size = None;
         caps.medium = Medium::Ethernet;
         caps
@@ -236,13 +400,9 @@ impl Device for DeviceWrapper {
 }
 
 struct AxNetRxToken<'a>(&'a RefCell<AxNetDevice>, NetBufPtr);
-struct AxNetTxToken<'a>(&'a RefCell<AxNetDevice>);
+struct AxNetTxToken<'a>(&'a RefCell<AxNetDevice>, &'a DeviceStats);
 
 impl<'a> RxToken for AxNetRxToken<'a> {
-    fn preprocess(&self, sockets: &mut SocketSet<'_>) {
-        snoop_tcp_packet(self.1.packet(), sockets).ok();
-    }
-
     fn consume<R, F>(self, f: F) -> R
     where
         F: FnOnce(&mut [u8]) -> R,
@@ -269,20 +429,35 @@ impl<'a> TxToken for AxNetTxToken<'a> {
         let ret = f(tx_buf.packet_mut());
         trace!("SEND {} bytes: {:02X?}", len, tx_buf.packet());
         dev.transmit(tx_buf).unwrap();
+        self.1.tx_packets.fetch_add(1, Ordering::Relaxed);
+        self.1.tx_bytes.fetch_add(len as u64, Ordering::Relaxed);
         ret
     }
 }
 
 fn snoop_tcp_packet(buf: &[u8], sockets: &mut SocketSet<'_>) -> Result<(), smoltcp::wire::Error> {
-    use smoltcp::wire::{EthernetFrame, IpProtocol, Ipv4Packet, TcpPacket};
-
-    let ether_frame = EthernetFrame::new_checked(buf)?;
-    let ipv4_packet = Ipv4Packet::new_checked(ether_frame.payload())?;
+    use smoltcp::wire::{IpVersion, Ipv4Packet, Ipv6Packet, TcpPacket};
+
+    let (src_ip, dst_ip, protocol, payload): (IpAddress, IpAddress, _, _) =
+        match IpVersion::of_packet(buf)? {
+            IpVersion::Ipv4 => {
+                let packet = Ipv4Packet::new_checked(buf)?;
+                let payload = packet.payload();
+                let (src, dst) = (packet.src_addr(), packet.dst_addr());
+                (src.into(), dst.into(), packet.next_header(), payload)
+            }
+            IpVersion::Ipv6 => {
+                let packet = Ipv6Packet::new_checked(buf)?;
+                let payload = packet.payload();
+                let (src, dst) = (packet.src_addr(), packet.dst_addr());
+                (src.into(), dst.into(), packet.next_header(), payload)
+            }
+        };
 
-    if ipv4_packet.next_header() == IpProtocol::Tcp {
-        let tcp_packet = TcpPacket::new_checked(ipv4_packet.payload())?;
-        let src_addr = (ipv4_packet.src_addr(), tcp_packet.src_port()).into();
-        let dst_addr = (ipv4_packet.dst_addr(), tcp_packet.dst_port()).into();
+    if protocol == IpProtocol::Tcp {
+        let tcp_packet = TcpPacket::new_checked(payload)?;
+        let src_addr = (src_ip, tcp_packet.src_port()).into();
+        let dst_addr = (dst_ip, tcp_packet.dst_port()).into();
         let is_first = tcp_packet.syn() && !tcp_packet.ack();
         if is_first {
             // create a socket for the first incoming TCP packet, as the later accept() returns.
@@ -302,29 +477,378 @@ pub fn poll_interfaces() {
 
 /// Benchmark raw socket transmit bandwidth.
 pub fn bench_transmit() {
-    ETH0.dev.lock().bench_transmit_bandwidth();
+    IFACES[0].dev.lock().bench_transmit_bandwidth();
 }
 
 /// Benchmark raw socket receive bandwidth.
 pub fn bench_receive() {
-    ETH0.dev.lock().bench_receive_bandwidth();
+    IFACES[0].dev.lock().bench_receive_bandwidth();
 }
 
-pub(crate) fn init(net_dev: AxNetDevice) {
-    let ether_addr = EthernetAddress(net_dev.mac_address().0);
-    let eth0 = InterfaceWrapper::new("eth0", net_dev, ether_addr);
+/// Returns the source address used to send packets to `dst`, or `None` if
+/// there is no route to it.
+pub(crate) fn source_addr_of(dst: IpAddress) -> Option<IpAddress> {
+    match dst {
+        IpAddress::Ipv4(addr) if LOOPBACK_ADDR.contains_addr(&addr) => {
+            return Some(LOOPBACK_ADDR.address().into());
+        }
+        _ if is_local_addr(dst) => return Some(dst),
+        _ => {}
+    }
+    let (iface, next_hop) = ROUTES.lock().lookup(dst)?;
+    IFACES[iface].source_addr(dst, next_hop)
+}
+
+/// Whether `addr` is assigned to any of the interfaces, including `lo`.
+pub(crate) fn is_local_addr(addr: IpAddress) -> bool {
+    addr == IpAddress::Ipv4(LOOPBACK_ADDR.address())
+        || addr == IpAddress::Ipv6(LOOPBACK_ADDR_V6.address())
+        || IFACES.iter().any(|iface| iface.has_ip_addr(addr))
+}
 
-    let ip = IP.parse().expect("invalid IP address");
-    let gateway = GATEWAY.parse().expect("invalid gateway IP address");
-    eth0.setup_ip_addr(ip, IP_PREFIX);
-    eth0.setup_gateway(gateway);
+fn iface_index(name: &str) -> AxResult<usize> {
+    IFACES
+        .iter()
+        .position(|iface| iface.name() == name)
+        .ok_or_else(|| ax_err_type!(NotFound, "no such network interface"))
+}
+
+fn to_ip_cidr(addr: IpAddr, prefix_len: u8) -> AxResult<IpCidr> {
+    match from_core_ipaddr(addr) {
+        IpAddress::Ipv4(addr) if prefix_len <= 32 => Ok(Ipv4Cidr::new(addr, prefix_len).into()),
+        IpAddress::Ipv6(addr) if prefix_len <= 128 => Ok(Ipv6Cidr::new(addr, prefix_len).into()),
+        _ => ax_err!(InvalidInput, "invalid prefix length"),
+    }
+}
 
-    ETH0.init_by(eth0);
+/// Assigns the address `addr/prefix_len` to the interface `iface` (e.g.
+/// `"eth0"`), and adds the route to its network through the interface.
+pub fn add_ip_addr(iface: &str, addr: IpAddr, prefix_len: u8) -> AxResult {
+    let idx = iface_index(iface)?;
+    let cidr = to_ip_cidr(addr, prefix_len)?;
+    if is_local_addr(cidr.address()) {
+        return ax_err!(AlreadyExists, "address already assigned");
+    }
+    STACK.add_ip_addr(cidr)?;
+    IFACES[idx].add_ip_addr(cidr)?;
+    STACK.router.lock().add_local_addr(cidr.address(), idx);
+    // there may be a route to the network already
+    ROUTES
+        .lock()
+        .add(Route {
+            dest: network_of(cidr),
+            gateway: None,
+            iface: idx,
+        })
+        .ok();
+    Ok(())
+}
+
+/// Removes the address `addr` from the interface `iface`, with the route to its
+/// network if no other addresses of the interface are in it.
+pub fn remove_ip_addr(iface: &str, addr: IpAddr) -> AxResult {
+    let idx = iface_index(iface)?;
+    let cidr = IFACES[idx].remove_ip_addr(from_core_ipaddr(addr))?;
+    STACK.remove_ip_addr(cidr);
+    STACK.router.lock().remove_local_addr(cidr.address());
+    let network = network_of(cidr);
+    let in_use = IFACES[idx]
+        .ip_addrs
+        .lock()
+        .iter()
+        .any(|&c| network_of(c) == network);
+    if !in_use {
+        let mut routes = ROUTES.lock();
+        if let Some(Route {
+            gateway: None,
+            iface,
+            ..
+        }) = routes.get(network)
+        {
+            if *iface == idx {
+                routes.remove(network).ok();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the DHCP lease of the interface `iface`, or `None` if it is not
+/// configured by DHCP yet.
+pub fn dhcp_lease(iface: &str) -> Option<DhcpLease> {
+    IFACES[iface_index(iface).ok()?].dhcp_lease()
+}
+
+/// Removes the configuration of an old DHCP lease, and applies the new one.
+fn apply_dhcp_config(idx: usize, old: Option<DhcpConfig>, new: Option<DhcpConfig>) {
+    let iface = IFACES[idx].name();
+    let default_route = IpCidr::new(Ipv4Address::UNSPECIFIED.into(), 0);
+    if let Some(old) = old {
+        let addr = into_core_ipaddr(IpAddress::Ipv4(old.address.address()));
+        remove_ip_addr(iface, addr).ok();
+        if let Some(router) = old.router {
+            let mut routes = ROUTES.lock();
+            if let Some(route) = routes.get(default_route) {
+                if route.iface == idx && route.gateway == Some(router.into()) {
+                    routes.remove(default_route).ok();
+                }
+            }
+        }
+        DNS_SERVERS.lock().clear();
+        info!("{}: DHCP lease of {} released", iface, old.address);
+    }
+    if let Some(new) = new {
+        let addr = into_core_ipaddr(IpAddress::Ipv4(new.address.address()));
+        if let Err(e) = add_ip_addr(iface, addr, new.address.prefix_len()) {
+            warn!(
+                "{}: failed to set up address {}: {:?}",
+                iface, new.address, e
+            );
+        }
+        if let Some(router) = new.router {
+            let route = Route {
+                dest: default_route,
+                gateway: Some(router.into()),
+                iface: idx,
+            };
+            if ROUTES.lock().add(route).is_err() {
+                warn!(
+                    "{}: default route exists, gateway {} ignored",
+                    iface, router
+                );
+            }
+        }
+        *DNS_SERVERS.lock() = new
+            .dns_servers
+            .iter()
+            .map(|&a| IpAddress::Ipv4(a))
+            .collect();
+        info!("{}: DHCP lease of {} acquired", iface, new.address);
+    }
+}
+
+/// Applies a change of the configuration learned from the router
+/// advertisements.
+fn apply_slaac_event(idx: usize, event: SlaacEvent) {
+    let iface = IFACES[idx].name();
+    let default_route = IpCidr::new(Ipv6Address::UNSPECIFIED.into(), 0);
+    match event {
+        SlaacEvent::AddAddr(cidr) => {
+            let addr = into_core_ipaddr(cidr.address().into());
+            match add_ip_addr(iface, addr, cidr.prefix_len()) {
+                Ok(()) => info!("{}: SLAAC address {} configured", iface, cidr),
+                Err(e) => warn!("{}: failed to set up address {}: {:?}", iface, cidr, e),
+            }
+        }
+        SlaacEvent::RemoveAddr(addr) => {
+            remove_ip_addr(iface, into_core_ipaddr(addr.into())).ok();
+            info!("{}: SLAAC address {} expired", iface, addr);
+        }
+        SlaacEvent::AddRouter(router) => {
+            let route = Route {
+                dest: default_route,
+                gateway: Some(router.into()),
+                iface: idx,
+            };
+            if ROUTES.lock().add(route).is_err() {
+                warn!(
+                    "{}: IPv6 default route exists, router {} ignored",
+                    iface, router
+                );
+            }
+        }
+        SlaacEvent::RemoveRouter(router) => {
+            let mut routes = ROUTES.lock();
+            if let Some(route) = routes.get(default_route) {
+                if route.iface == idx && route.gateway == Some(router.into()) {
+                    routes.remove(default_route).ok();
+                }
+            }
+        }
+    }
+}
+
+/// Adds a route to the network `dest/prefix_len` through the interface
+/// `iface`, via `gateway` if it is not directly reachable. The default route
+/// has a `prefix_len` of 0.
+pub fn add_route(dest: IpAddr, prefix_len: u8, gateway: Option<IpAddr>, iface: &str) -> AxResult {
+    let idx = iface_index(iface)?;
+    let dest = network_of(to_ip_cidr(dest, prefix_len)?);
+    let gateway = gateway.map(from_core_ipaddr);
+    if gateway.is_some_and(|gateway| gateway.version() != dest.address().version()) {
+        return ax_err!(InvalidInput, "gateway of another address family");
+    }
+    ROUTES.lock().add(Route {
+        dest,
+        gateway,
+        iface: idx,
+    })
+}
+
+/// Removes the route to the network `dest/prefix_len`.
+pub fn remove_route(dest: IpAddr, prefix_len: u8) -> AxResult {
+    let dest = network_of(to_ip_cidr(dest, prefix_len)?);
+    ROUTES.lock().remove(dest)
+}
+
+/// Parses an address in the form of `ip[/prefix_len]`.
+fn parse_ip_cidr(s: &str) -> (IpAddr, u8) {
+    let (ip, prefix_len) = match s.split_once('/') {
+        Some((ip, prefix_len)) => (ip, prefix_len.parse().expect("invalid IP prefix length")),
+        None => (s, IP_PREFIX),
+    };
+    (ip.trim().parse().expect("invalid IP address"), prefix_len)
+}
+
+pub(crate) fn init(net_devs: Vec<AxNetDevice>) {
+    let ifaces = net_devs
+        .into_iter()
+        .enumerate()
+        .map(|(i, dev)| InterfaceWrapper::new(format!("eth{}", i), dev))
+        .collect();
+    IFACES.init_by(ifaces);
+    STACK.init_by(StackWrapper::new(&IFACES));
     SOCKET_SET.init_by(SocketSetWrapper::new());
     LISTEN_TABLE.init_by(ListenTable::new());
 
-    info!("created net interface {:?}:", ETH0.name());
-    info!("  ether:    {}", ETH0.ethernet_address());
-    info!("  ip:       {}/{}", ip, IP_PREFIX);
-    info!("  gateway:  {}", gateway);
+    for addr in [IpCidr::Ipv4(LOOPBACK_ADDR), IpCidr::Ipv6(LOOPBACK_ADDR_V6)] {
+        STACK
+            .add_ip_addr(addr)
+            .expect("failed to set up loopback address");
+    }
+    register_loopback();
+
+    // The global IPv6 addresses and the default router are configured by SLAAC
+    // from the router advertisements.
+    for iface in IFACES.iter() {
+        let addr = into_core_ipaddr(iface.link_local_addr().into());
+        if let Err(e) = add_ip_addr(iface.name(), addr, 64) {
+            warn!(
+                "{}: failed to set up link-local address: {:?}",
+                iface.name(),
+                e
+            );
+        }
+    }
+
+    // `AX_IP` is a comma-separated list of addresses of eth0, eth1, ..., where
+    // a missing or empty one, or "dhcp" means to configure the interface by
+    // DHCP.
+    let mut ips = IP.split(',');
+    for iface in IFACES.iter() {
+        let ip = ips.next().unwrap_or("").trim();
+        if ip.is_empty() || ip == "dhcp" {
+            iface.enable_dhcp();
+            continue;
+        }
+        let (ip, prefix_len) = parse_ip_cidr(ip);
+        add_ip_addr(iface.name(), ip, prefix_len).expect("failed to set up IP address");
+    }
+    // the gateway is from DHCP if no address is configured
+    if !GATEWAY.is_empty() && !IP.is_empty() && !IFACES.is_empty() {
+        let gateway: IpAddr = GATEWAY.parse().expect("invalid gateway IP address");
+        // the gateway must be on the network of an interface
+        let route = ROUTES.lock().lookup(from_core_ipaddr(gateway));
+        let idx = route.map_or(0, |(idx, _)| idx);
+        let any: IpAddr = match gateway {
+            IpAddr::V4(_) => core::net::Ipv4Addr::UNSPECIFIED.into(),
+            IpAddr::V6(_) => core::net::Ipv6Addr::UNSPECIFIED.into(),
+        };
+        add_route(any, 0, Some(gateway), IFACES[idx].name())
+            .expect("failed to set up default route");
+        info!("default gateway {} on {}", gateway, IFACES[idx].name());
+    }
+    if IFACES.iter().any(|iface| iface.dhcp_pending()) {
+        wait_for_dhcp();
+    }
+
+    for iface in IFACES.iter() {
+        register_device(iface);
+        info!("created net interface {:?}:", iface.name());
+        info!("  ether:    {}", iface.ethernet_address());
+        for cidr in iface.ip_addrs.lock().iter() {
+            info!("  ip:       {}", cidr);
+        }
+    }
+}
+
+/// Waits for the DHCP leases of the interfaces, and renews the leases in the
+/// background if tasks are available.
+fn wait_for_dhcp() {
+    use axhal::time::current_time;
+
+    let deadline = current_time() + DHCP_TIMEOUT;
+    while IFACES.iter().any(|iface| iface.dhcp_pending()) {
+        if current_time() >= deadline {
+            warn!("DHCP timed out, retrying in the background");
+            break;
+        }
+        SOCKET_SET.poll_interfaces();
+        axtask::yield_now();
+    }
+
+    // Without tasks, the leases are renewed when the sockets poll the interfaces.
+    #[cfg(feature = "multitask")]
+    axtask::spawn(|| loop {
+        SOCKET_SET.poll_interfaces();
+        axtask::sleep(core::time::Duration::from_secs(1));
+    });
+}
+
+/// Registers the loopback interface to the driver model at `class/net/lo`.
+fn register_loopback() {
+    type Counter = fn(&DeviceStats) -> &AtomicU64;
+    let counter = |name: &str, f: Counter| {
+        DeviceAttr::new(name, move || {
+            let router = STACK.router.lock();
+            format!("{}\n", f(&router.loopback.stats).load(Ordering::Relaxed))
+        })
+    };
+
+    model::register_device(
+        "class/net/lo",
+        vec![
+            DeviceAttr::new("address", || "00:00:00:00:00:00\n".into()),
+            DeviceAttr::new("mtu", || format!("{}\n", StackWrapper::min_mtu())),
+            counter("statistics/rx_packets", |s| &s.rx_packets),
+            counter("statistics/rx_bytes", |s| &s.rx_bytes),
+            counter("statistics/tx_packets", |s| &s.tx_packets),
+            counter("statistics/tx_bytes", |s| &s.tx_bytes),
+        ],
+    );
+}
+
+/// Registers the interface to the driver model at `class/net/<name>`.
+fn register_device(iface: &'static InterfaceWrapper) {
+    type Counter = fn(&DeviceStats) -> &AtomicU64;
+    let counter = |name: &str, f: Counter| {
+        DeviceAttr::new(name, move || {
+            format!("{}\n", f(&iface.dev.lock().stats).load(Ordering::Relaxed))
+        })
+    };
+
+    let mac = iface.ethernet_address().0;
+    model::register_device(
+        &format!("class/net/{}", iface.name()),
+        vec![
+            DeviceAttr::new("address", move || {
+                format!(
+                    "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}\n",
+                    mac[0], mac[1], mac[2], mac[3], mac[4], mac[5]
+                )
+            }),
+            DeviceAttr::new_writable(
+                "mtu",
+                move || format!("{}\n", iface.mtu()),
+                move |value| {
+                    let mtu = value.trim().parse().map_err(|_| DevError::InvalidParam)?;
+                    iface.set_mtu(mtu)
+                },
+            ),
+            counter("statistics/rx_packets", |s| &s.rx_packets),
+            counter("statistics/rx_bytes", |s| &s.rx_bytes),
+            counter("statistics/tx_packets", |s| &s.tx_packets),
+            counter("statistics/tx_bytes", |s| &s.tx_bytes),
+        ],
+    );
 }
@@ -0,0 +1,294 @@
+use alloc::{vec, vec::Vec};
+
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::time::{Duration, Instant};
+use smoltcp::wire::RawHardwareAddress;
+use smoltcp::wire::{EthernetAddress, EthernetProtocol, Icmpv6Packet, Icmpv6Repr, IpAddress};
+use smoltcp::wire::{IpProtocol, Ipv6Address, Ipv6Cidr, Ipv6Packet, Ipv6Repr};
+use smoltcp::wire::{NdiscNeighborFlags, NdiscPrefixInfoFlags, NdiscPrefixInformation, NdiscRepr};
+
+use super::InterfaceWrapper;
+
+/// The maximum number of router solicitations sent at startup (RFC 4861).
+const MAX_RTR_SOLICITATIONS: u32 = 3;
+const RTR_SOLICITATION_INTERVAL: Duration = Duration::from_secs(4);
+/// The length of the prefixes which SLAAC can generate addresses in.
+const SLAAC_PREFIX_LEN: u8 = 64;
+
+/// A change of the configuration learned from the router advertisements.
+#[derive(Debug)]
+pub enum SlaacEvent {
+    AddAddr(Ipv6Cidr),
+    RemoveAddr(Ipv6Address),
+    AddRouter(Ipv6Address),
+    RemoveRouter(Ipv6Address),
+}
+
+/// The state of the stateless address autoconfiguration (RFC 4862) of an
+/// interface.
+#[derive(Default)]
+pub struct Slaac {
+    solicitations: u32,
+    last_solicitation: Option<Instant>,
+    /// Whether any router advertisement is received.
+    advertised: bool,
+    router: Option<Ipv6Address>,
+    addrs: Vec<Ipv6Cidr>,
+    /// The changes not applied to the network stack yet.
+    events: Vec<SlaacEvent>,
+}
+
+impl Slaac {
+    fn update_router(&mut self, router: Ipv6Address, lifetime: Duration) {
+        if lifetime > Duration::ZERO {
+            if self.router != Some(router) {
+                if let Some(old) = self.router.replace(router) {
+                    self.events.push(SlaacEvent::RemoveRouter(old));
+                }
+                self.events.push(SlaacEvent::AddRouter(router));
+            }
+        } else if self.router == Some(router) {
+            self.router = None;
+            self.events.push(SlaacEvent::RemoveRouter(router));
+        }
+    }
+
+    fn update_addr(&mut self, cidr: Ipv6Cidr, valid_lifetime: Duration) {
+        let idx = self.addrs.iter().position(|c| *c == cidr);
+        match idx {
+            None if valid_lifetime > Duration::ZERO => {
+                self.addrs.push(cidr);
+                self.events.push(SlaacEvent::AddAddr(cidr));
+            }
+            Some(idx) if valid_lifetime == Duration::ZERO => {
+                self.addrs.remove(idx);
+                self.events.push(SlaacEvent::RemoveAddr(cidr.address()));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the Ethernet multicast address of an IPv6 multicast address
+/// (RFC 2464).
+pub fn multicast_ether_addr(addr: Ipv6Address) -> EthernetAddress {
+    let b = addr.0;
+    EthernetAddress([0x33, 0x33, b[12], b[13], b[14], b[15]])
+}
+
+fn to_ether_addr(lladdr: Option<RawHardwareAddress>) -> Option<EthernetAddress> {
+    lladdr
+        .filter(|addr| addr.len() == 6)
+        .map(|addr| EthernetAddress::from_bytes(addr.as_bytes()))
+        .filter(|addr| addr.is_unicast())
+}
+
+impl InterfaceWrapper {
+    /// Returns the address in the /64 `prefix` with the modified EUI-64
+    /// interface identifier of the NIC (RFC 4291).
+    fn eui64_addr(&self, prefix: Ipv6Address) -> Ipv6Address {
+        let mac = self.ether_addr.0;
+        let mut bytes = prefix.0;
+        bytes[8..].copy_from_slice(&[
+            mac[0] ^ 0x02,
+            mac[1],
+            mac[2],
+            0xff,
+            0xfe,
+            mac[3],
+            mac[4],
+            mac[5],
+        ]);
+        Ipv6Address(bytes)
+    }
+
+    /// The link-local address of the interface.
+    pub fn link_local_addr(&self) -> Ipv6Address {
+        self.eui64_addr(Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0))
+    }
+
+    /// Handles the packet if it is an NDP message, and returns whether it is
+    /// handled.
+    pub fn process_ndisc(&self, packet: &[u8]) -> bool {
+        let Ok(ip_packet) = Ipv6Packet::new_checked(packet) else {
+            return false;
+        };
+        if ip_packet.next_header() != IpProtocol::Icmpv6 {
+            return false;
+        }
+        let Ok(icmp_packet) = Icmpv6Packet::new_checked(ip_packet.payload()) else {
+            return false;
+        };
+        if !icmp_packet.msg_type().is_ndisc() {
+            return false;
+        }
+        // NDP messages from other links are dropped (RFC 4861 section 6.1).
+        if ip_packet.hop_limit() != 255 {
+            return true;
+        }
+
+        let src_addr = ip_packet.src_addr();
+        let repr = Icmpv6Repr::parse(
+            &src_addr.into(),
+            &ip_packet.dst_addr().into(),
+            &icmp_packet,
+            &ChecksumCapabilities::default(),
+        );
+        match repr {
+            Ok(Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr,
+            })) => self.process_neighbor_solicit(src_addr, target_addr, to_ether_addr(lladdr)),
+            Ok(Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                target_addr,
+                lladdr,
+                ..
+            })) => {
+                if let Some(hw_addr) = to_ether_addr(lladdr) {
+                    self.update_neighbor(target_addr.into(), hw_addr, false);
+                }
+            }
+            Ok(Icmpv6Repr::Ndisc(NdiscRepr::RouterAdvert {
+                router_lifetime,
+                lladdr,
+                prefix_info,
+                ..
+            })) => self.process_router_advert(
+                src_addr,
+                router_lifetime,
+                to_ether_addr(lladdr),
+                prefix_info,
+            ),
+            _ => {}
+        }
+        true
+    }
+
+    fn process_neighbor_solicit(
+        &self,
+        src_addr: Ipv6Address,
+        target_addr: Ipv6Address,
+        lladdr: Option<EthernetAddress>,
+    ) {
+        if !self.has_ip_addr(target_addr.into()) {
+            return;
+        }
+        // A solicitation from an unspecified address is for duplicate address
+        // detection, whose advertisement goes to all the nodes.
+        let (dst_addr, dst_hw_addr, flags) = if src_addr.is_unspecified() {
+            let dst_addr = Ipv6Address::LINK_LOCAL_ALL_NODES;
+            let flags = NdiscNeighborFlags::OVERRIDE;
+            (dst_addr, multicast_ether_addr(dst_addr), flags)
+        } else {
+            let Some(hw_addr) = lladdr else {
+                return;
+            };
+            self.update_neighbor(src_addr.into(), hw_addr, true);
+            let flags = NdiscNeighborFlags::SOLICITED | NdiscNeighborFlags::OVERRIDE;
+            (src_addr, hw_addr, flags)
+        };
+        let repr = NdiscRepr::NeighborAdvert {
+            flags,
+            target_addr,
+            lladdr: Some(self.ether_addr.into()),
+        };
+        self.send_ndisc(target_addr, dst_addr, dst_hw_addr, repr);
+    }
+
+    fn process_router_advert(
+        &self,
+        src_addr: Ipv6Address,
+        router_lifetime: Duration,
+        lladdr: Option<EthernetAddress>,
+        prefix_info: Option<NdiscPrefixInformation>,
+    ) {
+        if !src_addr.is_link_local() {
+            return;
+        }
+        if let Some(hw_addr) = lladdr {
+            self.update_neighbor(src_addr.into(), hw_addr, true);
+        }
+
+        let mut slaac = self.slaac.lock();
+        slaac.advertised = true;
+        slaac.update_router(src_addr, router_lifetime);
+        if let Some(info) = prefix_info {
+            if info.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+                && info.prefix_len == SLAAC_PREFIX_LEN
+                && !info.prefix.is_link_local()
+            {
+                let cidr = Ipv6Cidr::new(self.eui64_addr(info.prefix), SLAAC_PREFIX_LEN);
+                slaac.update_addr(cidr, info.valid_lifetime);
+            }
+        }
+    }
+
+    /// Sends a neighbor solicitation to resolve the hardware address of
+    /// `target_addr`.
+    pub fn send_neighbor_solicit(&self, target_addr: Ipv6Address) {
+        let src_addr = match self.source_addr(target_addr.into(), target_addr.into()) {
+            Some(IpAddress::Ipv6(addr)) => addr,
+            _ => self.link_local_addr(),
+        };
+        let dst_addr = target_addr.solicited_node();
+        let repr = NdiscRepr::NeighborSolicit {
+            target_addr,
+            lladdr: Some(self.ether_addr.into()),
+        };
+        self.send_ndisc(src_addr, dst_addr, multicast_ether_addr(dst_addr), repr);
+    }
+
+    /// Sends the router solicitations due, and returns the changes of the
+    /// configuration learned from the router advertisements.
+    pub fn poll_slaac(&self) -> Vec<SlaacEvent> {
+        let now = Self::current_time();
+        let mut slaac = self.slaac.lock();
+        let solicit = !slaac.advertised
+            && slaac.solicitations < MAX_RTR_SOLICITATIONS
+            && slaac
+                .last_solicitation
+                .map_or(true, |last| now >= last + RTR_SOLICITATION_INTERVAL);
+        if solicit && self.has_ip_addr(self.link_local_addr().into()) {
+            slaac.solicitations += 1;
+            slaac.last_solicitation = Some(now);
+            let repr = NdiscRepr::RouterSolicit {
+                lladdr: Some(self.ether_addr.into()),
+            };
+            let dst_addr = Ipv6Address::LINK_LOCAL_ALL_ROUTERS;
+            self.send_ndisc(
+                self.link_local_addr(),
+                dst_addr,
+                multicast_ether_addr(dst_addr),
+                repr,
+            );
+        }
+        core::mem::take(&mut slaac.events)
+    }
+
+    fn send_ndisc(
+        &self,
+        src_addr: Ipv6Address,
+        dst_addr: Ipv6Address,
+        dst_hw_addr: EthernetAddress,
+        repr: NdiscRepr,
+    ) {
+        let icmp_repr = Icmpv6Repr::Ndisc(repr);
+        let ip_repr = Ipv6Repr {
+            src_addr,
+            dst_addr,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+            hop_limit: 255,
+        };
+        let mut packet = vec![0; ip_repr.buffer_len() + ip_repr.payload_len];
+        let mut ip_packet = Ipv6Packet::new_unchecked(&mut packet[..]);
+        ip_repr.emit(&mut ip_packet);
+        icmp_repr.emit(
+            &src_addr.into(),
+            &dst_addr.into(),
+            &mut Icmpv6Packet::new_unchecked(ip_packet.payload_mut()),
+            &ChecksumCapabilities::default(),
+        );
+        self.send_frame(dst_hw_addr, EthernetProtocol::Ipv6, &packet);
+    }
+}
@@ -0,0 +1,311 @@
+use alloc::{vec, vec::Vec};
+
+use axerrno::{ax_err, AxResult};
+use smoltcp::iface::SocketSet;
+use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
+use smoltcp::time::Instant;
+use smoltcp::wire::{IpAddress, IpCidr, IpVersion, Ipv4Packet, Ipv6Address, Ipv6Cidr, Ipv6Packet};
+
+use super::loopback::{Loopback, LOOPBACK_ADDR, LOOPBACK_ADDR_V6};
+use super::{snoop_tcp_packet, InterfaceWrapper, ETHERNET_HEADER_LEN, ROUTES};
+use driver_net::NetBufPtr;
+
+/// A route to the network `dest` through the interface `iface`.
+///
+/// The destinations are reached directly if `gateway` is `None`, otherwise
+/// the packets are sent to the gateway.
+#[derive(Debug, Clone, Copy)]
+pub struct Route {
+    pub dest: IpCidr,
+    pub gateway: Option<IpAddress>,
+    pub iface: usize,
+}
+
+/// The routing table, which selects the outgoing interface of each packet by
+/// the longest prefix match of the destination.
+pub struct RouteTable {
+    routes: Vec<Route>,
+}
+
+impl RouteTable {
+    pub const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Adds a route. There can be only one route to each network.
+    pub fn add(&mut self, route: Route) -> AxResult {
+        if self.routes.iter().any(|r| r.dest == route.dest) {
+            return ax_err!(AlreadyExists, "route already exists");
+        }
+        debug!("add route {:?}", route);
+        self.routes.push(route);
+        Ok(())
+    }
+
+    /// Removes the route to the network `dest`.
+    pub fn remove(&mut self, dest: IpCidr) -> AxResult {
+        match self.routes.iter().position(|r| r.dest == dest) {
+            Some(idx) => {
+                debug!("remove route {:?}", self.routes.remove(idx));
+                Ok(())
+            }
+            None => ax_err!(NotFound, "route not found"),
+        }
+    }
+
+    /// Returns the route to the network `dest`.
+    pub fn get(&self, dest: IpCidr) -> Option<&Route> {
+        self.routes.iter().find(|r| r.dest == dest)
+    }
+
+    /// Finds the route to `dst`, and returns the outgoing interface with the
+    /// next hop, which is either `dst` itself or a gateway.
+    pub fn lookup(&self, dst: IpAddress) -> Option<(usize, IpAddress)> {
+        self.routes
+            .iter()
+            .filter(|r| r.dest.contains_addr(&dst))
+            .max_by_key(|r| r.dest.prefix_len())
+            .map(|r| (r.iface, r.gateway.unwrap_or(dst)))
+    }
+}
+
+/// A device of the IP medium used by the smoltcp interface, which receives
+/// packets from all the NICs, and sends each packet to the NIC selected by the
+/// routing table. The packets to the local addresses go through the loopback
+/// interface instead.
+pub struct Router {
+    ifaces: &'static [InterfaceWrapper],
+    pub loopback: Loopback,
+    /// The addresses of the NICs with their indices, so that routing a packet
+    /// takes no locks of the NICs.
+    local_addrs: Vec<(IpAddress, usize)>,
+    /// The interface to receive from first, so that a busy NIC cannot starve
+    /// the others.
+    next_rx: usize,
+    /// The NIC of the last packet sent, or `None` for the loopback interface.
+    last_tx: Option<usize>,
+    mtu: usize,
+}
+
+/// Where an IP packet is sent.
+#[derive(Clone, Copy)]
+enum NextHop {
+    Loopback,
+    /// The index of the NIC, and the address of the next hop.
+    Nic(usize, IpAddress),
+}
+
+impl Router {
+    pub fn new(ifaces: &'static [InterfaceWrapper], mtu: usize) -> Self {
+        Self {
+            ifaces,
+            loopback: Loopback::default(),
+            local_addrs: Vec::new(),
+            next_rx: 0,
+            last_tx: (!ifaces.is_empty()).then_some(0),
+            mtu,
+        }
+    }
+
+    pub fn set_mtu(&mut self, mtu: usize) {
+        self.mtu = mtu;
+    }
+
+    /// Records an address assigned to the NIC `iface`.
+    pub fn add_local_addr(&mut self, addr: IpAddress, iface: usize) {
+        self.local_addrs.push((addr, iface));
+    }
+
+    pub fn remove_local_addr(&mut self, addr: IpAddress) {
+        self.local_addrs.retain(|&(a, _)| a != addr);
+    }
+
+    /// Whether the packets to `dst` go through the loopback interface.
+    fn is_loopback(&self, dst: IpAddress) -> bool {
+        let is_loopback = match dst {
+            IpAddress::Ipv4(addr) => LOOPBACK_ADDR.contains_addr(&addr),
+            IpAddress::Ipv6(addr) => addr == LOOPBACK_ADDR_V6.address(),
+        };
+        is_loopback || self.local_addrs.iter().any(|&(addr, _)| addr == dst)
+    }
+
+    /// Selects where to send an IP packet by its addresses, or returns `None`
+    /// if it should be dropped.
+    fn route(&self, packet: &[u8]) -> Option<NextHop> {
+        let (src, dst) = packet_addrs(packet)?;
+        if self.is_loopback(dst) {
+            return Some(NextHop::Loopback);
+        }
+        if dst.is_broadcast() || dst.is_multicast() {
+            if self.ifaces.is_empty() {
+                debug!("no interface for {}, packet dropped", dst);
+                return None;
+            }
+            // sent through the interface with the source address
+            let iface = self.local_addrs.iter().find(|&&(addr, _)| addr == src);
+            return Some(NextHop::Nic(iface.map_or(0, |&(_, idx)| idx), dst));
+        }
+        let route = ROUTES.lock().lookup(dst);
+        if route.is_none() {
+            debug!("no route to {}, packet dropped", dst);
+        }
+        route.map(|(iface, next_hop)| NextHop::Nic(iface, next_hop))
+    }
+
+    /// Sends an IP packet not built in a frame of the selected NIC.
+    fn send(&mut self, next_hop: NextHop, packet: Vec<u8>) {
+        match next_hop {
+            NextHop::Loopback => {
+                self.last_tx = None;
+                self.loopback.send(packet);
+            }
+            NextHop::Nic(iface, next_hop) => {
+                self.last_tx = Some(iface);
+                self.ifaces[iface].send_ip_packet(next_hop, packet);
+            }
+        }
+    }
+}
+
+impl Device for Router {
+    type RxToken<'a> = RouterRxToken where Self: 'a;
+    type TxToken<'a> = RouterTxToken<'a> where Self: 'a;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        if let Some(packet) = self.loopback.recv() {
+            return Some((RouterRxToken::Loopback(packet), RouterTxToken(self)));
+        }
+        let ifaces = self.ifaces;
+        let n = ifaces.len();
+        for i in 0..n {
+            let iface = &ifaces[(self.next_rx + i) % n];
+            if let Some((buf, offset)) = iface.recv_ip_packet() {
+                self.next_rx = (self.next_rx + i + 1) % n;
+                return Some((
+                    RouterRxToken::Nic { iface, buf, offset },
+                    RouterTxToken(self),
+                ));
+            }
+        }
+        None
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(RouterTxToken(self))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = self.mtu;
+        caps.max_burst_size = None;
+        caps.medium = Medium::Ip;
+        caps
+    }
+}
+
+/// A received IP packet, which is either at `offset` of a frame of `iface`, or
+/// from the loopback interface.
+pub enum RouterRxToken {
+    Nic {
+        iface: &'static InterfaceWrapper,
+        buf: NetBufPtr,
+        offset: usize,
+    },
+    Loopback(Vec<u8>),
+}
+
+pub struct RouterTxToken<'a>(&'a mut Router);
+
+impl RxToken for RouterRxToken {
+    fn preprocess(&self, sockets: &mut SocketSet<'_>) {
+        let packet = match self {
+            Self::Nic { buf, offset, .. } => &buf.packet()[*offset..],
+            Self::Loopback(packet) => &packet[..],
+        };
+        snoop_tcp_packet(packet, sockets).ok();
+    }
+
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        match self {
+            Self::Nic {
+                iface,
+                mut buf,
+                offset,
+            } => {
+                let result = f(&mut buf.packet_mut()[offset..]);
+                iface.recycle_rx_buffer(buf);
+                result
+            }
+            Self::Loopback(mut packet) => f(&mut packet),
+        }
+    }
+}
+
+impl<'a> TxToken for RouterTxToken<'a> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let router = self.0;
+        // The next hop is only known after the packet is built. It is built in
+        // place in a frame of the NIC of the last packet, which is copied only
+        // if the packet goes elsewhere.
+        let ifaces = router.ifaces;
+        if let Some(idx) = router.last_tx {
+            let iface = &ifaces[idx];
+            if let Some(mut buf) = iface.alloc_frame(len) {
+                let result = f(&mut buf.packet_mut()[ETHERNET_HEADER_LEN..]);
+                match router.route(&buf.packet()[ETHERNET_HEADER_LEN..]) {
+                    Some(NextHop::Nic(i, next_hop)) if i == idx => {
+                        iface.send_ip_frame(next_hop, buf)
+                    }
+                    Some(next_hop) => {
+                        let packet = buf.packet()[ETHERNET_HEADER_LEN..].to_vec();
+                        iface.free_frame(buf);
+                        router.send(next_hop, packet);
+                    }
+                    None => iface.free_frame(buf),
+                }
+                return result;
+            }
+        }
+        let mut packet = vec![0; len];
+        let result = f(&mut packet);
+        if let Some(next_hop) = router.route(&packet) {
+            router.send(next_hop, packet);
+        }
+        result
+    }
+}
+
+/// Returns the network of `cidr`, whose host bits are cleared.
+pub fn network_of(cidr: IpCidr) -> IpCidr {
+    match cidr {
+        IpCidr::Ipv4(cidr) => IpCidr::Ipv4(cidr.network()),
+        IpCidr::Ipv6(cidr) => {
+            let mut bytes = cidr.address().0;
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let bits = (cidr.prefix_len() as usize).saturating_sub(i * 8).min(8);
+                *byte &= (0xff00u16 >> bits) as u8;
+            }
+            IpCidr::Ipv6(Ipv6Cidr::new(Ipv6Address(bytes), cidr.prefix_len()))
+        }
+    }
+}
+
+/// Returns the source and destination addresses of an IP packet.
+fn packet_addrs(packet: &[u8]) -> Option<(IpAddress, IpAddress)> {
+    match IpVersion::of_packet(packet).ok()? {
+        IpVersion::Ipv4 => {
+            let p = Ipv4Packet::new_checked(packet).ok()?;
+            Some((p.src_addr().into(), p.dst_addr().into()))
+        }
+        IpVersion::Ipv6 => {
+            let p = Ipv6Packet::new_checked(packet).ok()?;
+            Some((p.src_addr().into(), p.dst_addr().into()))
+        }
+    }
+}
@@ -1,17 +1,22 @@
 use core::cell::UnsafeCell;
 use core::net::SocketAddr;
 use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::time::Duration;
 
 use axerrno::{ax_err, ax_err_type, AxError, AxResult};
+use axhal::time::current_time;
 use axio::PollState;
 use axsync::Mutex;
 
 use smoltcp::iface::SocketHandle;
 use smoltcp::socket::tcp::{self, ConnectError, State};
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpEndpoint, IpListenEndpoint, IpProtocol};
 
 use super::addr::{from_core_sockaddr, into_core_sockaddr, is_unspecified, UNSPECIFIED_ENDPOINT};
-use super::{SocketSetWrapper, ETH0, LISTEN_TABLE, SOCKET_SET};
+use super::{SocketSetWrapper, LISTEN_TABLE, SOCKET_SET, STACK};
+use super::{
+    DEFAULT_HOP_LIMIT, MAX_SOCKET_BUF_LEN, MIN_SOCKET_BUF_LEN, TCP_RX_BUF_LEN, TCP_TX_BUF_LEN,
+};
 
 // State transitions:
 // CLOSED -(connect)-> BUSY -> CONNECTING -> CONNECTED -(shutdown)-> BUSY -> CLOSED
@@ -25,6 +30,53 @@ const STATE_CONNECTING: u8 = 2;
 const STATE_CONNECTED: u8 = 3;
 const STATE_LISTENING: u8 = 4;
 
+/// The idle time before sending keep-alive probes when `SO_KEEPALIVE` is set.
+const TCP_KEEPALIVE_INTERVAL: smoltcp::time::Duration = smoltcp::time::Duration::from_secs(75);
+
+/// The socket options of a TCP socket.
+///
+/// The connections accepted on a listening socket inherit its options.
+#[derive(Clone, Copy)]
+pub(crate) struct TcpOptions {
+    reuse_addr: bool,
+    recv_buf_size: usize,
+    send_buf_size: usize,
+    nodelay: bool,
+    keepalive: bool,
+    hop_limit: u8,
+    linger: Option<Duration>,
+    recv_timeout: Option<Duration>,
+    send_timeout: Option<Duration>,
+}
+
+impl TcpOptions {
+    const DEFAULT: Self = Self {
+        reuse_addr: false,
+        recv_buf_size: TCP_RX_BUF_LEN,
+        send_buf_size: TCP_TX_BUF_LEN,
+        nodelay: false,
+        keepalive: false,
+        hop_limit: DEFAULT_HOP_LIMIT,
+        linger: None,
+        recv_timeout: None,
+        send_timeout: None,
+    };
+
+    /// Creates a smoltcp socket with the buffer sizes and options.
+    pub fn new_socket(&self) -> tcp::Socket<'static> {
+        let mut socket = SocketSetWrapper::new_tcp_socket(self.recv_buf_size, self.send_buf_size);
+        self.apply(&mut socket);
+        socket
+    }
+
+    /// Applies the options that can be changed on an existing smoltcp socket.
+    fn apply(&self, socket: &mut tcp::Socket) {
+        socket.set_nagle_enabled(!self.nodelay);
+        socket.set_keep_alive(self.keepalive.then_some(TCP_KEEPALIVE_INTERVAL));
+        socket.set_hop_limit(Some(self.hop_limit));
+    }
+}
+
 /// A TCP socket that provides POSIX-like APIs.
 ///
 /// - [`connect`] is for TCP clients.
@@ -41,6 +93,9 @@ pub struct TcpSocket {
     local_addr: UnsafeCell<IpEndpoint>,
     peer_addr: UnsafeCell<IpEndpoint>,
     nonblock: AtomicBool,
+    options: Mutex<TcpOptions>,
+    /// The pending error of a failed nonblocking connection (`SO_ERROR`).
+    error: Mutex<Option<AxError>>,
 }
 
 unsafe impl Sync for TcpSocket {}
@@ -54,6 +109,8 @@ impl TcpSocket {
             local_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT),
             peer_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT),
             nonblock: AtomicBool::new(false),
+            options: Mutex::new(TcpOptions::DEFAULT),
+            error: Mutex::new(None),
         }
     }
 
@@ -62,6 +119,7 @@ impl TcpSocket {
         handle: SocketHandle,
         local_addr: IpEndpoint,
         peer_addr: IpEndpoint,
+        options: TcpOptions,
     ) -> Self {
         Self {
             state: AtomicU8::new(STATE_CONNECTED),
@@ -69,6 +127,8 @@ impl TcpSocket {
             local_addr: UnsafeCell::new(local_addr),
             peer_addr: UnsafeCell::new(peer_addr),
             nonblock: AtomicBool::new(false),
+            options: Mutex::new(options),
+            error: Mutex::new(None),
         }
     }
 
@@ -115,21 +175,180 @@ impl TcpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
+    /// Returns whether the `SO_REUSEADDR` option is set.
+    pub fn reuse_address(&self) -> bool {
+        self.options.lock().reuse_addr
+    }
+
+    /// Sets the `SO_REUSEADDR` option, which allows [`bind`](Self::bind) to
+    /// succeed while connections on the same port still exist (e.g. in the
+    /// `TIME-WAIT` state).
+    pub fn set_reuse_address(&self, reuse: bool) {
+        self.update_options(|options| options.reuse_addr = reuse);
+    }
+
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> usize {
+        self.options.lock().recv_buf_size
+    }
+
+    /// Sets the size of the receive buffer (`SO_RCVBUF`).
+    ///
+    /// The size takes effect on the next connection of this socket, or on the
+    /// connections accepted after it if this socket is listening. The buffers
+    /// of an existing connection can not be resized, so it fails with
+    /// [`InvalidInput`](AxError::InvalidInput) if the socket is connecting or
+    /// connected.
+    pub fn set_recv_buffer_size(&self, size: usize) -> AxResult {
+        if matches!(self.get_state(), STATE_CONNECTING | STATE_CONNECTED) {
+            return ax_err!(
+                InvalidInput,
+                "socket set_recv_buffer_size() failed: already connected"
+            );
+        }
+        let size = size.clamp(MIN_SOCKET_BUF_LEN, MAX_SOCKET_BUF_LEN);
+        self.update_options(|options| options.recv_buf_size = size);
+        Ok(())
+    }
+
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> usize {
+        self.options.lock().send_buf_size
+    }
+
+    /// Sets the size of the send buffer (`SO_SNDBUF`).
+    ///
+    /// Like [`set_recv_buffer_size`](Self::set_recv_buffer_size), it fails if
+    /// the socket is connecting or connected.
+    pub fn set_send_buffer_size(&self, size: usize) -> AxResult {
+        if matches!(self.get_state(), STATE_CONNECTING | STATE_CONNECTED) {
+            return ax_err!(
+                InvalidInput,
+                "socket set_send_buffer_size() failed: already connected"
+            );
+        }
+        let size = size.clamp(MIN_SOCKET_BUF_LEN, MAX_SOCKET_BUF_LEN);
+        self.update_options(|options| options.send_buf_size = size);
+        Ok(())
+    }
+
+    /// Returns whether the Nagle's algorithm is disabled (`TCP_NODELAY`).
+    pub fn nodelay(&self) -> bool {
+        self.options.lock().nodelay
+    }
+
+    /// Enables or disables the Nagle's algorithm (`TCP_NODELAY`).
+    ///
+    /// If `nodelay` is true, the segments are sent as soon as possible, even
+    /// if there is only a small amount of data.
+    pub fn set_nodelay(&self, nodelay: bool) {
+        self.update_options(|options| options.nodelay = nodelay);
+    }
+
+    /// Returns whether the keep-alive probes are enabled (`SO_KEEPALIVE`).
+    pub fn keepalive(&self) -> bool {
+        self.options.lock().keepalive
+    }
+
+    /// Enables or disables sending keep-alive probes on an idle connection
+    /// (`SO_KEEPALIVE`).
+    pub fn set_keepalive(&self, keepalive: bool) {
+        self.update_options(|options| options.keepalive = keepalive);
+    }
+
+    /// Returns the time-to-live of the outgoing packets (`IP_TTL`).
+    pub fn ttl(&self) -> u8 {
+        self.options.lock().hop_limit
+    }
+
+    /// Sets the time-to-live of the outgoing packets (`IP_TTL`), or the hop
+    /// limit for IPv6.
+    pub fn set_ttl(&self, ttl: u8) -> AxResult {
+        if ttl == 0 {
+            return ax_err!(InvalidInput, "socket set_ttl() failed: zero TTL");
+        }
+        self.update_options(|options| options.hop_limit = ttl);
+        Ok(())
+    }
+
+    /// Returns the linger timeout (`SO_LINGER`).
+    pub fn linger(&self) -> Option<Duration> {
+        self.options.lock().linger
+    }
+
+    /// Sets the linger timeout (`SO_LINGER`).
+    ///
+    /// If it is `Some`, [`shutdown`](Self::shutdown) blocks until the remaining
+    /// data is acknowledged by the peer or the timeout expires. A zero timeout
+    /// resets the connection instead of closing it gracefully.
+    pub fn set_linger(&self, linger: Option<Duration>) {
+        self.update_options(|options| options.linger = linger);
+    }
+
+    /// Returns the timeout of the blocking receive operations (`SO_RCVTIMEO`).
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.options.lock().recv_timeout
+    }
+
+    /// Sets the timeout of the blocking [`recv`](Self::recv) and
+    /// [`accept`](Self::accept) (`SO_RCVTIMEO`), after which they return
+    /// [`Err(WouldBlock)`](AxError::WouldBlock). `None` means no timeout.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout == Some(Duration::ZERO) {
+            return ax_err!(InvalidInput, "socket set_read_timeout() failed");
+        }
+        self.update_options(|options| options.recv_timeout = timeout);
+        Ok(())
+    }
+
+    /// Returns the timeout of the blocking send operations (`SO_SNDTIMEO`).
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.options.lock().send_timeout
+    }
+
+    /// Sets the timeout of the blocking [`send`](Self::send) and
+    /// [`connect`](Self::connect) (`SO_SNDTIMEO`), after which they return
+    /// [`Err(WouldBlock)`](AxError::WouldBlock). `None` means no timeout.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout == Some(Duration::ZERO) {
+            return ax_err!(InvalidInput, "socket set_write_timeout() failed");
+        }
+        self.update_options(|options| options.send_timeout = timeout);
+        Ok(())
+    }
+
+    /// Returns and clears the pending error of the socket (`SO_ERROR`), which
+    /// is set when a nonblocking [`connect`](Self::connect) fails.
+    pub fn take_error(&self) -> Option<AxError> {
+        self.error.lock().take()
+    }
+
     /// Connects to the given address and port.
     ///
     /// The local port is generated automatically.
     pub fn connect(&self, remote_addr: SocketAddr) -> AxResult {
         self.update_state(STATE_CLOSED, STATE_CONNECTING, || {
+            let options = *self.options.lock();
             // SAFETY: no other threads can read or write these fields.
             let handle = unsafe { self.handle.get().read() }
-                .unwrap_or_else(|| SOCKET_SET.add(SocketSetWrapper::new_tcp_socket()));
+                .unwrap_or_else(|| SOCKET_SET.add(options.new_socket()));
 
-            // TODO: check remote addr unreachable
             let remote_endpoint = from_core_sockaddr(remote_addr);
-            let bound_endpoint = self.bound_endpoint()?;
-            let iface = &ETH0.iface;
+            let mut bound_endpoint = self.bound_endpoint()?;
+            if bound_endpoint.addr.is_none() {
+                // use the address of the interface to the remote host
+                let addr = super::source_addr_of(remote_endpoint.addr).ok_or_else(|| {
+                    ax_err_type!(
+                        ConnectionRefused,
+                        "socket connect() failed: no route to host"
+                    )
+                })?;
+                bound_endpoint.addr = Some(addr);
+            }
+            let iface = &STACK.iface;
             let (local_endpoint, remote_endpoint) = SOCKET_SET
                 .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+                    options.apply(socket);
                     socket
                         .connect(iface.lock().context(), remote_endpoint, bound_endpoint)
                         .or_else(|e| match e {
@@ -160,13 +379,15 @@ impl TcpSocket {
         if self.is_nonblocking() {
             Err(AxError::WouldBlock)
         } else {
-            self.block_on(|| {
+            self.block_on(self.write_timeout(), || {
                 let PollState { writable, .. } = self.poll_connect()?;
                 if !writable {
                     Err(AxError::WouldBlock)
                 } else if self.get_state() == STATE_CONNECTED {
                     Ok(())
                 } else {
+                    // the error is reported here rather than by `take_error`
+                    self.error.lock().take();
                     ax_err!(ConnectionRefused, "socket connect() failed")
                 }
             })
@@ -181,9 +402,17 @@ impl TcpSocket {
     /// [`accept`](Self::accept).
     pub fn bind(&self, mut local_addr: SocketAddr) -> AxResult {
         self.update_state(STATE_CLOSED, STATE_CLOSED, || {
-            // TODO: check addr is available
+            let local_endpoint = from_core_sockaddr(local_addr);
+            if !is_unspecified(local_endpoint.addr) && !super::is_local_addr(local_endpoint.addr) {
+                return ax_err!(InvalidInput, "socket bind() failed: address not available");
+            }
             if local_addr.port() == 0 {
                 local_addr.set_port(get_ephemeral_port()?);
+            } else if !LISTEN_TABLE.can_listen(local_addr.port())
+                || (!self.reuse_address()
+                    && SOCKET_SET.is_port_in_use(local_addr.port(), IpProtocol::Tcp))
+            {
+                return ax_err!(AddrInUse, "socket bind() failed");
             }
             // SAFETY: no other threads can read or write `self.local_addr` as we
             // have changed the state to `BUSY`.
@@ -209,7 +438,7 @@ impl TcpSocket {
             unsafe {
                 (*self.local_addr.get()).port = bound_endpoint.port;
             }
-            LISTEN_TABLE.listen(bound_endpoint)?;
+            LISTEN_TABLE.listen(bound_endpoint, *self.options.lock())?;
             debug!("TCP socket listening on {}", bound_endpoint);
             Ok(())
         })
@@ -229,26 +458,39 @@ impl TcpSocket {
 
         // SAFETY: `self.local_addr` should be initialized after `bind()`.
         let local_port = unsafe { self.local_addr.get().read().port };
-        self.block_on(|| {
+        self.block_on(self.read_timeout(), || {
             let (handle, (local_addr, peer_addr)) = LISTEN_TABLE.accept(local_port)?;
             debug!("TCP socket accepted a new connection {}", peer_addr);
-            Ok(TcpSocket::new_connected(handle, local_addr, peer_addr))
+            let options = *self.options.lock();
+            let socket = TcpSocket::new_connected(handle, local_addr, peer_addr, options);
+            Ok(socket)
         })
     }
 
     /// Close the connection.
+    ///
+    /// If the linger timeout is set, it blocks until the remaining data is
+    /// sent, see [`set_linger`](Self::set_linger).
     pub fn shutdown(&self) -> AxResult {
         // stream
         self.update_state(STATE_CONNECTED, STATE_CLOSED, || {
             // SAFETY: `self.handle` should be initialized in a connected socket, and
             // no other threads can read or write it.
             let handle = unsafe { self.handle.get().read().unwrap() };
+            let linger = self.linger();
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
                 debug!("TCP socket {}: shutting down", handle);
-                socket.close();
+                if linger == Some(Duration::ZERO) {
+                    socket.abort();
+                } else {
+                    socket.close();
+                }
             });
             unsafe { self.local_addr.get().write(UNSPECIFIED_ENDPOINT) }; // clear bound address
             SOCKET_SET.poll_interfaces();
+            if let Some(timeout) = linger.filter(|t| !t.is_zero()) {
+                wait_for_close(handle, timeout);
+            }
             Ok(())
         })
         .unwrap_or(Ok(()))?;
@@ -279,7 +521,7 @@ impl TcpSocket {
 
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        self.block_on(|| {
+        self.block_on(self.read_timeout(), || {
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
                 if !socket.is_active() {
                     // not open
@@ -312,7 +554,7 @@ impl TcpSocket {
 
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        self.block_on(|| {
+        self.block_on(self.write_timeout(), || {
             SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
                 if !socket.is_active() || !socket.may_send() {
                     // closed by remote
@@ -387,6 +629,28 @@ impl TcpSocket {
         }
     }
 
+    /// Updates the options, and applies them to the smoltcp socket or the
+    /// listen table entry if they exist.
+    fn update_options<F: FnOnce(&mut TcpOptions)>(&self, f: F) {
+        let mut options = self.options.lock();
+        f(&mut options);
+        match self.get_state() {
+            STATE_CONNECTING | STATE_CONNECTED => {
+                // SAFETY: `self.handle` should be initialized in a connecting or
+                // connected socket.
+                let handle = unsafe { self.handle.get().read().unwrap() };
+                SOCKET_SET
+                    .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| options.apply(socket));
+            }
+            STATE_LISTENING => {
+                // SAFETY: `self.local_addr` should be initialized in a listening socket.
+                let local_port = unsafe { self.local_addr.get().read().port };
+                LISTEN_TABLE.set_options(local_port, *options);
+            }
+            _ => {}
+        }
+    }
+
     #[inline]
     fn is_connecting(&self) -> bool {
         self.get_state() == STATE_CONNECTING
@@ -439,6 +703,7 @@ impl TcpSocket {
                         self.local_addr.get().write(UNSPECIFIED_ENDPOINT);
                         self.peer_addr.get().write(UNSPECIFIED_ENDPOINT);
                     }
+                    *self.error.lock() = Some(AxError::ConnectionRefused);
                     self.set_state(STATE_CLOSED); // connection failed
                     true
                 }
@@ -473,19 +738,26 @@ impl TcpSocket {
     ///
     /// If the socket is non-blocking, it calls the function once and returns
     /// immediately. Otherwise, it may call the function multiple times if it
-    /// returns [`Err(WouldBlock)`](AxError::WouldBlock).
-    fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
+    /// returns [`Err(WouldBlock)`](AxError::WouldBlock), until the `timeout`
+    /// expires.
+    fn block_on<F, T>(&self, timeout: Option<Duration>, mut f: F) -> AxResult<T>
     where
         F: FnMut() -> AxResult<T>,
     {
         if self.is_nonblocking() {
             f()
         } else {
+            let deadline = timeout.map(|t| current_time() + t);
             loop {
                 SOCKET_SET.poll_interfaces();
                 match f() {
                     Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
+                    Err(AxError::WouldBlock) => {
+                        if deadline.is_some_and(|d| current_time() >= d) {
+                            return Err(AxError::WouldBlock);
+                        }
+                        axtask::yield_now()
+                    }
                     Err(e) => return Err(e),
                 }
             }
@@ -503,6 +775,23 @@ impl Drop for TcpSocket {
     }
 }
 
+/// Waits until the remaining data and the FIN of a closed socket are
+/// acknowledged by the peer, or the timeout expires.
+fn wait_for_close(handle: SocketHandle, timeout: Duration) {
+    let deadline = current_time() + timeout;
+    while current_time() < deadline
+        && SOCKET_SET.with_socket::<tcp::Socket, _, _>(handle, |socket| {
+            matches!(
+                socket.state(),
+                State::FinWait1 | State::Closing | State::LastAck
+            )
+        })
+    {
+        axtask::yield_now();
+        SOCKET_SET.poll_interfaces();
+    }
+}
+
 fn get_ephemeral_port() -> AxResult<u16> {
     const PORT_START: u16 = 0xc000;
     const PORT_END: u16 = 0xffff;
@@ -1,17 +1,53 @@
 use core::net::SocketAddr;
 use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
 
 use axerrno::{ax_err, ax_err_type, AxError, AxResult};
+use axhal::time::current_time;
 use axio::PollState;
 use axsync::Mutex;
 use spin::RwLock;
 
 use smoltcp::iface::SocketHandle;
 use smoltcp::socket::udp::{self, BindError, SendError};
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpEndpoint, IpListenEndpoint, IpProtocol};
 
-use super::addr::{from_core_sockaddr, into_core_sockaddr, is_unspecified, UNSPECIFIED_ENDPOINT};
+use super::addr::{
+    from_core_ipaddr, from_core_sockaddr, into_core_sockaddr, is_unspecified, UNSPECIFIED_IP,
+};
 use super::{SocketSetWrapper, SOCKET_SET};
+use super::{
+    DEFAULT_HOP_LIMIT, MAX_SOCKET_BUF_LEN, MIN_SOCKET_BUF_LEN, UDP_RX_BUF_LEN, UDP_TX_BUF_LEN,
+};
+
+/// The socket options of a UDP socket.
+#[derive(Clone, Copy)]
+struct UdpOptions {
+    reuse_addr: bool,
+    recv_buf_size: usize,
+    send_buf_size: usize,
+    hop_limit: u8,
+    recv_timeout: Option<Duration>,
+    send_timeout: Option<Duration>,
+}
+
+impl UdpOptions {
+    const DEFAULT: Self = Self {
+        reuse_addr: false,
+        recv_buf_size: UDP_RX_BUF_LEN,
+        send_buf_size: UDP_TX_BUF_LEN,
+        hop_limit: DEFAULT_HOP_LIMIT,
+        recv_timeout: None,
+        send_timeout: None,
+    };
+
+    /// Creates a smoltcp socket with the buffer sizes and options.
+    fn new_socket(&self) -> udp::Socket<'static> {
+        let mut socket = SocketSetWrapper::new_udp_socket(self.recv_buf_size, self.send_buf_size);
+        socket.set_hop_limit(Some(self.hop_limit));
+        socket
+    }
+}
 
 /// A UDP socket that provides POSIX-like APIs.
 pub struct UdpSocket {
@@ -19,19 +55,21 @@ pub struct UdpSocket {
     local_addr: RwLock<Option<IpEndpoint>>,
     peer_addr: RwLock<Option<IpEndpoint>>,
     nonblock: AtomicBool,
+    options: Mutex<UdpOptions>,
 }
 
 impl UdpSocket {
     /// Creates a new UDP socket.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        let socket = SocketSetWrapper::new_udp_socket();
-        let handle = SOCKET_SET.add(socket);
+        let options = UdpOptions::DEFAULT;
+        let handle = SOCKET_SET.add(options.new_socket());
         Self {
             handle,
             local_addr: RwLock::new(None),
             peer_addr: RwLock::new(None),
             nonblock: AtomicBool::new(false),
+            options: Mutex::new(options),
         }
     }
 
@@ -69,6 +107,93 @@ impl UdpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
+    /// Returns whether the `SO_REUSEADDR` option is set.
+    pub fn reuse_address(&self) -> bool {
+        self.options.lock().reuse_addr
+    }
+
+    /// Sets the `SO_REUSEADDR` option, which allows [`bind`](Self::bind) to
+    /// a port that other UDP sockets are bound to.
+    pub fn set_reuse_address(&self, reuse: bool) {
+        self.options.lock().reuse_addr = reuse;
+    }
+
+    /// Returns the size of the receive buffer.
+    pub fn recv_buffer_size(&self) -> usize {
+        self.options.lock().recv_buf_size
+    }
+
+    /// Resizes the receive buffer (`SO_RCVBUF`).
+    ///
+    /// The datagrams queued in the buffers are dropped.
+    pub fn set_recv_buffer_size(&self, size: usize) {
+        let size = size.clamp(MIN_SOCKET_BUF_LEN, MAX_SOCKET_BUF_LEN);
+        self.resize_buffers(|options| options.recv_buf_size = size);
+    }
+
+    /// Returns the size of the send buffer.
+    pub fn send_buffer_size(&self) -> usize {
+        self.options.lock().send_buf_size
+    }
+
+    /// Resizes the send buffer (`SO_SNDBUF`).
+    ///
+    /// The datagrams queued in the buffers are dropped.
+    pub fn set_send_buffer_size(&self, size: usize) {
+        let size = size.clamp(MIN_SOCKET_BUF_LEN, MAX_SOCKET_BUF_LEN);
+        self.resize_buffers(|options| options.send_buf_size = size);
+    }
+
+    /// Returns the time-to-live of the outgoing packets (`IP_TTL`).
+    pub fn ttl(&self) -> u8 {
+        self.options.lock().hop_limit
+    }
+
+    /// Sets the time-to-live of the outgoing packets (`IP_TTL`), or the hop
+    /// limit for IPv6.
+    pub fn set_ttl(&self, ttl: u8) -> AxResult {
+        if ttl == 0 {
+            return ax_err!(InvalidInput, "socket set_ttl() failed: zero TTL");
+        }
+        self.options.lock().hop_limit = ttl;
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+            socket.set_hop_limit(Some(ttl))
+        });
+        Ok(())
+    }
+
+    /// Returns the timeout of the blocking receive operations (`SO_RCVTIMEO`).
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.options.lock().recv_timeout
+    }
+
+    /// Sets the timeout of the blocking receive operations (`SO_RCVTIMEO`),
+    /// after which they return [`Err(WouldBlock)`](AxError::WouldBlock).
+    /// `None` means no timeout.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout == Some(Duration::ZERO) {
+            return ax_err!(InvalidInput, "socket set_read_timeout() failed");
+        }
+        self.options.lock().recv_timeout = timeout;
+        Ok(())
+    }
+
+    /// Returns the timeout of the blocking send operations (`SO_SNDTIMEO`).
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.options.lock().send_timeout
+    }
+
+    /// Sets the timeout of the blocking send operations (`SO_SNDTIMEO`),
+    /// after which they return [`Err(WouldBlock)`](AxError::WouldBlock).
+    /// `None` means no timeout.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> AxResult {
+        if timeout == Some(Duration::ZERO) {
+            return ax_err!(InvalidInput, "socket set_write_timeout() failed");
+        }
+        self.options.lock().send_timeout = timeout;
+        Ok(())
+    }
+
     /// Binds an unbound socket to the given address and port.
     ///
     /// It's must be called before [`send_to`](Self::send_to) and
@@ -78,12 +203,19 @@ impl UdpSocket {
 
         if local_addr.port() == 0 {
             local_addr.set_port(get_ephemeral_port()?);
+        } else if !self.reuse_address()
+            && SOCKET_SET.is_port_in_use(local_addr.port(), IpProtocol::Udp)
+        {
+            return ax_err!(AddrInUse, "socket bind() failed");
         }
         if self_local_addr.is_some() {
             return ax_err!(InvalidInput, "socket bind() failed: already bound");
         }
 
         let local_endpoint = from_core_sockaddr(local_addr);
+        if !is_unspecified(local_endpoint.addr) && !super::is_local_addr(local_endpoint.addr) {
+            return ax_err!(InvalidInput, "socket bind() failed: address not available");
+        }
         let endpoint = IpListenEndpoint {
             addr: (!is_unspecified(local_endpoint.addr)).then_some(local_endpoint.addr),
             port: local_endpoint.port,
@@ -138,7 +270,10 @@ impl UdpSocket {
         let mut self_peer_addr = self.peer_addr.write();
 
         if self.local_addr.read().is_none() {
-            self.bind(into_core_sockaddr(UNSPECIFIED_ENDPOINT))?;
+            // bind to the address of the interface to the peer
+            let local_ip =
+                super::source_addr_of(from_core_ipaddr(addr.ip())).unwrap_or(UNSPECIFIED_IP);
+            self.bind(into_core_sockaddr(IpEndpoint::new(local_ip, 0)))?;
         }
 
         *self_peer_addr = Some(from_core_sockaddr(addr));
@@ -199,6 +334,22 @@ impl UdpSocket {
 
 /// Private methods
 impl UdpSocket {
+    /// Updates the buffer sizes, and replaces the smoltcp socket with a new one
+    /// bound to the same endpoint.
+    fn resize_buffers<F: FnOnce(&mut UdpOptions)>(&self, f: F) {
+        let mut options = self.options.lock();
+        f(&mut options);
+        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+            let mut new_socket = options.new_socket();
+            let endpoint = socket.endpoint();
+            if endpoint.port != 0 {
+                // it can not fail as the endpoint was accepted by the old socket
+                new_socket.bind(endpoint).ok();
+            }
+            *socket = new_socket;
+        });
+    }
+
     fn remote_endpoint(&self) -> AxResult<IpEndpoint> {
         match self.peer_addr.try_read() {
             Some(addr) => addr.ok_or(AxError::NotConnected),
@@ -211,7 +362,7 @@ impl UdpSocket {
             return ax_err!(NotConnected, "socket send() failed");
         }
 
-        self.block_on(|| {
+        self.block_on(self.write_timeout(), || {
             SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
                 if socket.can_send() {
                     socket
@@ -239,7 +390,7 @@ impl UdpSocket {
             return ax_err!(NotConnected, "socket send() failed");
         }
 
-        self.block_on(|| {
+        self.block_on(self.read_timeout(), || {
             SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
                 if socket.can_recv() {
                     // data available
@@ -252,18 +403,24 @@ impl UdpSocket {
         })
     }
 
-    fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
+    fn block_on<F, T>(&self, timeout: Option<Duration>, mut f: F) -> AxResult<T>
     where
         F: FnMut() -> AxResult<T>,
     {
         if self.is_nonblocking() {
             f()
         } else {
+            let deadline = timeout.map(|t| current_time() + t);
             loop {
                 SOCKET_SET.poll_interfaces();
                 match f() {
                     Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
+                    Err(AxError::WouldBlock) => {
+                        if deadline.is_some_and(|d| current_time() >= d) {
+                            return Err(AxError::WouldBlock);
+                        }
+                        axtask::yield_now()
+                    }
                     Err(e) => return Err(e),
                 }
             }
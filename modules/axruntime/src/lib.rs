@@ -191,6 +191,11 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
 
     unsafe { main() };
 
+    #[cfg(feature = "fs")]
+    if let Err(e) = axfs::fops::sync() {
+        warn!("failed to sync filesystems: {:?}", e);
+    }
+
     #[cfg(feature = "multitask")]
     axtask::exit(0);
     #[cfg(not(feature = "multitask"))]
@@ -232,26 +237,17 @@ fn init_allocator() {
 #[cfg(feature = "paging")]
 fn remap_kernel_memory() -> Result<(), axhal::paging::PagingError> {
     use axhal::mem::{memory_regions, phys_to_virt};
-    use axhal::paging::PageTable;
-    use lazy_init::LazyInit;
-
-    static KERNEL_PAGE_TABLE: LazyInit<PageTable> = LazyInit::new();
+    use axhal::paging::{kernel_page_table, PageTable};
 
     if axhal::cpu::this_cpu_is_bsp() {
-        let mut kernel_page_table = PageTable::try_new()?;
+        let mut page_table = PageTable::try_new()?;
         for r in memory_regions() {
-            kernel_page_table.map_region(
-                phys_to_virt(r.paddr),
-                r.paddr,
-                r.size,
-                r.flags.into(),
-                true,
-            )?;
+            page_table.map_region(phys_to_virt(r.paddr), r.paddr, r.size, r.flags.into(), true)?;
         }
-        KERNEL_PAGE_TABLE.init_by(kernel_page_table);
+        axhal::paging::init_kernel_page_table(page_table);
     }
 
-    unsafe { axhal::arch::write_page_table_root(KERNEL_PAGE_TABLE.root_paddr()) };
+    unsafe { axhal::arch::write_page_table_root(kernel_page_table().root_paddr()) };
     Ok(())
 }
 
@@ -259,14 +255,17 @@ fn remap_kernel_memory() -> Result<(), axhal::paging::PagingError> {
 fn init_interrupt() {
     use axhal::time::TIMER_IRQ_NUM;
 
-    // Setup timer interrupt handler
-    const PERIODIC_INTERVAL_NANOS: u64 =
-        axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
+    // Setup timer interrupt handler. With multitasking, the task manager
+    // programs the one-shot timer for the next scheduler tick or timed event,
+    // and skips ticks while the CPU is idle.
+    #[cfg(not(feature = "multitask"))]
+    fn update_timer() {
+        const PERIODIC_INTERVAL_NANOS: u64 =
+            axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
 
-    #[percpu::def_percpu]
-    static NEXT_DEADLINE: u64 = 0;
+        #[percpu::def_percpu]
+        static NEXT_DEADLINE: u64 = 0;
 
-    fn update_timer() {
         let now_ns = axhal::time::current_time_nanos();
         // Safety: we have disabled preemption in IRQ handler.
         let mut deadline = unsafe { NEXT_DEADLINE.read_current_raw() };
@@ -278,6 +277,7 @@ fn init_interrupt() {
     }
 
     axhal::irq::register_handler(TIMER_IRQ_NUM, || {
+        #[cfg(not(feature = "multitask"))]
         update_timer();
         #[cfg(feature = "multitask")]
         axtask::on_timer_tick();
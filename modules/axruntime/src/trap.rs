@@ -1,3 +1,5 @@
+use axhal::mem::VirtAddr;
+
 struct TrapHandlerImpl;
 
 #[crate_interface::impl_interface]
@@ -10,4 +12,16 @@ impl axhal::trap::TrapHandler for TrapHandlerImpl {
             drop(guard); // rescheduling may occur when preemption is re-enabled.
         }
     }
+
+    fn handle_page_fault(_vaddr: VirtAddr, _is_user: bool) -> bool {
+        #[cfg(all(feature = "multitask", feature = "paging"))]
+        if !_is_user {
+            if let Some(curr) = axtask::current_may_uninit() {
+                if curr.in_stack_guard(_vaddr) {
+                    panic!("stack overflow in task {}", curr.id_name());
+                }
+            }
+        }
+        false
+    }
 }
@@ -1,21 +1,29 @@
-//! A naïve sleeping mutex.
+//! A naïve sleeping mutex with priority inheritance.
 
 use core::cell::UnsafeCell;
 use core::fmt;
 use core::ops::{Deref, DerefMut};
 use core::sync::atomic::{AtomicU64, Ordering};
 
-use axtask::{current, WaitQueue};
+use axtask::{current, AxTaskRef, CurrentTask, WaitQueue};
+use spinlock::SpinNoIrq;
 
 /// A mutual exclusion primitive useful for protecting shared data, similar to
 /// [`std::sync::Mutex`](https://doc.rust-lang.org/std/sync/struct.Mutex.html).
 ///
 /// When the mutex is locked, the current task will block and be put into the
-/// wait queue. When the mutex is unlocked, all tasks waiting on the queue
-/// will be woken up.
+/// wait queue. When the mutex is unlocked, the waiting task with the highest
+/// priority will be woken up.
+///
+/// To avoid priority inversion, the owner inherits the highest priority of
+/// the tasks waiting for the mutex (see [`axtask::inherit_priority`]), and
+/// drops it when it unlocks the mutex, keeping the priorities inherited from
+/// the other mutexes it holds. The inheritance is not transitive: if the owner
+/// is blocked on another mutex, the owner of that one is not boosted.
 pub struct Mutex<T: ?Sized> {
     wq: WaitQueue,
     owner_id: AtomicU64,
+    owner: SpinNoIrq<Option<AxTaskRef>>,
     data: UnsafeCell<T>,
 }
 
@@ -38,6 +46,7 @@ impl<T> Mutex<T> {
         Self {
             wq: WaitQueue::new(),
             owner_id: AtomicU64::new(0),
+            owner: SpinNoIrq::new(None),
             data: UnsafeCell::new(data),
         }
     }
@@ -64,33 +73,32 @@ impl<T: ?Sized> Mutex<T> {
         self.owner_id.load(Ordering::Relaxed) != 0
     }
 
+    /// Returns `true` if the lock is currently held by the current task.
+    #[inline(always)]
+    pub fn is_owned_by_current(&self) -> bool {
+        self.owner_id.load(Ordering::Relaxed) == current().id().as_u64()
+    }
+
+    /// Returns the task holding the lock, or `None` if it is unlocked.
+    ///
+    /// Like [`is_locked`](Self::is_locked), the result may be out of date, it
+    /// is meant for diagnosing deadlocks.
+    pub fn owner(&self) -> Option<AxTaskRef> {
+        self.owner.lock().clone()
+    }
+
     /// Locks the [`Mutex`] and returns a guard that permits access to the inner data.
     ///
     /// The returned value may be dereferenced for data access
     /// and the lock will be dropped when the guard falls out of scope.
     pub fn lock(&self) -> MutexGuard<T> {
-        let current_id = current().id().as_u64();
-        loop {
-            // Can fail to lock even if the spinlock is not locked. May be more efficient than `try_lock`
-            // when called in a loop.
-            match self.owner_id.compare_exchange_weak(
-                0,
-                current_id,
-                Ordering::Acquire,
-                Ordering::Relaxed,
-            ) {
-                Ok(_) => break,
-                Err(owner_id) => {
-                    assert_ne!(
-                        owner_id,
-                        current_id,
-                        "{} tried to acquire mutex it already owns.",
-                        current().id_name()
-                    );
-                    // Wait until the lock looks unlocked before retrying
-                    self.wq.wait_until(|| !self.is_locked());
-                }
-            }
+        let curr = current();
+        while !self.try_acquire(&curr) {
+            // Wait until the lock looks unlocked before retrying. The owner is
+            // checked right before blocking, so that a new owner locking it
+            // after we are woken up is also boosted.
+            self.wq
+                .wait_until(|| !self.is_locked() || !self.boost_owner(&curr));
         }
         MutexGuard {
             lock: self,
@@ -101,14 +109,7 @@ impl<T: ?Sized> Mutex<T> {
     /// Try to lock this [`Mutex`], returning a lock guard if successful.
     #[inline(always)]
     pub fn try_lock(&self) -> Option<MutexGuard<T>> {
-        let current_id = current().id().as_u64();
-        // The reason for using a strong compare_exchange is explained here:
-        // https://github.com/Amanieu/parking_lot/pull/207#issuecomment-575869107
-        if self
-            .owner_id
-            .compare_exchange(0, current_id, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
-        {
+        if self.try_acquire(&current()) {
             Some(MutexGuard {
                 lock: self,
                 data: unsafe { &mut *self.data.get() },
@@ -118,6 +119,55 @@ impl<T: ?Sized> Mutex<T> {
         }
     }
 
+    /// The key of the priorities inherited by the owner from the waiters.
+    fn key(&self) -> usize {
+        self as *const Self as *const () as usize
+    }
+
+    /// Tries to set the current task as the owner, which then inherits the
+    /// priority of the tasks still waiting for the mutex.
+    fn try_acquire(&self, curr: &CurrentTask) -> bool {
+        {
+            let mut owner = self.owner.lock();
+            // `owner_id` is only changed with `owner` locked, so that waiters
+            // always see the owner task of a locked mutex.
+            if self
+                .owner_id
+                .compare_exchange(0, curr.id().as_u64(), Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                return false;
+            }
+            *owner = Some(curr.as_task_ref().clone());
+        }
+        // Tasks start waiting after this are checked by `boost_owner`. It must
+        // be called without `owner` locked, as waiters lock `owner` with the
+        // wait queue locked.
+        if let Some(prio) = self.wq.highest_priority() {
+            axtask::inherit_priority(curr.as_task_ref(), self.key(), prio);
+        }
+        true
+    }
+
+    /// Lends the priority of the current task to the owner before the current
+    /// task blocks.
+    ///
+    /// Returns `false` if the lock has been released in the meantime.
+    fn boost_owner(&self, curr: &CurrentTask) -> bool {
+        let owner = self.owner.lock();
+        let Some(task) = owner.as_ref() else {
+            return false;
+        };
+        assert_ne!(
+            task.id(),
+            curr.id(),
+            "{} tried to acquire mutex it already owns.",
+            curr.id_name()
+        );
+        axtask::inherit_priority(task, self.key(), curr.priority());
+        true
+    }
+
     /// Force unlock the [`Mutex`].
     ///
     /// # Safety
@@ -126,14 +176,19 @@ impl<T: ?Sized> Mutex<T> {
     /// thread. However, this can be useful in some instances for exposing
     /// the lock to FFI that doesn’t know how to deal with RAII.
     pub unsafe fn force_unlock(&self) {
-        let owner_id = self.owner_id.swap(0, Ordering::Release);
-        assert_eq!(
-            owner_id,
-            current().id().as_u64(),
-            "{} tried to release mutex it doesn't own",
-            current().id_name()
-        );
-        self.wq.notify_one(true);
+        {
+            let mut owner = self.owner.lock();
+            let owner_id = self.owner_id.swap(0, Ordering::Release);
+            assert_eq!(
+                owner_id,
+                current().id().as_u64(),
+                "{} tried to release mutex it doesn't own",
+                current().id_name()
+            );
+            *owner = None;
+        }
+        axtask::release_inherited_priority(self.key());
+        self.wq.notify_one_by_priority(true);
     }
 
     /// Returns a mutable reference to the underlying data.
@@ -162,11 +217,21 @@ impl<T: ?Sized + fmt::Debug> fmt::Debug for Mutex<T> {
             Some(guard) => write!(f, "Mutex {{ data: ")
                 .and_then(|()| (*guard).fmt(f))
                 .and_then(|()| write!(f, "}}")),
-            None => write!(f, "Mutex {{ <locked> }}"),
+            None => match self.owner() {
+                Some(owner) => write!(f, "Mutex {{ <locked by {}> }}", owner.id_name()),
+                None => write!(f, "Mutex {{ <locked> }}"),
+            },
         }
     }
 }
 
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    /// Returns the [`Mutex`] that the guard is locking.
+    pub(crate) fn mutex(guard: &Self) -> &'a Mutex<T> {
+        guard.lock
+    }
+}
+
 impl<'a, T: ?Sized> Deref for MutexGuard<'a, T> {
     type Target = T;
     #[inline(always)]
@@ -199,21 +264,13 @@ impl<'a, T: ?Sized> Drop for MutexGuard<'a, T> {
 
 #[cfg(test)]
 mod tests {
+    use crate::tests::{may_interrupt, INIT, SERIAL};
     use crate::Mutex;
     use axtask as thread;
-    use std::sync::Once;
-
-    static INIT: Once = Once::new();
-
-    fn may_interrupt() {
-        // simulate interrupts
-        if rand::random::<u32>() % 3 == 0 {
-            thread::yield_now();
-        }
-    }
 
     #[test]
     fn lots_and_lots() {
+        let _lock = SERIAL.lock();
         INIT.call_once(thread::init_scheduler);
 
         const NUM_TASKS: u32 = 10;
@@ -249,4 +306,69 @@ mod tests {
         assert_eq!(*M.lock(), NUM_ITERS * NUM_TASKS * 3);
         println!("Mutex test OK");
     }
+
+    #[test]
+    fn owner_tracking() {
+        let _lock = SERIAL.lock();
+        INIT.call_once(thread::init_scheduler);
+
+        static M: Mutex<()> = Mutex::new(());
+        assert!(M.owner().is_none());
+
+        let guard = M.lock();
+        assert_eq!(M.owner().unwrap().id(), thread::current().id());
+        let waiter = thread::spawn(|| {
+            assert!(!M.is_owned_by_current());
+            drop(M.lock());
+        });
+        // Let the waiter block on the mutex.
+        thread::yield_now();
+        assert_eq!(M.owner().unwrap().id(), thread::current().id());
+        // No priority is inherited with the FIFO scheduler.
+        assert_eq!(thread::current().priority(), 0);
+        drop(guard);
+
+        waiter.join();
+        assert!(M.owner().is_none());
+    }
+
+    #[cfg(feature = "sched_cfs")]
+    #[test]
+    fn priority_inheritance() {
+        let _lock = SERIAL.lock();
+        INIT.call_once(thread::init_scheduler);
+
+        static A: Mutex<()> = Mutex::new(());
+        static B: Mutex<()> = Mutex::new(());
+
+        fn wait_for_priority(prio: isize) {
+            while thread::current().priority() != prio {
+                thread::yield_now();
+            }
+        }
+
+        let a = A.lock();
+        let b = B.lock();
+        let mid = thread::spawn(|| {
+            assert!(thread::set_priority(-5));
+            drop(B.lock());
+        });
+        // The owner inherits the priority of the waiter once it is blocked.
+        wait_for_priority(-5);
+        let high = thread::spawn(|| {
+            assert!(thread::set_priority(-10));
+            drop(A.lock());
+        });
+        wait_for_priority(-10);
+        assert_eq!(thread::current().base_priority(), 0);
+
+        // The waiter of `B` is still blocked.
+        drop(a);
+        assert_eq!(thread::current().priority(), -5);
+        drop(b);
+        assert_eq!(thread::current().priority(), 0);
+
+        high.join();
+        mid.join();
+    }
 }
@@ -0,0 +1,170 @@
+use std::sync::{Mutex as StdMutex, Once as StdOnce};
+use std::vec::Vec;
+
+use axtask as thread;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Barrier, Condvar, Mutex, Once, OnceLock, RwLock, Semaphore};
+
+pub(crate) static INIT: StdOnce = StdOnce::new();
+pub(crate) static SERIAL: StdMutex<()> = StdMutex::new(());
+
+pub(crate) fn may_interrupt() {
+    // simulate interrupts
+    if rand::random::<u32>() % 3 == 0 {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn test_condvar() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_TASKS: usize = 10;
+    static PAIR: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            let (lock, cvar) = &PAIR;
+            may_interrupt();
+            *lock.lock() += 1;
+            cvar.notify_all();
+        });
+    }
+
+    let (lock, cvar) = &PAIR;
+    let count = cvar.wait_while(lock.lock(), |count| *count < NUM_TASKS);
+    assert_eq!(*count, NUM_TASKS);
+}
+
+#[test]
+fn test_semaphore() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_TASKS: usize = 10;
+    const NUM_PERMITS: usize = 3;
+    static SEM: Semaphore = Semaphore::new(NUM_PERMITS);
+    static HOLDERS: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: Semaphore = Semaphore::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            let _guard = SEM.access();
+            let holders = HOLDERS.fetch_add(1, Ordering::Relaxed) + 1;
+            assert!(holders <= NUM_PERMITS);
+            may_interrupt();
+            HOLDERS.fetch_sub(1, Ordering::Relaxed);
+            drop(_guard);
+            FINISHED.release();
+        });
+    }
+
+    for _ in 0..NUM_TASKS {
+        FINISHED.acquire();
+    }
+    assert_eq!(SEM.available_permits(), NUM_PERMITS);
+    assert!(!FINISHED.try_acquire());
+}
+
+#[test]
+fn test_rwlock() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_TASKS: usize = 10;
+    const NUM_ITERS: usize = 1000;
+    static LOCK: RwLock<(usize, usize)> = RwLock::new((0, 0));
+
+    let mut tasks = Vec::new();
+    for i in 0..NUM_TASKS {
+        tasks.push(thread::spawn(move || {
+            for _ in 0..NUM_ITERS {
+                if i % 2 == 0 {
+                    let mut val = LOCK.write();
+                    val.0 += 1;
+                    may_interrupt();
+                    val.1 += 1;
+                } else {
+                    let val = LOCK.read();
+                    may_interrupt();
+                    assert_eq!(val.0, val.1);
+                }
+                may_interrupt();
+            }
+        }));
+    }
+    for t in tasks {
+        t.join();
+    }
+
+    let val = LOCK.read();
+    assert_eq!(*val, (NUM_TASKS / 2 * NUM_ITERS, NUM_TASKS / 2 * NUM_ITERS));
+    assert!(LOCK.try_write().is_none());
+}
+
+#[test]
+fn test_barrier() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_TASKS: usize = 10;
+    static BARRIER: Barrier = Barrier::new(NUM_TASKS);
+    static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+    static LEADERS: AtomicUsize = AtomicUsize::new(0);
+
+    let mut tasks = Vec::new();
+    for _ in 0..NUM_TASKS - 1 {
+        tasks.push(thread::spawn(|| {
+            may_interrupt();
+            ARRIVED.fetch_add(1, Ordering::Relaxed);
+            if BARRIER.wait().is_leader() {
+                LEADERS.fetch_add(1, Ordering::Relaxed);
+            }
+            assert_eq!(ARRIVED.load(Ordering::Relaxed), NUM_TASKS);
+        }));
+    }
+
+    ARRIVED.fetch_add(1, Ordering::Relaxed);
+    if BARRIER.wait().is_leader() {
+        LEADERS.fetch_add(1, Ordering::Relaxed);
+    }
+    for t in tasks {
+        t.join();
+    }
+    assert_eq!(LEADERS.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_once() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(thread::init_scheduler);
+
+    const NUM_TASKS: usize = 10;
+    static ONCE: Once = Once::new();
+    static CELL: OnceLock<usize> = OnceLock::new();
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let mut tasks = Vec::new();
+    for i in 0..NUM_TASKS {
+        tasks.push(thread::spawn(move || {
+            ONCE.call_once(|| {
+                may_interrupt();
+                CALLS.fetch_add(1, Ordering::Relaxed);
+            });
+            assert!(ONCE.is_completed());
+            let val = *CELL.get_or_init(|| {
+                may_interrupt();
+                i
+            });
+            assert_eq!(CELL.get(), Some(&val));
+        }));
+    }
+    for t in tasks {
+        t.join();
+    }
+
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    assert!(CELL.set(NUM_TASKS).is_err());
+}
@@ -2,12 +2,23 @@
 
 use alloc::{string::String, sync::Arc};
 
-pub(crate) use crate::run_queue::{AxRunQueue, RUN_QUEUE};
+pub(crate) use crate::run_queue::{current_run_queue, AxRunQueue};
 
 #[doc(cfg(feature = "multitask"))]
-pub use crate::task::{CurrentTask, TaskId, TaskInner};
+pub use crate::cpumask::CpuMask;
+#[doc(cfg(feature = "multitask"))]
+pub use crate::stats::{for_each_task, task_stats, TaskStats};
+#[doc(cfg(feature = "multitask"))]
+pub use crate::task::{CurrentTask, TaskId, TaskInner, TaskState};
+#[cfg(feature = "irq")]
+#[doc(cfg(feature = "irq"))]
+pub use crate::timers::Timer;
 #[doc(cfg(feature = "multitask"))]
 pub use crate::wait_queue::WaitQueue;
+#[doc(cfg(feature = "multitask"))]
+pub use crate::workqueue::{flush_scheduled_work, schedule_work, Tasklet, WorkQueue};
+#[cfg(feature = "sched_prio")]
+pub use scheduler::SchedPolicy;
 
 /// The reference type of a task.
 pub type AxTaskRef = Arc<AxTask>;
@@ -20,6 +31,13 @@ cfg_if::cfg_if! {
     } else if #[cfg(feature = "sched_cfs")] {
         pub(crate) type AxTask = scheduler::CFSTask<TaskInner>;
         pub(crate) type Scheduler = scheduler::CFScheduler<TaskInner>;
+    } else if #[cfg(feature = "sched_prio")] {
+        const MAX_TIME_SLICE: usize = 5;
+        pub(crate) type AxTask = scheduler::PrioTask<TaskInner, MAX_TIME_SLICE>;
+        pub(crate) type Scheduler = scheduler::PrioScheduler<TaskInner, MAX_TIME_SLICE>;
+    } else if #[cfg(feature = "sched_edf")] {
+        pub(crate) type AxTask = scheduler::EdfTask<TaskInner>;
+        pub(crate) type Scheduler = scheduler::EdfScheduler<TaskInner>;
     } else {
         // If no scheduler features are set, use FIFO as the default.
         pub(crate) type AxTask = scheduler::FifoTask<TaskInner>;
@@ -68,6 +86,7 @@ pub fn init_scheduler() {
     crate::run_queue::init();
     #[cfg(feature = "irq")]
     crate::timers::init();
+    crate::workqueue::init();
 
     info!("  use {} scheduler.", Scheduler::scheduler_name());
 }
@@ -75,16 +94,23 @@ pub fn init_scheduler() {
 /// Initializes the task scheduler for secondary CPUs.
 pub fn init_scheduler_secondary() {
     crate::run_queue::init_secondary();
+    #[cfg(feature = "irq")]
+    crate::timers::init();
 }
 
-/// Handles periodic timer ticks for the task manager.
+/// Handles timer interrupts for the task manager.
 ///
-/// For example, advance scheduler states, checks timed events, etc.
+/// For example, checks timed events, advances scheduler states if a tick is
+/// due, etc. Then it programs the one-shot timer for the next event or tick.
+/// The ticks are stopped while the CPU is idle, on uniprocessors only.
 #[cfg(feature = "irq")]
 #[doc(cfg(feature = "irq"))]
 pub fn on_timer_tick() {
     crate::timers::check_events();
-    RUN_QUEUE.lock().scheduler_timer_tick();
+    if crate::timers::check_tick() {
+        current_run_queue().scheduler_timer_tick();
+    }
+    crate::timers::reprogram_timer();
 }
 
 /// Spawns a new task with the given parameters.
@@ -95,7 +121,7 @@ where
     F: FnOnce() + Send + 'static,
 {
     let task = TaskInner::new(f, name, stack_size);
-    RUN_QUEUE.lock().add_task(task.clone());
+    current_run_queue().add_task(task.clone());
     task
 }
 
@@ -116,19 +142,82 @@ where
 ///
 /// The range of the priority is dependent on the underlying scheduler. For
 /// example, in the [CFS] scheduler, the priority is the nice value, ranging from
-/// -20 to 19.
+/// -20 to 19, and in the fixed-priority scheduler (feature `sched_prio`), it
+/// ranges from [`PRIO_MIN`](scheduler::PRIO_MIN) to
+/// [`PRIO_MAX`](scheduler::PRIO_MAX).
+///
+/// A smaller value means a higher priority. If the current task has inherited
+/// a higher priority, it keeps running with that priority until the inherited
+/// one is released.
 ///
 /// Returns `true` if the priority is set successfully.
 ///
 /// [CFS]: https://en.wikipedia.org/wiki/Completely_Fair_Scheduler
 pub fn set_priority(prio: isize) -> bool {
-    RUN_QUEUE.lock().set_current_priority(prio)
+    current_run_queue().set_current_priority(prio)
+}
+
+/// Sets how the current task is scheduled among the tasks with the same
+/// priority.
+#[cfg(feature = "sched_prio")]
+pub fn set_sched_policy(policy: SchedPolicy) {
+    // Disable IRQs so that the policy is not changed during a timer tick.
+    let _rq = current_run_queue();
+    current().as_task_ref().set_policy(policy);
+}
+
+/// Reserves `budget` timer ticks in every `period` ticks for the current task,
+/// whose deadline is the end of each period. A zero `period` removes the
+/// reservation.
+///
+/// Returns `false` if `budget` is zero or larger than `period`.
+#[cfg(feature = "sched_edf")]
+pub fn set_deadline_params(period: u64, budget: u64) -> bool {
+    // Disable IRQs so that the parameters are not changed during a timer tick.
+    let _rq = current_run_queue();
+    current().as_task_ref().set_params(period, budget)
+}
+
+/// Lets `task` inherit the priority `prio` of a task waiting for the resource
+/// identified by `key` (e.g., the address of a mutex), which is held by `task`.
+///
+/// The effective priority of `task` is the highest one of its own priority and
+/// the priorities inherited for all resources it holds. An inherited priority
+/// is kept until `task` releases the resource with
+/// [`release_inherited_priority`]. It has no effect on the scheduling if the
+/// scheduler does not support priorities.
+pub fn inherit_priority(task: &AxTaskRef, key: usize, prio: isize) {
+    current_run_queue().inherit_task_priority(task, key, prio);
+}
+
+/// Drops the priority inherited by the current task for the resource `key`
+/// with [`inherit_priority`], usually when the resource is released.
+///
+/// The effective priority of the current task is recomputed from the priority
+/// set by [`set_priority`] and the priorities inherited for other resources.
+pub fn release_inherited_priority(key: usize) {
+    current_run_queue().release_current_inherited_priority(key);
+}
+
+/// Sets the CPU affinity for current task.
+///
+/// The current task will be migrated to a CPU in the set immediately if the
+/// CPU it is running on is not in the set.
+///
+/// Returns `false` if the set does not contain any existing CPU.
+pub fn set_affinity(cpumask: CpuMask) -> bool {
+    current_run_queue().set_current_affinity(cpumask)
+}
+
+/// Gets the CPU affinity of current task.
+pub fn get_affinity() -> CpuMask {
+    current().cpumask()
 }
 
 /// Current task gives up the CPU time voluntarily, and switches to another
 /// ready task.
 pub fn yield_now() {
-    RUN_QUEUE.lock().yield_current();
+    current_run_queue().yield_current();
 }
 
 /// Current task is going to sleep for the given duration.
@@ -143,14 +232,14 @@ pub fn sleep(dur: core::time::Duration) {
 /// If the feature `irq` is not enabled, it uses busy-wait instead.
 pub fn sleep_until(deadline: axhal::time::TimeValue) {
     #[cfg(feature = "irq")]
-    RUN_QUEUE.lock().sleep_until(deadline);
+    current_run_queue().sleep_until(deadline);
     #[cfg(not(feature = "irq"))]
     axhal::time::busy_wait_until(deadline);
 }
 
 /// Exits the current task.
 pub fn exit(exit_code: i32) -> ! {
-    RUN_QUEUE.lock().exit_current(exit_code)
+    current_run_queue().exit_current(exit_code)
 }
 
 /// The idle task routine.
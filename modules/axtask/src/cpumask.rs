@@ -0,0 +1,98 @@
+use core::fmt;
+
+/// A set of CPUs, usually used as the CPU affinity of a task.
+///
+/// Bit `i` represents the CPU with ID `i`. CPUs whose ID is not less than
+/// [`axconfig::SMP`] are never included in the set.
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct CpuMask(usize);
+
+const _: () = assert!(
+    axconfig::SMP <= usize::BITS as usize,
+    "too many CPUs for `CpuMask`"
+);
+
+impl CpuMask {
+    const VALID_BITS: usize = if axconfig::SMP == usize::BITS as usize {
+        usize::MAX
+    } else {
+        (1 << axconfig::SMP) - 1
+    };
+
+    /// Creates an empty set.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Creates a set containing all CPUs.
+    pub const fn full() -> Self {
+        Self(Self::VALID_BITS)
+    }
+
+    /// Creates a set that only contains the given CPU.
+    ///
+    /// Returns an empty set if `cpu_id` is out of range.
+    pub const fn one_shot(cpu_id: usize) -> Self {
+        if cpu_id < axconfig::SMP {
+            Self(1 << cpu_id)
+        } else {
+            Self::new()
+        }
+    }
+
+    /// Creates a set from the raw bits, the bits of nonexistent CPUs are
+    /// ignored.
+    pub const fn from_raw_bits(bits: usize) -> Self {
+        Self(bits & Self::VALID_BITS)
+    }
+
+    /// Returns the raw bits of the set.
+    pub const fn bits(&self) -> usize {
+        self.0
+    }
+
+    /// Whether the set contains the given CPU.
+    pub const fn get(&self, cpu_id: usize) -> bool {
+        cpu_id < axconfig::SMP && self.0 & (1 << cpu_id) != 0
+    }
+
+    /// Adds the given CPU to the set (`value` is `true`), or removes it from
+    /// the set (`value` is `false`).
+    ///
+    /// Nonexistent CPUs are ignored.
+    pub fn set(&mut self, cpu_id: usize, value: bool) {
+        if cpu_id < axconfig::SMP {
+            if value {
+                self.0 |= 1 << cpu_id;
+            } else {
+                self.0 &= !(1 << cpu_id);
+            }
+        }
+    }
+
+    /// Whether the set contains no CPUs.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the smallest CPU ID in the set, or [`None`] if it's empty.
+    pub const fn first(&self) -> Option<usize> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.0.trailing_zeros() as usize)
+        }
+    }
+
+    /// Returns an iterator over the CPU IDs in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let bits = self.0;
+        (0..axconfig::SMP).filter(move |&i| bits & (1 << i) != 0)
+    }
+}
+
+impl fmt::Debug for CpuMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
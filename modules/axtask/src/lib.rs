@@ -11,8 +11,11 @@
 //!   Otherwise, only a few APIs with naive implementation is available.
 //! - `irq`: Interrupts are enabled. If this feature is enabled, timer-based
 //!    APIs can be used, such as [`sleep`], [`sleep_until`], and
-//!    [`WaitQueue::wait_timeout`].
+//!    [`WaitQueue::wait_timeout`], and kernel [`Timer`]s.
 //! - `preempt`: Enable preemptive scheduling.
+//! - `paging`: Map task stacks with guard pages below them, so that stack
+//!   overflows are detected by page faults. Otherwise, a canary at the bottom
+//!   of each stack is checked at every context switch.
 //! - `sched_fifo`: Use the [FIFO cooperative scheduler][1]. It also enables the
 //!   `multitask` feature if it is enabled. This feature is enabled by default,
 //!   and it can be overriden by other scheduler features.
@@ -20,10 +23,16 @@
 //!   the `multitask` and `preempt` features if it is enabled.
 //! - `sched_cfs`: Use the [Completely Fair Scheduler][3]. It also enables the
 //!   the `multitask` and `preempt` features if it is enabled.
+//! - `sched_prio`: Use the [fixed-priority real-time scheduler][4]. It also
+//!   enables the `multitask` and `preempt` features if it is enabled.
+//! - `sched_edf`: Use the [Earliest Deadline First scheduler][5]. It also
+//!   enables the `multitask` and `preempt` features if it is enabled.
 //!
 //! [1]: scheduler::FifoScheduler
 //! [2]: scheduler::RRScheduler
 //! [3]: scheduler::CFScheduler
+//! [4]: scheduler::PrioScheduler
+//! [5]: scheduler::EdfScheduler
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
@@ -35,14 +44,21 @@ cfg_if::cfg_if! {
         extern crate log;
         extern crate alloc;
 
+        mod cpumask;
         mod run_queue;
+        mod stack;
+        mod stats;
         mod task;
         mod api;
         mod wait_queue;
+        mod workqueue;
 
         #[cfg(feature = "irq")]
         mod timers;
 
+        #[cfg(test)]
+        mod tests;
+
         #[doc(cfg(feature = "multitask"))]
         pub use self::api::*;
         pub use self::api::{sleep, sleep_until, yield_now};
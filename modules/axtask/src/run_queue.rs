@@ -1,72 +1,188 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::ops::Deref;
+use kernel_guard::NoPreemptIrqSave;
 use lazy_init::LazyInit;
 use scheduler::BaseScheduler;
-use spinlock::SpinNoIrq;
+use spinlock::{SpinNoIrq, SpinRaw};
 
 use crate::task::{CurrentTask, TaskState};
-use crate::{AxTaskRef, Scheduler, TaskInner, WaitQueue};
+use crate::{AxTask, AxTaskRef, CpuMask, Scheduler, TaskInner, WaitQueue};
 
-// TODO: per-CPU
-pub(crate) static RUN_QUEUE: LazyInit<SpinNoIrq<AxRunQueue>> = LazyInit::new();
+#[percpu::def_percpu]
+static RUN_QUEUE: LazyInit<AxRunQueue> = LazyInit::new();
 
-// TODO: per-CPU
+#[percpu::def_percpu]
 static EXITED_TASKS: SpinNoIrq<VecDeque<AxTaskRef>> = SpinNoIrq::new(VecDeque::new());
 
+#[percpu::def_percpu]
 static WAIT_FOR_EXIT: WaitQueue = WaitQueue::new();
 
 #[percpu::def_percpu]
 static IDLE_TASK: LazyInit<AxTaskRef> = LazyInit::new();
 
+/// The task that was just switched out on this CPU, whose `on_cpu` flag
+/// should be cleared once the context switch is completed.
+#[percpu::def_percpu]
+static PREV_TASK_PTR: usize = 0;
+
+/// The task that was just switched out on this CPU but is not allowed to run
+/// on it, which should be put into the run queue of another CPU once the
+/// context switch is completed.
+#[percpu::def_percpu]
+static MIGRATING_TASK_PTR: usize = 0;
+
+/// The run queue of a CPU.
+///
+/// Every CPU owns one run queue. Only the owner CPU picks tasks from its run
+/// queue to run, except that an idle CPU may steal ready tasks from the run
+/// queues of other CPUs.
 pub(crate) struct AxRunQueue {
-    scheduler: Scheduler,
+    cpu_id: usize,
+    // IRQs and preemption are already disabled when accessing the run queue,
+    // see `current_run_queue()`.
+    scheduler: SpinRaw<Scheduler>,
+}
+
+/// A reference to the run queue of the current CPU.
+///
+/// IRQs and preemption are disabled until it is dropped, so the current task
+/// will not be migrated to other CPUs while holding it.
+pub(crate) struct CurrentRunQueueRef {
+    inner: &'static AxRunQueue,
+    _guard: NoPreemptIrqSave,
+}
+
+impl Deref for CurrentRunQueueRef {
+    type Target = AxRunQueue;
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
+/// Gets the run queue of the current CPU, with IRQs and preemption disabled.
+pub(crate) fn current_run_queue() -> CurrentRunQueueRef {
+    let guard = NoPreemptIrqSave::new();
+    // Safety: IRQs and preemption are disabled, and the run queue is
+    // initialized on the CPU before any task can run.
+    let inner = unsafe { RUN_QUEUE.current_ref_raw().get_unchecked() };
+    CurrentRunQueueRef {
+        inner,
+        _guard: guard,
+    }
+}
+
+/// Gets the run queue of the given CPU, or `None` if it is not initialized.
+fn remote_run_queue(cpu_id: usize) -> Option<&'static AxRunQueue> {
+    // Safety: `cpu_id` is less than `axconfig::SMP`, and the run queue will
+    // never be modified after initialization.
+    unsafe { RUN_QUEUE.remote_ref_raw(cpu_id).try_get() }
 }
 
 impl AxRunQueue {
-    pub fn new() -> SpinNoIrq<Self> {
-        let gc_task = TaskInner::new(gc_entry, "gc".into(), axconfig::TASK_STACK_SIZE);
+    pub fn new(cpu_id: usize) -> Self {
+        let gc_task = TaskInner::new(
+            move || gc_entry(cpu_id),
+            "gc".into(),
+            axconfig::TASK_STACK_SIZE,
+        );
+        gc_task.set_cpu_id(cpu_id);
         let mut scheduler = Scheduler::new();
         scheduler.add_task(gc_task);
-        SpinNoIrq::new(Self { scheduler })
+        Self {
+            cpu_id,
+            scheduler: SpinRaw::new(scheduler),
+        }
     }
 
-    pub fn add_task(&mut self, task: AxTaskRef) {
-        debug!("task spawn: {}", task.id_name());
+    /// Puts a new task into the run queue of a CPU it is allowed to run on,
+    /// preferring this run queue.
+    pub fn add_task(&self, task: AxTaskRef) {
+        let rq = self.select_run_queue(&task);
+        debug!("task spawn: {} on CPU {}", task.id_name(), rq.cpu_id);
         assert!(task.is_ready());
-        self.scheduler.add_task(task);
+        task.set_cpu_id(rq.cpu_id);
+        rq.scheduler.lock().add_task(task);
     }
 
     #[cfg(feature = "irq")]
-    pub fn scheduler_timer_tick(&mut self) {
+    pub fn scheduler_timer_tick(&self) {
         let curr = crate::current();
-        if !curr.is_idle() && self.scheduler.task_tick(curr.as_task_ref()) {
+        if !curr.is_idle() && self.scheduler.lock().task_tick(curr.as_task_ref()) {
             #[cfg(feature = "preempt")]
             curr.set_preempt_pending(true);
         }
     }
 
-    pub fn yield_current(&mut self) {
+    pub fn yield_current(&self) {
         let curr = crate::current();
         trace!("task yield: {}", curr.id_name());
         assert!(curr.is_running());
         self.resched(false);
     }
 
-    pub fn set_current_priority(&mut self, prio: isize) -> bool {
-        self.scheduler
-            .set_priority(crate::current().as_task_ref(), prio)
+    pub fn set_current_priority(&self, prio: isize) -> bool {
+        let curr = crate::current();
+        let mut state = curr.priority_state();
+        let mut scheduler = self.scheduler.lock();
+        if !scheduler.set_priority(curr.as_task_ref(), prio) {
+            return false;
+        }
+        state.base = prio;
+        // Keep the inherited priorities if they are higher.
+        let effective = state.effective();
+        if effective != prio {
+            scheduler.set_priority(curr.as_task_ref(), effective);
+        }
+        curr.set_effective_priority(effective);
+        true
+    }
+
+    /// Lets `task` inherit the priority `prio` of a task waiting for the
+    /// resource `key` held by `task`, and raises its effective priority if
+    /// `prio` is higher.
+    ///
+    /// The task may be in the run queue of another CPU, or running on it, so
+    /// its priority is changed through the run queue it was put into.
+    pub fn inherit_task_priority(&self, task: &AxTaskRef, key: usize, prio: isize) {
+        let mut state = task.priority_state();
+        match state.inherited.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, inherited)) => *inherited = prio.min(*inherited),
+            None => state.inherited.push((key, prio)),
+        }
+        let effective = state.effective();
+        if effective < task.priority() {
+            debug!("task boost priority: {} to {}", task.id_name(), effective);
+            self.set_task_priority(task, effective);
+        }
+    }
+
+    /// Drops the priority the current task inherited for the resource `key`,
+    /// and recomputes its effective priority from the base priority and the
+    /// priorities inherited for other resources.
+    pub fn release_current_inherited_priority(&self, key: usize) {
+        let curr = crate::current();
+        let mut state = curr.priority_state();
+        let Some(index) = state.inherited.iter().position(|(k, _)| *k == key) else {
+            return;
+        };
+        state.inherited.swap_remove(index);
+        let effective = state.effective();
+        if effective != curr.priority() {
+            debug!("task restore priority: {} to {}", curr.id_name(), effective);
+            self.set_task_priority(curr.as_task_ref(), effective);
+        }
     }
 
     #[cfg(feature = "preempt")]
-    pub fn preempt_resched(&mut self) {
+    pub fn preempt_resched(&self) {
         let curr = crate::current();
         assert!(curr.is_running());
 
-        // When we get the mutable reference of the run queue, we must
-        // have held the `SpinNoIrq` lock with both IRQs and preemption
-        // disabled. So we need to set `current_disable_count` to 1 in
-        // `can_preempt()` to obtain the preemption permission before
-        //  locking the run queue.
+        // When we get the reference of the current run queue, we must have
+        // both IRQs and preemption disabled. So we need to set
+        // `current_disable_count` to 1 in `can_preempt()` to obtain the
+        // preemption permission.
         let can_preempt = curr.can_preempt(1);
 
         debug!(
@@ -81,25 +197,25 @@ impl AxRunQueue {
         }
     }
 
-    pub fn exit_current(&mut self, exit_code: i32) -> ! {
+    pub fn exit_current(&self, exit_code: i32) -> ! {
         let curr = crate::current();
         debug!("task exit: {}, exit_code={}", curr.id_name(), exit_code);
         assert!(curr.is_running());
         assert!(!curr.is_idle());
         if curr.is_init() {
-            EXITED_TASKS.lock().clear();
+            EXITED_TASKS.with_current(|t| t.lock().clear());
             axhal::misc::terminate();
         } else {
             curr.set_state(TaskState::Exited);
             curr.notify_exit(exit_code, self);
-            EXITED_TASKS.lock().push_back(curr.clone());
-            WAIT_FOR_EXIT.notify_one_locked(false, self);
+            EXITED_TASKS.with_current(|t| t.lock().push_back(curr.clone()));
+            WAIT_FOR_EXIT.with_current(|wq| wq.notify_one_locked(false, self));
             self.resched(false);
         }
         unreachable!("task exited!");
     }
 
-    pub fn block_current<F>(&mut self, wait_queue_push: F)
+    pub fn block_current<F>(&self, wait_queue_push: F)
     where
         F: FnOnce(AxTaskRef),
     {
@@ -108,8 +224,10 @@ impl AxRunQueue {
         assert!(curr.is_running());
         assert!(!curr.is_idle());
 
-        // we must not block current task with preemption disabled.
-        #[cfg(feature = "preempt")]
+        // we must not block current task with preemption disabled. The
+        // preemption is never disabled by the kernel guards on non-bare-metal
+        // targets (e.g., in unit tests), so it can't be checked there.
+        #[cfg(all(feature = "preempt", target_os = "none"))]
         assert!(curr.can_preempt(1));
 
         curr.set_state(TaskState::Blocked);
@@ -117,11 +235,23 @@ impl AxRunQueue {
         self.resched(false);
     }
 
-    pub fn unblock_task(&mut self, task: AxTaskRef, resched: bool) {
+    /// Wakes up a blocked task and puts it into this run queue, or the run
+    /// queue of another CPU if the task is not allowed to run on this CPU.
+    ///
+    /// The task may be blocked on another CPU, and it is still possible that
+    /// it has not been switched out completely there.
+    pub fn unblock_task(&self, task: AxTaskRef, resched: bool) {
         debug!("task unblock: {}", task.id_name());
-        if task.is_blocked() {
-            task.set_state(TaskState::Ready);
-            self.scheduler.add_task(task); // TODO: priority
+        if task.transition_state(TaskState::Blocked, TaskState::Ready) {
+            task.account_wakeup();
+            // Wait until the task has been switched out on its CPU, to avoid
+            // running it on two CPUs at the same time.
+            while task.on_cpu() {
+                core::hint::spin_loop();
+            }
+            let rq = self.select_run_queue(&task);
+            task.set_cpu_id(rq.cpu_id);
+            rq.scheduler.lock().add_task(task); // TODO: priority
             if resched {
                 #[cfg(feature = "preempt")]
                 crate::current().set_preempt_pending(true);
@@ -129,8 +259,24 @@ impl AxRunQueue {
         }
     }
 
+    /// Sets the CPU affinity of the current task, and migrates it to an
+    /// allowed CPU if this CPU is not in the new set.
+    pub fn set_current_affinity(&self, cpumask: CpuMask) -> bool {
+        let curr = crate::current();
+        if !curr.set_cpumask(cpumask) {
+            return false;
+        }
+        if !cpumask.get(self.cpu_id) {
+            debug!("task migrate: {} from CPU {}", curr.id_name(), self.cpu_id);
+            assert!(curr.is_running());
+            // `resched` will put the current task into an allowed run queue.
+            self.resched(false);
+        }
+        true
+    }
+
     #[cfg(feature = "irq")]
-    pub fn sleep_until(&mut self, deadline: axhal::time::TimeValue) {
+    pub fn sleep_until(&self, deadline: axhal::time::TimeValue) {
         let curr = crate::current();
         debug!("task sleep: {}, deadline={:?}", curr.id_name(), deadline);
         assert!(curr.is_running());
@@ -148,27 +294,140 @@ impl AxRunQueue {
 impl AxRunQueue {
     /// Common reschedule subroutine. If `preempt`, keep current task's time
     /// slice, otherwise reset it.
-    fn resched(&mut self, preempt: bool) {
+    fn resched(&self, preempt: bool) {
         let prev = crate::current();
         if prev.is_running() {
             prev.set_state(TaskState::Ready);
             if !prev.is_idle() {
-                self.scheduler.put_prev_task(prev.clone(), preempt);
+                if core::ptr::eq(self.select_run_queue(prev.as_task_ref()), self) {
+                    self.scheduler.lock().put_prev_task(prev.clone(), preempt);
+                } else {
+                    self.migrate_task(prev.clone());
+                }
             }
         }
-        let next = self.scheduler.pick_next_task().unwrap_or_else(|| unsafe {
-            // Safety: IRQs must be disabled at this time.
-            IDLE_TASK.current_ref_raw().get_unchecked().clone()
-        });
+
+        let next = self
+            .pick_next_task()
+            .or_else(|| self.steal_task())
+            .unwrap_or_else(|| unsafe {
+                // Safety: IRQs must be disabled at this time.
+                IDLE_TASK.current_ref_raw().get_unchecked().clone()
+            });
         self.switch_to(prev, next);
     }
 
-    fn switch_to(&mut self, prev_task: CurrentTask, next_task: AxTaskRef) {
+    /// Selects the run queue to put the given task, which is this run queue if
+    /// the task is allowed to run on this CPU, or the run queue of the first
+    /// allowed CPU otherwise.
+    ///
+    /// If none of the allowed CPUs is online, this run queue is selected.
+    fn select_run_queue(&self, task: &AxTaskRef) -> &AxRunQueue {
+        let cpumask = task.cpumask();
+        if cpumask.get(self.cpu_id) {
+            return self;
+        }
+        cpumask.iter().find_map(remote_run_queue).unwrap_or(self)
+    }
+
+    /// Sets the effective priority of `task` through the run queue it was put
+    /// into, which is not necessarily this run queue. The priority state of
+    /// `task` must be locked.
+    fn set_task_priority(&self, task: &AxTaskRef, prio: isize) {
+        loop {
+            let cpu_id = task.cpu_id();
+            let rq = remote_run_queue(cpu_id).unwrap_or(self);
+            let mut scheduler = rq.scheduler.lock();
+            // The task may have been moved to another run queue before the
+            // lock is acquired.
+            if task.cpu_id() != cpu_id {
+                continue;
+            }
+            if scheduler.set_priority(task, prio) {
+                task.set_effective_priority(prio);
+            }
+            return;
+        }
+    }
+
+    /// Moves a ready task which is not allowed to run on this CPU to the run
+    /// queue of an allowed CPU.
+    ///
+    /// If the task is being switched out on this CPU, it is moved after the
+    /// context switch is completed (see [`finish_task_switch`]), so that
+    /// other CPUs never pick a task whose context has not been saved.
+    fn migrate_task(&self, task: AxTaskRef) {
+        if task.on_cpu() {
+            // Safety: IRQs are disabled, and only the previous task of this
+            // CPU can be on it here.
+            unsafe {
+                assert_eq!(MIGRATING_TASK_PTR.read_current_raw(), 0);
+                MIGRATING_TASK_PTR.write_current_raw(Arc::into_raw(task) as usize);
+            }
+            return;
+        }
+        let rq = self.select_run_queue(&task);
+        debug!(
+            "task migrate: {} from CPU {} to CPU {}",
+            task.id_name(),
+            self.cpu_id,
+            rq.cpu_id
+        );
+        task.set_cpu_id(rq.cpu_id);
+        rq.scheduler.lock().add_task(task);
+    }
+
+    /// Picks the next task to run from this run queue.
+    ///
+    /// The affinity of a ready task may be changed after it was put into this
+    /// run queue, such tasks are moved to the run queues of allowed CPUs.
+    fn pick_next_task(&self) -> Option<AxTaskRef> {
+        loop {
+            let task = self.scheduler.lock().pick_next_task()?;
+            if core::ptr::eq(self.select_run_queue(&task), self) {
+                return Some(task);
+            }
+            self.migrate_task(task);
+        }
+    }
+
+    /// Steals a ready task from the run queues of other CPUs, which is called
+    /// when there are no ready tasks in this run queue.
+    fn steal_task(&self) -> Option<AxTaskRef> {
+        let victims = (self.cpu_id + 1..axconfig::SMP).chain(0..self.cpu_id);
+        victims
+            .filter_map(remote_run_queue)
+            .find_map(|victim| self.steal_task_from(victim))
+    }
+
+    /// Steals a ready task from the run queue `victim`.
+    fn steal_task_from(&self, victim: &AxRunQueue) -> Option<AxTaskRef> {
+        // Do not wait for a busy CPU, just try the next one.
+        let mut scheduler = victim.scheduler.try_lock()?;
+        // A task which was just put back by the victim may have not been
+        // switched out yet, leave it to the victim. Tasks not allowed to run
+        // on this CPU are skipped, without changing their order.
+        let task = scheduler.pick_next_task_if(|t| !t.on_cpu() && t.cpumask().get(self.cpu_id))?;
+        debug!(
+            "task steal: {} from CPU {} to CPU {}",
+            task.id_name(),
+            victim.cpu_id,
+            self.cpu_id
+        );
+        task.set_cpu_id(self.cpu_id);
+        Some(task)
+    }
+
+    fn switch_to(&self, prev_task: CurrentTask, next_task: AxTaskRef) {
         trace!(
             "context switch: {} -> {}",
             prev_task.id_name(),
             next_task.id_name()
         );
+        #[cfg(not(feature = "paging"))]
+        prev_task.check_stack_canary();
+        #[cfg(feature = "paging")]
+        crate::stack::flush_stale_tlb();
         #[cfg(feature = "preempt")]
         next_task.set_preempt_pending(false);
         next_task.set_state(TaskState::Running);
@@ -176,6 +435,17 @@ impl AxRunQueue {
             return;
         }
 
+        // Tasks are put into the run queues of other CPUs or stolen only after
+        // they are switched out completely.
+        assert!(!next_task.on_cpu());
+        next_task.set_on_cpu(true);
+        #[cfg(feature = "irq")]
+        crate::timers::update_tick(next_task.is_idle());
+
+        let now = axhal::time::current_time_nanos();
+        prev_task.account_switch_out(now);
+        next_task.account_switch_in(now);
+
         unsafe {
             let prev_ctx_ptr = prev_task.ctx_mut_ptr();
             let next_ctx_ptr = next_task.ctx_mut_ptr();
@@ -185,49 +455,136 @@ impl AxRunQueue {
             assert!(Arc::strong_count(prev_task.as_task_ref()) > 1);
             assert!(Arc::strong_count(&next_task) >= 1);
 
+            PREV_TASK_PTR.write_current_raw(Arc::as_ptr(prev_task.as_task_ref()) as usize);
             CurrentTask::set_current(prev_task, next_task);
             (*prev_ctx_ptr).switch_to(&*next_ctx_ptr);
+
+            // We are now running the next task on this CPU (maybe a different
+            // CPU from the one before the switch).
+            finish_task_switch();
         }
     }
 }
 
-fn gc_entry() {
+/// Clears the `on_cpu` flag of the task that was just switched out on the
+/// current CPU, so that it can be run by other CPUs, and moves it to another
+/// run queue if it is migrating.
+///
+/// # Safety
+///
+/// It must be called right after the context switch, with IRQs disabled.
+pub(crate) unsafe fn finish_task_switch() {
+    let prev_ptr = PREV_TASK_PTR.read_current_raw() as *const AxTask;
+    if !prev_ptr.is_null() {
+        PREV_TASK_PTR.write_current_raw(0);
+        // `prev` will not be dropped before its `on_cpu` flag is cleared.
+        (*prev_ptr).set_on_cpu(false);
+    }
+    let migrating_ptr = MIGRATING_TASK_PTR.read_current_raw() as *const AxTask;
+    if !migrating_ptr.is_null() {
+        MIGRATING_TASK_PTR.write_current_raw(0);
+        let task = Arc::from_raw(migrating_ptr);
+        RUN_QUEUE
+            .current_ref_raw()
+            .get_unchecked()
+            .migrate_task(task);
+    }
+}
+
+fn gc_entry(cpu_id: usize) {
+    // Safety: `cpu_id` is valid, and these per-CPU data are protected by
+    // locks.
+    let exited_tasks = unsafe { EXITED_TASKS.remote_ref_raw(cpu_id) };
+    let wait_for_exit = unsafe { WAIT_FOR_EXIT.remote_ref_raw(cpu_id) };
     loop {
         // Drop all exited tasks and recycle resources.
-        let n = EXITED_TASKS.lock().len();
+        let n = exited_tasks.lock().len();
         for _ in 0..n {
             // Do not do the slow drops in the critical section.
-            let task = EXITED_TASKS.lock().pop_front();
+            let task = exited_tasks.lock().pop_front();
             if let Some(task) = task {
-                if Arc::strong_count(&task) == 1 {
+                if Arc::strong_count(&task) == 1 && !task.on_cpu() {
                     // If I'm the last holder of the task, drop it immediately.
                     drop(task);
                 } else {
                     // Otherwise (e.g, `switch_to` is not compeleted, held by the
                     // joiner, etc), push it back and wait for them to drop first.
-                    EXITED_TASKS.lock().push_back(task);
+                    exited_tasks.lock().push_back(task);
                 }
             }
         }
-        WAIT_FOR_EXIT.wait();
+        wait_for_exit.wait();
     }
 }
 
 pub(crate) fn init() {
     const IDLE_TASK_STACK_SIZE: usize = 4096;
+    let cpu_id = axhal::cpu::this_cpu_id();
     let idle_task = TaskInner::new(|| crate::run_idle(), "idle".into(), IDLE_TASK_STACK_SIZE);
+    idle_task.set_cpu_id(cpu_id);
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
 
     let main_task = TaskInner::new_init("main".into());
     main_task.set_state(TaskState::Running);
+    main_task.set_on_cpu(true);
+    main_task.set_cpu_id(cpu_id);
 
-    RUN_QUEUE.init_by(AxRunQueue::new());
+    #[cfg(feature = "paging")]
+    crate::stack::init_percpu();
+    RUN_QUEUE.with_current(|rq| rq.init_by(AxRunQueue::new(cpu_id)));
     unsafe { CurrentTask::init_current(main_task) }
 }
 
 pub(crate) fn init_secondary() {
+    let cpu_id = axhal::cpu::this_cpu_id();
     let idle_task = TaskInner::new_init("idle".into());
     idle_task.set_state(TaskState::Running);
+    idle_task.set_on_cpu(true);
+    idle_task.set_cpu_id(cpu_id);
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
+
+    #[cfg(feature = "paging")]
+    crate::stack::init_percpu();
+    RUN_QUEUE.with_current(|rq| rq.init_by(AxRunQueue::new(cpu_id)));
     unsafe { CurrentTask::init_current(idle_task) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AxRunQueue;
+    use crate::tests::{INIT, SERIAL};
+    use crate::{self as axtask, TaskInner};
+
+    fn new_task(name: &str) -> crate::AxTaskRef {
+        TaskInner::new(|| {}, name.into(), 0x1000)
+    }
+
+    #[test]
+    fn test_steal_task() {
+        let _lock = SERIAL.lock();
+        INIT.call_once(axtask::init_scheduler);
+
+        let thief = AxRunQueue::new(0);
+        let victim = AxRunQueue::new(0);
+        // Take the GC task of the victim first.
+        assert_eq!(thief.steal_task_from(&victim).unwrap().name(), "gc");
+
+        let (t1, t2) = (new_task("T1"), new_task("T2"));
+        victim.add_task(t1.clone());
+        victim.add_task(t2.clone());
+        // `T1` is still running on the victim, skip it.
+        t1.set_on_cpu(true);
+        assert!(alloc::sync::Arc::ptr_eq(
+            &thief.steal_task_from(&victim).unwrap(),
+            &t2
+        ));
+        assert!(thief.steal_task_from(&victim).is_none());
+
+        t1.set_on_cpu(false);
+        assert!(alloc::sync::Arc::ptr_eq(
+            &thief.steal_task_from(&victim).unwrap(),
+            &t1
+        ));
+        assert!(thief.steal_task_from(&victim).is_none());
+    }
+}
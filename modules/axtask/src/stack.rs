@@ -0,0 +1,280 @@
+//! Task stacks.
+//!
+//! When the `paging` feature is enabled, task stacks are mapped in a dedicated
+//! region of the kernel address space (see [`axconfig::TASK_STACK_REGION_BASE`]),
+//! with an unmapped guard page below each stack, so that a stack overflow
+//! causes a page fault. Otherwise, they are allocated from the heap, and a
+//! canary at the bottom of each stack is checked at every context switch.
+
+use memory_addr::VirtAddr;
+
+/// The byte that a new stack is filled with, to find out how much of the
+/// stack has been used.
+const STACK_PAINT_BYTE: u8 = 0xcc;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "paging")] {
+        use alloc::vec::Vec;
+        use core::sync::atomic::{AtomicU64, Ordering};
+
+        use axhal::mem::{virt_to_phys, PAGE_SIZE_4K};
+        use axhal::paging::MappingFlags;
+        use spinlock::SpinNoIrq;
+
+        /// Allocates the virtual address ranges of stacks in the task stack
+        /// region, and maps them to newly allocated frames.
+        ///
+        /// A freed stack is unmapped at once, but the TLBs of other CPUs may
+        /// still cache its mappings. So its frames and its address range are
+        /// reclaimed only after every CPU has flushed its TLB (see
+        /// [`flush_stale_tlb`]).
+        struct StackRegion {
+            /// The start of the unused part of the region.
+            next: usize,
+            /// The free address ranges below `next`, including the guard pages,
+            /// as sorted and non-adjacent `(start, end)` pairs.
+            free: Vec<(usize, usize)>,
+            /// The unmapped stacks that are not reclaimed yet.
+            pending: Vec<UnmappedStack>,
+        }
+
+        struct UnmappedStack {
+            base: usize,
+            size: usize,
+            frames: usize,
+            /// The value of [`UNMAP_GEN`] after the stack is unmapped.
+            gen: u64,
+        }
+
+        static STACK_REGION: SpinNoIrq<StackRegion> = SpinNoIrq::new(StackRegion {
+            next: axconfig::TASK_STACK_REGION_BASE,
+            free: Vec::new(),
+            pending: Vec::new(),
+        });
+
+        /// Incremented every time a stack is unmapped.
+        static UNMAP_GEN: AtomicU64 = AtomicU64::new(0);
+
+        /// The value of [`UNMAP_GEN`] when each CPU last flushed its TLB, or
+        /// `u64::MAX` if the CPU is not online.
+        static FLUSHED_GEN: [AtomicU64; axconfig::SMP] =
+            [const { AtomicU64::new(u64::MAX) }; axconfig::SMP];
+
+        /// Starts tracking the TLB of the current CPU, before it runs any task.
+        pub(crate) fn init_percpu() {
+            let cpu_id = axhal::cpu::this_cpu_id();
+            FLUSHED_GEN[cpu_id].store(UNMAP_GEN.load(Ordering::Acquire), Ordering::Release);
+        }
+
+        /// Flushes the TLB of the current CPU if any stack has been unmapped
+        /// since the last flush, so that the stack can be reclaimed.
+        ///
+        /// It is called at every context switch.
+        pub(crate) fn flush_stale_tlb() {
+            let cpu_id = axhal::cpu::this_cpu_id();
+            let gen = UNMAP_GEN.load(Ordering::Acquire);
+            if FLUSHED_GEN[cpu_id].load(Ordering::Relaxed) < gen {
+                axhal::arch::flush_tlb(None);
+                FLUSHED_GEN[cpu_id].store(gen, Ordering::Release);
+            }
+        }
+
+        impl StackRegion {
+            /// Returns the base of a mapped stack of `size` bytes, with an
+            /// unmapped guard page below it, and the frames mapped to it.
+            fn alloc(&mut self, size: usize) -> (usize, usize) {
+                self.reclaim();
+                let len = size + PAGE_SIZE_4K;
+                let start = if let Some(idx) = self.free.iter().position(|&(s, e)| e - s >= len) {
+                    let (start, end) = self.free[idx];
+                    if end - start == len {
+                        self.free.remove(idx);
+                    } else {
+                        self.free[idx].0 = start + len;
+                    }
+                    start
+                } else {
+                    let region_end =
+                        axconfig::TASK_STACK_REGION_BASE + axconfig::TASK_STACK_REGION_SIZE;
+                    if region_end - self.next < len {
+                        panic!("task stack region exhausted");
+                    }
+                    self.next += len;
+                    self.next - len
+                };
+                let base = start + PAGE_SIZE_4K;
+                let frames = axalloc::global_allocator()
+                    .alloc_pages(size / PAGE_SIZE_4K, PAGE_SIZE_4K)
+                    .expect("failed to allocate task stack");
+                axhal::paging::kernel_page_table()
+                    .map_region(
+                        base.into(),
+                        virt_to_phys(frames.into()),
+                        size,
+                        MappingFlags::READ | MappingFlags::WRITE,
+                        false,
+                    )
+                    .expect("failed to map task stack");
+                // Entries for invalid mappings may be cached.
+                axhal::arch::flush_tlb(None);
+                (base, frames)
+            }
+
+            fn dealloc(&mut self, base: usize, size: usize, frames: usize) {
+                axhal::paging::kernel_page_table()
+                    .unmap_region(base.into(), size)
+                    .expect("failed to unmap task stack");
+                axhal::arch::flush_tlb(None);
+                // Unmappings are serialized by the lock, so the flush above
+                // covers all the stacks unmapped so far.
+                let gen = UNMAP_GEN.fetch_add(1, Ordering::AcqRel) + 1;
+                FLUSHED_GEN[axhal::cpu::this_cpu_id()].store(gen, Ordering::Release);
+                self.pending.push(UnmappedStack {
+                    base,
+                    size,
+                    frames,
+                    gen,
+                });
+            }
+
+            /// Frees the frames and the address ranges of the unmapped stacks
+            /// that are no longer cached in any TLB.
+            fn reclaim(&mut self) {
+                let flushed = FLUSHED_GEN
+                    .iter()
+                    .map(|gen| gen.load(Ordering::Acquire))
+                    .min()
+                    .unwrap();
+                let mut i = 0;
+                while i < self.pending.len() {
+                    if self.pending[i].gen > flushed {
+                        i += 1;
+                        continue;
+                    }
+                    let stack = self.pending.swap_remove(i);
+                    axalloc::global_allocator()
+                        .dealloc_pages(stack.frames, stack.size / PAGE_SIZE_4K);
+                    self.insert_free(stack.base - PAGE_SIZE_4K, stack.base + stack.size);
+                }
+            }
+
+            /// Inserts a free address range, merging it with the adjacent ones.
+            fn insert_free(&mut self, mut start: usize, mut end: usize) {
+                let mut idx = self.free.partition_point(|&(s, _)| s < start);
+                if idx < self.free.len() && self.free[idx].0 == end {
+                    end = self.free.remove(idx).1;
+                }
+                if idx > 0 && self.free[idx - 1].1 == start {
+                    idx -= 1;
+                    start = self.free.remove(idx).0;
+                }
+                if end == self.next {
+                    self.next = start;
+                } else {
+                    self.free.insert(idx, (start, end));
+                }
+            }
+        }
+
+        pub(crate) struct TaskStack {
+            base: usize,
+            size: usize,
+            frames: usize,
+        }
+
+        impl TaskStack {
+            /// Allocates a stack of `size` bytes, which must be page-aligned.
+            pub fn alloc(size: usize) -> Self {
+                let (base, frames) = STACK_REGION.lock().alloc(size);
+                let stack = Self { base, size, frames };
+                unsafe { stack.bottom().write_bytes(STACK_PAINT_BYTE, size) };
+                stack
+            }
+
+            fn bottom(&self) -> *mut u8 {
+                self.base as *mut u8
+            }
+
+            pub fn size(&self) -> usize {
+                self.size
+            }
+
+            /// Whether `vaddr` is in the guard page below the stack.
+            pub fn guard_contains(&self, vaddr: VirtAddr) -> bool {
+                (self.base - PAGE_SIZE_4K..self.base).contains(&vaddr.as_usize())
+            }
+        }
+
+        impl Drop for TaskStack {
+            fn drop(&mut self) {
+                STACK_REGION.lock().dealloc(self.base, self.size, self.frames);
+            }
+        }
+    } else {
+        use core::{alloc::Layout, ptr::NonNull};
+
+        /// The value at the bottom of every stack, which is overwritten when
+        /// the stack overflows.
+        const STACK_CANARY: u64 = 0x5354_4143_4b43_414e; // "STACKCAN"
+
+        const CANARY_SIZE: usize = core::mem::size_of::<u64>();
+
+        pub(crate) struct TaskStack {
+            ptr: NonNull<u8>,
+            layout: Layout,
+        }
+
+        impl TaskStack {
+            pub fn alloc(size: usize) -> Self {
+                let layout = Layout::from_size_align(size, 16).unwrap();
+                let ptr = NonNull::new(unsafe { alloc::alloc::alloc(layout) }).unwrap();
+                unsafe {
+                    ptr.as_ptr().write_bytes(STACK_PAINT_BYTE, size);
+                    ptr.as_ptr().cast::<u64>().write(STACK_CANARY);
+                }
+                Self { ptr, layout }
+            }
+
+            fn bottom(&self) -> *mut u8 {
+                self.ptr.as_ptr()
+            }
+
+            pub fn size(&self) -> usize {
+                self.layout.size()
+            }
+
+            /// Whether the canary at the bottom of the stack is intact.
+            pub fn canary_intact(&self) -> bool {
+                unsafe { self.bottom().cast::<u64>().read_volatile() == STACK_CANARY }
+            }
+        }
+
+        impl Drop for TaskStack {
+            fn drop(&mut self) {
+                unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+            }
+        }
+    }
+}
+
+impl TaskStack {
+    pub fn top(&self) -> VirtAddr {
+        VirtAddr::from(self.bottom() as usize + self.size())
+    }
+
+    /// Returns the maximum number of bytes ever used, by finding the lowest
+    /// byte that is not the paint byte (and not a part of the canary).
+    ///
+    /// The stack may be in use, so it is read with volatile reads.
+    pub fn high_water_mark(&self) -> usize {
+        #[cfg(feature = "paging")]
+        let start = 0;
+        #[cfg(not(feature = "paging"))]
+        let start = CANARY_SIZE;
+        let bottom = self.bottom();
+        let untouched = (start..self.size())
+            .find(|&i| unsafe { bottom.add(i).read_volatile() } != STACK_PAINT_BYTE)
+            .unwrap_or(self.size());
+        self.size() - untouched
+    }
+}
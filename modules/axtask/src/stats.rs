@@ -0,0 +1,73 @@
+//! The table of live tasks and their statistics.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+
+use spinlock::SpinNoIrq;
+
+use crate::{AxTask, AxTaskRef, TaskId, TaskState};
+
+/// All tasks that have not been dropped, keyed by the task IDs.
+static TASK_TABLE: SpinNoIrq<BTreeMap<u64, Weak<AxTask>>> = SpinNoIrq::new(BTreeMap::new());
+
+/// A snapshot of the statistics of a task, see [`TaskInner::stats`].
+///
+/// [`TaskInner::stats`]: crate::TaskInner::stats
+#[derive(Debug, Clone)]
+pub struct TaskStats {
+    /// The task ID.
+    pub id: u64,
+    /// The task name.
+    pub name: String,
+    /// The current state.
+    pub state: TaskState,
+    /// The CPU that the task is running on or was put into the run queue of.
+    pub cpu_id: usize,
+    /// The effective priority.
+    pub priority: isize,
+    /// The total time running on CPUs, in nanoseconds.
+    pub runtime_ns: u64,
+    /// The number of times the task is switched out.
+    pub context_switches: u64,
+    /// The number of times the task is woken up after it is blocked.
+    pub wakeups: u64,
+    /// The size of the task stack in bytes, or 0 for the tasks running on the
+    /// boot stacks (e.g., the main task).
+    pub stack_size: usize,
+    /// The maximum number of bytes of the task stack that have been used.
+    pub stack_used: usize,
+}
+
+pub(crate) fn register_task(task: AxTaskRef) -> AxTaskRef {
+    TASK_TABLE
+        .lock()
+        .insert(task.id().as_u64(), Arc::downgrade(&task));
+    task
+}
+
+pub(crate) fn unregister_task(id: TaskId) {
+    TASK_TABLE.lock().remove(&id.as_u64());
+}
+
+/// Calls `f` on every task that has not been dropped, in the order of the
+/// task IDs.
+///
+/// The tasks are collected first, so `f` may spawn or drop tasks.
+pub fn for_each_task<F: FnMut(&AxTaskRef)>(mut f: F) {
+    let tasks: Vec<AxTaskRef> = TASK_TABLE
+        .lock()
+        .values()
+        .filter_map(Weak::upgrade)
+        .collect();
+    tasks.iter().for_each(&mut f);
+}
+
+/// Returns the statistics of all tasks that have not been dropped, in the
+/// order of the task IDs.
+pub fn task_stats() -> Vec<TaskStats> {
+    let mut stats = Vec::new();
+    for_each_task(|task| stats.push(task.stats()));
+    stats
+}
@@ -1,18 +1,19 @@
-use alloc::{boxed::Box, string::String, sync::Arc};
+use alloc::{boxed::Box, string::String, sync::Arc, vec::Vec};
 use core::ops::Deref;
-use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, Ordering};
-use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
-
-#[cfg(feature = "preempt")]
-use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{
+    AtomicBool, AtomicI32, AtomicIsize, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+use core::{cell::UnsafeCell, fmt};
 
 #[cfg(feature = "tls")]
 use axhal::tls::TlsArea;
 
 use axhal::arch::TaskContext;
 use memory_addr::{align_up_4k, VirtAddr};
+use spinlock::{SpinNoIrq, SpinNoIrqGuard};
 
-use crate::{AxRunQueue, AxTask, AxTaskRef, WaitQueue};
+use crate::stack::TaskStack;
+use crate::{AxRunQueue, AxTask, AxTaskRef, CpuMask, TaskStats, WaitQueue};
 
 /// A unique identifier for a thread.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -21,13 +22,35 @@ pub struct TaskId(u64);
 /// The possible states of a task.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub(crate) enum TaskState {
+pub enum TaskState {
+    /// The task is running on a CPU.
     Running = 1,
+    /// The task is ready to run, in a run queue.
     Ready = 2,
+    /// The task is blocked, e.g., in a wait queue or sleeping.
     Blocked = 3,
+    /// The task has exited, but has not been dropped.
     Exited = 4,
 }
 
+/// The priority set by the task itself, and the priorities it inherits.
+pub(crate) struct TaskPriority {
+    /// The priority set by [`set_priority`](crate::set_priority).
+    pub base: isize,
+    /// The highest priorities of the tasks waiting for the resources held by
+    /// the task, keyed by the resources.
+    pub inherited: Vec<(usize, isize)>,
+}
+
+impl TaskPriority {
+    /// The highest one of the base priority and the inherited priorities.
+    pub fn effective(&self) -> isize {
+        self.inherited
+            .iter()
+            .fold(self.base, |prio, &(_, inherited)| prio.min(inherited))
+    }
+}
+
 /// The inner task structure.
 pub struct TaskInner {
     id: TaskId,
@@ -37,11 +60,30 @@ pub struct TaskInner {
 
     entry: Option<*mut dyn FnOnce()>,
     state: AtomicU8,
+    /// Whether the task is running on a CPU, or its context has not been
+    /// saved completely after it is switched out.
+    on_cpu: AtomicBool,
+    /// The CPUs that the task is allowed to run on.
+    cpumask: AtomicUsize,
+    /// The CPU whose run queue the task was put into last time.
+    cpu_id: AtomicUsize,
+
+    /// The effective priority, which may be higher than the base priority
+    /// when the task inherits priorities from others.
+    priority: AtomicIsize,
+    priority_state: SpinNoIrq<TaskPriority>,
 
     in_wait_queue: AtomicBool,
     #[cfg(feature = "irq")]
     in_timer_list: AtomicBool,
 
+    /// The time running on CPUs in nanoseconds, excluding the current run.
+    runtime_ns: AtomicU64,
+    /// When the task was switched in last time, in nanoseconds.
+    last_run_ns: AtomicU64,
+    context_switches: AtomicU64,
+    wakeups: AtomicU64,
+
     #[cfg(feature = "preempt")]
     need_resched: AtomicBool,
     #[cfg(feature = "preempt")]
@@ -101,6 +143,86 @@ impl TaskInner {
         alloc::format!("Task({}, {:?})", self.id.as_u64(), self.name)
     }
 
+    /// Gets the set of CPUs that the task is allowed to run on.
+    pub fn cpumask(&self) -> CpuMask {
+        CpuMask::from_raw_bits(self.cpumask.load(Ordering::Acquire))
+    }
+
+    /// Sets the set of CPUs that the task is allowed to run on.
+    ///
+    /// If the task is running or ready on a CPU not in the set, it will be
+    /// migrated the next time it is scheduled on that CPU. To migrate the
+    /// current task immediately, use [`set_affinity`](crate::set_affinity).
+    ///
+    /// Returns `false` if the set does not contain any existing CPU.
+    pub fn set_cpumask(&self, cpumask: CpuMask) -> bool {
+        if cpumask.is_empty() {
+            return false;
+        }
+        self.cpumask.store(cpumask.bits(), Ordering::Release);
+        true
+    }
+
+    /// Gets the effective priority of the task.
+    ///
+    /// A smaller value means a higher priority. It may be higher than the
+    /// priority set by the task itself when it inherits the priority of a
+    /// task waiting for it, see [`inherit_priority`](crate::inherit_priority).
+    pub fn priority(&self) -> isize {
+        self.priority.load(Ordering::Acquire)
+    }
+
+    /// Gets the priority set by the task itself, regardless of the inherited
+    /// priorities.
+    pub fn base_priority(&self) -> isize {
+        self.priority_state.lock().base
+    }
+
+    /// Gets the state of the task.
+    #[inline]
+    pub fn state(&self) -> TaskState {
+        self.state.load(Ordering::Acquire).into()
+    }
+
+    /// Gets the statistics of the task.
+    pub fn stats(&self) -> TaskStats {
+        let mut runtime_ns = self.runtime_ns.load(Ordering::Acquire);
+        if self.is_running() {
+            let now = axhal::time::current_time_nanos();
+            runtime_ns += now.saturating_sub(self.last_run_ns.load(Ordering::Acquire));
+        }
+        TaskStats {
+            id: self.id.as_u64(),
+            name: self.name.clone(),
+            state: self.state(),
+            cpu_id: self.cpu_id(),
+            priority: self.priority(),
+            runtime_ns,
+            context_switches: self.context_switches.load(Ordering::Relaxed),
+            wakeups: self.wakeups.load(Ordering::Relaxed),
+            stack_size: self.kstack.as_ref().map_or(0, |s| s.size()),
+            stack_used: self.kstack.as_ref().map_or(0, |s| s.high_water_mark()),
+        }
+    }
+
+    /// Whether `vaddr` is in the guard page below the stack of the task, which
+    /// means the stack has overflowed if it is accessed.
+    #[cfg(feature = "paging")]
+    pub fn in_stack_guard(&self, vaddr: VirtAddr) -> bool {
+        self.kstack
+            .as_ref()
+            .is_some_and(|s| s.guard_contains(vaddr))
+    }
+
+    /// Panics if the canary at the bottom of the stack of the task is
+    /// overwritten.
+    #[cfg(not(feature = "paging"))]
+    pub(crate) fn check_stack_canary(&self) {
+        if self.kstack.as_ref().is_some_and(|s| !s.canary_intact()) {
+            panic!("stack overflow in task {}", self.id_name());
+        }
+    }
+
     /// Wait for the task to exit, and return the exit code.
     ///
     /// It will return immediately if the task has already exited (but not dropped).
@@ -121,9 +243,21 @@ impl TaskInner {
             is_init: false,
             entry: None,
             state: AtomicU8::new(TaskState::Ready as u8),
+            on_cpu: AtomicBool::new(false),
+            cpumask: AtomicUsize::new(CpuMask::full().bits()),
+            cpu_id: AtomicUsize::new(0),
+            priority: AtomicIsize::new(0),
+            priority_state: SpinNoIrq::new(TaskPriority {
+                base: 0,
+                inherited: Vec::new(),
+            }),
             in_wait_queue: AtomicBool::new(false),
             #[cfg(feature = "irq")]
             in_timer_list: AtomicBool::new(false),
+            runtime_ns: AtomicU64::new(0),
+            last_run_ns: AtomicU64::new(0),
+            context_switches: AtomicU64::new(0),
+            wakeups: AtomicU64::new(0),
             #[cfg(feature = "preempt")]
             need_resched: AtomicBool::new(false),
             #[cfg(feature = "preempt")]
@@ -157,7 +291,7 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        crate::stats::register_task(Arc::new(AxTask::new(t)))
     }
 
     /// Creates an "init task" using the current CPU states, to use as the
@@ -174,17 +308,24 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        // It is running since it is created.
+        *t.last_run_ns.get_mut() = axhal::time::current_time_nanos();
+        crate::stats::register_task(Arc::new(AxTask::new(t)))
     }
 
     #[inline]
-    pub(crate) fn state(&self) -> TaskState {
-        self.state.load(Ordering::Acquire).into()
+    pub(crate) fn set_state(&self, state: TaskState) {
+        self.state.store(state as u8, Ordering::Release)
     }
 
+    /// Changes the state of the task from `from` to `to` atomically.
+    ///
+    /// Returns `false` if the current state is not `from`.
     #[inline]
-    pub(crate) fn set_state(&self, state: TaskState) {
-        self.state.store(state as u8, Ordering::Release)
+    pub(crate) fn transition_state(&self, from: TaskState, to: TaskState) -> bool {
+        self.state
+            .compare_exchange(from as u8, to as u8, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
     }
 
     #[inline]
@@ -197,11 +338,6 @@ impl TaskInner {
         matches!(self.state(), TaskState::Ready)
     }
 
-    #[inline]
-    pub(crate) fn is_blocked(&self) -> bool {
-        matches!(self.state(), TaskState::Blocked)
-    }
-
     #[inline]
     pub(crate) const fn is_init(&self) -> bool {
         self.is_init
@@ -212,6 +348,55 @@ impl TaskInner {
         self.is_idle
     }
 
+    #[inline]
+    pub(crate) fn on_cpu(&self) -> bool {
+        self.on_cpu.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_on_cpu(&self, on_cpu: bool) {
+        self.on_cpu.store(on_cpu, Ordering::Release)
+    }
+
+    /// Updates the accounting when the task is switched out at `now`.
+    pub(crate) fn account_switch_out(&self, now: u64) {
+        let ran = now.saturating_sub(self.last_run_ns.load(Ordering::Acquire));
+        self.runtime_ns.fetch_add(ran, Ordering::AcqRel);
+        self.context_switches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Updates the accounting when the task is switched in at `now`.
+    pub(crate) fn account_switch_in(&self, now: u64) {
+        self.last_run_ns.store(now, Ordering::Release);
+    }
+
+    #[inline]
+    pub(crate) fn account_wakeup(&self) {
+        self.wakeups.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn cpu_id(&self) -> usize {
+        self.cpu_id.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub(crate) fn set_cpu_id(&self, cpu_id: usize) {
+        self.cpu_id.store(cpu_id, Ordering::Release)
+    }
+
+    /// Locks the priority state. The effective priority must only be changed
+    /// with it locked.
+    #[inline]
+    pub(crate) fn priority_state(&self) -> SpinNoIrqGuard<'_, TaskPriority> {
+        self.priority_state.lock()
+    }
+
+    #[inline]
+    pub(crate) fn set_effective_priority(&self, prio: isize) {
+        self.priority.store(prio, Ordering::Release)
+    }
+
     #[inline]
     pub(crate) fn in_wait_queue(&self) -> bool {
         self.in_wait_queue.load(Ordering::Acquire)
@@ -265,14 +450,14 @@ impl TaskInner {
     fn current_check_preempt_pending() {
         let curr = crate::current();
         if curr.need_resched.load(Ordering::Acquire) && curr.can_preempt(0) {
-            let mut rq = crate::RUN_QUEUE.lock();
+            let rq = crate::current_run_queue();
             if curr.need_resched.load(Ordering::Acquire) {
                 rq.preempt_resched();
             }
         }
     }
 
-    pub(crate) fn notify_exit(&self, exit_code: i32, rq: &mut AxRunQueue) {
+    pub(crate) fn notify_exit(&self, exit_code: i32, rq: &AxRunQueue) {
         self.exit_code.store(exit_code, Ordering::Release);
         self.wait_for_exit.notify_all_locked(false, rq);
     }
@@ -289,6 +474,7 @@ impl fmt::Debug for TaskInner {
             .field("id", &self.id)
             .field("name", &self.name)
             .field("state", &self.state())
+            .field("priority", &self.priority())
             .finish()
     }
 }
@@ -296,31 +482,7 @@ impl fmt::Debug for TaskInner {
 impl Drop for TaskInner {
     fn drop(&mut self) {
         debug!("task drop: {}", self.id_name());
-    }
-}
-
-struct TaskStack {
-    ptr: NonNull<u8>,
-    layout: Layout,
-}
-
-impl TaskStack {
-    pub fn alloc(size: usize) -> Self {
-        let layout = Layout::from_size_align(size, 16).unwrap();
-        Self {
-            ptr: NonNull::new(unsafe { alloc::alloc::alloc(layout) }).unwrap(),
-            layout,
-        }
-    }
-
-    pub const fn top(&self) -> VirtAddr {
-        unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.layout.size())) }
-    }
-}
-
-impl Drop for TaskStack {
-    fn drop(&mut self) {
-        unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+        crate::stats::unregister_task(self.id);
     }
 }
 
@@ -379,8 +541,8 @@ impl Deref for CurrentTask {
 }
 
 extern "C" fn task_entry() -> ! {
-    // release the lock that was implicitly held across the reschedule
-    unsafe { crate::RUN_QUEUE.force_unlock() };
+    // finish the context switch from the previous task
+    unsafe { crate::run_queue::finish_task_switch() };
     #[cfg(feature = "irq")]
     axhal::arch::enable_irqs();
     let task = crate::current();
@@ -1,10 +1,10 @@
 use core::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Mutex, Once};
 
-use crate::{self as axtask, current, WaitQueue};
+use crate::{self as axtask, current, CpuMask, WaitQueue};
 
-static INIT: Once = Once::new();
-static SERIAL: Mutex<()> = Mutex::new(());
+pub(crate) static INIT: Once = Once::new();
+pub(crate) static SERIAL: Mutex<()> = Mutex::new(());
 
 #[test]
 fn test_sched_fifo() {
@@ -128,3 +128,123 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_affinity() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    assert_eq!(axtask::get_affinity(), CpuMask::full());
+    assert!(!axtask::set_affinity(CpuMask::new()));
+    assert!(!axtask::set_affinity(CpuMask::one_shot(axconfig::SMP)));
+    assert!(axtask::set_affinity(CpuMask::one_shot(0)));
+    assert_eq!(axtask::get_affinity(), CpuMask::one_shot(0));
+
+    let task = axtask::spawn(|| {
+        assert_eq!(axtask::get_affinity(), CpuMask::full());
+        assert!(axtask::set_affinity(CpuMask::one_shot(0)));
+        axtask::yield_now();
+        assert_eq!(current().cpumask(), CpuMask::one_shot(0));
+    });
+    assert_eq!(task.join(), Some(0));
+    assert!(axtask::set_affinity(CpuMask::full()));
+}
+
+#[test]
+fn test_task_stats() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let task = axtask::spawn_raw(
+        || {
+            axtask::yield_now();
+            axtask::yield_now();
+        },
+        "stats".into(),
+        0x4000,
+    );
+    let stats = axtask::task_stats();
+    assert!(stats.iter().any(|s| s.id == current().id().as_u64()));
+    assert!(stats.iter().any(|s| s.id == task.id().as_u64()));
+
+    assert_eq!(task.join(), Some(0));
+    let stats = task.stats();
+    assert_eq!(stats.state, axtask::TaskState::Exited);
+    assert!(stats.context_switches >= 1);
+    assert_eq!(stats.stack_size, 0x4000);
+    assert!(stats.stack_used > 0 && stats.stack_used <= stats.stack_size);
+}
+
+#[test]
+#[cfg(not(feature = "paging"))]
+fn test_stack_canary() {
+    let stack = crate::stack::TaskStack::alloc(0x1000);
+    assert!(stack.canary_intact());
+    assert_eq!(stack.high_water_mark(), 0);
+
+    let bottom = (stack.top().as_usize() - stack.size()) as *mut u8;
+    unsafe { bottom.add(0x800).write(0) };
+    assert!(stack.canary_intact());
+    assert_eq!(stack.high_water_mark(), 0x800);
+
+    unsafe { bottom.write(0) }; // overflow
+    assert!(!stack.canary_intact());
+}
+
+#[test]
+fn test_workqueue() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static ORDER: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+    let wq = axtask::WorkQueue::new("test_wq");
+    for i in 0..5 {
+        wq.queue(move || ORDER.lock().unwrap().push(i));
+    }
+    wq.flush();
+    assert_eq!(*ORDER.lock().unwrap(), [0, 1, 2, 3, 4]);
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let tasklet = axtask::Tasklet::new(|| {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+    });
+    assert!(tasklet.schedule());
+    assert!(!tasklet.schedule()); // already scheduled
+    axtask::flush_scheduled_work();
+    assert!(!tasklet.is_scheduled());
+    assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+#[cfg(feature = "irq")]
+fn test_timer() {
+    use core::time::Duration;
+
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let inc = || {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+    };
+
+    let timer = axtask::Timer::new(inc);
+    timer.start_after(Duration::ZERO);
+    assert!(timer.is_pending());
+    axtask::on_timer_tick();
+    assert!(!timer.is_pending());
+    assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+
+    timer.start_after(Duration::ZERO);
+    assert!(timer.cancel());
+    assert!(!timer.cancel());
+    axtask::on_timer_tick();
+    assert_eq!(COUNT.load(Ordering::Relaxed), 1);
+
+    let deferred = axtask::Timer::new_deferred(inc);
+    deferred.start_after(Duration::ZERO);
+    axtask::on_timer_tick();
+    axtask::flush_scheduled_work();
+    assert_eq!(COUNT.load(Ordering::Relaxed), 2);
+}
@@ -1,40 +1,153 @@
+use alloc::boxed::Box;
 use alloc::sync::Arc;
-use axhal::time::current_time;
+use core::time::Duration;
+
+use axhal::time::{current_time, current_time_nanos, NANOS_PER_SEC};
 use lazy_init::LazyInit;
 use spinlock::SpinNoIrq;
 use timer_list::{TimeValue, TimerEvent, TimerList};
 
-use crate::{AxTaskRef, RUN_QUEUE};
+use crate::{current_run_queue, AxTaskRef};
+
+/// The interval between two scheduler ticks, in nanoseconds.
+const TICK_INTERVAL_NANOS: u64 = NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
+
+#[percpu::def_percpu]
+static TIMER_LIST: LazyInit<SpinNoIrq<TimerList<AxTimerEvent>>> = LazyInit::new();
+
+/// The deadline of the next scheduler tick in nanoseconds, or [`u64::MAX`]
+/// if the ticks are stopped since the CPU is idle (see [`update_tick`]).
+#[percpu::def_percpu]
+static NEXT_TICK_NANOS: u64 = 0;
+
+/// The deadline that the one-shot timer of the CPU is programmed with, or
+/// [`u64::MAX`] if it is not programmed.
+#[percpu::def_percpu]
+static PROGRAMMED_NANOS: u64 = u64::MAX;
+
+enum AxTimerEvent {
+    /// Wakes up a task sleeping with a timeout.
+    TaskWakeup(AxTaskRef),
+    /// Fires a [`Timer`], if it is still armed with the generation.
+    Timer(Arc<TimerInner>, u64),
+}
+
+impl TimerEvent for AxTimerEvent {
+    fn callback(self, now: TimeValue) {
+        match self {
+            Self::TaskWakeup(task) => {
+                let rq = current_run_queue();
+                task.set_in_timer_list(false);
+                rq.unblock_task(task, true);
+            }
+            Self::Timer(timer, generation) => timer.fire(generation, now),
+        }
+    }
+}
+
+fn set_event(deadline: TimeValue, event: AxTimerEvent) {
+    TIMER_LIST.with_current(|timer_list| {
+        let mut timers = timer_list.lock();
+        timers.set(deadline, event);
+        program_timer(&timers, false);
+    })
+}
+
+/// Programs the one-shot timer of the current CPU with the earliest of the
+/// next scheduler tick and the timer events, if it is not programmed so or
+/// `force` is true.
+///
+/// It must be called with IRQs disabled.
+fn program_timer(timers: &TimerList<AxTimerEvent>, force: bool) {
+    let mut deadline = unsafe { NEXT_TICK_NANOS.read_current_raw() };
+    if let Some(next) = timers.next_deadline() {
+        deadline = deadline.min(next.as_nanos() as u64);
+    }
+    if force || deadline != unsafe { PROGRAMMED_NANOS.read_current_raw() } {
+        unsafe { PROGRAMMED_NANOS.write_current_raw(deadline) };
+        axhal::time::set_oneshot_timer(deadline);
+    }
+}
+
+/// Reprograms the one-shot timer of the current CPU after it fired.
+///
+/// The timer is always programmed, even with no deadline ([`u64::MAX`]), as
+/// some timers (e.g., the RISC-V SBI timer) keep the interrupt pending until
+/// they are programmed again.
+pub fn reprogram_timer() {
+    TIMER_LIST.with_current(|timer_list| program_timer(&timer_list.lock(), true));
+}
 
-// TODO: per-CPU
-static TIMER_LIST: LazyInit<SpinNoIrq<TimerList<TaskWakeupEvent>>> = LazyInit::new();
+/// Returns whether a scheduler tick is due, and sets the deadline of the next
+/// one if so. Ticks missed (e.g., with IRQs disabled) are skipped.
+pub fn check_tick() -> bool {
+    let now = current_time_nanos();
+    let next = unsafe { NEXT_TICK_NANOS.read_current_raw() };
+    if now < next {
+        return false; // also if the ticks are stopped
+    }
+    let next = if now - next < TICK_INTERVAL_NANOS {
+        next + TICK_INTERVAL_NANOS
+    } else {
+        now + TICK_INTERVAL_NANOS
+    };
+    unsafe { NEXT_TICK_NANOS.write_current_raw(next) };
+    true
+}
 
-struct TaskWakeupEvent(AxTaskRef);
+/// Stops the scheduler ticks of the current CPU when it switches to the idle
+/// task, so that it is only woken up by timer events and other IRQs, or
+/// restarts them when it switches to other tasks.
+///
+/// On multiprocessors, the ticks keep running on idle CPUs, as there are no
+/// IPIs to wake them up when other CPUs put tasks into their run queues, or
+/// have tasks to be stolen.
+///
+/// It must be called with IRQs disabled.
+pub fn update_tick(next_is_idle: bool) {
+    if axconfig::SMP > 1 {
+        return;
+    }
+    let next = unsafe { NEXT_TICK_NANOS.read_current_raw() };
+    let new_next = match (next_is_idle, next == u64::MAX) {
+        (true, false) => u64::MAX,
+        (false, true) => current_time_nanos() + TICK_INTERVAL_NANOS,
+        _ => return,
+    };
+    unsafe { NEXT_TICK_NANOS.write_current_raw(new_next) };
+    TIMER_LIST.with_current(|timer_list| program_timer(&timer_list.lock(), false));
+}
 
-impl TimerEvent for TaskWakeupEvent {
-    fn callback(self, _now: TimeValue) {
-        let mut rq = RUN_QUEUE.lock();
-        self.0.set_in_timer_list(false);
-        rq.unblock_task(self.0, true);
+/// Removes the events that satisfy `condition` on all CPUs.
+fn cancel_events<F: Fn(&AxTimerEvent) -> bool>(condition: F) {
+    for cpu_id in 0..axconfig::SMP {
+        // Safety: `cpu_id` is valid, and the timer list is protected by a lock.
+        if let Some(timer_list) = unsafe { TIMER_LIST.remote_ref_raw(cpu_id).try_get() } {
+            timer_list.lock().cancel(&condition);
+        }
     }
 }
 
 pub fn set_alarm_wakeup(deadline: TimeValue, task: AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
-    task.set_in_timer_list(true);
-    timers.set(deadline, TaskWakeupEvent(task));
+    TIMER_LIST.with_current(|timer_list| {
+        let mut timers = timer_list.lock();
+        task.set_in_timer_list(true);
+        timers.set(deadline, AxTimerEvent::TaskWakeup(task));
+        program_timer(&timers, false);
+    })
 }
 
+/// Cancels the alarm of the task. The alarm may be set on any CPU, as the task
+/// may be migrated after it is woken up.
 pub fn cancel_alarm(task: &AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
     task.set_in_timer_list(false);
-    timers.cancel(|t| Arc::ptr_eq(&t.0, task));
+    cancel_events(|e| matches!(e, AxTimerEvent::TaskWakeup(t) if Arc::ptr_eq(t, task)));
 }
 
 pub fn check_events() {
     loop {
         let now = current_time();
-        let event = TIMER_LIST.lock().expire_one(now);
+        let event = TIMER_LIST.with_current(|timer_list| timer_list.lock().expire_one(now));
         if let Some((_deadline, event)) = event {
             event.callback(now);
         } else {
@@ -44,5 +157,164 @@ pub fn check_events() {
 }
 
 pub fn init() {
-    TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
+    TIMER_LIST.with_current(|timer_list| timer_list.init_by(SpinNoIrq::new(TimerList::new())));
+}
+
+struct TimerState {
+    /// Increased every time the timer is started or canceled, so that the
+    /// events set before are ignored.
+    generation: u64,
+    armed: bool,
+    deadline: TimeValue,
+    period: Option<Duration>,
+}
+
+struct TimerInner {
+    callback: Box<dyn Fn() + Send + Sync>,
+    deferred: bool,
+    state: SpinNoIrq<TimerState>,
+}
+
+impl TimerInner {
+    fn fire(self: Arc<Self>, generation: u64, now: TimeValue) {
+        let mut state = self.state.lock();
+        if !state.armed || state.generation != generation {
+            return;
+        }
+        if let Some(period) = state.period {
+            // Keep the deadlines on the period grid, but skip the missed ones.
+            let mut deadline = state.deadline + period;
+            if deadline <= now {
+                let periods = (now - state.deadline).as_nanos() / period.as_nanos() + 1;
+                deadline =
+                    state.deadline + Duration::from_nanos((period.as_nanos() * periods) as u64);
+            }
+            state.deadline = deadline;
+            set_event(deadline, AxTimerEvent::Timer(self.clone(), generation));
+        } else {
+            state.armed = false;
+        }
+        drop(state);
+
+        if self.deferred {
+            crate::workqueue::schedule_work(move || {
+                // It may be canceled or restarted before the work runs.
+                if self.state.lock().generation == generation {
+                    (self.callback)();
+                }
+            });
+        } else {
+            (self.callback)();
+        }
+    }
+}
+
+/// A kernel timer that calls a function at a deadline, once or periodically.
+///
+/// The function is called in IRQ context on the CPU that starts the timer,
+/// or in the system work queue if the timer is created by
+/// [`Timer::new_deferred`]. In IRQ context, the function must not block.
+///
+/// The timer is canceled when it is dropped.
+pub struct Timer {
+    inner: Arc<TimerInner>,
+}
+
+impl Timer {
+    /// Creates a new timer that calls `f` in IRQ context.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self::new_inner(Box::new(f), false)
+    }
+
+    /// Creates a new timer that calls `f` in the system work queue, so that
+    /// `f` may block.
+    pub fn new_deferred<F>(f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self::new_inner(Box::new(f), true)
+    }
+
+    fn new_inner(callback: Box<dyn Fn() + Send + Sync>, deferred: bool) -> Self {
+        Self {
+            inner: Arc::new(TimerInner {
+                callback,
+                deferred,
+                state: SpinNoIrq::new(TimerState {
+                    generation: 0,
+                    armed: false,
+                    deadline: TimeValue::ZERO,
+                    period: None,
+                }),
+            }),
+        }
+    }
+
+    fn arm(&self, deadline: TimeValue, period: Option<Duration>) {
+        self.cancel();
+        let mut state = self.inner.state.lock();
+        state.armed = true;
+        state.deadline = deadline;
+        state.period = period;
+        set_event(
+            deadline,
+            AxTimerEvent::Timer(self.inner.clone(), state.generation),
+        );
+    }
+
+    /// Starts the timer to fire once at `deadline`. If the timer is pending,
+    /// it is restarted.
+    pub fn start_at(&self, deadline: TimeValue) {
+        self.arm(deadline, None);
+    }
+
+    /// Starts the timer to fire once after `dur`. If the timer is pending,
+    /// it is restarted.
+    pub fn start_after(&self, dur: Duration) {
+        self.arm(current_time() + dur, None);
+    }
+
+    /// Starts the timer to fire every `period`, starting after a period. If
+    /// the timer is pending, it is restarted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn start_periodic(&self, period: Duration) {
+        assert!(!period.is_zero(), "zero timer period");
+        self.arm(current_time() + period, Some(period));
+    }
+
+    /// Cancels the timer.
+    ///
+    /// Returns `true` if the timer was pending. The function may still be
+    /// running on another CPU or in the work queue when it returns.
+    pub fn cancel(&self) -> bool {
+        let was_armed = {
+            let mut state = self.inner.state.lock();
+            state.generation += 1;
+            core::mem::replace(&mut state.armed, false)
+        };
+        if was_armed {
+            cancel_events(
+                |e| matches!(e, AxTimerEvent::Timer(t, _) if Arc::ptr_eq(t, &self.inner)),
+            );
+        }
+        was_armed
+    }
+
+    /// Whether the timer is started and has not fired (for one-shot timers)
+    /// or been canceled.
+    pub fn is_pending(&self) -> bool {
+        self.inner.state.lock().armed
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.cancel();
+    }
 }
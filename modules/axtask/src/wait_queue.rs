@@ -1,8 +1,8 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
-use spinlock::SpinRaw;
+use spinlock::SpinNoIrq;
 
-use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
+use crate::{current_run_queue, AxRunQueue, AxTaskRef, CurrentTask};
 
 /// A queue to store sleeping tasks.
 ///
@@ -27,21 +27,21 @@ use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
 /// assert_eq!(VALUE.load(Ordering::Relaxed), 1);
 /// ```
 pub struct WaitQueue {
-    queue: SpinRaw<VecDeque<AxTaskRef>>, // we already disabled IRQs when lock the `RUN_QUEUE`
+    queue: SpinNoIrq<VecDeque<AxTaskRef>>,
 }
 
 impl WaitQueue {
     /// Creates an empty wait queue.
     pub const fn new() -> Self {
         Self {
-            queue: SpinRaw::new(VecDeque::new()),
+            queue: SpinNoIrq::new(VecDeque::new()),
         }
     }
 
     /// Creates an empty wait queue with space for at least `capacity` elements.
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            queue: SpinRaw::new(VecDeque::with_capacity(capacity)),
+            queue: SpinNoIrq::new(VecDeque::with_capacity(capacity)),
         }
     }
 
@@ -50,8 +50,6 @@ impl WaitQueue {
         // the event from another queue.
         if curr.in_wait_queue() {
             // wake up by timer (timeout).
-            // `RUN_QUEUE` is not locked here, so disable IRQs.
-            let _guard = kernel_guard::IrqSave::new();
             self.queue.lock().retain(|t| !curr.ptr_eq(t));
             curr.set_in_wait_queue(false);
         }
@@ -65,7 +63,7 @@ impl WaitQueue {
     /// Blocks the current task and put it into the wait queue, until other task
     /// notifies it.
     pub fn wait(&self) {
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -82,13 +80,17 @@ impl WaitQueue {
         F: Fn() -> bool,
     {
         loop {
-            let mut rq = RUN_QUEUE.lock();
+            let rq = current_run_queue();
+            // Check the condition with the wait queue locked, so that the
+            // notification from other CPUs will not be missed.
+            let mut wq = self.queue.lock();
             if condition() {
                 break;
             }
             rq.block_current(|task| {
                 task.set_in_wait_queue(true);
-                self.queue.lock().push_back(task);
+                wq.push_back(task);
+                drop(wq);
             });
         }
         self.cancel_events(crate::current());
@@ -105,9 +107,11 @@ impl WaitQueue {
             curr.id_name(),
             deadline
         );
-        crate::timers::set_alarm_wakeup(deadline, curr.clone());
 
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
+            // Set the alarm with IRQs disabled, on the same CPU as the task is
+            // blocked.
+            crate::timers::set_alarm_wakeup(deadline, task.clone());
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -133,18 +137,22 @@ impl WaitQueue {
             curr.id_name(),
             deadline
         );
-        crate::timers::set_alarm_wakeup(deadline, curr.clone());
 
         let mut timeout = true;
         while axhal::time::current_time() < deadline {
-            let mut rq = RUN_QUEUE.lock();
+            let rq = current_run_queue();
+            let mut wq = self.queue.lock();
             if condition() {
                 timeout = false;
                 break;
             }
             rq.block_current(|task| {
+                if !task.in_timer_list() {
+                    crate::timers::set_alarm_wakeup(deadline, task.clone());
+                }
                 task.set_in_wait_queue(true);
-                self.queue.lock().push_back(task);
+                wq.push_back(task);
+                drop(wq);
             });
         }
         self.cancel_events(curr);
@@ -156,9 +164,34 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_one(&self, resched: bool) -> bool {
-        let mut rq = RUN_QUEUE.lock();
-        if !self.queue.lock().is_empty() {
-            self.notify_one_locked(resched, &mut rq)
+        let rq = current_run_queue();
+        self.notify_one_locked(resched, &rq)
+    }
+
+    /// Returns the highest (numerically smallest) priority of the tasks in the
+    /// wait queue, or `None` if it is empty.
+    pub fn highest_priority(&self) -> Option<isize> {
+        self.queue.lock().iter().map(|t| t.priority()).min()
+    }
+
+    /// Wakes up the task with the highest priority in the wait queue, or the
+    /// first one of them if there are several.
+    ///
+    /// If `resched` is true, the current task will be preempted when the
+    /// preemption is enabled.
+    pub fn notify_one_by_priority(&self, resched: bool) -> bool {
+        let rq = current_run_queue();
+        let mut wq = self.queue.lock();
+        let index = wq
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, t)| t.priority())
+            .map(|(i, _)| i);
+        if let Some(task) = index.and_then(|i| wq.remove(i)) {
+            drop(wq);
+            task.set_in_wait_queue(false);
+            rq.unblock_task(task, resched);
+            true
         } else {
             false
         }
@@ -169,16 +202,8 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_all(&self, resched: bool) {
-        loop {
-            let mut rq = RUN_QUEUE.lock();
-            if let Some(task) = self.queue.lock().pop_front() {
-                task.set_in_wait_queue(false);
-                rq.unblock_task(task, resched);
-            } else {
-                break;
-            }
-            drop(rq); // we must unlock `RUN_QUEUE` after unlocking `self.queue`.
-        }
+        let rq = current_run_queue();
+        self.notify_all_locked(resched, &rq);
     }
 
     /// Wake up the given task in the wait queue.
@@ -186,7 +211,7 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_task(&mut self, resched: bool, task: &AxTaskRef) -> bool {
-        let mut rq = RUN_QUEUE.lock();
+        let rq = current_run_queue();
         let mut wq = self.queue.lock();
         if let Some(index) = wq.iter().position(|t| Arc::ptr_eq(t, task)) {
             task.set_in_wait_queue(false);
@@ -197,7 +222,7 @@ impl WaitQueue {
         }
     }
 
-    pub(crate) fn notify_one_locked(&self, resched: bool, rq: &mut AxRunQueue) -> bool {
+    pub(crate) fn notify_one_locked(&self, resched: bool, rq: &AxRunQueue) -> bool {
         if let Some(task) = self.queue.lock().pop_front() {
             task.set_in_wait_queue(false);
             rq.unblock_task(task, resched);
@@ -207,7 +232,7 @@ impl WaitQueue {
         }
     }
 
-    pub(crate) fn notify_all_locked(&self, resched: bool, rq: &mut AxRunQueue) {
+    pub(crate) fn notify_all_locked(&self, resched: bool, rq: &AxRunQueue) {
         while let Some(task) = self.queue.lock().pop_front() {
             task.set_in_wait_queue(false);
             rq.unblock_task(task, resched);
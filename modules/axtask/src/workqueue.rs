@@ -0,0 +1,162 @@
+//! Deferred work, run in the context of kernel worker tasks.
+//!
+//! IRQ handlers must not block, so they can put the work that may block or
+//! take long into a [`WorkQueue`], which is run later by a worker task.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use lazy_init::LazyInit;
+use spinlock::SpinNoIrq;
+
+use crate::WaitQueue;
+
+type Work = Box<dyn FnOnce() + Send>;
+
+struct WorkQueueInner {
+    works: SpinNoIrq<VecDeque<Work>>,
+    wq: WaitQueue,
+    stopped: AtomicBool,
+}
+
+/// A queue of works that are run one by one in FIFO order, by a dedicated
+/// worker task.
+///
+/// Works can be queued from any context, including IRQ handlers. When the
+/// queue is dropped, the worker task exits after running the remaining works.
+pub struct WorkQueue {
+    inner: Arc<WorkQueueInner>,
+}
+
+impl WorkQueue {
+    /// Creates a new work queue, and spawns its worker task with the given
+    /// name.
+    pub fn new(name: &str) -> Self {
+        let inner = Arc::new(WorkQueueInner {
+            works: SpinNoIrq::new(VecDeque::new()),
+            wq: WaitQueue::new(),
+            stopped: AtomicBool::new(false),
+        });
+        let worker = inner.clone();
+        crate::spawn_raw(move || worker.run(), name.into(), axconfig::TASK_STACK_SIZE);
+        Self { inner }
+    }
+
+    /// Puts the work `f` at the end of the queue, and wakes up the worker.
+    pub fn queue<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.inner.works.lock().push_back(Box::new(f));
+        self.inner.wq.notify_one(true);
+    }
+
+    /// Blocks the current task until all works queued before are finished.
+    ///
+    /// It must not be called in IRQ context, or in a work of this queue.
+    pub fn flush(&self) {
+        let done = Arc::new((AtomicBool::new(false), WaitQueue::new()));
+        let done2 = done.clone();
+        self.queue(move || {
+            done2.0.store(true, Ordering::Release);
+            done2.1.notify_one(true);
+        });
+        done.1.wait_until(|| done.0.load(Ordering::Acquire));
+    }
+}
+
+impl Drop for WorkQueue {
+    fn drop(&mut self) {
+        self.inner.stopped.store(true, Ordering::Release);
+        self.inner.wq.notify_one(true);
+    }
+}
+
+impl WorkQueueInner {
+    fn run(&self) {
+        loop {
+            self.wq.wait_until(|| {
+                self.stopped.load(Ordering::Acquire) || !self.works.lock().is_empty()
+            });
+            // Only this task takes works, so it's `None` only if stopped.
+            match self.works.lock().pop_front() {
+                Some(work) => work(),
+                None => break,
+            }
+        }
+    }
+}
+
+/// A function that is run in the system work queue when it is scheduled.
+///
+/// Scheduling a tasklet that is already scheduled has no effect, so it runs
+/// once for several schedules before it starts to run. Since the system work
+/// queue runs works one by one, a tasklet never runs concurrently with itself.
+#[derive(Clone)]
+pub struct Tasklet {
+    inner: Arc<TaskletInner>,
+}
+
+struct TaskletInner {
+    scheduled: AtomicBool,
+    func: Box<dyn Fn() + Send + Sync>,
+}
+
+impl Tasklet {
+    /// Creates a new tasklet that runs `f`.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(TaskletInner {
+                scheduled: AtomicBool::new(false),
+                func: Box::new(f),
+            }),
+        }
+    }
+
+    /// Schedules the tasklet to run in the system work queue.
+    ///
+    /// Returns `false` if it is already scheduled but has not started.
+    pub fn schedule(&self) -> bool {
+        if self.inner.scheduled.swap(true, Ordering::AcqRel) {
+            return false;
+        }
+        let inner = self.inner.clone();
+        schedule_work(move || {
+            // Clear it first, so that it can be scheduled again while running.
+            inner.scheduled.store(false, Ordering::Release);
+            (inner.func)();
+        });
+        true
+    }
+
+    /// Whether the tasklet is scheduled but has not started.
+    pub fn is_scheduled(&self) -> bool {
+        self.inner.scheduled.load(Ordering::Acquire)
+    }
+}
+
+static SYSTEM_WORK_QUEUE: LazyInit<WorkQueue> = LazyInit::new();
+
+/// Puts the work `f` into the system work queue, which is shared by all
+/// users, so `f` should not block for long.
+pub fn schedule_work<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    SYSTEM_WORK_QUEUE.queue(f);
+}
+
+/// Blocks the current task until all works queued into the system work queue
+/// before are finished.
+pub fn flush_scheduled_work() {
+    SYSTEM_WORK_QUEUE.flush();
+}
+
+pub(crate) fn init() {
+    SYSTEM_WORK_QUEUE.init_by(WorkQueue::new("kworker"));
+}
@@ -2,13 +2,6 @@
 #include <sys/stat.h>
 #include <sys/types.h>
 
-// TODO:
-int fchmod(int fd, mode_t mode)
-{
-    unimplemented();
-    return 0;
-}
-
 // TODO:
 int mkdir(const char *path, mode_t mode)
 {
@@ -16,13 +9,6 @@ int mkdir(const char *path, mode_t mode)
     return 0;
 }
 
-// TODO
-int chmod(const char *path, mode_t mode)
-{
-    unimplemented();
-    return 0;
-}
-
 // TODO
 mode_t umask(mode_t mask)
 {
@@ -36,3 +22,8 @@ int fstatat(int fd, const char *restrict path, struct stat *restrict st, int fla
     unimplemented();
     return 0;
 }
+
+int futimens(int fd, const struct timespec times[2])
+{
+    return utimensat(fd, 0, times, 0);
+}
@@ -2,6 +2,7 @@
 #define _SCHED_H
 
 #include <stddef.h>
+#include <sys/types.h>
 
 typedef struct cpu_set_t {
     unsigned long __bits[128 / sizeof(long)];
@@ -18,6 +19,10 @@ typedef struct cpu_set_t {
 #define CPU_SET(i, set) CPU_SET_S(i, sizeof(cpu_set_t), set);
 #define CPU_ZERO(set)   CPU_ZERO_S(sizeof(cpu_set_t), set)
 
+#ifdef AX_CONFIG_MULTITASK
+
 int sched_setaffinity(pid_t, size_t, const cpu_set_t *);
 
+#endif // AX_CONFIG_MULTITASK
+
 #endif // _SCHED_H
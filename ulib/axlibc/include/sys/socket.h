@@ -53,6 +53,11 @@ struct sockaddr_storage {
     unsigned long __ss_align;
 };
 
+struct linger {
+    int l_onoff;
+    int l_linger;
+};
+
 int socket(int, int, int);
 int shutdown(int, int);
 
@@ -63,6 +63,8 @@ mod net;
 mod pipe;
 #[cfg(feature = "multitask")]
 mod pthread;
+#[cfg(feature = "multitask")]
+mod sched;
 #[cfg(feature = "alloc")]
 mod strftime;
 #[cfg(feature = "fp_simd")]
@@ -100,7 +102,10 @@ pub use self::strftime::strftime;
 pub use self::fd_ops::{ax_fcntl, close, dup, dup2, dup3};
 
 #[cfg(feature = "fs")]
-pub use self::fs::{ax_open, fstat, getcwd, lseek, lstat, rename, stat};
+pub use self::fs::{
+    ax_open, chmod, chown, fchmod, fchown, fstat, fstatfs, fsync, getcwd, lchown, link, lseek,
+    lstat, readlink, rename, stat, statfs, symlink, sync, unlink, utimensat,
+};
 
 #[cfg(feature = "net")]
 pub use self::net::{
@@ -109,9 +114,35 @@ pub use self::net::{
 };
 
 #[cfg(feature = "multitask")]
-pub use self::pthread::{pthread_create, pthread_exit, pthread_join, pthread_self};
+pub use self::pthread::{
+    pthread_attr_destroy, pthread_attr_getstacksize, pthread_attr_init, pthread_attr_setstacksize,
+    pthread_create, pthread_detach, pthread_exit, pthread_join, pthread_once, pthread_self,
+    pthread_setaffinity_np,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_barrier_destroy, pthread_barrier_init, pthread_barrier_wait, pthread_rwlock_destroy,
+    pthread_rwlock_init, pthread_rwlock_rdlock, pthread_rwlock_tryrdlock, pthread_rwlock_trywrlock,
+    pthread_rwlock_unlock, pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_cond_broadcast, pthread_cond_destroy, pthread_cond_init, pthread_cond_signal,
+    pthread_cond_timedwait, pthread_cond_wait, pthread_condattr_destroy, pthread_condattr_getclock,
+    pthread_condattr_init, pthread_condattr_setclock,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_getspecific, pthread_key_create, pthread_key_delete, pthread_setspecific,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_mutex_destroy, pthread_mutex_init, pthread_mutex_lock, pthread_mutex_trylock,
+    pthread_mutex_unlock, pthread_mutexattr_destroy, pthread_mutexattr_gettype,
+    pthread_mutexattr_init, pthread_mutexattr_settype,
+};
 #[cfg(feature = "multitask")]
-pub use self::pthread::{pthread_mutex_init, pthread_mutex_lock, pthread_mutex_unlock};
+pub use self::sched::sched_setaffinity;
 
 #[cfg(feature = "pipe")]
 pub use self::pipe::pipe;
@@ -1,6 +1,6 @@
-use crate::{ctypes, utils::e};
+use crate::{ctypes, utils::pe};
 use arceos_posix_api as api;
-use core::ffi::{c_int, c_void};
+use core::ffi::{c_int, c_uint, c_void};
 
 /// Returns the `pthread` struct of current thread.
 #[no_mangle]
@@ -19,7 +19,7 @@ pub unsafe extern "C" fn pthread_create(
     start_routine: extern "C" fn(arg: *mut c_void) -> *mut c_void,
     arg: *mut c_void,
 ) -> c_int {
-    e(api::sys_pthread_create(res, attr, start_routine, arg))
+    pe(api::sys_pthread_create(res, attr, start_routine, arg))
 }
 
 /// Exits the current thread. The value `retval` will be returned to the joiner.
@@ -34,7 +34,62 @@ pub unsafe extern "C" fn pthread_join(
     thread: ctypes::pthread_t,
     retval: *mut *mut c_void,
 ) -> c_int {
-    e(api::sys_pthread_join(thread, retval))
+    pe(api::sys_pthread_join(thread, retval))
+}
+
+/// Detach the given thread, its resources are released when it exits.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_detach(thread: ctypes::pthread_t) -> c_int {
+    pe(api::sys_pthread_detach(thread))
+}
+
+/// Set the CPU affinity of the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    pe(api::sys_pthread_setaffinity_np(thread, cpusetsize, cpuset))
+}
+
+/// Initialize a thread attributes object with the default attributes.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_init(attr: *mut ctypes::pthread_attr_t) -> c_int {
+    pe(api::sys_pthread_attr_init(attr))
+}
+
+/// Destroy a thread attributes object.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_destroy(attr: *mut ctypes::pthread_attr_t) -> c_int {
+    pe(api::sys_pthread_attr_destroy(attr))
+}
+
+/// Get the stack size attribute.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_getstacksize(
+    attr: *const ctypes::pthread_attr_t,
+    stacksize: *mut usize,
+) -> c_int {
+    pe(api::sys_pthread_attr_getstacksize(attr, stacksize))
+}
+
+/// Set the stack size attribute.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_setstacksize(
+    attr: *mut ctypes::pthread_attr_t,
+    stacksize: usize,
+) -> c_int {
+    pe(api::sys_pthread_attr_setstacksize(attr, stacksize))
+}
+
+/// Run `init_routine` once and only once.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_once(
+    once_control: *mut ctypes::pthread_once_t,
+    init_routine: extern "C" fn(),
+) -> c_int {
+    pe(api::sys_pthread_once(once_control, init_routine))
 }
 
 /// Initialize a mutex.
@@ -43,17 +98,238 @@ pub unsafe extern "C" fn pthread_mutex_init(
     mutex: *mut ctypes::pthread_mutex_t,
     attr: *const ctypes::pthread_mutexattr_t,
 ) -> c_int {
-    e(api::sys_pthread_mutex_init(mutex, attr))
+    pe(api::sys_pthread_mutex_init(mutex, attr))
 }
 
 /// Lock the given mutex.
 #[no_mangle]
 pub unsafe extern "C" fn pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
-    e(api::sys_pthread_mutex_lock(mutex))
+    pe(api::sys_pthread_mutex_lock(mutex))
 }
 
 /// Unlock the given mutex.
 #[no_mangle]
 pub unsafe extern "C" fn pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
-    e(api::sys_pthread_mutex_unlock(mutex))
+    pe(api::sys_pthread_mutex_unlock(mutex))
+}
+
+/// Try to lock the given mutex without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutex_trylock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    pe(api::sys_pthread_mutex_trylock(mutex))
+}
+
+/// Destroy a mutex.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutex_destroy(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    pe(api::sys_pthread_mutex_destroy(mutex))
+}
+
+/// Initialize a mutex attributes object with the default attributes.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutexattr_init(attr: *mut ctypes::pthread_mutexattr_t) -> c_int {
+    pe(api::sys_pthread_mutexattr_init(attr))
+}
+
+/// Destroy a mutex attributes object.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutexattr_destroy(
+    attr: *mut ctypes::pthread_mutexattr_t,
+) -> c_int {
+    pe(api::sys_pthread_mutexattr_destroy(attr))
+}
+
+/// Get the mutex type attribute.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutexattr_gettype(
+    attr: *const ctypes::pthread_mutexattr_t,
+    kind: *mut c_int,
+) -> c_int {
+    pe(api::sys_pthread_mutexattr_gettype(attr, kind))
+}
+
+/// Set the mutex type attribute.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutexattr_settype(
+    attr: *mut ctypes::pthread_mutexattr_t,
+    kind: c_int,
+) -> c_int {
+    pe(api::sys_pthread_mutexattr_settype(attr, kind))
+}
+
+/// Initialize a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    pe(api::sys_pthread_cond_init(cond, attr))
+}
+
+/// Destroy a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    pe(api::sys_pthread_cond_destroy(cond))
+}
+
+/// Wait on a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    pe(api::sys_pthread_cond_wait(cond, mutex))
+}
+
+/// Wait on a condition variable until the absolute time `abstime`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    pe(api::sys_pthread_cond_timedwait(cond, mutex, abstime))
+}
+
+/// Wake up one thread waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    pe(api::sys_pthread_cond_signal(cond))
+}
+
+/// Wake up all threads waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    pe(api::sys_pthread_cond_broadcast(cond))
+}
+
+/// Initialize a condition variable attributes object with the default
+/// attributes.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_condattr_init(attr: *mut ctypes::pthread_condattr_t) -> c_int {
+    pe(api::sys_pthread_condattr_init(attr))
+}
+
+/// Destroy a condition variable attributes object.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_condattr_destroy(attr: *mut ctypes::pthread_condattr_t) -> c_int {
+    pe(api::sys_pthread_condattr_destroy(attr))
+}
+
+/// Get the clock used by `pthread_cond_timedwait`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_condattr_getclock(
+    attr: *const ctypes::pthread_condattr_t,
+    clock_id: *mut ctypes::clockid_t,
+) -> c_int {
+    pe(api::sys_pthread_condattr_getclock(attr, clock_id))
+}
+
+/// Set the clock used by `pthread_cond_timedwait`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_condattr_setclock(
+    attr: *mut ctypes::pthread_condattr_t,
+    clock_id: ctypes::clockid_t,
+) -> c_int {
+    pe(api::sys_pthread_condattr_setclock(attr, clock_id))
+}
+
+/// Initialize a read-write lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    pe(api::sys_pthread_rwlock_init(rwlock, attr))
+}
+
+/// Destroy a read-write lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    pe(api::sys_pthread_rwlock_destroy(rwlock))
+}
+
+/// Lock a read-write lock for reading.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    pe(api::sys_pthread_rwlock_rdlock(rwlock))
+}
+
+/// Try to lock a read-write lock for reading without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    pe(api::sys_pthread_rwlock_tryrdlock(rwlock))
+}
+
+/// Lock a read-write lock for writing.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    pe(api::sys_pthread_rwlock_wrlock(rwlock))
+}
+
+/// Try to lock a read-write lock for writing without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    pe(api::sys_pthread_rwlock_trywrlock(rwlock))
+}
+
+/// Unlock a read-write lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    pe(api::sys_pthread_rwlock_unlock(rwlock))
+}
+
+/// Initialize a barrier which blocks `count` threads.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_barrier_init(
+    barrier: *mut ctypes::pthread_barrier_t,
+    attr: *const ctypes::pthread_barrierattr_t,
+    count: c_uint,
+) -> c_int {
+    pe(api::sys_pthread_barrier_init(barrier, attr, count))
+}
+
+/// Destroy a barrier.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_barrier_destroy(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    pe(api::sys_pthread_barrier_destroy(barrier))
+}
+
+/// Wait on a barrier until all threads have reached it.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_barrier_wait(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    match api::sys_pthread_barrier_wait(barrier) {
+        ctypes::PTHREAD_BARRIER_SERIAL_THREAD => ctypes::PTHREAD_BARRIER_SERIAL_THREAD,
+        ret => pe(ret),
+    }
+}
+
+/// Create a thread-specific data key.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_key_create(
+    key: *mut ctypes::pthread_key_t,
+    destructor: Option<unsafe extern "C" fn(*mut c_void)>,
+) -> c_int {
+    pe(api::sys_pthread_key_create(key, destructor))
+}
+
+/// Delete a thread-specific data key.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_key_delete(key: ctypes::pthread_key_t) -> c_int {
+    pe(api::sys_pthread_key_delete(key))
+}
+
+/// Get the value of the current thread bound to the given key.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_getspecific(key: ctypes::pthread_key_t) -> *mut c_void {
+    api::sys_pthread_getspecific(key)
+}
+
+/// Bind a value of the current thread to the given key.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_setspecific(
+    key: ctypes::pthread_key_t,
+    value: *const c_void,
+) -> c_int {
+    pe(api::sys_pthread_setspecific(key, value))
 }
@@ -0,0 +1,15 @@
+use crate::{ctypes, utils::e};
+use arceos_posix_api::sys_sched_setaffinity;
+use core::ffi::c_int;
+
+/// Set the CPU affinity of the thread with ID `pid` to `mask`.
+///
+/// If `pid` is zero, the current thread is used.
+#[no_mangle]
+pub unsafe extern "C" fn sched_setaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    mask: *const ctypes::cpu_set_t,
+) -> c_int {
+    e(sys_sched_setaffinity(pid, cpusetsize, mask))
+}
@@ -8,3 +8,13 @@ pub fn e(ret: c_int) -> c_int {
         ret as _
     }
 }
+
+/// Converts the return value of a `pthread_*` API, which returns the error
+/// number directly instead of setting `errno`.
+pub fn pe(ret: c_int) -> c_int {
+    if ret < 0 {
+        -ret
+    } else {
+        ret
+    }
+}
@@ -11,6 +11,10 @@ use alloc::{string::String, vec::Vec};
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
 
+/// Attributes of a filesystem, such as the total and free space. It is
+/// returned by [`statfs`].
+pub type FileSystemInfo = arceos_api::fs::AxFileSystemInfo;
+
 /// Read the entire contents of a file into a bytes vector.
 #[cfg(feature = "alloc")]
 pub fn read(path: &str) -> io::Result<Vec<u8>> {
@@ -42,6 +46,38 @@ pub fn metadata(path: &str) -> io::Result<Metadata> {
     File::open(path)?.metadata()
 }
 
+/// Query the metadata about a file without following symlinks.
+pub fn symlink_metadata(path: &str) -> io::Result<Metadata> {
+    arceos_api::fs::ax_symlink_attr(path).map(Metadata)
+}
+
+/// Returns the attributes of the filesystem that contains the node at `path`,
+/// such as the total and free space.
+pub fn statfs(path: &str) -> io::Result<FileSystemInfo> {
+    arceos_api::fs::ax_statfs(path)
+}
+
+/// Reads a symbolic link, returning the file that the link points to.
+#[cfg(feature = "alloc")]
+pub fn read_link(path: &str) -> io::Result<String> {
+    arceos_api::fs::ax_read_link(path)
+}
+
+/// Creates a new symbolic link on the filesystem.
+///
+/// The `link` path will be a symbolic link pointing to the `original` path.
+pub fn symlink(original: &str, link: &str) -> io::Result<()> {
+    arceos_api::fs::ax_symlink(original, link)
+}
+
+/// Creates a new hard link on the filesystem.
+///
+/// The `link` path will be a link pointing to the `original` path. Note that
+/// directories cannot be hard linked.
+pub fn hard_link(original: &str, link: &str) -> io::Result<()> {
+    arceos_api::fs::ax_hard_link(original, link)
+}
+
 /// Returns an iterator over the entries within a directory.
 pub fn read_dir(path: &str) -> io::Result<ReadDir> {
     ReadDir::new(path)
@@ -9,6 +9,8 @@ use core::{cell::UnsafeCell, num::NonZeroU64};
 use arceos_api::task::{self as api, AxTaskHandle};
 use axerrno::ax_err_type;
 
+pub use arceos_api::task::AxCpuMask as CpuMask;
+
 /// A unique identifier for a running thread.
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 pub struct ThreadId(NonZeroU64);
@@ -49,6 +51,8 @@ pub struct Builder {
     name: Option<String>,
     // The size of the stack for the spawned thread in bytes
     stack_size: Option<usize>,
+    // The CPUs that the spawned thread is allowed to run on
+    affinity: Option<CpuMask>,
 }
 
 impl Builder {
@@ -58,6 +62,7 @@ impl Builder {
         Builder {
             name: None,
             stack_size: None,
+            affinity: None,
         }
     }
 
@@ -73,6 +78,14 @@ impl Builder {
         self
     }
 
+    /// Sets the CPUs that the new thread is allowed to run on.
+    ///
+    /// The new thread runs on all CPUs by default.
+    pub fn affinity(mut self, cpumask: CpuMask) -> Builder {
+        self.affinity = Some(cpumask);
+        self
+    }
+
     /// Spawns a new thread by taking ownership of the `Builder`, and returns an
     /// [`io::Result`] to its [`JoinHandle`].
     ///
@@ -99,6 +112,10 @@ impl Builder {
         let stack_size = self
             .stack_size
             .unwrap_or(arceos_api::config::TASK_STACK_SIZE);
+        if self.affinity.is_some_and(|cpumask| cpumask.is_empty()) {
+            return Err(ax_err_type!(InvalidInput));
+        }
+        let affinity = self.affinity;
 
         let my_packet = Arc::new(Packet {
             result: UnsafeCell::new(None),
@@ -106,6 +123,10 @@ impl Builder {
         let their_packet = my_packet.clone();
 
         let main = move || {
+            if let Some(cpumask) = affinity {
+                // Migrate to an allowed CPU before running the user code.
+                api::ax_set_affinity(cpumask).expect("failed to set thread affinity");
+            }
             let ret = f();
             // SAFETY: `their_packet` as been built just above and moved by the
             // closure (it is an Arc<...>) and `my_packet` will be stored in the